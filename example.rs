@@ -11,7 +11,7 @@ fn main() {
     }
 
     rule CheckLocation:
-        if farmer.location == "valid"
+        if farmer.location == valid
         then approve_farmer(farmer)
 
     flow ProcessFarmers {
@@ -31,6 +31,12 @@ pub fn deserialize_ast<R: Read>(reader: &mut R) -> IoResult<ProgramNode> {
     deserialize_program_node(reader)
 }
 
+// List counts come from the input, so a corrupt count must not drive a huge
+// allocation; larger lists still grow as their elements are read
+fn preallocated(count: u32) -> usize {
+    count.min(256) as usize
+}
+
 fn deserialize_program_node<R: Read>(reader: &mut R) -> IoResult<ProgramNode> {
     // Read node kind (should be Program)
     let mut kind_bytes = [0u8; 2];
@@ -126,7 +132,7 @@ fn deserialize_entity_list<R: Read>(reader: &mut R) -> IoResult<Vec<EntityNode>>
     reader.read_exact(&mut count_bytes)?;
     let count = u32::from_le_bytes(count_bytes);
 
-    let mut entities = Vec::with_capacity(count as usize);
+    let mut entities = Vec::with_capacity(preallocated(count));
     for _ in 0..count {
         entities.push(deserialize_entity_node(reader)?);
     }
@@ -152,7 +158,7 @@ fn deserialize_rule_list<R: Read>(reader: &mut R) -> IoResult<Vec<RuleNode>> {
     reader.read_exact(&mut count_bytes)?;
     let count = u32::from_le_bytes(count_bytes);
 
-    let mut rules = Vec::with_capacity(count as usize);
+    let mut rules = Vec::with_capacity(preallocated(count));
     for _ in 0..count {
         rules.push(deserialize_rule_node(reader)?);
     }
@@ -178,7 +184,7 @@ fn deserialize_flow_list<R: Read>(reader: &mut R) -> IoResult<Vec<FlowNode>> {
     reader.read_exact(&mut count_bytes)?;
     let count = u32::from_le_bytes(count_bytes);
 
-    let mut flows = Vec::with_capacity(count as usize);
+    let mut flows = Vec::with_capacity(preallocated(count));
     for _ in 0..count {
         flows.push(deserialize_flow_node(reader)?);
     }
@@ -204,7 +210,7 @@ fn deserialize_constraint_list<R: Read>(reader: &mut R) -> IoResult<Vec<Constrai
     reader.read_exact(&mut count_bytes)?;
     let count = u32::from_le_bytes(count_bytes);
 
-    let mut constraints = Vec::with_capacity(count as usize);
+    let mut constraints = Vec::with_capacity(preallocated(count));
     for _ in 0..count {
         constraints.push(deserialize_constraint_node(reader)?);
     }
@@ -304,7 +310,7 @@ fn deserialize_attribute_list<R: Read>(reader: &mut R) -> IoResult<Vec<Attribute
     reader.read_exact(&mut count_bytes)?;
     let count = u32::from_le_bytes(count_bytes);
 
-    let mut attributes = Vec::with_capacity(count as usize);
+    let mut attributes = Vec::with_capacity(preallocated(count));
     for _ in 0..count {
         attributes.push(deserialize_attribute_node(reader)?);
     }
@@ -330,7 +336,7 @@ fn deserialize_rule_ref_list<R: Read>(reader: &mut R) -> IoResult<Vec<RuleRefNod
     reader.read_exact(&mut count_bytes)?;
     let count = u32::from_le_bytes(count_bytes);
 
-    let mut rule_refs = Vec::with_capacity(count as usize);
+    let mut rule_refs = Vec::with_capacity(preallocated(count));
     for _ in 0..count {
         rule_refs.push(deserialize_rule_ref_node(reader)?);
     }
@@ -356,7 +362,7 @@ fn deserialize_constraint_ref_list<R: Read>(reader: &mut R) -> IoResult<Vec<Cons
     reader.read_exact(&mut count_bytes)?;
     let count = u32::from_le_bytes(count_bytes);
 
-    let mut constraint_refs = Vec::with_capacity(count as usize);
+    let mut constraint_refs = Vec::with_capacity(preallocated(count));
     for _ in 0..count {
         constraint_refs.push(deserialize_constraint_ref_node(reader)?);
     }
@@ -428,7 +434,7 @@ fn deserialize_attribute_node<R: Read>(reader: &mut R) -> IoResult<AttributeNode
 
     // Deserialize default value (if present)
     let default_value = if field_count == 3 {
-        Some(deserialize_expression_node(reader)?)
+        Some(deserialize_expression_ref(reader)?)
     } else {
         None
     };
@@ -625,7 +631,7 @@ fn deserialize_rule_node<R: Read>(reader: &mut R) -> IoResult<RuleNode> {
     let parameters = deserialize_parameter_list(reader)?;
 
     // Deserialize condition
-    let condition = deserialize_expression_node(reader)?;
+    let condition = deserialize_expression_ref(reader)?;
 
     // Deserialize actions
     let actions = deserialize_action_list(reader)?;
@@ -657,7 +663,7 @@ fn deserialize_parameter_list<R: Read>(reader: &mut R) -> IoResult<Vec<Parameter
     reader.read_exact(&mut count_bytes)?;
     let count = u32::from_le_bytes(count_bytes);
 
-    let mut parameters = Vec::with_capacity(count as usize);
+    let mut parameters = Vec::with_capacity(preallocated(count));
     for _ in 0..count {
         parameters.push(deserialize_parameter_node(reader)?);
     }
@@ -683,7 +689,7 @@ fn deserialize_action_list<R: Read>(reader: &mut R) -> IoResult<Vec<ActionNode>>
     reader.read_exact(&mut count_bytes)?;
     let count = u32::from_le_bytes(count_bytes);
 
-    let mut actions = Vec::with_capacity(count as usize);
+    let mut actions = Vec::with_capacity(preallocated(count));
     for _ in 0..count {
         actions.push(deserialize_action_node(reader)?);
     }
@@ -844,7 +850,7 @@ fn deserialize_flow_step_list<R: Read>(reader: &mut R) -> IoResult<Vec<FlowStepN
     reader.read_exact(&mut count_bytes)?;
     let count = u32::from_le_bytes(count_bytes);
 
-    let mut steps = Vec::with_capacity(count as usize);
+    let mut steps = Vec::with_capacity(preallocated(count));
     for _ in 0..count {
         steps.push(deserialize_flow_step_node(reader)?);
     }
@@ -912,7 +918,7 @@ fn deserialize_constraint_node<R: Read>(reader: &mut R) -> IoResult<ConstraintNo
     let name = deserialize_identifier_node(reader)?;
 
     // Deserialize expression
-    let expression = deserialize_expression_node(reader)?;
+    let expression = deserialize_expression_ref(reader)?;
 
     // Deserialize severity
     let severity = deserialize_severity_level(reader)?;
@@ -925,6 +931,22 @@ fn deserialize_constraint_node<R: Read>(reader: &mut R) -> IoResult<ConstraintNo
     })
 }
 
+fn deserialize_expression_ref<R: Read>(reader: &mut R) -> IoResult<ExpressionNode> {
+    // Read field kind (should be NodeRef)
+    let mut field_kind_byte = [0u8; 1];
+    reader.read_exact(&mut field_kind_byte)?;
+
+    if field_kind_byte[0] != 0 {
+        // FieldKind::NodeRef as u8
+        return Err(IoError::new(
+            ErrorKind::InvalidData,
+            "Expected NodeRef field",
+        ));
+    }
+
+    deserialize_expression_node(reader)
+}
+
 fn deserialize_expression_node<R: Read>(reader: &mut R) -> IoResult<ExpressionNode> {
     // Read node kind
     let mut kind_bytes = [0u8; 2];
@@ -1008,13 +1030,13 @@ fn deserialize_binary_expr_node<R: Read>(reader: &mut R) -> IoResult<BinaryExprN
     }
 
     // Deserialize left
-    let left = Box::new(deserialize_expression_node(reader)?);
+    let left = Box::new(deserialize_expression_ref(reader)?);
 
     // Deserialize operator
     let operator = deserialize_binary_operator(reader)?;
 
     // Deserialize right
-    let right = Box::new(deserialize_expression_node(reader)?);
+    let right = Box::new(deserialize_expression_ref(reader)?);
 
     Ok(BinaryExprNode {
         left,
@@ -1071,7 +1093,7 @@ fn deserialize_unary_expr_node<R: Read>(reader: &mut R) -> IoResult<UnaryExprNod
     let operator = deserialize_unary_operator(reader)?;
 
     // Deserialize operand
-    let operand = Box::new(deserialize_expression_node(reader)?);
+    let operand = Box::new(deserialize_expression_ref(reader)?);
 
     Ok(UnaryExprNode {
         operator,
@@ -1305,7 +1327,7 @@ fn deserialize_assign_action_node<R: Read>(reader: &mut R) -> IoResult<AssignAct
     let target = deserialize_identifier_node(reader)?;
 
     // Deserialize value
-    let value = deserialize_expression_node(reader)?;
+    let value = deserialize_expression_ref(reader)?;
 
     Ok(AssignActionNode {
         target,
@@ -1364,6 +1386,19 @@ fn deserialize_emit_action_node<R: Read>(reader: &mut R) -> IoResult<EmitActionN
 }
 
 fn deserialize_flow_step_node<R: Read>(reader: &mut R) -> IoResult<FlowStepNode> {
+    // Read node kind (should be FlowStep)
+    let mut kind_bytes = [0u8; 2];
+    reader.read_exact(&mut kind_bytes)?;
+    let kind = u16::from_le_bytes(kind_bytes);
+
+    if kind != 6 {
+        // NodeKind::FlowStep as u16
+        return Err(IoError::new(
+            ErrorKind::InvalidData,
+            "Expected FlowStep node",
+        ));
+    }
+
     // Read location
     let mut location_bytes = [0u8; 16]; // 4 * u32 = 16 bytes
     reader.read_exact(&mut location_bytes)?;
@@ -1414,7 +1449,7 @@ fn deserialize_flow_step_node<R: Read>(reader: &mut R) -> IoResult<FlowStepNode>
 
     // Deserialize condition (if present)
     let condition = if field_count == 3 {
-        Some(deserialize_expression_node(reader)?)
+        Some(deserialize_expression_ref(reader)?)
     } else {
         None
     };
@@ -1428,6 +1463,16 @@ fn deserialize_flow_step_node<R: Read>(reader: &mut R) -> IoResult<FlowStepNode>
 }
 
 fn deserialize_type_node<R: Read>(reader: &mut R) -> IoResult<TypeNode> {
+    // Read node kind (should be Type)
+    let mut kind_bytes = [0u8; 2];
+    reader.read_exact(&mut kind_bytes)?;
+    let kind = u16::from_le_bytes(kind_bytes);
+
+    if kind != 15 {
+        // NodeKind::Type as u16
+        return Err(IoError::new(ErrorKind::InvalidData, "Expected Type node"));
+    }
+
     // Read location
     let mut location_bytes = [0u8; 16]; // 4 * u32 = 16 bytes
     reader.read_exact(&mut location_bytes)?;
@@ -1474,6 +1519,14 @@ fn deserialize_type_node<R: Read>(reader: &mut R) -> IoResult<TypeNode> {
     let name = deserialize_identifier_node(reader)?;
 
     // Deserialize nullable
+    let mut field_kind_byte = [0u8; 1];
+    reader.read_exact(&mut field_kind_byte)?;
+
+    if field_kind_byte[0] != 4 {
+        // FieldKind::Bool as u8
+        return Err(IoError::new(ErrorKind::InvalidData, "Expected Bool field"));
+    }
+
     let mut nullable_byte = [0u8; 1];
     reader.read_exact(&mut nullable_byte)?;
     let nullable = nullable_byte[0] != 0;
@@ -1486,6 +1539,19 @@ fn deserialize_type_node<R: Read>(reader: &mut R) -> IoResult<TypeNode> {
 }
 
 fn deserialize_identifier_node<R: Read>(reader: &mut R) -> IoResult<IdentifierNode> {
+    // Read node kind (should be Identifier)
+    let mut kind_bytes = [0u8; 2];
+    reader.read_exact(&mut kind_bytes)?;
+    let kind = u16::from_le_bytes(kind_bytes);
+
+    if kind != 16 {
+        // NodeKind::Identifier as u16
+        return Err(IoError::new(
+            ErrorKind::InvalidData,
+            "Expected Identifier node",
+        ));
+    }
+
     // Read location
     let mut location_bytes = [0u8; 16]; // 4 * u32 = 16 bytes
     reader.read_exact(&mut location_bytes)?;
@@ -1678,10 +1744,18 @@ fn deserialize_string<R: Read>(reader: &mut R) -> IoResult<String> {
 
     let mut len_bytes = [0u8; 4];
     reader.read_exact(&mut len_bytes)?;
-    let len = u32::from_le_bytes(len_bytes) as usize;
+    let len = u32::from_le_bytes(len_bytes) as u64;
 
-    let mut buffer = vec![0u8; len];
-    reader.read_exact(&mut buffer)?;
+    // Read through `take` so a corrupt length fails at end of input instead
+    // of allocating the claimed size up front
+    let mut buffer = Vec::new();
+    reader.take(len).read_to_end(&mut buffer)?;
+    if buffer.len() as u64 != len {
+        return Err(IoError::new(
+            ErrorKind::UnexpectedEof,
+            "String shorter than its length",
+        ));
+    }
 
     String::from_utf8(buffer)
         .map_err(|_| IoError::new(ErrorKind::InvalidData, "Invalid UTF-8 string"))
@@ -1705,10 +1779,298 @@ fn deserialize_expression_list<R: Read>(reader: &mut R) -> IoResult<Vec<Expressi
     reader.read_exact(&mut count_bytes)?;
     let count = u32::from_le_bytes(count_bytes);
 
-    let mut expressions = Vec::with_capacity(count as usize);
+    let mut expressions = Vec::with_capacity(preallocated(count));
     for _ in 0..count {
         expressions.push(deserialize_expression_node(reader)?);
     }
 
     Ok(expressions)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize_ast;
+
+    // Small deterministic generator so every run checks the same programs
+    struct Gen(u64);
+
+    impl Gen {
+        fn next(&mut self) -> u32 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 33) as u32
+        }
+
+        fn below(&mut self, n: u32) -> u32 {
+            self.next() % n
+        }
+
+        fn location(&mut self) -> SourceLocation {
+            SourceLocation::new(
+                self.below(4),
+                self.below(500) + 1,
+                self.below(80) + 1,
+                self.below(30),
+            )
+        }
+
+        fn ident(&mut self, prefix: &str) -> IdentifierNode {
+            let text = format!("{}{}", prefix, self.below(1000));
+            IdentifierNode::new(text, self.location())
+        }
+
+        fn type_node(&mut self) -> TypeNode {
+            let name = self.ident("Type");
+            TypeNode::new(name, self.below(2) == 0, self.location())
+        }
+
+        fn literal(&mut self) -> LiteralValue {
+            match self.below(5) {
+                0 => LiteralValue::Integer(self.next() as i64 - i32::MAX as i64),
+                1 => LiteralValue::Float(self.next() as f64 / 7.0),
+                2 => LiteralValue::String(format!("s{}", self.below(100))),
+                3 => LiteralValue::Boolean(self.below(2) == 0),
+                _ => LiteralValue::Null,
+            }
+        }
+
+        fn expression(&mut self, depth: u32) -> ExpressionNode {
+            let kind = if depth == 0 {
+                2 + self.below(2)
+            } else {
+                self.below(5)
+            };
+            match kind {
+                0 => ExpressionNode::Binary(BinaryExprNode {
+                    left: Box::new(self.expression(depth - 1)),
+                    operator: match self.below(4) {
+                        0 => BinaryOperator::Add,
+                        1 => BinaryOperator::GreaterEqual,
+                        2 => BinaryOperator::And,
+                        _ => BinaryOperator::Xor,
+                    },
+                    right: Box::new(self.expression(depth - 1)),
+                    location: self.location(),
+                }),
+                1 => ExpressionNode::Unary(UnaryExprNode {
+                    operator: match self.below(3) {
+                        0 => UnaryOperator::Neg,
+                        1 => UnaryOperator::Not,
+                        _ => UnaryOperator::Pos,
+                    },
+                    operand: Box::new(self.expression(depth - 1)),
+                    location: self.location(),
+                }),
+                2 => ExpressionNode::Literal(LiteralExprNode {
+                    value: self.literal(),
+                    location: self.location(),
+                }),
+                3 => ExpressionNode::Identifier(IdentifierExprNode {
+                    name: self.ident("var"),
+                    location: self.location(),
+                }),
+                _ => {
+                    let callee = self.ident("fn");
+                    let args = (0..self.below(3))
+                        .map(|_| self.expression(depth - 1))
+                        .collect();
+                    ExpressionNode::Call(CallExprNode {
+                        callee,
+                        args,
+                        location: self.location(),
+                    })
+                }
+            }
+        }
+
+        fn entity(&mut self) -> EntityNode {
+            let name = self.ident("Entity");
+            let attributes = (0..self.below(3))
+                .map(|_| {
+                    let name = self.ident("attr");
+                    let r#type = self.type_node();
+                    let default_value = if self.below(2) == 0 {
+                        Some(self.expression(1))
+                    } else {
+                        None
+                    };
+                    AttributeNode::new(name, r#type, default_value, self.location())
+                })
+                .collect();
+            let rules = (0..self.below(3))
+                .map(|_| RuleRefNode::new(self.ident("Rule"), self.location()))
+                .collect();
+            let constraints = (0..self.below(3))
+                .map(|_| ConstraintRefNode::new(self.ident("Constraint"), self.location()))
+                .collect();
+            EntityNode::new(name, attributes, rules, constraints, self.location())
+        }
+
+        fn rule(&mut self) -> RuleNode {
+            let name = self.ident("Rule");
+            let parameters = (0..self.below(3))
+                .map(|_| ParameterNode::new(self.ident("param"), self.type_node(), self.location()))
+                .collect();
+            let condition = self.expression(2);
+            let actions = (0..self.below(4))
+                .map(|_| {
+                    if self.below(2) == 0 {
+                        let target = self.ident("target");
+                        let value = self.expression(1);
+                        ActionNode::Assign(AssignActionNode::new(target, value, self.location()))
+                    } else {
+                        ActionNode::Emit(EmitActionNode::new(self.ident("event"), self.location()))
+                    }
+                })
+                .collect();
+            RuleNode::new(name, parameters, condition, actions, self.location())
+        }
+
+        fn flow(&mut self) -> FlowNode {
+            let name = self.ident("Flow");
+            let steps = (0..self.below(4))
+                .map(|_| {
+                    let from = self.ident("State");
+                    let to = self.ident("State");
+                    let condition = if self.below(2) == 0 {
+                        Some(self.expression(1))
+                    } else {
+                        None
+                    };
+                    FlowStepNode::new(from, to, condition, self.location())
+                })
+                .collect();
+            FlowNode::new(name, steps, self.location())
+        }
+
+        fn constraint(&mut self) -> ConstraintNode {
+            let name = self.ident("Constraint");
+            let expression = self.expression(2);
+            let severity = match self.below(4) {
+                0 => SeverityLevel::Info,
+                1 => SeverityLevel::Warn,
+                2 => SeverityLevel::Error,
+                _ => SeverityLevel::Fatal,
+            };
+            ConstraintNode::new(name, expression, severity, self.location())
+        }
+
+        fn program(&mut self) -> ProgramNode {
+            let entities = (0..1 + self.below(2)).map(|_| self.entity()).collect();
+            let rules = (0..1 + self.below(2)).map(|_| self.rule()).collect();
+            let flows = (0..1 + self.below(2)).map(|_| self.flow()).collect();
+            let constraints = (0..1 + self.below(2)).map(|_| self.constraint()).collect();
+            ProgramNode::new(entities, rules, flows, constraints, self.location())
+        }
+    }
+
+    fn round_trip(program: &ProgramNode) -> IoResult<ProgramNode> {
+        let mut bytes = Vec::new();
+        serialize_ast(&mut bytes, program)?;
+        deserialize_ast(&mut bytes.as_slice())
+    }
+
+    #[test]
+    fn test_round_trip_all_definition_kinds() {
+        for seed in 0..64 {
+            let program = Gen(seed).program();
+            let decoded = round_trip(&program)
+                .unwrap_or_else(|err| panic!("seed {} failed to decode: {}", seed, err));
+            assert_eq!(decoded, program, "seed {} did not round-trip", seed);
+        }
+    }
+
+    #[test]
+    fn test_malformed_field_counts_are_invalid_data() {
+        let mut gen = Gen(7);
+        let single = |program: ProgramNode| {
+            let mut bytes = Vec::new();
+            serialize_ast(&mut bytes, &program).unwrap();
+            bytes
+        };
+        // Header (6) + program kind (2) + location (16) + field count (2), then
+        // four list headers; the three empty lists ahead of the node take 5 bytes each
+        let node_kind_offset = |lists_before: usize| 6 + 2 + 16 + 2 + 5 * lists_before + 5;
+        let cases = [
+            (
+                single(ProgramNode::new(
+                    vec![],
+                    vec![gen.rule()],
+                    vec![],
+                    vec![],
+                    gen.location(),
+                )),
+                1,
+                4,
+            ),
+            (
+                single(ProgramNode::new(
+                    vec![],
+                    vec![],
+                    vec![gen.flow()],
+                    vec![],
+                    gen.location(),
+                )),
+                2,
+                2,
+            ),
+            (
+                single(ProgramNode::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![gen.constraint()],
+                    gen.location(),
+                )),
+                3,
+                3,
+            ),
+        ];
+
+        for (bytes, lists_before, expected) in cases {
+            // Node kind (2) + location (16) puts the field count here
+            let field_count = node_kind_offset(lists_before) + 2 + 16;
+            assert_eq!(
+                bytes[field_count..field_count + 2],
+                (expected as u16).to_le_bytes()
+            );
+            assert!(deserialize_ast(&mut bytes.as_slice()).is_ok());
+            for bad in [0u16, 1, 9, u16::MAX] {
+                let mut corrupted = bytes.clone();
+                corrupted[field_count..field_count + 2].copy_from_slice(&bad.to_le_bytes());
+                let err = deserialize_ast(&mut corrupted.as_slice()).unwrap_err();
+                assert_eq!(err.kind(), ErrorKind::InvalidData);
+            }
+        }
+    }
+
+    #[test]
+    fn test_oversized_list_count_does_not_panic() {
+        let mut bytes = Vec::new();
+        serialize_ast(&mut bytes, &ProgramNode::empty()).unwrap();
+        // Claim u32::MAX entities in the first list header
+        let count = 6 + 2 + 16 + 2 + 1;
+        bytes[count..count + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(deserialize_ast(&mut bytes.as_slice()).is_err());
+
+        // Same for a string length inside an identifier
+        let program = ProgramNode::new(
+            vec![],
+            vec![],
+            vec![Gen(3).flow()],
+            vec![],
+            SourceLocation::default(),
+        );
+        let mut bytes = Vec::new();
+        serialize_ast(&mut bytes, &program).unwrap();
+        // Three list headers, the flow header (2 + 16 + 2), then the name
+        // identifier's header (2 + 16 + 2) and its StringId tag
+        let len = 6 + 2 + 16 + 2 + 5 * 3 + 20 + 20 + 1;
+        bytes[len..len + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = deserialize_ast(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
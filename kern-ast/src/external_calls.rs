@@ -0,0 +1,107 @@
+use crate::{ASTVisitor, ActionNode, ExpressionNode, ProgramNode};
+use std::collections::BTreeSet;
+
+/// ExternalCallCollector gathers the names of external functions a program references.
+///
+/// Both call expressions and emitted events are dispatched to the host, so both
+/// are collected. Names are kept in a sorted set for deterministic output.
+#[derive(Debug, Default)]
+pub struct ExternalCallCollector {
+    pub names: BTreeSet<String>,
+}
+
+impl ASTVisitor for ExternalCallCollector {
+    fn visit_action(&mut self, node: &ActionNode) {
+        match node {
+            ActionNode::Assign(assign) => {
+                self.visit_identifier(&assign.target);
+                self.visit_expression(&assign.value);
+            }
+            ActionNode::Emit(emit) => {
+                self.names.insert(emit.event.text().to_string());
+                self.visit_identifier(&emit.event);
+            }
+        }
+    }
+
+    fn visit_expression(&mut self, node: &ExpressionNode) {
+        match node {
+            ExpressionNode::Binary(binary) => {
+                self.visit_expression(&binary.left);
+                self.visit_expression(&binary.right);
+            }
+            ExpressionNode::Unary(unary) => {
+                self.visit_expression(&unary.operand);
+            }
+            ExpressionNode::Literal(_) => {}
+            ExpressionNode::Identifier(ident) => {
+                self.visit_identifier(&ident.name);
+            }
+            ExpressionNode::Call(call) => {
+                self.names.insert(call.callee.text().to_string());
+                self.visit_identifier(&call.callee);
+                for arg in &call.args {
+                    self.visit_expression(arg);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the deduplicated, sorted names of all external functions called by a program
+pub fn collect_external_calls(program: &ProgramNode) -> Vec<String> {
+    let mut collector = ExternalCallCollector::default();
+    collector.visit_program(program);
+    collector.names.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AssignActionNode, CallExprNode, EmitActionNode, IdentifierExprNode, IdentifierNode,
+        RuleNode, SourceLocation,
+    };
+
+    fn call(name: &str, args: Vec<ExpressionNode>) -> ExpressionNode {
+        ExpressionNode::Call(CallExprNode {
+            callee: IdentifierNode::new_with_default_location(name.to_string()),
+            args,
+            location: SourceLocation::default(),
+        })
+    }
+
+    #[test]
+    fn test_collect_external_calls() {
+        let farmer = ExpressionNode::Identifier(IdentifierExprNode {
+            name: IdentifierNode::new_with_default_location("farmer".to_string()),
+            location: SourceLocation::default(),
+        });
+
+        let rule = RuleNode::new(
+            IdentifierNode::new_with_default_location("ApproveFarmer".to_string()),
+            vec![],
+            call("log", vec![]),
+            vec![
+                ActionNode::Assign(AssignActionNode::new(
+                    IdentifierNode::new_with_default_location("result".to_string()),
+                    call("approve_farmer", vec![farmer]),
+                    SourceLocation::default(),
+                )),
+                ActionNode::Emit(EmitActionNode::new(
+                    IdentifierNode::new_with_default_location("log".to_string()),
+                    SourceLocation::default(),
+                )),
+            ],
+            SourceLocation::default(),
+        );
+
+        let mut program = ProgramNode::empty();
+        program.rules.push(rule);
+
+        assert_eq!(
+            collect_external_calls(&program),
+            vec!["approve_farmer".to_string(), "log".to_string()]
+        );
+    }
+}
@@ -8,6 +8,7 @@ mod constraint_node;
 mod deserializer;
 mod entity_node;
 mod expression_nodes;
+mod external_calls;
 mod flow_node;
 mod identifier_node;
 mod program_node;
@@ -21,6 +22,7 @@ pub use constraint_node::*;
 pub use deserializer::deserialize_ast;
 pub use entity_node::*;
 pub use expression_nodes::*;
+pub use external_calls::{collect_external_calls, ExternalCallCollector};
 pub use flow_node::*;
 pub use identifier_node::IdentifierNode;
 pub use program_node::ProgramNode;
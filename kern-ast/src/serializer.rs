@@ -205,7 +205,7 @@ fn serialize_attribute_node<W: Write>(writer: &mut W, node: &AttributeNode) -> I
     writer.write_all(&node.location.length.to_le_bytes())?;
 
     // Write field count
-    let field_count = 3u16; // name, type, default_value
+    let field_count = 2u16 + node.default_value.is_some() as u16; // name, type, [default_value]
     writer.write_all(&field_count.to_le_bytes())?;
 
     // Serialize name
@@ -221,8 +221,7 @@ fn serialize_attribute_node<W: Write>(writer: &mut W, node: &AttributeNode) -> I
             serialize_expression_node(writer, expr)?;
         }
         None => {
-            // In a real implementation, we would have a null node reference
-            // For now, we'll just skip
+            // Absent; the field count tells the reader to skip it
         }
     }
 
@@ -437,6 +436,7 @@ fn serialize_literal_expr_node<W: Write>(writer: &mut W, node: &LiteralExprNode)
         }
         LiteralValue::String(s) => {
             writer.write_all(&2u8.to_le_bytes())?; // String type
+            writer.write_all(&FieldKind::StringId.as_u8().to_le_bytes())?;
             let bytes = s.as_bytes();
             writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
             writer.write_all(bytes)?;
@@ -583,7 +583,7 @@ fn serialize_flow_step_node<W: Write>(writer: &mut W, node: &FlowStepNode) -> Io
     writer.write_all(&node.location.length.to_le_bytes())?;
 
     // Write field count
-    let field_count = 3u16; // from, to, condition
+    let field_count = 2u16 + node.condition.is_some() as u16; // from, to, [condition]
     writer.write_all(&field_count.to_le_bytes())?;
 
     // Serialize from
@@ -599,8 +599,7 @@ fn serialize_flow_step_node<W: Write>(writer: &mut W, node: &FlowStepNode) -> Io
             serialize_expression_node(writer, expr)?;
         }
         None => {
-            // In a real implementation, we would have a null node reference
-            // For now, we'll just skip
+            // Absent; the field count tells the reader to skip it
         }
     }
 
@@ -1,21 +1,65 @@
-use crate::{BytecodeModule, ModuleHeader, SectionOffsets, Opcode, Instruction};
+use crate::{ArithmeticMode, BytecodeModule, ModuleHeader, SectionOffsets, Opcode, Instruction};
 use crate::lir::{LirOp, LirProgram, Register};
 use crate::lir_builder::LirBuilder;
 use crate::register_allocator::LinearScanAllocator;
 use crate::emitter::BytecodeEmitter;
 use kern_ast::ProgramNode as Program;
-use kern_graph_builder::{ExecutionGraph, SpecializedNode, EdgeType};
+use kern_graph_builder::{ExecutionGraph, GraphNode, SpecializedNode, EdgeType, OP_ADD, OP_SUB, OP_MUL, OP_DIV, OP_MOD};
 use serde::Serialize;
 use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct BytecodeCompiler {
-    // Compiler state
+    /// Record a relocation table so the module can be offset by a linker
+    pub position_independent: bool,
+    /// Replace arithmetic and comparisons of literals with their result
+    pub constant_folding: bool,
+    /// Overflow behaviour folded arithmetic must match; keep it in step with
+    /// the VM's `VMConfig::arithmetic_mode`
+    pub arithmetic_mode: ArithmeticMode,
+    /// Skip graph nodes that no entry point reaches
+    pub eliminate_dead_nodes: bool,
+    eliminated_nodes: usize,
+}
+
+// What compile_graph works out about the graph once, before emitting any node
+struct EmitContext<'a> {
+    graph: &'a ExecutionGraph,
+    adj_data: HashMap<u32, Vec<u32>>,
+    adj_control: HashMap<u32, Vec<u32>>,
+    folded: HashMap<u32, i64>, // Operation nodes replaced by their constant value
 }
 
 impl BytecodeCompiler {
     pub fn new() -> Self {
-        BytecodeCompiler {}
+        BytecodeCompiler {
+            position_independent: false,
+            constant_folding: true,
+            arithmetic_mode: ArithmeticMode::Checked,
+            eliminate_dead_nodes: true,
+            eliminated_nodes: 0,
+        }
+    }
+
+    pub fn set_position_independent(&mut self, enabled: bool) {
+        self.position_independent = enabled;
+    }
+
+    pub fn set_constant_folding(&mut self, enabled: bool) {
+        self.constant_folding = enabled;
+    }
+
+    pub fn set_arithmetic_mode(&mut self, mode: ArithmeticMode) {
+        self.arithmetic_mode = mode;
+    }
+
+    pub fn set_dead_node_elimination(&mut self, enabled: bool) {
+        self.eliminate_dead_nodes = enabled;
+    }
+
+    /// Number of unreachable nodes the last `compile_graph` left out
+    pub fn eliminated_nodes(&self) -> usize {
+        self.eliminated_nodes
     }
 
     pub fn compile(&mut self, _program: &Program) -> Result<BytecodeModule, String> {
@@ -41,6 +85,7 @@ impl BytecodeCompiler {
             rule_table: Vec::new(),
             graph_table: Vec::new(),
             metadata: Vec::new(),
+            relocations: Vec::new(),
         })
     }
 
@@ -64,30 +109,146 @@ impl BytecodeCompiler {
             }
         }
         
+        let folded = if self.constant_folding {
+            self.fold_constants(graph, &adj_data)
+        } else {
+            HashMap::new()
+        };
+        let ctx = EmitContext { graph, adj_data, adj_control, folded };
+        
         // Process entry points
         for entry in &graph.entry_points {
             self.emit_node_recursive(
                 entry.node_id, 
-                graph, 
+                &ctx, 
                 &mut lir_builder, 
                 &mut node_regs, 
-                &mut visited,
-                &adj_data,
-                &adj_control
+                &mut visited
             );
         }
         
+        // Whatever the entry points didn't reach is dead, unless it is kept
+        let reachable = graph.reachable_nodes();
+        self.eliminated_nodes = 0;
+        for node in &graph.nodes {
+            if reachable.contains(&node.id()) {
+                continue;
+            }
+            if self.eliminate_dead_nodes {
+                self.eliminated_nodes += 1;
+            } else {
+                self.emit_node_recursive(
+                    node.id(),
+                    &ctx,
+                    &mut lir_builder,
+                    &mut node_regs,
+                    &mut visited
+                );
+            }
+        }
+        
+        self.compile_lir(&lir_builder.build())
+    }
+
+    /// Values of the operation nodes that can be computed at compile time,
+    /// keyed by node id. An operation folds when both of its data inputs are
+    /// literals (or folded operations) that nothing else consumes.
+    fn fold_constants(&self, graph: &ExecutionGraph, adj_data: &HashMap<u32, Vec<u32>>) -> HashMap<u32, i64> {
+        let nodes: HashMap<u32, &SpecializedNode> = graph.nodes.iter().map(|n| (n.id(), n)).collect();
+        let mut consumers: HashMap<u32, usize> = HashMap::new();
+        for edge in graph.edges.iter().filter(|e| e.edge_type == EdgeType::Data) {
+            *consumers.entry(edge.to_node).or_default() += 1;
+        }
+        
+        let mut values = HashMap::new();
+        for node in &graph.nodes {
+            self.constant_value(node.id(), &nodes, &consumers, adj_data, &mut values);
+        }
+        values
+            .into_iter()
+            .filter(|(id, _)| matches!(nodes[id], SpecializedNode::Base(_)))
+            .filter_map(|(id, value)| Some((id, value?)))
+            .collect()
+    }
+    
+    fn constant_value(
+        &self,
+        node_id: u32,
+        nodes: &HashMap<u32, &SpecializedNode>,
+        consumers: &HashMap<u32, usize>,
+        adj_data: &HashMap<u32, Vec<u32>>,
+        values: &mut HashMap<u32, Option<i64>>,
+    ) -> Option<i64> {
+        if let Some(value) = values.get(&node_id) {
+            return *value;
+        }
+        // Seeded first so a data cycle reads as not constant
+        values.insert(node_id, None);
+        
+        let value = match nodes.get(&node_id) {
+            Some(SpecializedNode::Value(val_node)) if val_node.base.opcode == 0x11 => { // LOAD_NUM
+                Some(val_node.value_num as i64)
+            },
+            Some(SpecializedNode::Base(base_node)) => {
+                match adj_data.get(&node_id).map(Vec::as_slice) {
+                    Some(&[left, right]) if consumers.get(&left) == Some(&1) && consumers.get(&right) == Some(&1) => {
+                        let left = self.constant_value(left, nodes, consumers, adj_data, values);
+                        let right = self.constant_value(right, nodes, consumers, adj_data, values);
+                        left.zip(right).and_then(|(left, right)| self.fold_operation(base_node, left, right))
+                    },
+                    _ => None,
+                }
+            },
+            _ => None,
+        };
+        values.insert(node_id, value);
+        value
+    }
+    
+    // The value the VM would compute for the node, or None where it would
+    // fail instead (overflow under Checked, division by zero)
+    fn fold_operation(&self, node: &GraphNode, left: i64, right: i64) -> Option<i64> {
+        let arithmetic = |wrapping: fn(i64, i64) -> i64, saturating: fn(i64, i64) -> i64, checked: fn(i64, i64) -> Option<i64>| {
+            match self.arithmetic_mode {
+                ArithmeticMode::Wrapping => Some(wrapping(left, right)),
+                ArithmeticMode::Saturating => Some(saturating(left, right)),
+                ArithmeticMode::Checked => checked(left, right),
+            }
+        };
+        match node.opcode {
+            0x13 => { // COMPARE, comparator in the flags
+                let result = match node.flags {
+                    0 => left == right,
+                    1 => left != right,
+                    2 => left > right,
+                    3 => left < right,
+                    4 => left >= right,
+                    5 => left <= right,
+                    _ => return None,
+                };
+                Some(result as i64)
+            },
+            OP_ADD => arithmetic(i64::wrapping_add, i64::saturating_add, i64::checked_add),
+            OP_SUB => arithmetic(i64::wrapping_sub, i64::saturating_sub, i64::checked_sub),
+            OP_MUL => arithmetic(i64::wrapping_mul, i64::saturating_mul, i64::checked_mul),
+            OP_DIV => left.checked_div(right),
+            OP_MOD => left.checked_rem(right),
+            _ => None,
+        }
+    }
+
+    /// Allocates registers for a LIR program and emits it as a module
+    pub fn compile_lir(&mut self, lir_program: &LirProgram) -> BytecodeModule {
         // Allocate registers
-        let lir_program = lir_builder.build();
         let mut allocator = LinearScanAllocator::new();
-        let allocation = allocator.allocate(&lir_program);
+        let allocation = allocator.allocate(lir_program);
         
         // Emit bytecode
         let mut emitter = BytecodeEmitter::new();
         let instructions = emitter.emit_from_lir(&lir_program.instructions, &allocation);
         
         // Construct module
-        BytecodeModule {
+        let mut module = BytecodeModule {
             header: ModuleHeader {
                 magic: *b"KERN",
                 version: 1,
@@ -103,24 +264,30 @@ impl BytecodeCompiler {
                 checksum: 0,
             },
             instruction_stream: instructions,
-            constant_pool: Vec::new(), // TODO: Extract constants
-            symbol_table: Vec::new(),
-            rule_table: Vec::new(),
+            constant_pool: std::mem::take(&mut emitter.constant_pool),
+            symbol_table: std::mem::take(&mut emitter.symbol_table),
+            rule_table: std::mem::take(&mut emitter.rule_table),
             graph_table: Vec::new(),
             metadata: Vec::new(),
-        }
+            relocations: if self.position_independent {
+                std::mem::take(&mut emitter.relocations)
+            } else {
+                Vec::new()
+            },
+        };
+        module.canonicalize_constants();
+        module
     }
     
     fn emit_node_recursive(
         &self,
         node_id: u32,
-        graph: &ExecutionGraph,
+        ctx: &EmitContext,
         builder: &mut LirBuilder,
         node_regs: &mut HashMap<u32, Register>,
         visited: &mut HashSet<u32>,
-        adj_data: &HashMap<u32, Vec<u32>>,
-        adj_control: &HashMap<u32, Vec<u32>>,
     ) -> Option<Register> {
+        let EmitContext { graph, adj_data, adj_control, folded } = ctx;
         if visited.contains(&node_id) {
             return node_regs.get(&node_id).cloned();
         }
@@ -134,11 +301,12 @@ impl BytecodeCompiler {
         let node = node.unwrap();
         let base_node = node.base();
         
-        // Process data dependencies first (children)
+        // Process data dependencies first (children). A folded node stands in
+        // for its whole operand subtree, so it has none to emit.
         let mut input_regs = Vec::new();
-        if let Some(children) = adj_data.get(&node_id) {
+        if let Some(children) = adj_data.get(&node_id).filter(|_| !folded.contains_key(&node_id)) {
             for child_id in children {
-                if let Some(reg) = self.emit_node_recursive(*child_id, graph, builder, node_regs, visited, adj_data, adj_control) {
+                if let Some(reg) = self.emit_node_recursive(*child_id, ctx, builder, node_regs, visited) {
                     input_regs.push(reg);
                 }
             }
@@ -146,6 +314,7 @@ impl BytecodeCompiler {
         
         // Emit instruction for this node
         let output_reg = match node {
+             _ if folded.contains_key(&node_id) => Some(builder.load_num(folded[&node_id])),
              SpecializedNode::Value(val_node) => {
                  match base_node.opcode {
                      0x10 => { // LOAD_SYM
@@ -185,21 +354,29 @@ impl BytecodeCompiler {
              _ => match base_node.opcode {
                  0x13 => { // COMPARE
                      if input_regs.len() >= 2 {
-                         // Assume Eq for now
-                         let reg = builder.program.alloc_register();
-                         builder.program.add_instruction(crate::lir::LirInstruction {
-                             op: LirOp::CmpEq(input_regs[0], input_regs[1]),
-                             dst: Some(reg),
-                             src1: Some(input_regs[0]),
-                             src2: Some(input_regs[1]),
-                             immediate: None,
-                             label: None,
-                         });
-                         Some(reg)
+                         let (left, right) = (input_regs[0], input_regs[1]);
+                         match base_node.flags {
+                             1 => Some(builder.cmp_ne(left, right)),
+                             2 => Some(builder.cmp_gt(left, right)),
+                             3 => Some(builder.cmp_lt(left, right)),
+                             4 => Some(builder.cmp_ge(left, right)),
+                             5 => Some(builder.cmp_le(left, right)),
+                             _ => Some(builder.cmp_eq(left, right)),
+                         }
                      } else {
                          None
                      }
                  },
+                 op @ (OP_ADD | OP_SUB | OP_MUL | OP_DIV | OP_MOD) if input_regs.len() >= 2 => {
+                     let (left, right) = (input_regs[0], input_regs[1]);
+                     match op {
+                         OP_ADD => Some(builder.add(left, right)),
+                         OP_SUB => Some(builder.sub(left, right)),
+                         OP_MUL => Some(builder.mul(left, right)),
+                         OP_DIV => Some(builder.div(left, right)),
+                         _ => Some(builder.mod_op(left, right)),
+                     }
+                 },
                  0x31 => { // RULE_EVAL
                      // Just a placeholder for rule entry
                      None
@@ -215,7 +392,7 @@ impl BytecodeCompiler {
         // Process control flow (next blocks)
         if let Some(next_nodes) = adj_control.get(&node_id) {
             for next_id in next_nodes {
-                self.emit_node_recursive(*next_id, graph, builder, node_regs, visited, adj_data, adj_control);
+                self.emit_node_recursive(*next_id, ctx, builder, node_regs, visited);
             }
         }
         
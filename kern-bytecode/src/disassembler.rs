@@ -0,0 +1,180 @@
+//! Bytecode Disassembler
+//!
+//! Renders instructions as text using the mnemonics defined on `Opcode`, so
+//! tools printing bytecode agree with what the compiler and VM use.
+
+use crate::{Instruction, Opcode};
+use std::collections::{BTreeSet, HashMap};
+
+/// Disassembles a single instruction as `MNEMONIC R<arg1>, R<arg2>, R<arg3>`.
+/// Opcodes outside the instruction set print as `UNKNOWN(0xNN)`.
+pub fn disassemble_one(instruction: &Instruction) -> String {
+    let mnemonic = match Opcode::from_byte(instruction.opcode) {
+        Some(opcode) => opcode.mnemonic().to_string(),
+        None => format!("UNKNOWN(0x{:02X})", instruction.opcode),
+    };
+    format!(
+        "{} R{}, R{}, R{}",
+        mnemonic, instruction.arg1, instruction.arg2, instruction.arg3
+    )
+}
+
+/// Disassembles a program, one line per instruction prefixed with its index.
+/// An EXT prefix is shown on one line with the instruction it extends, as
+/// `EXT <MNEMONIC> R<arg1>, <64-bit operand>`.
+pub fn disassemble(instructions: &[Instruction]) -> Vec<String> {
+    let mut lines = Vec::with_capacity(instructions.len());
+    let mut i = 0;
+    while i < instructions.len() {
+        let instruction = &instructions[i];
+        if instruction.opcode == Opcode::Ext as u8 {
+            if let Some(next) = instructions.get(i + 1) {
+                lines.push(format!(
+                    "{:04}: {}",
+                    i,
+                    disassemble_extended(instruction, next)
+                ));
+                i += 2;
+                continue;
+            }
+        }
+        lines.push(format!("{:04}: {}", i, disassemble_one(instruction)));
+        i += 1;
+    }
+    lines
+}
+
+/// Instruction count per opcode byte, most frequent first. Equal counts are
+/// ordered by opcode so the listing is stable.
+pub fn opcode_histogram(instructions: &[Instruction]) -> Vec<(u8, usize)> {
+    let mut counts: HashMap<u8, usize> = HashMap::new();
+    for instruction in instructions {
+        *counts.entry(instruction.opcode).or_insert(0) += 1;
+    }
+    let mut histogram: Vec<(u8, usize)> = counts.into_iter().collect();
+    histogram.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    histogram
+}
+
+/// Whether the instruction is a JMP or JMP_IF, both of which take their
+/// target from arg1
+pub fn is_jump(instruction: &Instruction) -> bool {
+    instruction.opcode == Opcode::Jmp as u8 || instruction.opcode == Opcode::JmpIf as u8
+}
+
+/// Distinct instruction indices the program's jumps can land on, ascending
+pub fn branch_targets(instructions: &[Instruction]) -> Vec<u32> {
+    let targets: BTreeSet<u32> = instructions
+        .iter()
+        .filter(|i| is_jump(i))
+        .map(|i| i.arg1 as u32)
+        .collect();
+    targets.into_iter().collect()
+}
+
+fn disassemble_extended(prefix: &Instruction, instruction: &Instruction) -> String {
+    let wide = Instruction::wide_operand(prefix.ext_payload(), instruction.arg2);
+    let mnemonic = Opcode::from_byte(instruction.opcode).map_or("UNKNOWN", Opcode::mnemonic);
+    format!("EXT {} R{}, 0x{:016X}", mnemonic, instruction.arg1, wide)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mnemonic_for_each_opcode() {
+        let expected = [
+            (0x00, "NOP"),
+            (0x01, "JMP"),
+            (0x02, "JMP_IF"),
+            (0x03, "HALT"),
+            (0x04, "EXT"),
+            (0x05, "LOOP_BEGIN"),
+            (0x06, "LOOP_END"),
+            (0x10, "LOAD_SYM"),
+            (0x11, "LOAD_NUM"),
+            (0x12, "LOAD_BOOL"),
+            (0x13, "MOVE"),
+            (0x14, "COMPARE"),
+            (0x15, "LOAD_CONST"),
+            (0x16, "READ_STEPS"),
+            (0x20, "ADD"),
+            (0x21, "SUB"),
+            (0x22, "MUL"),
+            (0x23, "DIV"),
+            (0x24, "MOD"),
+            (0x30, "AND"),
+            (0x31, "OR"),
+            (0x32, "NOT"),
+            (0x40, "CREATE_NODE"),
+            (0x41, "CONNECT"),
+            (0x42, "MERGE"),
+            (0x43, "DELETE_NODE"),
+            (0x50, "CALL_RULE"),
+            (0x51, "RETURN_RULE"),
+            (0x52, "CHECK_CONDITION"),
+            (0x53, "INCREMENT_EXEC_COUNT"),
+            (0x60, "PUSH_CTX"),
+            (0x61, "POP_CTX"),
+            (0x62, "SET_SYMBOL"),
+            (0x63, "GET_SYMBOL"),
+            (0x64, "COPY_CTX"),
+            (0x70, "THROW"),
+            (0x71, "TRY"),
+            (0x72, "CATCH"),
+            (0x73, "CLEAR_ERR"),
+            (0x80, "CALL_EXTERN"),
+            (0x81, "READ_IO"),
+            (0x82, "WRITE_IO"),
+        ];
+        assert_eq!(expected.len(), Opcode::ALL.len());
+        for (byte, mnemonic) in expected {
+            let line = disassemble_one(&Instruction::new(byte, 1, 2, 3, 0));
+            assert_eq!(line, format!("{} R1, R2, R3", mnemonic));
+        }
+        assert_eq!(
+            disassemble_one(&Instruction::new(0xEE, 0, 0, 0, 0)),
+            "UNKNOWN(0xEE) R0, R0, R0"
+        );
+    }
+
+    #[test]
+    fn test_disassemble_folds_ext_prefix() {
+        let wide: u64 = 0x0123_4567_89AB_CDEF;
+        let program = vec![
+            Instruction::ext_prefix(wide),
+            Instruction::new(Opcode::LoadNum as u8, 2, wide as u16, 0, 0),
+            Instruction::new(Opcode::Halt as u8, 0, 0, 0, 0),
+        ];
+        assert_eq!(
+            disassemble(&program),
+            vec![
+                "0000: EXT LOAD_NUM R2, 0x0123456789ABCDEF".to_string(),
+                "0002: HALT R0, R0, R0".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_histogram_and_branch_targets() {
+        let program = vec![
+            Instruction::new(Opcode::Jmp as u8, 3, 0, 0, 0),
+            Instruction::new(Opcode::LoadNum as u8, 1, 5, 0, 0),
+            Instruction::new(Opcode::JmpIf as u8, 3, 0, 0, 0),
+            Instruction::new(Opcode::JmpIf as u8, 1, 0, 0, 0),
+            Instruction::new(Opcode::Halt as u8, 0, 0, 0, 0),
+        ];
+        assert_eq!(
+            opcode_histogram(&program),
+            vec![
+                (Opcode::JmpIf as u8, 2),
+                (Opcode::Jmp as u8, 1),
+                (Opcode::Halt as u8, 1),
+                (Opcode::LoadNum as u8, 1),
+            ]
+        );
+        assert_eq!(program.iter().filter(|i| is_jump(i)).count(), 3);
+        assert_eq!(branch_targets(&program), vec![1, 3]);
+    }
+}
@@ -3,8 +3,12 @@
 //! This module converts LIR instructions to KERN bytecode instructions.
 
 use crate::lir::{LirInstruction, LirOp, Register};
+use crate::operand_utils::{encode_compare, encode_move};
 use crate::register_allocator::{PhysicalRegister, RegisterAllocation};
-use crate::{Instruction, Opcode};
+use crate::{Constant, Instruction, Opcode, Relocation, RuleEntry, Symbol, CALL_RULE_UNRESOLVED};
+
+/// Largest numeric literal that fits the inline immediate field of LOAD_NUM
+pub const MAX_INLINE_IMMEDIATE: i64 = u16::MAX as i64;
 
 /// Bytecode emitter that converts LIR to bytecode
 pub struct BytecodeEmitter {
@@ -16,6 +20,14 @@ pub struct BytecodeEmitter {
     pub pending_jumps: Vec<(usize, u32)>, // (instruction_index, label)
     /// Label to instruction index mapping
     pub label_map: std::collections::HashMap<u32, u32>,
+    /// Constants referenced by LOAD_CONST instructions
+    pub constant_pool: Vec<Constant>,
+    /// Jump operands patched with absolute targets
+    pub relocations: Vec<Relocation>,
+    /// Names referenced by CALL_RULE instructions
+    pub symbol_table: Vec<Symbol>,
+    /// Rules defined by the emitted code
+    pub rule_table: Vec<RuleEntry>,
 }
 
 impl BytecodeEmitter {
@@ -25,9 +37,32 @@ impl BytecodeEmitter {
             current_pc: 0,
             pending_jumps: Vec::new(),
             label_map: std::collections::HashMap::new(),
+            constant_pool: Vec::new(),
+            relocations: Vec::new(),
+            symbol_table: Vec::new(),
+            rule_table: Vec::new(),
         }
     }
 
+    /// Add a constant to the pool, reusing an existing identical entry
+    pub fn intern_constant(&mut self, constant: Constant) -> u16 {
+        if let Some(idx) = self.constant_pool.iter().position(|c| *c == constant) {
+            return idx as u16;
+        }
+        self.constant_pool.push(constant);
+        (self.constant_pool.len() - 1) as u16
+    }
+
+    /// Add a name to the symbol table, reusing an existing entry
+    pub fn intern_symbol(&mut self, name: &str) -> u16 {
+        if let Some(symbol) = self.symbol_table.iter().find(|s| s.name == name) {
+            return symbol.id as u16;
+        }
+        let id = self.symbol_table.len() as u32;
+        self.symbol_table.push(Symbol { id, name: name.to_string() });
+        id as u16
+    }
+
     /// Emit bytecode from LIR instructions with register allocation
     pub fn emit_from_lir(&mut self, lir_instructions: &[LirInstruction], allocation: &RegisterAllocation) -> Vec<Instruction> {
         // First pass: emit instructions and record label positions
@@ -56,10 +91,25 @@ impl BytecodeEmitter {
                     let instr = &mut self.instructions[*instr_idx];
                     // For jumps, the target is stored in arg1
                     instr.arg1 = target_pc as u16;
+                    self.relocations.push(Relocation::new(*instr_idx as u32, 1));
                 }
             }
         }
 
+        // Third pass: point calls to rules defined in this program at their entry.
+        // Calls to other rules stay unresolved for the linker.
+        for (idx, instr) in self.instructions.iter_mut().enumerate() {
+            if instr.opcode != Opcode::CallRule as u8 || instr.flags & CALL_RULE_UNRESOLVED == 0 {
+                continue;
+            }
+            let name = &self.symbol_table[instr.arg2 as usize].name;
+            if let Some(rule) = self.rule_table.iter().find(|r| &r.name == name) {
+                instr.arg1 = rule.entry_pc as u16;
+                instr.flags &= !CALL_RULE_UNRESOLVED;
+                self.relocations.push(Relocation::new(idx as u32, 1));
+            }
+        }
+
         std::mem::take(&mut self.instructions)
     }
 
@@ -98,15 +148,21 @@ impl BytecodeEmitter {
             // Data & Symbol Operations
             LirOp::LoadSym(symbol) => {
                 let dst_reg = self.get_physical_reg(lir_instr.dst.unwrap(), allocation);
-                // For now, we'll use a simple encoding where symbol names are stored in constants
-                // In a real implementation, we'd have a symbol table
-                instructions.push(Instruction::new(Opcode::LoadSym as u8, dst_reg as u16, 0, 0, 0));
+                // The register receives the constant pool index of the symbol's text;
+                // LOAD_SYM carries it in arg1 (low) / arg2 (high) and the destination in arg3
+                let const_idx = self.intern_constant(Constant::Sym(symbol.clone()));
+                instructions.push(Instruction::new(Opcode::LoadSym as u8, const_idx, 0, dst_reg as u16, 0));
             },
             
             LirOp::LoadNum(value) => {
                 let dst_reg = self.get_physical_reg(lir_instr.dst.unwrap(), allocation);
-                // In a real implementation, we'd store the value in the constant pool
-                instructions.push(Instruction::new(Opcode::LoadNum as u8, dst_reg as u16, (*value as u16), 0, 0));
+                if (0..=MAX_INLINE_IMMEDIATE).contains(value) {
+                    instructions.push(Instruction::new(Opcode::LoadNum as u8, dst_reg as u16, *value as u16, 0, 0));
+                } else {
+                    // Literals that don't fit the immediate field are loaded from the constant pool
+                    let const_idx = self.intern_constant(Constant::Num(*value));
+                    instructions.push(Instruction::new(Opcode::LoadConst as u8, dst_reg as u16, const_idx, 0, 0));
+                }
             },
             
             LirOp::LoadBool(value) => {
@@ -118,49 +174,49 @@ impl BytecodeEmitter {
             LirOp::Move(src, dst) => {
                 let src_reg = self.get_physical_reg(*src, allocation);
                 let dst_reg = self.get_physical_reg(*dst, allocation);
-                instructions.push(Instruction::new(Opcode::Move as u8, src_reg as u16, dst_reg as u16, 0, 0));
+                instructions.push(Instruction::from_operand(Opcode::Move as u8, encode_move(src_reg as u16, dst_reg as u16), 0));
             },
             
             LirOp::CmpEq(left, right) => {
                 let dst_reg = self.get_physical_reg(lir_instr.dst.unwrap(), allocation);
                 let left_reg = self.get_physical_reg(*left, allocation);
                 let right_reg = self.get_physical_reg(*right, allocation);
-                instructions.push(Instruction::new(Opcode::Compare as u8, left_reg as u16, right_reg as u16, dst_reg as u16, 0x00)); // EQ flag
+                instructions.push(Instruction::from_operand(Opcode::Compare as u8, encode_compare(left_reg as u16, right_reg as u16, dst_reg as u16), 0x00)); // EQ flag
             },
             
             LirOp::CmpNe(left, right) => {
                 let dst_reg = self.get_physical_reg(lir_instr.dst.unwrap(), allocation);
                 let left_reg = self.get_physical_reg(*left, allocation);
                 let right_reg = self.get_physical_reg(*right, allocation);
-                instructions.push(Instruction::new(Opcode::Compare as u8, left_reg as u16, right_reg as u16, dst_reg as u16, 0x01)); // NE flag
+                instructions.push(Instruction::from_operand(Opcode::Compare as u8, encode_compare(left_reg as u16, right_reg as u16, dst_reg as u16), 0x01)); // NE flag
             },
             
             LirOp::CmpLt(left, right) => {
                 let dst_reg = self.get_physical_reg(lir_instr.dst.unwrap(), allocation);
                 let left_reg = self.get_physical_reg(*left, allocation);
                 let right_reg = self.get_physical_reg(*right, allocation);
-                instructions.push(Instruction::new(Opcode::Compare as u8, left_reg as u16, right_reg as u16, dst_reg as u16, 0x03)); // LT flag
+                instructions.push(Instruction::from_operand(Opcode::Compare as u8, encode_compare(left_reg as u16, right_reg as u16, dst_reg as u16), 0x03)); // LT flag
             },
             
             LirOp::CmpLe(left, right) => {
                 let dst_reg = self.get_physical_reg(lir_instr.dst.unwrap(), allocation);
                 let left_reg = self.get_physical_reg(*left, allocation);
                 let right_reg = self.get_physical_reg(*right, allocation);
-                instructions.push(Instruction::new(Opcode::Compare as u8, left_reg as u16, right_reg as u16, dst_reg as u16, 0x05)); // LE flag
+                instructions.push(Instruction::from_operand(Opcode::Compare as u8, encode_compare(left_reg as u16, right_reg as u16, dst_reg as u16), 0x05)); // LE flag
             },
             
             LirOp::CmpGt(left, right) => {
                 let dst_reg = self.get_physical_reg(lir_instr.dst.unwrap(), allocation);
                 let left_reg = self.get_physical_reg(*left, allocation);
                 let right_reg = self.get_physical_reg(*right, allocation);
-                instructions.push(Instruction::new(Opcode::Compare as u8, left_reg as u16, right_reg as u16, dst_reg as u16, 0x02)); // GT flag
+                instructions.push(Instruction::from_operand(Opcode::Compare as u8, encode_compare(left_reg as u16, right_reg as u16, dst_reg as u16), 0x02)); // GT flag
             },
             
             LirOp::CmpGe(left, right) => {
                 let dst_reg = self.get_physical_reg(lir_instr.dst.unwrap(), allocation);
                 let left_reg = self.get_physical_reg(*left, allocation);
                 let right_reg = self.get_physical_reg(*right, allocation);
-                instructions.push(Instruction::new(Opcode::Compare as u8, left_reg as u16, right_reg as u16, dst_reg as u16, 0x04)); // GE flag
+                instructions.push(Instruction::from_operand(Opcode::Compare as u8, encode_compare(left_reg as u16, right_reg as u16, dst_reg as u16), 0x04)); // GE flag
             },
             
             // Arithmetic Operations
@@ -253,7 +309,14 @@ impl BytecodeEmitter {
             
             // Rule Execution Operations
             LirOp::RuleEntry(label, name) => {
-                // Rule entry points are handled specially
+                // The placeholder NOP is the rule's entry point
+                let entry_pc = self.instructions.len() as u32;
+                self.label_map.insert(*label, entry_pc);
+                self.rule_table.push(RuleEntry {
+                    id: self.rule_table.len() as u32,
+                    entry_pc,
+                    name: name.clone(),
+                });
                 instructions.push(Instruction::new(Opcode::Nop as u8, 0, 0, 0, 0)); // Placeholder
             },
             
@@ -268,8 +331,9 @@ impl BytecodeEmitter {
             },
             
             LirOp::CallRule(name) => {
-                // In a real implementation, we'd store the rule name in the constant pool
-                instructions.push(Instruction::new(Opcode::CallRule as u8, 0, 0, 0, 0));
+                // arg1 = target (resolved after emission), arg2 = symbol naming the rule
+                let symbol = self.intern_symbol(name);
+                instructions.push(Instruction::new(Opcode::CallRule as u8, 0, symbol, 0, CALL_RULE_UNRESOLVED));
             },
             
             LirOp::ReturnRule => {
@@ -320,13 +384,13 @@ impl BytecodeEmitter {
                     let src_phys = self.get_physical_reg(*arg_reg, allocation);
                     let dst_phys = i as u8;
                     if src_phys != dst_phys {
-                        instructions.push(Instruction::new(Opcode::Move as u8, src_phys as u16, dst_phys as u16, 0, 0));
+                        instructions.push(Instruction::from_operand(Opcode::Move as u8, encode_move(src_phys as u16, dst_phys as u16), 0));
                     }
                 }
                 
-                // In a real implementation, we'd store the function name in the constant pool
-                // For now, using ID 0 for all external calls as per current limitation
-                instructions.push(Instruction::new(Opcode::CallExtern as u8, 0, 0, 0, 0));
+                // The VM names the call after this symbol for sandbox checks
+                let fn_id = self.intern_symbol(func_name);
+                instructions.push(Instruction::new(Opcode::CallExtern as u8, fn_id, 0, 0, 0));
             },
             
             LirOp::ReadIo(io_name) => {
@@ -400,6 +464,25 @@ mod tests {
         assert_eq!(bytecode[2].opcode, Opcode::Add as u8);
     }
 
+    #[test]
+    fn test_oversized_literal_uses_constant_pool() {
+        let mut builder = LirBuilder::new();
+        builder.load_num(5_000_000_000);
+        builder.load_num(-1);
+        builder.load_num(5_000_000_000);
+
+        let lir_program = builder.build();
+        let mut allocator = LinearScanAllocator::new();
+        let allocation = allocator.allocate(&lir_program);
+
+        let mut emitter = BytecodeEmitter::new();
+        let bytecode = emitter.emit_from_lir(&lir_program.instructions, &allocation);
+
+        assert!(bytecode.iter().all(|i| i.opcode == Opcode::LoadConst as u8));
+        assert_eq!(bytecode[0].arg2, bytecode[2].arg2);
+        assert_eq!(emitter.constant_pool, vec![Constant::Num(5_000_000_000), Constant::Num(-1)]);
+    }
+
     #[test]
     fn test_bytecode_emitter_with_control_flow() {
         let mut builder = LirBuilder::new();
@@ -430,4 +513,26 @@ mod tests {
         // Should have generated several bytecode instructions
         assert!(bytecode.len() >= 6);
     }
+
+    #[test]
+    fn test_extern_calls_name_their_symbol() {
+        let mut builder = LirBuilder::new();
+        builder.call("approve_farmer", vec![]);
+        builder.call("log_event", vec![]);
+        builder.call("approve_farmer", vec![]);
+
+        let lir_program = builder.build();
+        let mut allocator = LinearScanAllocator::new();
+        let allocation = allocator.allocate(&lir_program);
+
+        let mut emitter = BytecodeEmitter::new();
+        let bytecode = emitter.emit_from_lir(&lir_program.instructions, &allocation);
+
+        let names: Vec<&str> = bytecode
+            .iter()
+            .filter(|i| i.opcode == Opcode::CallExtern as u8)
+            .map(|i| emitter.symbol_table[i.arg1 as usize].name.as_str())
+            .collect();
+        assert_eq!(names, vec!["approve_farmer", "log_event", "approve_farmer"]);
+    }
 }
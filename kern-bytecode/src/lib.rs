@@ -12,8 +12,15 @@ pub mod optimizer;
 pub mod verifier;
 pub mod serializer;
 pub mod compiler_driver;
+pub mod sha256;
+pub mod crc32;
+pub mod linker;
+pub mod disassembler;
+pub mod operand_utils;
 
 pub use compiler_driver::BytecodeCompiler;
+pub use linker::{link, LinkError};
+pub use disassembler::{branch_targets, disassemble, disassemble_one, is_jump, opcode_histogram};
 
 // Define the KERN bytecode instruction format
 // Each instruction is 8 bytes: OPCODE (1B) | ARG1 (2B) | ARG2 (2B) | ARG3 (2B) | FLAGS (1B)
@@ -37,6 +44,34 @@ impl Instruction {
         }
     }
 
+    /// Builds an instruction from a packed 48-bit operand (arg1 | arg2 << 16 | arg3 << 32).
+    /// Bits above 48 are ignored.
+    pub fn from_operand(opcode: u8, operand: u64, flags: u8) -> Self {
+        Instruction::new(opcode, operand as u16, (operand >> 16) as u16, (operand >> 32) as u16, flags)
+    }
+
+    /// The three arguments packed into a single 48-bit operand
+    pub fn operand(&self) -> u64 {
+        (self.arg1 as u64) | ((self.arg2 as u64) << 16) | ((self.arg3 as u64) << 32)
+    }
+
+    /// Builds an EXT prefix carrying the upper 48 bits of a 64-bit operand.
+    /// The following instruction supplies the low 16 bits in its arg2.
+    pub fn ext_prefix(wide: u64) -> Self {
+        let high = wide >> 16;
+        Instruction::new(Opcode::Ext as u8, high as u16, (high >> 16) as u16, (high >> 32) as u16, 0)
+    }
+
+    /// The 48-bit payload of an EXT prefix (arg1 | arg2 << 16 | arg3 << 32)
+    pub fn ext_payload(&self) -> u64 {
+        self.operand()
+    }
+
+    /// Combines an EXT payload with the low 16 bits of the prefixed instruction's operand
+    pub fn wide_operand(ext_payload: u64, low: u16) -> u64 {
+        (ext_payload << 16) | low as u64
+    }
+
     // Serialize the instruction to bytes (8 bytes total)
     pub fn to_bytes(&self) -> [u8; 8] {
         [
@@ -75,6 +110,9 @@ pub enum Opcode {
     Jmp = 0x01,   // Jump unconditionally to address
     JmpIf = 0x02, // Jump if condition is true
     Halt = 0x03,  // Stop execution
+    Ext = 0x04,   // Prefix: extra 48 operand bits for the following instruction
+    LoopBegin = 0x05, // Open a counted loop whose body starts at the next instruction
+    LoopEnd = 0x06,   // Count an iteration and jump back to the body until exhausted
 
     // Data & Symbol Instructions
     LoadSym = 0x10, // Load symbol value into register
@@ -82,6 +120,8 @@ pub enum Opcode {
     LoadBool = 0x12, // Load boolean literal into register
     Move = 0x13,    // Move value between registers
     Compare = 0x14, // Compare values, result in register
+    LoadConst = 0x15, // Load constant pool entry into register
+    ReadSteps = 0x16, // Read the VM's executed step count into register
 
     // Arithmetic Instructions
     Add = 0x20,     // Add two registers
@@ -126,48 +166,110 @@ pub enum Opcode {
     WriteIo = 0x82,    // Write value to external output
 }
 
+impl Opcode {
+    /// Every defined opcode, in encoding order
+    pub const ALL: [Opcode; 42] = [
+        Opcode::Nop,
+        Opcode::Jmp,
+        Opcode::JmpIf,
+        Opcode::Halt,
+        Opcode::Ext,
+        Opcode::LoopBegin,
+        Opcode::LoopEnd,
+        Opcode::LoadSym,
+        Opcode::LoadNum,
+        Opcode::LoadBool,
+        Opcode::Move,
+        Opcode::Compare,
+        Opcode::LoadConst,
+        Opcode::ReadSteps,
+        Opcode::Add,
+        Opcode::Sub,
+        Opcode::Mul,
+        Opcode::Div,
+        Opcode::Mod,
+        Opcode::And,
+        Opcode::Or,
+        Opcode::Not,
+        Opcode::CreateNode,
+        Opcode::Connect,
+        Opcode::Merge,
+        Opcode::DeleteNode,
+        Opcode::CallRule,
+        Opcode::ReturnRule,
+        Opcode::CheckCondition,
+        Opcode::IncrementExecCount,
+        Opcode::PushCtx,
+        Opcode::PopCtx,
+        Opcode::SetSymbol,
+        Opcode::GetSymbol,
+        Opcode::CopyCtx,
+        Opcode::Throw,
+        Opcode::Try,
+        Opcode::Catch,
+        Opcode::ClearErr,
+        Opcode::CallExtern,
+        Opcode::ReadIo,
+        Opcode::WriteIo,
+    ];
+
+    /// The opcode encoded as `byte`, or `None` if no opcode uses it
+    pub fn from_byte(byte: u8) -> Option<Opcode> {
+        Opcode::ALL.into_iter().find(|opcode| *opcode as u8 == byte)
+    }
+
+    /// Assembly mnemonic, as printed by the disassembler
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Opcode::Nop => "NOP",
+            Opcode::Jmp => "JMP",
+            Opcode::JmpIf => "JMP_IF",
+            Opcode::Halt => "HALT",
+            Opcode::Ext => "EXT",
+            Opcode::LoopBegin => "LOOP_BEGIN",
+            Opcode::LoopEnd => "LOOP_END",
+            Opcode::LoadSym => "LOAD_SYM",
+            Opcode::LoadNum => "LOAD_NUM",
+            Opcode::LoadBool => "LOAD_BOOL",
+            Opcode::Move => "MOVE",
+            Opcode::Compare => "COMPARE",
+            Opcode::LoadConst => "LOAD_CONST",
+            Opcode::ReadSteps => "READ_STEPS",
+            Opcode::Add => "ADD",
+            Opcode::Sub => "SUB",
+            Opcode::Mul => "MUL",
+            Opcode::Div => "DIV",
+            Opcode::Mod => "MOD",
+            Opcode::And => "AND",
+            Opcode::Or => "OR",
+            Opcode::Not => "NOT",
+            Opcode::CreateNode => "CREATE_NODE",
+            Opcode::Connect => "CONNECT",
+            Opcode::Merge => "MERGE",
+            Opcode::DeleteNode => "DELETE_NODE",
+            Opcode::CallRule => "CALL_RULE",
+            Opcode::ReturnRule => "RETURN_RULE",
+            Opcode::CheckCondition => "CHECK_CONDITION",
+            Opcode::IncrementExecCount => "INCREMENT_EXEC_COUNT",
+            Opcode::PushCtx => "PUSH_CTX",
+            Opcode::PopCtx => "POP_CTX",
+            Opcode::SetSymbol => "SET_SYMBOL",
+            Opcode::GetSymbol => "GET_SYMBOL",
+            Opcode::CopyCtx => "COPY_CTX",
+            Opcode::Throw => "THROW",
+            Opcode::Try => "TRY",
+            Opcode::Catch => "CATCH",
+            Opcode::ClearErr => "CLEAR_ERR",
+            Opcode::CallExtern => "CALL_EXTERN",
+            Opcode::ReadIo => "READ_IO",
+            Opcode::WriteIo => "WRITE_IO",
+        }
+    }
+}
+
 impl From<u8> for Opcode {
     fn from(opcode: u8) -> Self {
-        match opcode {
-            0x00 => Opcode::Nop,
-            0x01 => Opcode::Jmp,
-            0x02 => Opcode::JmpIf,
-            0x03 => Opcode::Halt,
-            0x10 => Opcode::LoadSym,
-            0x11 => Opcode::LoadNum,
-            0x12 => Opcode::LoadBool,
-            0x13 => Opcode::Move,
-            0x14 => Opcode::Compare,
-            0x20 => Opcode::Add,
-            0x21 => Opcode::Sub,
-            0x22 => Opcode::Mul,
-            0x23 => Opcode::Div,
-            0x24 => Opcode::Mod,
-            0x30 => Opcode::And,
-            0x31 => Opcode::Or,
-            0x32 => Opcode::Not,
-            0x40 => Opcode::CreateNode,
-            0x41 => Opcode::Connect,
-            0x42 => Opcode::Merge,
-            0x43 => Opcode::DeleteNode,
-            0x50 => Opcode::CallRule,
-            0x51 => Opcode::ReturnRule,
-            0x52 => Opcode::CheckCondition,
-            0x53 => Opcode::IncrementExecCount,
-            0x60 => Opcode::PushCtx,
-            0x61 => Opcode::PopCtx,
-            0x62 => Opcode::SetSymbol,
-            0x63 => Opcode::GetSymbol,
-            0x64 => Opcode::CopyCtx,
-            0x70 => Opcode::Throw,
-            0x71 => Opcode::Try,
-            0x72 => Opcode::Catch,
-            0x73 => Opcode::ClearErr,
-            0x80 => Opcode::CallExtern,
-            0x81 => Opcode::ReadIo,
-            0x82 => Opcode::WriteIo,
-            _ => Opcode::Nop, // Default to NOP for unknown opcodes
-        }
+        Opcode::from_byte(opcode).unwrap_or(Opcode::Nop) // Default to NOP for unknown opcodes
     }
 }
 
@@ -181,6 +283,325 @@ pub struct BytecodeModule {
     pub rule_table: Vec<RuleEntry>,
     pub graph_table: Vec<GraphEntry>,
     pub metadata: Vec<u8>,
+    /// Operands holding absolute instruction addresses. Empty unless the
+    /// module was compiled position-independent.
+    #[serde(default)]
+    pub relocations: Vec<Relocation>,
+}
+
+/// CALL_RULE flag: the target in arg1 is not known yet and arg2 is the id of
+/// the symbol naming the callee. Cleared once the call is resolved.
+pub const CALL_RULE_UNRESOLVED: u8 = 0x01;
+
+/// COMPARE flag: both registers hold constant pool indices and the constants
+/// are compared by value. The low bits still select the comparator.
+pub const COMPARE_TYPED: u8 = 0x80;
+
+// How ADD, SUB and MUL handle results outside the i64 range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ArithmeticMode {
+    Wrapping,   // Two's complement wrap-around
+    Saturating, // Clamp to i64::MIN / i64::MAX
+    Checked,    // Set the error flag and fail with VmError::ArithmeticOverflow
+}
+
+/// An instruction operand that holds an absolute instruction address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Relocation {
+    pub instruction: u32,
+    /// Which argument carries the address: 1, 2 or 3
+    pub operand: u8,
+}
+
+impl Relocation {
+    pub fn new(instruction: u32, operand: u8) -> Self {
+        Relocation { instruction, operand }
+    }
+
+    fn operand_mut<'a>(&self, instruction: &'a mut Instruction) -> Option<&'a mut u16> {
+        match self.operand {
+            1 => Some(&mut instruction.arg1),
+            2 => Some(&mut instruction.arg2),
+            3 => Some(&mut instruction.arg3),
+            _ => None,
+        }
+    }
+}
+
+impl BytecodeModule {
+    /// SHA-256 over the module's canonical binary form: instructions, constant
+    /// pool and symbol names. Header, tables and metadata (debug info) are
+    /// excluded, so identical source always yields an identical hash.
+    pub fn content_hash(&self) -> [u8; 32] {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&(self.instruction_stream.len() as u32).to_le_bytes());
+        for instruction in &self.instruction_stream {
+            bytes.extend_from_slice(&instruction.to_bytes());
+        }
+
+        bytes.extend_from_slice(&(self.constant_pool.len() as u32).to_le_bytes());
+        for constant in &self.constant_pool {
+            encode_constant(constant, &mut bytes);
+        }
+
+        // Symbols are ordered by id so table construction order doesn't matter
+        let mut symbols: Vec<&Symbol> = self.symbol_table.iter().collect();
+        symbols.sort_by(|a, b| (a.id, &a.name).cmp(&(b.id, &b.name)));
+        bytes.extend_from_slice(&(symbols.len() as u32).to_le_bytes());
+        for symbol in symbols {
+            bytes.extend_from_slice(&symbol.id.to_le_bytes());
+            encode_str(&symbol.name, &mut bytes);
+        }
+
+        sha256::sha256(&bytes)
+    }
+
+    /// Sorts the constant pool into canonical order (variant, then value) and
+    /// remaps LOAD_CONST operands, so the pool doesn't depend on the order in
+    /// which the compiler first met each constant
+    pub fn canonicalize_constants(&mut self) {
+        let mut order: Vec<usize> = (0..self.constant_pool.len()).collect();
+        order.sort_by(|&a, &b| compare_constants(&self.constant_pool[a], &self.constant_pool[b]));
+
+        let mut remap = vec![0u16; order.len()];
+        for (new_idx, &old_idx) in order.iter().enumerate() {
+            remap[old_idx] = new_idx as u16;
+        }
+        for instruction in &mut self.instruction_stream {
+            if let Some(operand) = constant_operand_mut(instruction) {
+                if let Some(&idx) = remap.get(*operand as usize) {
+                    *operand = idx;
+                }
+            }
+        }
+        self.constant_pool = order.iter().map(|&idx| self.constant_pool[idx].clone()).collect();
+    }
+
+    /// Adds `base` to every operand listed in the relocation table, so the
+    /// module can be placed `base` instructions into a larger program. Fails,
+    /// leaving the module unchanged, if a relocated address no longer fits
+    /// its 16-bit operand
+    pub fn relocate(&mut self, base: u32) -> Result<(), BytecodeError> {
+        let mut relocated = self.instruction_stream.clone();
+        for relocation in &self.relocations {
+            if let Some(instruction) = relocated.get_mut(relocation.instruction as usize) {
+                if let Some(operand) = relocation.operand_mut(instruction) {
+                    *operand = u32::from(*operand)
+                        .checked_add(base)
+                        .and_then(|target| u16::try_from(target).ok())
+                        .ok_or(BytecodeError::RelocationOverflow(relocation.instruction))?;
+                }
+            }
+        }
+        self.instruction_stream = relocated;
+        Ok(())
+    }
+
+    /// Binary form of the instruction stream. All fields are little-endian:
+    ///
+    /// | bytes | field                                  |
+    /// |-------|----------------------------------------|
+    /// | 0-3   | magic `KBC\0`                          |
+    /// | 4-5   | format version (`BINARY_VERSION`)      |
+    /// | 6-9   | instruction count                      |
+    /// | 10-13 | CRC-32 of the instruction region       |
+    /// | 14-   | instructions, 8 bytes each             |
+    pub fn to_binary(&self) -> Vec<u8> {
+        let body: Vec<u8> = self
+            .instruction_stream
+            .iter()
+            .flat_map(|instruction| instruction.to_bytes())
+            .collect();
+
+        let mut bytes = Vec::with_capacity(BINARY_HEADER_LEN + body.len());
+        bytes.extend_from_slice(&BINARY_MAGIC);
+        bytes.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.instruction_stream.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&crc32::crc32(&body).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    /// Parses the form written by `to_binary`. The magic and version must
+    /// match, the instruction region must be exactly `instruction_count * 8`
+    /// bytes long and its CRC-32 must match the header.
+    pub fn from_binary(bytes: &[u8]) -> Result<BytecodeModule, BytecodeError> {
+        if bytes.len() < BINARY_HEADER_LEN {
+            return Err(BytecodeError::TruncatedHeader(bytes.len()));
+        }
+
+        let magic = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if magic != BINARY_MAGIC {
+            return Err(BytecodeError::BadMagic(magic));
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != BINARY_VERSION {
+            return Err(BytecodeError::UnsupportedVersion(version));
+        }
+        let instruction_count = u32::from_le_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]);
+        let checksum = u32::from_le_bytes([bytes[10], bytes[11], bytes[12], bytes[13]]);
+
+        let region = &bytes[BINARY_HEADER_LEN..];
+        let declared = instruction_count as usize * 8;
+        if declared != region.len() {
+            return Err(BytecodeError::LengthMismatch { declared, actual: region.len() });
+        }
+        let actual = crc32::crc32(region);
+        if actual != checksum {
+            return Err(BytecodeError::ChecksumMismatch { expected: checksum, actual });
+        }
+
+        let instruction_stream = region
+            .chunks_exact(8)
+            .filter_map(Instruction::from_bytes)
+            .collect();
+
+        Ok(BytecodeModule {
+            header: ModuleHeader {
+                magic: *b"KERN",
+                version: version as u32,
+                instruction_count,
+                section_offsets: SectionOffsets {
+                    instruction_offset: BINARY_HEADER_LEN as u32,
+                    constant_pool_offset: 0,
+                    symbol_table_offset: 0,
+                    rule_table_offset: 0,
+                    graph_table_offset: 0,
+                    metadata_offset: 0,
+                },
+                checksum: checksum as u64,
+            },
+            instruction_stream,
+            constant_pool: Vec::new(),
+            symbol_table: Vec::new(),
+            rule_table: Vec::new(),
+            graph_table: Vec::new(),
+            metadata: Vec::new(),
+            relocations: Vec::new(),
+        })
+    }
+}
+
+/// The operand of `instruction` that indexes the constant pool, if any:
+/// LOAD_CONST's arg2 or LOAD_SYM's arg1
+pub fn constant_operand_mut(instruction: &mut Instruction) -> Option<&mut u16> {
+    match Opcode::from_byte(instruction.opcode)? {
+        Opcode::LoadConst => Some(&mut instruction.arg2),
+        Opcode::LoadSym => Some(&mut instruction.arg1),
+        _ => None,
+    }
+}
+
+/// Leading bytes of the binary module form
+pub const BINARY_MAGIC: [u8; 4] = *b"KBC\0";
+/// Binary module format version written by `to_binary`
+pub const BINARY_VERSION: u16 = 1;
+
+// Size of the header preceding the instruction region in the binary form
+const BINARY_HEADER_LEN: usize = 14;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BytecodeError {
+    TruncatedHeader(usize),
+    BadMagic([u8; 4]),
+    UnsupportedVersion(u16),
+    // Instruction region length in bytes, as declared by the header and as found
+    LengthMismatch { declared: usize, actual: usize },
+    // CRC-32 of the instruction region, as recorded in the header and as computed
+    ChecksumMismatch { expected: u32, actual: u32 },
+    // Relocating this instruction's address operand overflowed 16 bits
+    RelocationOverflow(u32),
+}
+
+impl std::fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BytecodeError::TruncatedHeader(len) => {
+                write!(f, "Bytecode header truncated: {} bytes", len)
+            }
+            BytecodeError::BadMagic(magic) => {
+                write!(f, "Not a KERN bytecode module: magic {:02x?}", magic)
+            }
+            BytecodeError::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported bytecode format version {} (expected {})",
+                version, BINARY_VERSION
+            ),
+            BytecodeError::LengthMismatch { declared, actual } => write!(
+                f,
+                "Instruction region length mismatch: header declares {} bytes, found {}",
+                declared, actual
+            ),
+            BytecodeError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Instruction region checksum mismatch: header has {:08x}, computed {:08x}",
+                expected, actual
+            ),
+            BytecodeError::RelocationOverflow(instruction) => write!(
+                f,
+                "Relocated address of instruction {} exceeds the 16-bit operand range",
+                instruction
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BytecodeError {}
+
+// Canonical constant order: by variant tag (as in encode_constant), then value
+fn compare_constants(a: &Constant, b: &Constant) -> std::cmp::Ordering {
+    fn tag(constant: &Constant) -> u8 {
+        match constant {
+            Constant::Num(_) => 0,
+            Constant::Bool(_) => 1,
+            Constant::Sym(_) => 2,
+            Constant::Vec(_) => 3,
+        }
+    }
+
+    match (a, b) {
+        (Constant::Num(x), Constant::Num(y)) => x.cmp(y),
+        (Constant::Bool(x), Constant::Bool(y)) => x.cmp(y),
+        (Constant::Sym(x), Constant::Sym(y)) => x.cmp(y),
+        (Constant::Vec(x), Constant::Vec(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(l, r)| compare_constants(l, r))
+            .find(|ord| ord.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        _ => tag(a).cmp(&tag(b)),
+    }
+}
+
+// Canonical, length-prefixed encodings used for content hashing
+fn encode_str(value: &str, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn encode_constant(constant: &Constant, out: &mut Vec<u8>) {
+    match constant {
+        Constant::Num(n) => {
+            out.push(0);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+        Constant::Bool(b) => {
+            out.push(1);
+            out.push(*b as u8);
+        }
+        Constant::Sym(s) => {
+            out.push(2);
+            encode_str(s, out);
+        }
+        Constant::Vec(items) => {
+            out.push(3);
+            out.extend_from_slice(&(items.len() as u32).to_le_bytes());
+            for item in items {
+                encode_constant(item, out);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -202,7 +623,7 @@ pub struct SectionOffsets {
     pub metadata_offset: u32,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum Constant {
     Num(i64),
     Bool(bool),
@@ -252,6 +673,17 @@ mod tests {
         assert_eq!(instruction.flags, parsed_instruction.flags);
     }
 
+    #[test]
+    fn test_instruction_operand_round_trip() {
+        let compare = Instruction::new(Opcode::Compare as u8, 0, 1, 2, 0); // three distinct args
+        let parsed = Instruction::from_bytes(&compare.to_bytes()).unwrap();
+        assert_eq!(parsed, compare);
+        assert_eq!((parsed.arg1, parsed.arg2, parsed.arg3), (0, 1, 2));
+
+        assert_eq!(compare.operand(), 0x0002_0001_0000);
+        assert_eq!(Instruction::from_operand(Opcode::Compare as u8, compare.operand(), 0), compare);
+    }
+
     #[test]
     fn test_opcode_conversion() {
         assert_eq!(Opcode::from(0x00), Opcode::Nop);
@@ -277,6 +709,184 @@ mod tests {
         assert_eq!(instr.flags, 0);   // No special flags
     }
 
+    // Binary form with a hand-written instruction count and a valid checksum
+    fn binary_with(instruction_count: u32, body: &[u8]) -> Vec<u8> {
+        let mut bytes = BINARY_MAGIC.to_vec();
+        bytes.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&instruction_count.to_le_bytes());
+        bytes.extend_from_slice(&crc32::crc32(body).to_le_bytes());
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    fn module_with(instructions: Vec<Instruction>) -> BytecodeModule {
+        // An empty binary module gives us a default header to fill in
+        let mut module = BytecodeModule::from_binary(&binary_with(0, &[])).unwrap();
+        module.header.instruction_count = instructions.len() as u32;
+        module.instruction_stream = instructions;
+        module
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let module = module_with(vec![
+            Instruction::new(0x11, 1, 42, 0, 0),
+            Instruction::new(0x03, 0, 0, 0, 0),
+        ]);
+        let parsed = BytecodeModule::from_binary(&module.to_binary()).unwrap();
+        assert_eq!(parsed.instruction_stream, module.instruction_stream);
+        assert_eq!(parsed.header.instruction_count, 2);
+    }
+
+    #[test]
+    fn test_from_binary_rejects_length_mismatch() {
+        // Header declares 3 instructions, but only 2 follow
+        let mut body = Instruction::new(0x11, 1, 42, 0, 0).to_bytes().to_vec();
+        body.extend_from_slice(&Instruction::new(0x03, 0, 0, 0, 0).to_bytes());
+        let bytes = binary_with(3, &body);
+
+        assert_eq!(
+            BytecodeModule::from_binary(&bytes).unwrap_err(),
+            BytecodeError::LengthMismatch { declared: 24, actual: 16 }
+        );
+        assert_eq!(
+            BytecodeModule::from_binary(&[3, 0]).unwrap_err(),
+            BytecodeError::TruncatedHeader(2)
+        );
+    }
+
+    #[test]
+    fn test_from_binary_rejects_bad_header_and_corruption() {
+        let module = module_with(vec![
+            Instruction::new(0x11, 1, 42, 0, 0),
+            Instruction::new(0x03, 0, 0, 0, 0),
+        ]);
+        let bytes = module.to_binary();
+        assert_eq!(&bytes[..4], b"KBC\0");
+        let parsed = BytecodeModule::from_binary(&bytes).unwrap();
+        assert_eq!(parsed.header.version, BINARY_VERSION as u32);
+        assert_eq!(parsed.to_binary(), bytes);
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(
+            BytecodeModule::from_binary(&bad_magic).unwrap_err(),
+            BytecodeError::BadMagic(*b"XBC\0")
+        );
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 9;
+        assert_eq!(
+            BytecodeModule::from_binary(&bad_version).unwrap_err(),
+            BytecodeError::UnsupportedVersion(9)
+        );
+
+        // Flip one bit of the first instruction's immediate
+        let mut corrupted = bytes.clone();
+        corrupted[BINARY_HEADER_LEN + 3] ^= 0x01;
+        assert!(matches!(
+            BytecodeModule::from_binary(&corrupted).unwrap_err(),
+            BytecodeError::ChecksumMismatch { .. }
+        ));
+    }
+
+    fn branching_program(threshold: i64) -> crate::lir::LirProgram {
+        let mut builder = crate::lir_builder::LirBuilder::new();
+        let x = builder.load_num(10);
+        let limit = builder.load_num(threshold);
+        let cond = builder.cmp_gt(x, limit);
+        builder.jmp_if(cond, 1);
+        builder.load_num(0);
+        builder.jmp(2);
+        builder.label(1);
+        builder.load_num(1);
+        builder.label(2);
+        builder.halt();
+        builder.build()
+    }
+
+    #[test]
+    fn test_relocated_modules_concatenate() {
+        let mut compiler = BytecodeCompiler::new();
+        compiler.set_position_independent(true);
+        let first = compiler.compile_lir(&branching_program(5));
+        let original = compiler.compile_lir(&branching_program(20));
+        assert_eq!(original.relocations.len(), 2);
+
+        let base = first.instruction_stream.len() as u32;
+        let mut second = original.clone();
+        second.relocate(base).unwrap();
+
+        let mut combined = first.instruction_stream.clone();
+        combined.extend(second.instruction_stream.iter().cloned());
+
+        for relocation in &original.relocations {
+            let index = relocation.instruction as usize;
+            let (before, after) = match relocation.operand {
+                1 => (original.instruction_stream[index].arg1, combined[base as usize + index].arg1),
+                _ => (original.instruction_stream[index].arg2, combined[base as usize + index].arg2),
+            };
+            assert_eq!(after as u32, before as u32 + base);
+            assert_eq!(combined[after as usize], original.instruction_stream[before as usize]);
+        }
+        // The first module's jumps are untouched
+        assert_eq!(&combined[..base as usize], &first.instruction_stream[..]);
+    }
+
+    #[test]
+    fn test_relocate_rejects_addresses_past_the_operand_range() {
+        let mut compiler = BytecodeCompiler::new();
+        compiler.set_position_independent(true);
+        let original = compiler.compile_lir(&branching_program(5));
+        let highest = original
+            .relocations
+            .iter()
+            .map(|r| original.instruction_stream[r.instruction as usize].arg1 as u32)
+            .max()
+            .unwrap();
+
+        let mut module = original.clone();
+        assert!(module.relocate(u16::MAX as u32 - highest).is_ok());
+
+        let mut module = original.clone();
+        assert!(matches!(
+            module.relocate(u16::MAX as u32 - highest + 1),
+            Err(BytecodeError::RelocationOverflow(_))
+        ));
+        assert_eq!(module.instruction_stream, original.instruction_stream);
+        // A base past 16 bits is rejected rather than truncated
+        assert!(module.relocate(0x1_0000).is_err());
+    }
+
+    #[test]
+    fn test_relocations_are_opt_in() {
+        let module = BytecodeCompiler::new().compile_lir(&branching_program(5));
+        assert!(module.relocations.is_empty());
+    }
+
+    #[test]
+    fn test_canonicalize_constants_remaps_operands() {
+        let mut module = module_with(vec![
+            Instruction::new(0x15, 0, 0, 0, 0),
+            Instruction::new(0x15, 1, 1, 0, 0),
+            Instruction::new(0x15, 2, 2, 0, 0),
+        ]);
+        module.constant_pool = vec![
+            Constant::Sym("b".to_string()),
+            Constant::Num(9),
+            Constant::Sym("a".to_string()),
+        ];
+        module.canonicalize_constants();
+
+        assert_eq!(
+            module.constant_pool,
+            vec![Constant::Num(9), Constant::Sym("a".to_string()), Constant::Sym("b".to_string())]
+        );
+        // Each register still receives the constant it loaded before
+        let loaded: Vec<u16> = module.instruction_stream.iter().map(|i| i.arg2).collect();
+        assert_eq!(loaded, vec![2, 0, 1]);
+    }
+
     #[test]
     fn test_decode_function() {
         let instruction = Instruction::new(0x20, 1, 2, 3, 0); // Add R1, R2 -> R3
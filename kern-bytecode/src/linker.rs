@@ -0,0 +1,319 @@
+//! Bytecode Linker
+//!
+//! Combines separately compiled modules into one program. Modules are placed
+//! back to back in the order given, so the first module's entry is the
+//! program entry. Each module after the first must carry a relocation table
+//! (see `BytecodeCompiler::set_position_independent`) for its jumps to
+//! survive being moved; one that doesn't is rejected.
+
+use crate::{
+    constant_operand_mut, is_jump, BytecodeModule, Constant, Instruction, ModuleHeader, Opcode,
+    Relocation, RuleEntry, SectionOffsets, Symbol, CALL_RULE_UNRESOLVED,
+};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkError {
+    NoModules,
+    // A CALL_RULE names a rule that no module defines
+    UnresolvedRule(String),
+    // The same rule name is defined by more than one module
+    DuplicateRule(String),
+    // An instruction refers to a constant or symbol index its module doesn't have
+    InvalidReference { module: usize, instruction: usize },
+    // The combined program no longer fits 16-bit operands
+    TooLarge,
+    // A module that must move has a code address with no relocation entry,
+    // i.e. it wasn't compiled position independent
+    NotRelocatable(usize),
+}
+
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkError::NoModules => write!(f, "No modules to link"),
+            LinkError::UnresolvedRule(name) => write!(f, "Unresolved rule '{}'", name),
+            LinkError::DuplicateRule(name) => {
+                write!(f, "Rule '{}' is defined more than once", name)
+            }
+            LinkError::InvalidReference {
+                module,
+                instruction,
+            } => write!(
+                f,
+                "Module {} instruction {} refers to a missing constant or symbol",
+                module, instruction
+            ),
+            LinkError::TooLarge => write!(f, "Linked program exceeds the 16-bit operand range"),
+            LinkError::NotRelocatable(module) => write!(
+                f,
+                "Module {} has no relocation table and cannot be moved",
+                module
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+/// Links `modules` into a single module.
+///
+/// Instruction streams are concatenated and relocated, constant pools are
+/// merged without duplicates, symbol tables are unioned by name, and calls
+/// left unresolved by the compiler are bound to the rule of the same name.
+pub fn link(modules: &[BytecodeModule]) -> Result<BytecodeModule, LinkError> {
+    if modules.is_empty() {
+        return Err(LinkError::NoModules);
+    }
+
+    let mut instructions: Vec<Instruction> = Vec::new();
+    let mut constant_pool: Vec<Constant> = Vec::new();
+    let mut symbol_table: Vec<Symbol> = Vec::new();
+    let mut rule_table: Vec<RuleEntry> = Vec::new();
+    let mut graph_table = Vec::new();
+    let mut relocations: Vec<Relocation> = Vec::new();
+
+    for (module_idx, module) in modules.iter().enumerate() {
+        let base = instructions.len() as u32;
+        if base > u16::MAX as u32 {
+            return Err(LinkError::TooLarge);
+        }
+        if base > 0 && !is_relocatable(module) {
+            return Err(LinkError::NotRelocatable(module_idx));
+        }
+
+        let mut placed = module.clone();
+        placed.relocate(base).map_err(|_| LinkError::TooLarge)?;
+
+        // Index remapping from this module's tables into the merged ones
+        let mut constant_map = Vec::with_capacity(module.constant_pool.len());
+        for constant in &module.constant_pool {
+            let idx = match constant_pool.iter().position(|c| c == constant) {
+                Some(idx) => idx,
+                None => {
+                    constant_pool.push(constant.clone());
+                    constant_pool.len() - 1
+                }
+            };
+            constant_map.push(idx as u16);
+        }
+        let mut symbol_map = HashMap::new();
+        for symbol in &module.symbol_table {
+            let id = match symbol_table.iter().find(|s| s.name == symbol.name) {
+                Some(existing) => existing.id,
+                None => {
+                    let id = symbol_table.len() as u32;
+                    symbol_table.push(Symbol {
+                        id,
+                        name: symbol.name.clone(),
+                    });
+                    id
+                }
+            };
+            symbol_map.insert(symbol.id, id as u16);
+        }
+
+        for (idx, instr) in placed.instruction_stream.iter_mut().enumerate() {
+            let invalid = LinkError::InvalidReference {
+                module: module_idx,
+                instruction: idx,
+            };
+            if let Some(operand) = constant_operand_mut(instr) {
+                *operand = *constant_map.get(*operand as usize).ok_or(invalid)?;
+            } else if instr.opcode == Opcode::CallRule as u8
+                && instr.flags & CALL_RULE_UNRESOLVED != 0
+            {
+                instr.arg2 = *symbol_map.get(&(instr.arg2 as u32)).ok_or(invalid)?;
+            } else if instr.opcode == Opcode::CallExtern as u8 {
+                instr.arg1 = *symbol_map.get(&(instr.arg1 as u32)).ok_or(invalid)?;
+            }
+        }
+
+        for rule in &module.rule_table {
+            if rule_table.iter().any(|r| r.name == rule.name) {
+                return Err(LinkError::DuplicateRule(rule.name.clone()));
+            }
+            rule_table.push(RuleEntry {
+                id: rule_table.len() as u32,
+                entry_pc: rule.entry_pc + base,
+                name: rule.name.clone(),
+            });
+        }
+
+        relocations.extend(
+            module
+                .relocations
+                .iter()
+                .map(|r| Relocation::new(r.instruction + base, r.operand)),
+        );
+        graph_table.extend(module.graph_table.iter().cloned());
+        instructions.extend(placed.instruction_stream);
+    }
+
+    // Bind calls the compiler couldn't resolve within their own module
+    for (idx, instr) in instructions.iter_mut().enumerate() {
+        if instr.opcode != Opcode::CallRule as u8 || instr.flags & CALL_RULE_UNRESOLVED == 0 {
+            continue;
+        }
+        let name = &symbol_table[instr.arg2 as usize].name;
+        let rule = rule_table
+            .iter()
+            .find(|r| &r.name == name)
+            .ok_or_else(|| LinkError::UnresolvedRule(name.clone()))?;
+        instr.arg1 = u16::try_from(rule.entry_pc).map_err(|_| LinkError::TooLarge)?;
+        instr.flags &= !CALL_RULE_UNRESOLVED;
+        relocations.push(Relocation::new(idx as u32, 1));
+    }
+
+    let mut linked = BytecodeModule {
+        header: ModuleHeader {
+            magic: modules[0].header.magic,
+            version: modules[0].header.version,
+            instruction_count: instructions.len() as u32,
+            section_offsets: SectionOffsets {
+                instruction_offset: modules[0].header.section_offsets.instruction_offset,
+                constant_pool_offset: 0,
+                symbol_table_offset: 0,
+                rule_table_offset: 0,
+                graph_table_offset: 0,
+                metadata_offset: 0,
+            },
+            checksum: 0,
+        },
+        instruction_stream: instructions,
+        constant_pool,
+        symbol_table,
+        rule_table,
+        graph_table,
+        // Per-module debug info refers to pre-link offsets, so none is carried over
+        metadata: Vec::new(),
+        relocations,
+    };
+    linked.canonicalize_constants();
+    Ok(linked)
+}
+
+// Every instruction that holds a code address (a jump, a TRY's catch target or
+// a CALL_RULE the compiler bound) must be listed in the relocation table
+fn is_relocatable(module: &BytecodeModule) -> bool {
+    module
+        .instruction_stream
+        .iter()
+        .enumerate()
+        .all(|(idx, instr)| {
+            let addresses_code = is_jump(instr)
+                || instr.opcode == Opcode::Try as u8
+                || (instr.opcode == Opcode::CallRule as u8
+                    && instr.flags & CALL_RULE_UNRESOLVED == 0);
+            !addresses_code
+                || module
+                    .relocations
+                    .iter()
+                    .any(|r| r.instruction == idx as u32)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lir_builder::LirBuilder;
+    use crate::BytecodeCompiler;
+
+    fn compile(build: impl FnOnce(&mut LirBuilder)) -> BytecodeModule {
+        let mut builder = LirBuilder::new();
+        build(&mut builder);
+        let mut compiler = BytecodeCompiler::new();
+        compiler.set_position_independent(true);
+        compiler.compile_lir(&builder.build())
+    }
+
+    #[test]
+    fn test_link_merges_constant_pools() {
+        let first = compile(|b| {
+            b.load_num(5_000_000_000);
+            b.load_num(-1);
+        });
+        let second = compile(|b| {
+            b.load_num(-1);
+            b.load_num(7_000_000_000);
+        });
+
+        let linked = link(&[first, second]).unwrap();
+        assert_eq!(
+            linked.constant_pool,
+            vec![
+                Constant::Num(-1),
+                Constant::Num(5_000_000_000),
+                Constant::Num(7_000_000_000)
+            ]
+        );
+        let indices: Vec<u16> = linked.instruction_stream.iter().map(|i| i.arg2).collect();
+        assert_eq!(indices, vec![1, 0, 0, 2]);
+    }
+
+    #[test]
+    fn test_link_reports_unresolved_and_duplicate_rules() {
+        let caller = compile(|b| {
+            b.call_rule("Missing");
+        });
+        assert_eq!(
+            link(&[caller]).unwrap_err(),
+            LinkError::UnresolvedRule("Missing".to_string())
+        );
+
+        let define = || {
+            compile(|b| {
+                b.rule_entry(1, "Twice");
+                b.return_rule();
+            })
+        };
+        assert_eq!(
+            link(&[define(), define()]).unwrap_err(),
+            LinkError::DuplicateRule("Twice".to_string())
+        );
+        assert_eq!(link(&[]).unwrap_err(), LinkError::NoModules);
+    }
+
+    #[test]
+    fn test_link_rejects_targets_past_the_operand_range() {
+        let first = compile(|b| {
+            b.load_num(1);
+            b.load_num(2);
+        });
+        let mut second = compile(|b| {
+            b.jmp(1);
+            b.label(1);
+            b.halt();
+        });
+        // As if the jump's target sat near the end of a very large module
+        second.instruction_stream[0].arg1 = u16::MAX - 1;
+
+        assert_eq!(link(&[first, second]).unwrap_err(), LinkError::TooLarge);
+    }
+
+    #[test]
+    fn test_link_rejects_modules_that_are_not_position_independent() {
+        let build = |b: &mut LirBuilder| {
+            b.jmp(1);
+            b.label(1);
+            b.halt();
+        };
+        let mut builder = LirBuilder::new();
+        build(&mut builder);
+        let fixed = BytecodeCompiler::new().compile_lir(&builder.build());
+        assert!(fixed.relocations.is_empty());
+
+        // Placed first it stays at address 0 and needs no relocation
+        assert!(link(&[fixed.clone(), compile(build)]).is_ok());
+        assert_eq!(
+            link(&[compile(build), fixed]).unwrap_err(),
+            LinkError::NotRelocatable(1)
+        );
+
+        // Without code addresses there is nothing to relocate
+        let mut builder = LirBuilder::new();
+        builder.load_num(1);
+        let straight = BytecodeCompiler::new().compile_lir(&builder.build());
+        assert!(link(&[compile(build), straight]).is_ok());
+    }
+}
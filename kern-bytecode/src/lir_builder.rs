@@ -391,6 +391,43 @@ impl LirBuilder {
         self
     }
 
+    // Rule Execution Operations
+    pub fn rule_entry(&mut self, label: u32, name: &str) -> &mut Self {
+        self.program.add_instruction(LirInstruction {
+            op: LirOp::RuleEntry(label, name.to_string()),
+            dst: None,
+            src1: None,
+            src2: None,
+            immediate: None,
+            label: Some(label),
+        });
+        self
+    }
+
+    pub fn call_rule(&mut self, name: &str) -> &mut Self {
+        self.program.add_instruction(LirInstruction {
+            op: LirOp::CallRule(name.to_string()),
+            dst: None,
+            src1: None,
+            src2: None,
+            immediate: None,
+            label: None,
+        });
+        self
+    }
+
+    pub fn return_rule(&mut self) -> &mut Self {
+        self.program.add_instruction(LirInstruction {
+            op: LirOp::ReturnRule,
+            dst: None,
+            src1: None,
+            src2: None,
+            immediate: None,
+            label: None,
+        });
+        self
+    }
+
     // Context & State Operations
     pub fn push_ctx(&mut self) -> &mut Self {
         self.program.add_instruction(LirInstruction {
@@ -0,0 +1,57 @@
+//! Operand Encodings
+//!
+//! Named packers for the register layouts the compiler emits. Each helper
+//! works on the 48-bit operand of `Instruction::from_operand` /
+//! `Instruction::operand` (arg1 | arg2 << 16 | arg3 << 32), so the VM and
+//! tools can decode MOVE and COMPARE without re-deriving the field order.
+
+/// MOVE: arg1 = source register, arg2 = destination register
+pub fn encode_move(src: u16, dst: u16) -> u64 {
+    (src as u64) | ((dst as u64) << 16)
+}
+
+/// Returns `(src, dst)`
+pub fn decode_move(operand: u64) -> (u16, u16) {
+    (operand as u16, (operand >> 16) as u16)
+}
+
+/// COMPARE: arg1 = left register, arg2 = right register, arg3 = result register.
+/// The comparator itself travels in the instruction flags.
+pub fn encode_compare(a: u16, b: u16, result: u16) -> u64 {
+    (a as u64) | ((b as u64) << 16) | ((result as u64) << 32)
+}
+
+/// Returns `(a, b, result)`
+pub fn decode_compare(operand: u64) -> (u16, u16, u16) {
+    (
+        operand as u16,
+        (operand >> 16) as u16,
+        (operand >> 32) as u16,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Instruction, Opcode};
+
+    #[test]
+    fn test_move_round_trip() {
+        let operand = encode_move(3, 11);
+        assert_eq!(decode_move(operand), (3, 11));
+
+        let instr = Instruction::from_operand(Opcode::Move as u8, operand, 0);
+        assert_eq!((instr.arg1, instr.arg2, instr.arg3), (3, 11, 0));
+        assert_eq!(decode_move(instr.operand()), (3, 11));
+    }
+
+    #[test]
+    fn test_compare_round_trip() {
+        let operand = encode_compare(1, 2, 15);
+        assert_eq!(decode_compare(operand), (1, 2, 15));
+
+        let instr = Instruction::from_operand(Opcode::Compare as u8, operand, 0x03);
+        assert_eq!((instr.arg1, instr.arg2, instr.arg3), (1, 2, 15));
+        assert_eq!(decode_compare(instr.operand()), (1, 2, 15));
+    }
+}
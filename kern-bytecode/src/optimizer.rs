@@ -54,7 +54,14 @@ impl BytecodeOptimizer {
             optimizations_applied.push("Redundant Move Removal".to_string());
         }
 
-        // 5. No-Op Removal
+        // 5. LOAD_NUM + MOVE Fusion
+        let prev_len = instructions.len();
+        instructions = self.load_move_fusion(instructions);
+        if instructions.len() != prev_len {
+            optimizations_applied.push("Load-Move Fusion".to_string());
+        }
+
+        // 6. No-Op Removal
         let prev_len = instructions.len();
         instructions = self.no_op_removal(instructions);
         if instructions.len() != prev_len {
@@ -120,6 +127,92 @@ impl BytecodeOptimizer {
         instructions
     }
 
+    /// LOAD_NUM + MOVE Fusion
+    /// Rewrites `LOAD_NUM Rtmp, #k; MOVE Rtmp -> Rdst` into `LOAD_NUM Rdst, #k`
+    /// when Rtmp is dead after the move
+    fn load_move_fusion(&self, mut instructions: Vec<Instruction>) -> Vec<Instruction> {
+        let mut i = 0;
+        while i + 1 < instructions.len() {
+            let load = &instructions[i];
+            let mv = &instructions[i + 1];
+            let tmp = load.arg1;
+
+            let fusable = load.opcode == Opcode::LoadNum as u8
+                && mv.opcode == Opcode::Move as u8
+                && mv.arg1 == tmp
+                && mv.arg2 != tmp
+                && !Self::is_jump_target(&instructions, i + 1)
+                && Self::register_dead_from(&instructions, i + 2, tmp);
+
+            if fusable {
+                instructions[i].arg1 = instructions[i + 1].arg2;
+                instructions.remove(i + 1);
+                Self::shift_jump_targets(&mut instructions, i + 1);
+            }
+            i += 1;
+        }
+        instructions
+    }
+
+    /// Conservative liveness: `reg` is dead from `start` if it is overwritten before
+    /// being read, or the program halts first. Any jump, or any instruction whose
+    /// register use we don't model, counts as a read.
+    fn register_dead_from(instructions: &[Instruction], start: usize, reg: u16) -> bool {
+        for instr in &instructions[start.min(instructions.len())..] {
+            match Opcode::from(instr.opcode) {
+                Opcode::Halt => return true,
+                Opcode::Nop | Opcode::Ext => {}
+                // LOAD_SYM keeps its symbol id in arg1/arg2 and writes arg3
+                Opcode::LoadSym => {
+                    if instr.arg3 == reg {
+                        return true;
+                    }
+                }
+                Opcode::LoadNum | Opcode::LoadBool | Opcode::LoadConst | Opcode::ReadSteps => {
+                    if instr.arg1 == reg {
+                        return true;
+                    }
+                }
+                Opcode::Move => {
+                    if instr.arg1 == reg {
+                        return false;
+                    }
+                    if instr.arg2 == reg {
+                        return true;
+                    }
+                }
+                Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div | Opcode::Mod
+                | Opcode::And | Opcode::Or | Opcode::Not | Opcode::Compare => {
+                    if instr.arg1 == reg || instr.arg2 == reg || instr.arg3 == reg {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+        // Falling off the end halts the program
+        true
+    }
+
+    fn is_jump_target(instructions: &[Instruction], index: usize) -> bool {
+        instructions.iter().any(|instr| match Opcode::from(instr.opcode) {
+            Opcode::Jmp | Opcode::Try => instr.arg1 as usize == index,
+            Opcode::JmpIf => instr.arg2 as usize == index,
+            _ => false,
+        })
+    }
+
+    /// Adjusts jump and TRY handler targets after the instruction at `removed` was deleted
+    fn shift_jump_targets(instructions: &mut [Instruction], removed: usize) {
+        for instr in instructions.iter_mut() {
+            match Opcode::from(instr.opcode) {
+                Opcode::Jmp | Opcode::Try if instr.arg1 as usize > removed => instr.arg1 -= 1,
+                Opcode::JmpIf if instr.arg2 as usize > removed => instr.arg2 -= 1,
+                _ => {}
+            }
+        }
+    }
+
     /// No-Op Removal
     /// Removes NOP instructions that have no effect
     fn no_op_removal(&self, instructions: Vec<Instruction>) -> Vec<Instruction> {
@@ -153,6 +246,77 @@ mod tests {
         assert!(result.optimizations_applied.contains(&"No-Op Removal".to_string()));
     }
 
+    #[test]
+    fn test_load_move_fusion() {
+        let optimizer = BytecodeOptimizer::new();
+
+        let instructions = vec![
+            Instruction::new(Opcode::LoadNum as u8, 4, 42, 0, 0), // LOAD_NUM R4, 42
+            Instruction::new(Opcode::Move as u8, 4, 2, 0, 0),     // MOVE R4 -> R2
+            Instruction::new(Opcode::Add as u8, 3, 2, 2, 0),      // R3 = R2 + R2
+            Instruction::new(Opcode::Halt as u8, 0, 0, 0, 0),
+        ];
+        let result = optimizer.optimize(instructions);
+
+        assert_eq!(result.instructions.len(), 3);
+        assert_eq!(result.instructions[0], Instruction::new(Opcode::LoadNum as u8, 2, 42, 0, 0));
+        // R4 is no longer needed at all
+        assert!(result.instructions.iter().all(|i| i.arg1 != 4 && i.arg2 != 4 && i.arg3 != 4));
+        assert!(result.optimizations_applied.contains(&"Load-Move Fusion".to_string()));
+    }
+
+    #[test]
+    fn test_load_move_fusion_shifts_try_handler() {
+        let optimizer = BytecodeOptimizer::new();
+
+        let instructions = vec![
+            Instruction::new(Opcode::Try as u8, 4, 0, 0, 0),      // TRY, catch at 4
+            Instruction::new(Opcode::LoadNum as u8, 4, 42, 0, 0), // LOAD_NUM R4, 42
+            Instruction::new(Opcode::Move as u8, 4, 2, 0, 0),     // MOVE R4 -> R2
+            Instruction::new(Opcode::Halt as u8, 0, 0, 0, 0),
+            Instruction::new(Opcode::LoadNum as u8, 5, 1, 0, 0),  // handler
+            Instruction::new(Opcode::Halt as u8, 0, 0, 0, 0),
+        ];
+        // Run the pass alone: dead instruction elimination drops code after HALT
+        let result = optimizer.load_move_fusion(instructions);
+
+        assert_eq!(result.len(), 5);
+        assert_eq!(result[0], Instruction::new(Opcode::Try as u8, 3, 0, 0, 0));
+        assert_eq!(result[3], Instruction::new(Opcode::LoadNum as u8, 5, 1, 0, 0));
+    }
+
+    #[test]
+    fn test_load_move_fusion_keeps_live_temporary() {
+        let optimizer = BytecodeOptimizer::new();
+
+        let instructions = vec![
+            Instruction::new(Opcode::LoadNum as u8, 4, 42, 0, 0), // LOAD_NUM R4, 42
+            Instruction::new(Opcode::Move as u8, 4, 2, 0, 0),     // MOVE R4 -> R2
+            Instruction::new(Opcode::Add as u8, 3, 2, 4, 0),      // R4 is read here
+            Instruction::new(Opcode::Halt as u8, 0, 0, 0, 0),
+        ];
+        let result = optimizer.optimize(instructions.clone());
+
+        assert_eq!(result.instructions, instructions);
+    }
+
+    #[test]
+    fn test_load_move_fusion_reads_load_sym_destination() {
+        let optimizer = BytecodeOptimizer::new();
+
+        // LOAD_SYM #2 -> R4 writes R4, not R2, so R2 is still live at the ADD
+        let instructions = vec![
+            Instruction::new(Opcode::LoadNum as u8, 2, 5, 0, 0), // LOAD_NUM R2, 5
+            Instruction::new(Opcode::Move as u8, 2, 7, 0, 0),    // MOVE R2 -> R7
+            Instruction::new(Opcode::LoadSym as u8, 2, 0, 4, 0), // LOAD_SYM #2 -> R4
+            Instruction::new(Opcode::Add as u8, 3, 2, 7, 0),     // R3 = R2 + R7
+            Instruction::new(Opcode::Halt as u8, 0, 0, 0, 0),
+        ];
+        let result = optimizer.optimize(instructions.clone());
+
+        assert_eq!(result.instructions, instructions);
+    }
+
     #[test]
     fn test_dead_instruction_elimination() {
         let optimizer = BytecodeOptimizer::new();
@@ -138,15 +138,7 @@ impl LinearScanAllocator {
 
         for interval in sorted_intervals {
             // Expire old intervals (those that end before current interval starts)
-            active.retain(|(end_point, phys_reg)| {
-                if *end_point < interval.def {
-                    // This register is no longer needed, so we can free it
-                    true // We'll handle this differently - just keep track of free registers
-                } else {
-                    // Still active
-                    false
-                }
-            });
+            active.retain(|(end_point, _)| *end_point >= interval.def);
 
             // Find a free physical register
             let free_reg = self.find_free_register(&active);
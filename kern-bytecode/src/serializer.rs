@@ -110,6 +110,7 @@ impl BytecodeSerializer {
             rule_table,
             graph_table,
             metadata,
+            relocations: Vec::new(),
         })
     }
 
@@ -74,8 +74,10 @@ impl BytecodeVerifier {
         for instr in instructions {
             // Verify opcode is valid
             match Opcode::from(instr.opcode) {
-                Opcode::Nop | Opcode::Jmp | Opcode::JmpIf | Opcode::Halt |
+                Opcode::Nop | Opcode::Jmp | Opcode::JmpIf | Opcode::Halt | Opcode::Ext |
+                Opcode::LoopBegin | Opcode::LoopEnd |
                 Opcode::LoadSym | Opcode::LoadNum | Opcode::LoadBool | Opcode::Move | Opcode::Compare |
+                Opcode::LoadConst | Opcode::ReadSteps |
                 Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div | Opcode::Mod |
                 Opcode::And | Opcode::Or | Opcode::Not |
                 Opcode::CreateNode | Opcode::Connect | Opcode::Merge | Opcode::DeleteNode |
@@ -88,6 +90,16 @@ impl BytecodeVerifier {
                 _ => return Err(VerificationError::InvalidOpcode(instr.opcode)),
             }
         }
+
+        // An EXT prefix must be followed by a non-prefix instruction
+        for (idx, instr) in instructions.iter().enumerate() {
+            if instr.opcode == Opcode::Ext as u8 {
+                match instructions.get(idx + 1) {
+                    Some(next) if next.opcode != Opcode::Ext as u8 => {},
+                    _ => return Err(VerificationError::InvalidOperandFormat),
+                }
+            }
+        }
         Ok(())
     }
 
@@ -141,10 +153,14 @@ impl BytecodeVerifier {
     /// Helper to determine if an argument is a register
     fn is_register_arg(&self, instr: &Instruction, arg_num: u8) -> bool {
         match Opcode::from(instr.opcode) {
-            Opcode::LoadSym | Opcode::LoadNum | Opcode::LoadBool => {
+            Opcode::LoadNum | Opcode::LoadBool | Opcode::LoadConst | Opcode::ReadSteps => {
                 // arg1 is destination register
                 arg_num == 1
             },
+            Opcode::LoadSym => {
+                // arg1/arg2 hold the symbol, arg3 is destination register
+                arg_num == 3
+            },
             Opcode::Move => {
                 // arg1 is destination, arg2 is source
                 arg_num == 1 || arg_num == 2
@@ -158,8 +174,8 @@ impl BytecodeVerifier {
                 // arg1 is destination, arg2 is source
                 arg_num == 1 || arg_num == 2
             },
-            Opcode::JmpIf => {
-                // arg1 is condition register
+            Opcode::JmpIf | Opcode::LoopBegin => {
+                // arg1 is condition register / iteration count register
                 arg_num == 1
             },
             _ => false,
@@ -297,4 +313,20 @@ mod tests {
         let result = verifier.verify(&instructions);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_ext_prefix_needs_following_instruction() {
+        let verifier = BytecodeVerifier::new();
+
+        let instructions = vec![
+            Instruction::ext_prefix(u64::MAX),
+            Instruction::new(Opcode::LoadNum as u8, 0, 0xFFFF, 0, 0),
+            Instruction::new(Opcode::Halt as u8, 0, 0, 0, 0),
+        ];
+        assert!(verifier.verify(&instructions).is_ok());
+
+        // A trailing EXT has nothing to extend
+        let instructions = vec![Instruction::ext_prefix(1)];
+        assert_eq!(verifier.verify(&instructions), Err(VerificationError::InvalidOperandFormat));
+    }
 }
\ No newline at end of file
@@ -1,19 +1,46 @@
 use crate::flow_execution_context::FlowExecutionContext;
-use crate::flow_step_info::FlowStepExecutionInfo;
+use crate::flow_step_info::{FlowStepExecutionInfo, FlowStepStatus};
 use crate::types::Value;
+use std::collections::HashMap;
+
+/// Action executed for a flow step
+pub type StepHandler =
+    Box<dyn FnMut(&mut FlowExecutionContext) -> Result<Value, FlowEvaluationError>>;
+
+/// What `evaluate_steps` does when a step input can't be resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndefinedSymbolPolicy {
+    Error,       // Fail the flow with FlowEvaluationError::UndefinedSymbol
+    DefaultZero, // Bind the input to Num(0) and run the step
+    SkipStep,    // Mark the step Skipped and move on
+}
 
 /// FlowEvaluator handles the execution of flow pipelines
 pub struct FlowEvaluator {
     pub max_iterations: u32,
+    pub undefined_policy: UndefinedSymbolPolicy,
+    step_handlers: HashMap<u32, StepHandler>,
 }
 
 impl FlowEvaluator {
     pub fn new() -> Self {
         FlowEvaluator {
             max_iterations: 100, // Default max iterations per loop
+            undefined_policy: UndefinedSymbolPolicy::Error,
+            step_handlers: HashMap::new(),
         }
     }
 
+    /// Sets how unresolved step inputs are handled
+    pub fn set_undefined_policy(&mut self, policy: UndefinedSymbolPolicy) {
+        self.undefined_policy = policy;
+    }
+
+    /// Registers the action run when step `step_id` is evaluated
+    pub fn register_step(&mut self, step_id: u32, handler: StepHandler) {
+        self.step_handlers.insert(step_id, handler);
+    }
+
     /// Sets the maximum number of iterations allowed for loops
     pub fn set_max_iterations(&mut self, max_iterations: u32) {
         self.max_iterations = max_iterations;
@@ -34,7 +61,7 @@ impl FlowEvaluator {
     pub fn evaluate_step(
         &mut self,
         step_info: FlowStepExecutionInfo,
-        _context: &mut FlowExecutionContext,
+        context: &mut FlowExecutionContext,
     ) -> Result<Value, FlowEvaluationError> {
         // Check if already evaluated
         if step_info.evaluated {
@@ -43,9 +70,98 @@ impl FlowEvaluator {
                 .unwrap_or(Value::Sym("cached".to_string())));
         }
 
-        // For now, just return a success value
-        // In a real implementation, this would execute the step
-        Ok(Value::Sym(format!("step_{}_evaluated", step_info.step_id)))
+        self.run_step(step_info.step_id, context)
+    }
+
+    /// Evaluates steps in order with saga-style error handling. When a step fails,
+    /// its compensation step (if any) runs, then the step is retried while retries
+    /// remain. A compensated step that is out of retries lets the flow continue;
+    /// an uncompensated one aborts it. Each step's `status` records the outcome.
+    /// Unresolved step inputs are handled per `undefined_policy` before the
+    /// step runs.
+    pub fn evaluate_steps(
+        &mut self,
+        steps: &mut [FlowStepExecutionInfo],
+        context: &mut FlowExecutionContext,
+    ) -> Result<Vec<Value>, FlowEvaluationError> {
+        let mut results = Vec::new();
+        for step in steps.iter_mut() {
+            if let Some(missing) = step
+                .inputs
+                .iter()
+                .find(|name| context.get_symbol(name).is_none())
+                .cloned()
+            {
+                match self.undefined_policy {
+                    UndefinedSymbolPolicy::Error => {
+                        step.status = FlowStepStatus::Failed;
+                        return Err(FlowEvaluationError::UndefinedSymbol(missing));
+                    }
+                    UndefinedSymbolPolicy::DefaultZero => {
+                        for name in &step.inputs {
+                            if context.get_symbol(name).is_none() {
+                                context.set_symbol(name, Value::Num(0));
+                            }
+                        }
+                    }
+                    UndefinedSymbolPolicy::SkipStep => {
+                        step.status = FlowStepStatus::Skipped;
+                        context.increment_step();
+                        continue;
+                    }
+                }
+            }
+
+            let mut retries = 0;
+            loop {
+                match self.run_step(step.step_id, context) {
+                    Ok(value) => {
+                        step.status = if retries > 0 {
+                            FlowStepStatus::Retried(retries)
+                        } else {
+                            FlowStepStatus::Completed
+                        };
+                        step.mark_evaluated(value.clone());
+                        results.push(value);
+                        break;
+                    }
+                    Err(err) => {
+                        if let Some(compensation_id) = step.compensation_step_id {
+                            if let Err(compensation_err) = self.run_step(compensation_id, context) {
+                                step.status = FlowStepStatus::Failed;
+                                return Err(compensation_err);
+                            }
+                        }
+
+                        if retries < step.max_retries {
+                            retries += 1;
+                            continue;
+                        }
+
+                        if step.compensation_step_id.is_some() {
+                            step.status = FlowStepStatus::Compensated;
+                            break;
+                        }
+                        step.status = FlowStepStatus::Failed;
+                        return Err(err);
+                    }
+                }
+            }
+            context.increment_step();
+        }
+        Ok(results)
+    }
+
+    fn run_step(
+        &mut self,
+        step_id: u32,
+        context: &mut FlowExecutionContext,
+    ) -> Result<Value, FlowEvaluationError> {
+        match self.step_handlers.get_mut(&step_id) {
+            Some(handler) => handler(context),
+            // Steps without a registered action succeed trivially
+            None => Ok(Value::Sym(format!("step_{}_evaluated", step_id))),
+        }
     }
 
     /// Executes a node in the execution graph
@@ -66,4 +182,6 @@ pub enum FlowEvaluationError {
     MissingRegisterValue(u16),
     InvalidComparison(String),
     ExecutionLimitExceeded,
+    StepFailed(u32, String),
+    UndefinedSymbol(String), // Step input not found in the context
 }
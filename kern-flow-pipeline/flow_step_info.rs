@@ -1,6 +1,17 @@
 // Import the shared types
 use crate::types::Value;
 
+/// Outcome of executing a flow step
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlowStepStatus {
+    Pending,
+    Completed,
+    Retried(u32), // Completed after this many retries
+    Compensated,  // Failed, and its compensation step ran instead
+    Skipped,      // An input was unresolved under UndefinedSymbolPolicy::SkipStep
+    Failed,
+}
+
 /// FlowStepExecutionInfo contains information about a single flow step
 #[derive(Debug, Clone)]
 pub struct FlowStepExecutionInfo {
@@ -9,6 +20,10 @@ pub struct FlowStepExecutionInfo {
     pub action_graph_id: u32,
     pub evaluated: bool,
     pub cached_result: Option<Value>,
+    pub compensation_step_id: Option<u32>, // Step run when this step fails
+    pub max_retries: u32,
+    pub status: FlowStepStatus,
+    pub inputs: Vec<String>, // Symbols that must resolve before the step runs
 }
 
 impl FlowStepExecutionInfo {
@@ -19,6 +34,10 @@ impl FlowStepExecutionInfo {
             action_graph_id,
             evaluated: false,
             cached_result: None,
+            compensation_step_id: None,
+            max_retries: 0,
+            status: FlowStepStatus::Pending,
+            inputs: Vec::new(),
         }
     }
 
@@ -29,9 +48,31 @@ impl FlowStepExecutionInfo {
             action_graph_id,
             evaluated: false,
             cached_result: None,
+            compensation_step_id: None,
+            max_retries: 0,
+            status: FlowStepStatus::Pending,
+            inputs: Vec::new(),
         }
     }
 
+    /// Runs `compensation_step_id` when this step fails
+    pub fn with_compensation(mut self, compensation_step_id: u32) -> Self {
+        self.compensation_step_id = Some(compensation_step_id);
+        self
+    }
+
+    /// Declares the symbols this step reads from the execution context
+    pub fn with_inputs(mut self, inputs: &[&str]) -> Self {
+        self.inputs = inputs.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    /// Retries this step up to `max_retries` times after a failure
+    pub fn with_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
     pub fn mark_evaluated(&mut self, result: Value) {
         self.evaluated = true;
         self.cached_result = Some(result);
@@ -18,9 +18,9 @@ pub use context_manager::{ContextError, ContextManager};
 pub use control_ops_break_halt::BreakHaltHandler;
 pub use control_ops_if_then_else::IfThenElseHandler;
 pub use control_ops_loop::LoopHandler;
-pub use flow_evaluator::{FlowEvaluationError, FlowEvaluator};
+pub use flow_evaluator::{FlowEvaluationError, FlowEvaluator, StepHandler, UndefinedSymbolPolicy};
 pub use flow_execution_context::FlowExecutionContext;
-pub use flow_step_info::FlowStepExecutionInfo;
+pub use flow_step_info::{FlowStepExecutionInfo, FlowStepStatus};
 pub use lazy_evaluation_manager::LazyEvaluationManager;
 pub use types::{SymbolTable, Value};
 
@@ -49,6 +49,121 @@ mod tests {
         assert_eq!(evaluator.max_iterations, 100);
     }
 
+    #[test]
+    fn test_failing_step_runs_compensation() {
+        let mut evaluator = FlowEvaluator::new();
+        evaluator.register_step(
+            2,
+            Box::new(|_| {
+                Err(FlowEvaluationError::StepFailed(
+                    2,
+                    "payment declined".into(),
+                ))
+            }),
+        );
+        evaluator.register_step(
+            3,
+            Box::new(|context| {
+                context.set_symbol("refunded", Value::Bool(true));
+                Ok(Value::Sym("refund".to_string()))
+            }),
+        );
+
+        let mut steps = vec![
+            FlowStepExecutionInfo::new(1, 10),
+            FlowStepExecutionInfo::new(2, 20)
+                .with_compensation(3)
+                .with_retries(1),
+            FlowStepExecutionInfo::new(4, 40),
+        ];
+        let mut context = FlowExecutionContext::new(1);
+        let results = evaluator.evaluate_steps(&mut steps, &mut context).unwrap();
+
+        // The flow completes past the failed step
+        assert_eq!(results.len(), 2);
+        assert_eq!(steps[0].status, FlowStepStatus::Completed);
+        assert_eq!(steps[1].status, FlowStepStatus::Compensated);
+        assert_eq!(steps[2].status, FlowStepStatus::Completed);
+        assert_eq!(context.get_symbol("refunded"), Some(&Value::Bool(true)));
+        assert_eq!(context.step_index, 3);
+    }
+
+    #[test]
+    fn test_failing_step_retries_then_aborts_without_compensation() {
+        let mut evaluator = FlowEvaluator::new();
+        let mut attempts = 0;
+        evaluator.register_step(
+            1,
+            Box::new(move |_| {
+                attempts += 1;
+                if attempts < 3 {
+                    Err(FlowEvaluationError::StepFailed(1, "busy".into()))
+                } else {
+                    Ok(Value::Num(attempts))
+                }
+            }),
+        );
+        evaluator.register_step(
+            2,
+            Box::new(|_| Err(FlowEvaluationError::StepFailed(2, "down".into()))),
+        );
+
+        let mut steps = vec![
+            FlowStepExecutionInfo::new(1, 10).with_retries(2),
+            FlowStepExecutionInfo::new(2, 20),
+        ];
+        let mut context = FlowExecutionContext::new(1);
+        let result = evaluator.evaluate_steps(&mut steps, &mut context);
+
+        assert!(matches!(result, Err(FlowEvaluationError::StepFailed(2, _))));
+        assert_eq!(steps[0].status, FlowStepStatus::Retried(2));
+        assert_eq!(steps[0].get_result(), Some(&Value::Num(3)));
+        assert_eq!(steps[1].status, FlowStepStatus::Failed);
+    }
+
+    #[test]
+    fn test_undefined_step_input_follows_policy() {
+        let run = |policy: UndefinedSymbolPolicy| {
+            let mut evaluator = FlowEvaluator::new();
+            evaluator.set_undefined_policy(policy);
+            evaluator.register_step(
+                1,
+                Box::new(|context| match context.get_symbol("discount") {
+                    Some(Value::Num(discount)) => Ok(Value::Num(100 - discount)),
+                    _ => Err(FlowEvaluationError::MissingRegisterValue(0)),
+                }),
+            );
+
+            let mut steps = vec![
+                FlowStepExecutionInfo::new(1, 10).with_inputs(&["discount"]),
+                FlowStepExecutionInfo::new(2, 20),
+            ];
+            let mut context = FlowExecutionContext::new(1);
+            let result = evaluator.evaluate_steps(&mut steps, &mut context);
+            let statuses: Vec<FlowStepStatus> = steps.iter().map(|s| s.status.clone()).collect();
+            (result, statuses)
+        };
+
+        let (result, statuses) = run(UndefinedSymbolPolicy::Error);
+        assert!(matches!(
+            result,
+            Err(FlowEvaluationError::UndefinedSymbol(name)) if name == "discount"
+        ));
+        assert_eq!(statuses[0], FlowStepStatus::Failed);
+        assert_eq!(statuses[1], FlowStepStatus::Pending);
+
+        let (result, statuses) = run(UndefinedSymbolPolicy::DefaultZero);
+        assert_eq!(result.unwrap()[0], Value::Num(100));
+        assert_eq!(statuses, vec![FlowStepStatus::Completed; 2]);
+
+        let (result, statuses) = run(UndefinedSymbolPolicy::SkipStep);
+        assert_eq!(result.unwrap().len(), 1);
+        assert_eq!(
+            statuses,
+            vec![FlowStepStatus::Skipped, FlowStepStatus::Completed]
+        );
+    }
+
     #[test]
     fn test_lazy_evaluation() {
         let mut lazy_manager = LazyEvaluationManager::new();
@@ -1,12 +1,12 @@
 use kern_parser::{
     Action, Assignment, Condition, ConstraintDef, ControlAction, Definition, EntityDef, Expression,
-    FlowDef, HaltAction, IfAction, LoopAction, Predicate, Program, RuleDef, Term,
+    FlowDef, HaltAction, IfAction, LoopAction, Predicate, Program, RuleDef, SourceLocation, Term,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 // Define the execution graph data structures as specified in the KERN language documentation
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum GraphNodeType {
     Op,      // bytecode operation
     Rule,    // rule evaluation
@@ -15,14 +15,14 @@ pub enum GraphNodeType {
     Io,      // external interface
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, Serialize, Deserialize)]
 pub enum EdgeType {
     Control,   // execution order
     Data,      // value dependency
     Condition, // conditional routing
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphNode {
     pub id: u32,
     pub node_type: GraphNodeType,
@@ -36,7 +36,7 @@ pub struct GraphNode {
 }
 
 // Specialized control nodes as specified in the KERN language documentation
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IfNode {
     pub base: GraphNode,
     pub condition_reg: u8,
@@ -44,7 +44,7 @@ pub struct IfNode {
     pub false_edge: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoopNode {
     pub base: GraphNode,
     pub body_entry: Option<u32>,
@@ -52,22 +52,23 @@ pub struct LoopNode {
     pub iteration_limit: u32,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuleNode {
     pub base: GraphNode,
     pub rule_id: u32,
     pub priority: u16,
-    pub evaluation_mode: u8, // 0 = eager, 1 = lazy
+    pub evaluation_mode: u8,      // 0 = eager, 1 = lazy
+    pub name_symbol: Option<u32>, // symbol id of the rule's name, if known
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphOpNode {
     pub base: GraphNode,
     pub graph_op_type: u8, // 0 = create, 1 = match, 2 = traverse
     pub operand_id: u32,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValueNode {
     pub base: GraphNode,
     pub value_num: f64,
@@ -103,6 +104,7 @@ impl RuleNode {
             rule_id,
             priority,
             evaluation_mode,
+            name_symbol: None,
         }
     }
 }
@@ -135,7 +137,7 @@ impl ValueNode {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IoNode {
     pub base: GraphNode,
     pub io_type: u8, // 0 = call, 1 = read, 2 = write
@@ -152,12 +154,25 @@ impl IoNode {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// `condition_flag` of a control edge leaving a loop node that is taken
+/// once the loop finishes, rather than on each iteration
+pub const LOOP_EXIT_EDGE: u8 = 0x01;
+
+/// Opcodes of arithmetic operation nodes, which combine their two data inputs.
+/// The builder doesn't produce them from source yet; the bytecode compiler
+/// lowers (and folds) them when a graph contains them.
+pub const OP_ADD: u8 = 0x20;
+pub const OP_SUB: u8 = 0x21;
+pub const OP_MUL: u8 = 0x22;
+pub const OP_DIV: u8 = 0x23;
+pub const OP_MOD: u8 = 0x24;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphEdge {
     pub from_node: u32,
     pub to_node: u32,
     pub edge_type: EdgeType,
-    pub condition_flag: u8, // used only for conditional edges
+    pub condition_flag: u8, // conditional edges, and LOOP_EXIT_EDGE on loop control edges
 }
 
 impl GraphEdge {
@@ -181,6 +196,14 @@ impl GraphEdge {
         }
     }
 
+    /// Creates a control edge from a loop node to the node run after the loop exits
+    pub fn new_loop_exit(from_node: u32, to_node: u32) -> Self {
+        GraphEdge {
+            condition_flag: LOOP_EXIT_EDGE,
+            ..GraphEdge::new_control(from_node, to_node)
+        }
+    }
+
     /// Creates a new conditional edge
     pub fn new_condition(from_node: u32, to_node: u32, condition_flag: u8) -> Self {
         GraphEdge {
@@ -192,42 +215,42 @@ impl GraphEdge {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeMeta {
     pub source_ref: u32, // mapping to KERN source
     pub cost_hint: u16,  // heuristic cost
 }
 
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Register {
     pub reg_type: u8,  // sym, num, ref, vec (represented as u8)
     pub value_id: u32, // index into value table
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RegisterSet {
     pub regs: [Register; 16], // R0–R15
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Context {
     pub id: u32,
     pub registers: RegisterSet,
     pub flags: u8,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContextPool {
     pub contexts: Vec<Context>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntryPoint {
     pub node_id: u32,
     pub entry_type: u8, // 0=rule, 1=flow, 2=external call
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SpecializedNode {
     Base(GraphNode),
     If(IfNode),
@@ -251,6 +274,18 @@ impl SpecializedNode {
         }
     }
 
+    pub fn get_base_mut(&mut self) -> &mut GraphNode {
+        match self {
+            SpecializedNode::Base(node) => node,
+            SpecializedNode::If(node) => &mut node.base,
+            SpecializedNode::Loop(node) => &mut node.base,
+            SpecializedNode::Rule(node) => &mut node.base,
+            SpecializedNode::GraphOp(node) => &mut node.base,
+            SpecializedNode::Value(node) => &mut node.base,
+            SpecializedNode::Io(node) => &mut node.base,
+        }
+    }
+
     pub fn base(&self) -> &GraphNode {
         self.get_base()
     }
@@ -260,7 +295,18 @@ impl SpecializedNode {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Register an assignment's value is loaded into before its MOVE copies it
+/// to the destination. Flow outputs are bound below it, to R1..R14.
+pub const ASSIGNMENT_VALUE_REGISTER: u16 = 15;
+
+/// A named flow output bound to the register that holds its value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowOutput {
+    pub name: String,
+    pub register: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionGraph {
     pub nodes: Vec<SpecializedNode>,
     pub edges: Vec<GraphEdge>,
@@ -271,9 +317,130 @@ pub struct ExecutionGraph {
     pub registers: RegisterSet,
     pub contexts: ContextPool,
     pub metadata: GraphMeta,
+    pub flow_outputs: HashMap<u32, Vec<FlowOutput>>, // flow node id -> declared outputs
+    pub symbol_table: HashMap<u32, String>, // symbol id -> name, referenced by LOAD_SYM flags
+}
+
+/// A structural problem found by `ExecutionGraph::validate`: a reference to a
+/// node id that isn't in the graph, or a symbol a node can't address
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphError {
+    DanglingEntryPoint(u32),        // entry point node id
+    DanglingEdgeSource(usize, u32), // edge index, from_node
+    DanglingEdgeTarget(usize, u32), // edge index, to_node
+    SymbolIdOverflow(u32),          // symbol id too large for a node's 16-bit flags
+}
+
+impl std::fmt::Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphError::DanglingEntryPoint(node) => {
+                write!(f, "entry point references missing node {}", node)
+            }
+            GraphError::DanglingEdgeSource(edge, node) => {
+                write!(f, "edge {} starts at missing node {}", edge, node)
+            }
+            GraphError::DanglingEdgeTarget(edge, node) => {
+                write!(f, "edge {} ends at missing node {}", edge, node)
+            }
+            GraphError::SymbolIdOverflow(id) => {
+                write!(
+                    f,
+                    "symbol id {} exceeds the {} symbols a node can reference",
+                    id,
+                    u16::MAX as u32 + 1
+                )
+            }
+        }
+    }
+}
+
+impl ExecutionGraph {
+    /// A graph with no nodes, edges or entry points
+    pub fn empty() -> Self {
+        ExecutionGraph {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            node_count: 0,
+            edge_count: 0,
+            entry_points: Vec::new(),
+            entry_count: 0,
+            registers: RegisterSet {
+                regs: [Register {
+                    reg_type: 0,
+                    value_id: 0,
+                }; 16],
+            },
+            contexts: ContextPool {
+                contexts: Vec::new(),
+            },
+            metadata: GraphMeta {
+                build_hash: 0,
+                version: 1,
+            },
+            flow_outputs: HashMap::new(),
+            symbol_table: HashMap::new(),
+        }
+    }
+
+    /// Checks that every entry point and edge endpoint references a node in
+    /// the graph and that every symbol id fits in a node's flags, reporting
+    /// all problems found
+    pub fn validate(&self) -> Result<(), Vec<GraphError>> {
+        let node_ids: std::collections::HashSet<u32> =
+            self.nodes.iter().map(|n| n.get_base().id).collect();
+        let mut errors = Vec::new();
+
+        for entry in &self.entry_points {
+            if !node_ids.contains(&entry.node_id) {
+                errors.push(GraphError::DanglingEntryPoint(entry.node_id));
+            }
+        }
+        for (index, edge) in self.edges.iter().enumerate() {
+            if !node_ids.contains(&edge.from_node) {
+                errors.push(GraphError::DanglingEdgeSource(index, edge.from_node));
+            }
+            if !node_ids.contains(&edge.to_node) {
+                errors.push(GraphError::DanglingEdgeTarget(index, edge.to_node));
+            }
+        }
+        let mut overflowed: Vec<u32> = self
+            .symbol_table
+            .keys()
+            .copied()
+            .filter(|id| u16::try_from(*id).is_err())
+            .collect();
+        overflowed.sort_unstable();
+        errors.extend(overflowed.into_iter().map(GraphError::SymbolIdOverflow));
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Ids of the nodes some entry point reaches by following edges of any type
+    pub fn reachable_nodes(&self) -> std::collections::HashSet<u32> {
+        let mut visited = std::collections::HashSet::new();
+        let mut stack: Vec<u32> = self.entry_points.iter().map(|e| e.node_id).collect();
+
+        while let Some(node_id) = stack.pop() {
+            if visited.insert(node_id) {
+                stack.extend(
+                    self.edges
+                        .iter()
+                        .filter(|edge| edge.from_node == node_id)
+                        .map(|edge| edge.to_node),
+                );
+            }
+        }
+
+        visited
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphMeta {
     pub build_hash: u32,
     pub version: u16,
@@ -288,8 +455,53 @@ pub struct GraphBuilder {
     entry_points: Vec<EntryPoint>,
     registers: RegisterSet,
     contexts: ContextPool,
+    flow_outputs: HashMap<u32, Vec<FlowOutput>>,
+    current_flow_outputs: Vec<FlowOutput>, // outputs of the flow being processed
+    symbol_table: HashMap<u32, String>,    // symbol id -> name, referenced by LOAD_SYM flags
+    symbol_ids: HashMap<String, u32>,
     #[allow(dead_code)]
     source_map: HashMap<u32, String>, // Maps node IDs to source locations for debugging
+    definition_cache: Option<HashMap<u64, CachedDefinition>>, // keyed by definition_hash
+    definitions_lowered: u32,
+    definition_cache_hits: u32,
+}
+
+/// The graph fragment one definition lowered to, with ids relative to the
+/// node and edge counters at the time it was built
+#[derive(Debug, Clone)]
+struct CachedDefinition {
+    definition: Definition, // As hashed, so a hash collision can be told apart
+    node_base: u32,
+    edge_base: u32,
+    node_span: u32,
+    edge_span: u32,
+    nodes: Vec<SpecializedNode>,
+    edges: Vec<GraphEdge>,
+    entry_points: Vec<EntryPoint>,
+    flow_outputs: Vec<(u32, Vec<FlowOutput>)>,
+}
+
+/// Content hash of a definition's AST (64-bit FNV-1a over its debug form).
+/// Stable across runs, unlike `DefaultHasher`, and any change to the
+/// definition changes the text being hashed. Where a rule sits in the
+/// source is not part of its content, so the same rule moved to another
+/// line hashes the same.
+pub fn definition_hash(definition: &Definition) -> u64 {
+    let text = format!("{:?}", definition_content(definition));
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// The part of a definition `definition_hash` covers: everything but its location
+fn definition_content(definition: &Definition) -> Definition {
+    match definition {
+        Definition::Rule(rule) => Definition::Rule(RuleDef {
+            location: SourceLocation::default(),
+            ..rule.clone()
+        }),
+        other => other.clone(),
+    }
 }
 
 impl GraphBuilder {
@@ -309,14 +521,66 @@ impl GraphBuilder {
             contexts: ContextPool {
                 contexts: Vec::new(),
             },
+            flow_outputs: HashMap::new(),
+            current_flow_outputs: Vec::new(),
+            symbol_table: HashMap::new(),
+            symbol_ids: HashMap::new(),
             source_map: HashMap::new(),
+            definition_cache: None,
+            definitions_lowered: 0,
+            definition_cache_hits: 0,
         }
     }
 
-    // Main function to convert a Program AST to an ExecutionGraph
+    /// Reuses the lowered nodes of definitions this builder has already seen,
+    /// matched by `definition_hash`, instead of lowering them again
+    pub fn enable_definition_cache(&mut self) {
+        self.definition_cache.get_or_insert_with(HashMap::new);
+    }
+
+    /// Number of definitions actually lowered, i.e. not served from the cache
+    pub fn definitions_lowered(&self) -> u32 {
+        self.definitions_lowered
+    }
+
+    pub fn definition_cache_hits(&self) -> u32 {
+        self.definition_cache_hits
+    }
+
+    /// Converts a Program AST to an ExecutionGraph.
+    ///
+    /// Every call builds a separate graph: node and edge ids restart at 0, and
+    /// nothing built by an earlier call is carried into it. Only the symbol
+    /// table and the definition cache persist across calls, so a symbol keeps
+    /// its id for the lifetime of the builder.
     pub fn build_execution_graph(&mut self, program: &Program) -> ExecutionGraph {
+        self.node_id_counter = 0;
+        self.edge_id_counter = 0;
+        self.nodes.clear();
+        self.edges.clear();
+        self.entry_points.clear();
+        self.flow_outputs.clear();
+
         // Process each definition in the program
         for definition in &program.definitions {
+            let key = self
+                .definition_cache
+                .as_ref()
+                .map(|_| definition_hash(definition));
+            // A matching hash alone could be a collision, so compare the definitions
+            let cached = key
+                .and_then(|key| self.definition_cache.as_ref()?.get(&key))
+                .filter(|cached| cached.definition == definition_content(definition))
+                .cloned();
+            if let Some(cached) = cached {
+                self.replay_definition(&cached);
+                self.definition_cache_hits += 1;
+                continue;
+            }
+
+            let (node_base, edge_base) = (self.node_id_counter, self.edge_id_counter);
+            let (first_node, first_edge, first_entry) =
+                (self.nodes.len(), self.edges.len(), self.entry_points.len());
             match definition {
                 Definition::Entity(entity_def) => {
                     self.process_entity_def(entity_def);
@@ -331,6 +595,31 @@ impl GraphBuilder {
                     self.process_constraint_def(constraint_def);
                 }
             }
+            self.definitions_lowered += 1;
+
+            if let Some(key) = key {
+                let mut flow_outputs: Vec<(u32, Vec<FlowOutput>)> = self
+                    .flow_outputs
+                    .iter()
+                    .filter(|(id, _)| **id >= node_base)
+                    .map(|(id, outputs)| (*id, outputs.clone()))
+                    .collect();
+                flow_outputs.sort_by_key(|(id, _)| *id);
+                let fragment = CachedDefinition {
+                    definition: definition_content(definition),
+                    node_base,
+                    edge_base,
+                    node_span: self.node_id_counter - node_base,
+                    edge_span: self.edge_id_counter - edge_base,
+                    nodes: self.nodes[first_node..].to_vec(),
+                    edges: self.edges[first_edge..].to_vec(),
+                    entry_points: self.entry_points[first_entry..].to_vec(),
+                    flow_outputs,
+                };
+                if let Some(cache) = self.definition_cache.as_mut() {
+                    cache.insert(key, fragment);
+                }
+            }
         }
 
         // Create the final execution graph
@@ -347,9 +636,86 @@ impl GraphBuilder {
                 build_hash: 0, // In a real implementation, this would be a proper hash
                 version: 1,
             },
+            flow_outputs: self.flow_outputs.clone(),
+            symbol_table: self.symbol_table.clone(),
         }
     }
 
+    // Appends a cached fragment, shifting its ids to the current counters
+    fn replay_definition(&mut self, cached: &CachedDefinition) {
+        let node = |id: u32| id - cached.node_base + self.node_id_counter;
+        let edge = |id: u32| id - cached.edge_base + self.edge_id_counter;
+
+        let mut nodes = cached.nodes.clone();
+        for specialized in &mut nodes {
+            match specialized {
+                SpecializedNode::If(n) => {
+                    n.true_edge = n.true_edge.map(edge);
+                    n.false_edge = n.false_edge.map(edge);
+                }
+                SpecializedNode::Loop(n) => {
+                    n.body_entry = n.body_entry.map(node);
+                    n.exit_edge = n.exit_edge.map(edge);
+                }
+                SpecializedNode::Rule(n) => n.rule_id = node(n.rule_id),
+                _ => {}
+            }
+            let base = specialized.get_base_mut();
+            base.id = node(base.id);
+            base.first_edge = edge(base.first_edge);
+        }
+        let edges: Vec<GraphEdge> = cached
+            .edges
+            .iter()
+            .map(|e| GraphEdge {
+                from_node: node(e.from_node),
+                to_node: node(e.to_node),
+                ..e.clone()
+            })
+            .collect();
+        let entry_points: Vec<EntryPoint> = cached
+            .entry_points
+            .iter()
+            .map(|e| EntryPoint {
+                node_id: node(e.node_id),
+                entry_type: e.entry_type,
+            })
+            .collect();
+        let flow_outputs: Vec<(u32, Vec<FlowOutput>)> = cached
+            .flow_outputs
+            .iter()
+            .map(|(id, outputs)| (node(*id), outputs.clone()))
+            .collect();
+
+        self.nodes.extend(nodes);
+        self.edges.extend(edges);
+        self.entry_points.extend(entry_points);
+        self.flow_outputs.extend(flow_outputs);
+        self.node_id_counter += cached.node_span;
+        self.edge_id_counter += cached.edge_span;
+    }
+
+    /// Symbols referenced by LOAD_SYM nodes, keyed by the id stored in their flags
+    pub fn symbol_table(&self) -> &HashMap<u32, String> {
+        &self.symbol_table
+    }
+
+    // Ids past u16::MAX don't fit in a node's flags. The symbol is still
+    // recorded, so `ExecutionGraph::validate` reports it as SymbolIdOverflow,
+    // and the node gets u16::MAX rather than a wrapped id naming another symbol.
+    fn intern_symbol(&mut self, name: &str) -> u16 {
+        let id = match self.symbol_ids.get(name) {
+            Some(&id) => id,
+            None => {
+                let id = self.symbol_table.len() as u32;
+                self.symbol_table.insert(id, name.to_string());
+                self.symbol_ids.insert(name.to_string(), id);
+                id
+            }
+        };
+        u16::try_from(id).unwrap_or(u16::MAX)
+    }
+
     fn process_entity_def(&mut self, entity_def: &EntityDef) {
         // Entities define data structures but don't create executable nodes
         // However, we might create nodes for validation or initialization
@@ -379,7 +745,8 @@ impl GraphBuilder {
         };
 
         // Create the specialized RuleNode
-        let rule_node = RuleNode::new(base_node, rule_node_id, 10, 0); // rule_id, priority, evaluation_mode
+        let mut rule_node = RuleNode::new(base_node, rule_node_id, 10, 0); // rule_id, priority, evaluation_mode
+        rule_node.name_symbol = Some(self.intern_symbol(&rule_def.name) as u32);
 
         // Store the RuleNode in our nodes vector using the SpecializedNode enum
         self.nodes.push(SpecializedNode::Rule(rule_node));
@@ -428,10 +795,27 @@ impl GraphBuilder {
             entry_type: 1, // 1 = flow
         });
 
+        // Bind each declared output to its own register, starting at R1 since
+        // register 0 means "no register". Outputs beyond R14 are left unbound;
+        // R15 is the assignment value register.
+        let outputs: Vec<FlowOutput> = flow_def
+            .outputs
+            .iter()
+            .zip(1..ASSIGNMENT_VALUE_REGISTER)
+            .map(|(name, register)| FlowOutput {
+                name: name.clone(),
+                register,
+            })
+            .collect();
+        self.current_flow_outputs = outputs.clone();
+        self.flow_outputs.insert(flow_node_id, outputs);
+
         // Process the flow's actions
         for action in &flow_def.actions {
             self.process_action(action, flow_node_id);
         }
+
+        self.current_flow_outputs.clear();
     }
 
     fn process_constraint_def(&mut self, constraint_def: &ConstraintDef) {
@@ -533,8 +917,8 @@ impl GraphBuilder {
                 self.nodes.push(SpecializedNode::Base(compare_node));
 
                 // Process the left and right terms
-                self.process_term(left, compare_node_id);
-                self.process_term(right, compare_node_id);
+                self.process_term(left, compare_node_id, 0);
+                self.process_term(right, compare_node_id, 0);
 
                 // Create an edge from the parent to this comparison node
                 self.create_edge(parent_node_id, compare_node_id, EdgeType::Data);
@@ -545,20 +929,22 @@ impl GraphBuilder {
         }
     }
 
-    fn process_term(&mut self, term: &Term, parent_node_id: u32) {
+    // Creates the load node for `term`, writing its value to `output_reg`
+    fn process_term(&mut self, term: &Term, parent_node_id: u32, output_reg: u16) {
         match term {
             Term::Identifier(name) => {
                 // Create a node to load the identifier value
                 let load_node_id = self.node_id_counter;
                 self.node_id_counter += 1;
+                let symbol_id = self.intern_symbol(name);
 
                 let load_node = GraphNode {
                     id: load_node_id,
                     node_type: GraphNodeType::Op,
-                    opcode: 0x10, // LOAD_SYM
-                    flags: 0,
+                    opcode: 0x10,     // LOAD_SYM
+                    flags: symbol_id, // symbol table id
                     input_regs: [0; 4],
-                    output_regs: [0; 2],
+                    output_regs: [output_reg, 0],
                     first_edge: self.edge_id_counter,
                     edge_count: 0,
                     meta: NodeMeta {
@@ -582,7 +968,7 @@ impl GraphBuilder {
                     opcode: 0x11, // LOAD_NUM
                     flags: 0,
                     input_regs: [0; 4],
-                    output_regs: [0; 2],
+                    output_regs: [output_reg, 0],
                     first_edge: self.edge_id_counter,
                     edge_count: 0,
                     meta: NodeMeta {
@@ -595,18 +981,20 @@ impl GraphBuilder {
                 self.nodes.push(SpecializedNode::Value(value_node));
                 self.create_edge(parent_node_id, load_node_id, EdgeType::Data);
             }
-            Term::QualifiedRef(_entity, _field) => {
+            Term::QualifiedRef(entity, field) => {
                 // Create a node to load the qualified reference
                 let load_node_id = self.node_id_counter;
                 self.node_id_counter += 1;
+                let name = format!("{}.{}", entity, field);
+                let symbol_id = self.intern_symbol(&name);
 
                 let load_node = GraphNode {
                     id: load_node_id,
                     node_type: GraphNodeType::Op,
-                    opcode: 0x10, // LOAD_SYM
-                    flags: 0,
+                    opcode: 0x10,     // LOAD_SYM
+                    flags: symbol_id, // symbol table id
                     input_regs: [0; 4],
-                    output_regs: [0; 2],
+                    output_regs: [output_reg, 0],
                     first_edge: self.edge_id_counter,
                     edge_count: 0,
                     meta: NodeMeta {
@@ -615,7 +1003,8 @@ impl GraphBuilder {
                     },
                 };
 
-                self.nodes.push(SpecializedNode::Base(load_node));
+                let value_node = ValueNode::new_sym(load_node, name);
+                self.nodes.push(SpecializedNode::Value(value_node));
                 self.create_edge(parent_node_id, load_node_id, EdgeType::Data);
             }
         }
@@ -646,7 +1035,7 @@ impl GraphBuilder {
 
         // Process the arguments
         for arg in &predicate.arguments {
-            self.process_term(arg, pred_node_id);
+            self.process_term(arg, pred_node_id, 0);
         }
 
         // Create an edge from the parent to this predicate node
@@ -672,13 +1061,20 @@ impl GraphBuilder {
         let assign_node_id = self.node_id_counter;
         self.node_id_counter += 1;
 
+        // Assignments to a declared flow output write that output's register
+        let output_reg = self
+            .current_flow_outputs
+            .iter()
+            .find(|output| output.name == assignment.variable)
+            .map_or(0, |output| output.register);
+
         let assign_node = GraphNode {
             id: assign_node_id,
             node_type: GraphNodeType::Op,
             opcode: 0x12, // MOVE
             flags: 0,
-            input_regs: [0; 4],
-            output_regs: [0; 2],
+            input_regs: [ASSIGNMENT_VALUE_REGISTER, 0, 0, 0],
+            output_regs: [output_reg, 0],
             first_edge: self.edge_id_counter,
             edge_count: 0,
             meta: NodeMeta {
@@ -689,8 +1085,8 @@ impl GraphBuilder {
 
         self.nodes.push(SpecializedNode::Base(assign_node));
 
-        // Process the value being assigned
-        self.process_term(&assignment.value, assign_node_id);
+        // Load the value being assigned into the register the MOVE reads
+        self.process_term(&assignment.value, assign_node_id, ASSIGNMENT_VALUE_REGISTER);
 
         // Create an edge from the parent to this assignment node
         self.create_edge(parent_node_id, assign_node_id, EdgeType::Data);
@@ -831,7 +1227,7 @@ impl GraphBuilder {
     /// Validates the execution graph according to KERN specifications
     pub fn validate_graph(&self, graph: &ExecutionGraph) -> Result<(), String> {
         // Check for unreachable nodes
-        let reachable = self.find_reachable_nodes(graph);
+        let reachable = graph.reachable_nodes();
 
         for (i, node) in graph.nodes.iter().enumerate() {
             let node_id = match node {
@@ -867,30 +1263,6 @@ impl GraphBuilder {
         Ok(())
     }
 
-    /// Finds all reachable nodes in the graph using DFS
-    fn find_reachable_nodes(&self, graph: &ExecutionGraph) -> std::collections::HashSet<u32> {
-        let mut visited = std::collections::HashSet::new();
-        let mut stack = Vec::new();
-
-        // Start from all entry points
-        for entry_point in &graph.entry_points {
-            stack.push(entry_point.node_id);
-        }
-
-        while let Some(node_id) = stack.pop() {
-            if visited.insert(node_id) {
-                // Find all outgoing edges from this node
-                for edge in &graph.edges {
-                    if edge.from_node == node_id {
-                        stack.push(edge.to_node);
-                    }
-                }
-            }
-        }
-
-        visited
-    }
-
     /// Checks if the graph contains cycles
     pub fn has_cycles(&self, graph: &ExecutionGraph) -> bool {
         let mut visited = vec![false; graph.node_count as usize];
@@ -1035,7 +1407,7 @@ impl GraphBuilder {
 
     /// Removes nodes that are not reachable from entry points
     fn remove_unreachable_nodes(&mut self, graph: &mut ExecutionGraph) -> bool {
-        let reachable = self.find_reachable_nodes(graph);
+        let reachable = graph.reachable_nodes();
         let mut removed = false;
 
         // Create a mapping from old indices to new indices
@@ -1141,8 +1513,8 @@ mod tests {
         }
 
         rule CheckLocation:
-            if location == "valid"
-            then output("Farmer approved")
+            if location == valid
+            then approve_farmer(farmer)
         "#;
 
         let mut parser = Parser::new(input);
@@ -1165,4 +1537,239 @@ mod tests {
             graph.edges.len()
         );
     }
+
+    #[test]
+    fn test_load_sym_nodes_reference_symbol_table() {
+        let input = "rule CheckLocation: if farmer.location == valid then approve(farmer)";
+        let program = Parser::new(input).parse_program().unwrap();
+
+        let mut builder = GraphBuilder::new();
+        let graph = builder.build_execution_graph(&program);
+
+        let mut loaded: Vec<&str> = graph
+            .nodes
+            .iter()
+            .map(|n| n.get_base())
+            .filter(|n| n.opcode == 0x10)
+            .map(|n| builder.symbol_table()[&(n.flags as u32)].as_str())
+            .collect();
+        loaded.sort();
+        assert_eq!(loaded, vec!["farmer", "farmer.location", "valid"]);
+        // The rule's own name is interned alongside the loaded symbols
+        assert_eq!(builder.symbol_table().len(), 4);
+        let rule = graph
+            .nodes
+            .iter()
+            .find_map(|n| match n {
+                SpecializedNode::Rule(rule) => Some(rule),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(
+            builder.symbol_table()[&rule.name_symbol.unwrap()],
+            "CheckLocation"
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_dangling_references() {
+        let input = "flow Totals(total) { total = 5 }";
+        let program = Parser::new(input).parse_program().unwrap();
+        let mut graph = GraphBuilder::new().build_execution_graph(&program);
+        assert_eq!(graph.validate(), Ok(()));
+
+        graph.entry_points.push(EntryPoint {
+            node_id: 999,
+            entry_type: 0,
+        });
+        let existing = graph.nodes[0].get_base().id;
+        graph.edges.push(GraphEdge::new_data(998, existing));
+        assert_eq!(
+            graph.validate(),
+            Err(vec![
+                GraphError::DanglingEntryPoint(999),
+                GraphError::DanglingEdgeSource(graph.edges.len() - 1, 998),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_symbol_id_overflow() {
+        let mut builder = GraphBuilder::new();
+        for index in 0..=u16::MAX as u32 {
+            builder.intern_symbol(&format!("s{}", index));
+        }
+        assert_eq!(builder.intern_symbol("s65535"), u16::MAX);
+        assert_eq!(builder.intern_symbol("overflow"), u16::MAX);
+
+        // `price` is interned while building, past the last addressable id
+        let program = Parser::new("flow Totals(total) { total = price }")
+            .parse_program()
+            .unwrap();
+        let graph = builder.build_execution_graph(&program);
+        assert_eq!(
+            graph.validate(),
+            Err(vec![
+                GraphError::SymbolIdOverflow(65536),
+                GraphError::SymbolIdOverflow(65537),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_flow_outputs_bound_to_registers() {
+        let input = "flow Totals(total, count) { total = 5, other = 1 }";
+        let program = Parser::new(input).parse_program().unwrap();
+
+        let mut builder = GraphBuilder::new();
+        let graph = builder.build_execution_graph(&program);
+
+        let flow_id = graph.entry_points[0].node_id;
+        let outputs = &graph.flow_outputs[&flow_id];
+        assert_eq!(
+            outputs,
+            &vec![
+                FlowOutput {
+                    name: "total".to_string(),
+                    register: 1,
+                },
+                FlowOutput {
+                    name: "count".to_string(),
+                    register: 2,
+                },
+            ]
+        );
+
+        // Only the assignment to the declared output writes its register
+        let move_outputs: Vec<u16> = graph
+            .nodes
+            .iter()
+            .map(|n| n.get_base())
+            .filter(|n| n.opcode == 0x12)
+            .map(|n| n.output_regs[0])
+            .collect();
+        assert_eq!(move_outputs, vec![1, 0]);
+
+        // Each MOVE reads the register its value's load writes
+        for assign in graph.nodes.iter().map(|n| n.get_base()) {
+            if assign.opcode != 0x12 {
+                continue;
+            }
+            let edge = graph
+                .edges
+                .iter()
+                .find(|e| e.from_node == assign.id)
+                .unwrap();
+            let load = graph.nodes.iter().find(|n| n.id() == edge.to_node).unwrap();
+            assert_eq!(load.get_base().output_regs[0], assign.input_regs[0]);
+        }
+    }
+
+    #[test]
+    fn test_definition_cache_reuses_identical_definitions() {
+        let first = "entity Farmer { id location } \
+            rule CheckLocation: if farmer.location == valid then approve(farmer)";
+        let second = "entity Farmer { id location } \
+            rule CheckId: if farmer.id == 1 then reject(farmer) \
+            rule CheckLocation: if farmer.location == valid then approve(farmer)";
+
+        let mut builder = GraphBuilder::new();
+        builder.enable_definition_cache();
+        let first_graph =
+            builder.build_execution_graph(&Parser::new(first).parse_program().unwrap());
+        assert_eq!(builder.definitions_lowered(), 2);
+        assert_eq!(builder.definition_cache_hits(), 0);
+
+        let graph = builder.build_execution_graph(&Parser::new(second).parse_program().unwrap());
+        // Only CheckId is new; Farmer and CheckLocation come from the cache
+        assert_eq!(builder.definitions_lowered(), 3);
+        assert_eq!(builder.definition_cache_hits(), 2);
+        assert_eq!(graph.validate(), Ok(()));
+
+        // The reused rule lands after CheckId with its ids shifted to match
+        let ids: Vec<u32> = graph.nodes.iter().map(|n| n.id()).collect();
+        assert_eq!(ids, (0..graph.nodes.len() as u32).collect::<Vec<_>>());
+        let reused = graph.entry_points[1].node_id;
+        assert_eq!(reused as usize, graph.nodes.len() - first_graph.nodes.len());
+        match &graph.nodes[reused as usize] {
+            SpecializedNode::Rule(rule) => assert_eq!(rule.rule_id, reused),
+            other => panic!("expected a rule node, got {:?}", other),
+        }
+        assert_eq!(graph.edges.len(), first_graph.edges.len() * 2);
+
+        // Any change to a definition misses the cache
+        let changed = "entity Farmer { id location produce }";
+        builder.build_execution_graph(&Parser::new(changed).parse_program().unwrap());
+        assert_eq!(builder.definitions_lowered(), 4);
+        assert_eq!(
+            definition_hash(&Parser::new(first).parse_program().unwrap().definitions[0]),
+            definition_hash(&Parser::new(second).parse_program().unwrap().definitions[0])
+        );
+    }
+
+    #[test]
+    fn test_definition_cache_hit_requires_equal_definition() {
+        let cached =
+            Parser::new("rule CheckLocation: if farmer.location == valid then approve(farmer)")
+                .parse_program()
+                .unwrap();
+        let other = Parser::new("rule CheckId: if farmer.id == 1 then reject(farmer)")
+            .parse_program()
+            .unwrap();
+
+        let mut builder = GraphBuilder::new();
+        builder.enable_definition_cache();
+        builder.build_execution_graph(&cached);
+
+        // File CheckLocation's fragment under CheckId's hash, as a collision would
+        let cache = builder.definition_cache.as_mut().unwrap();
+        let fragment = cache
+            .remove(&definition_hash(&cached.definitions[0]))
+            .unwrap();
+        cache.insert(definition_hash(&other.definitions[0]), fragment);
+
+        let graph = builder.build_execution_graph(&other);
+        assert_eq!(builder.definition_cache_hits(), 0);
+        assert_eq!(builder.definitions_lowered(), 2);
+        let symbols: Vec<&str> = graph
+            .nodes
+            .iter()
+            .filter_map(|n| match n {
+                SpecializedNode::Value(value) if value.base.opcode == 0x10 => {
+                    Some(value.value_sym.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        assert!(symbols.contains(&"farmer.id"));
+        assert!(!symbols.contains(&"farmer.location"));
+    }
+
+    #[test]
+    fn test_each_build_starts_a_new_graph() {
+        let first =
+            Parser::new("rule CheckLocation: if farmer.location == valid then approve(farmer)")
+                .parse_program()
+                .unwrap();
+        let second = Parser::new("rule CheckId: if farmer.id == 1 then reject(farmer)")
+            .parse_program()
+            .unwrap();
+
+        let mut builder = GraphBuilder::new();
+        builder.build_execution_graph(&first);
+        let location_id = builder.symbol_ids["farmer.location"];
+        let graph = builder.build_execution_graph(&second);
+
+        // Nothing from the first build leaks into the second graph
+        let fresh = GraphBuilder::new().build_execution_graph(&second);
+        let ids: Vec<u32> = graph.nodes.iter().map(|n| n.id()).collect();
+        let fresh_ids: Vec<u32> = fresh.nodes.iter().map(|n| n.id()).collect();
+        assert_eq!(ids, fresh_ids);
+        assert_eq!(graph.edges.len(), fresh.edges.len());
+        assert_eq!(graph.entry_points.len(), 1);
+
+        // The symbol table carries over, keeping earlier symbols' ids
+        assert_eq!(builder.symbol_ids["farmer.location"], location_id);
+        assert!(builder.symbol_table().contains_key(&location_id));
+    }
 }
@@ -1,6 +1,7 @@
 mod graph_builder;
 pub use graph_builder::{
-    Context, ContextPool, EdgeType, EntryPoint, ExecutionGraph, GraphBuilder, GraphEdge, GraphMeta,
-    GraphNode, GraphNodeType, GraphOpNode, IfNode, LoopNode, Register, RegisterSet, RuleNode,
-    SpecializedNode,
+    definition_hash, Context, ContextPool, EdgeType, EntryPoint, ExecutionGraph, FlowOutput,
+    GraphBuilder, GraphEdge, GraphError, GraphMeta, GraphNode, GraphNodeType, GraphOpNode, IfNode,
+    LoopNode, NodeMeta, Register, RegisterSet, RuleNode, SpecializedNode, ValueNode,
+    ASSIGNMENT_VALUE_REGISTER, LOOP_EXIT_EDGE, OP_ADD, OP_DIV, OP_MOD, OP_MUL, OP_SUB,
 };
@@ -58,14 +58,9 @@ impl Lexer {
         }
     }
 
+    // read_char keeps line and column up to date
     fn skip_whitespace(&mut self) {
         while self.ch.is_whitespace() {
-            if self.ch == '\n' {
-                self.line += 1;
-                self.column = 0;
-            } else {
-                self.column += 1;
-            }
             self.read_char();
         }
     }
@@ -83,12 +78,6 @@ impl Lexer {
 
         // Continue until we find the closing quote or reach end of input
         while self.ch != '\0' && self.ch != quote_char {
-            if self.ch == '\n' {
-                self.line += 1;
-                self.column = 0;
-            } else {
-                self.column += 1;
-            }
             self.read_char();
         }
 
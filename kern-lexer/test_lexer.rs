@@ -257,7 +257,7 @@ entity Farmer {
 }
 
 rule CheckLocation:
-    if farmer.location == "valid"
+    if farmer.location == valid
     then approve_farmer(farmer)
 "#;
 
@@ -22,7 +22,7 @@ rule ValidateFarmer:
     then mark_valid(farmer)
 
 rule CheckLocation:
-    if farmer.location == "valid"
+    if farmer.location == valid
     then approve_farmer(farmer)
 
 flow ProcessFarmers {
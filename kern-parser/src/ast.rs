@@ -46,6 +46,14 @@ pub struct RuleDef {
     pub name: String,
     pub condition: Condition,
     pub actions: Vec<Action>,
+    pub location: SourceLocation, // The `rule` keyword
+}
+
+// 1-based line and column of a node in the source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -131,6 +139,7 @@ pub struct HaltAction;
 #[derive(Debug, Clone, PartialEq)]
 pub struct FlowDef {
     pub name: String,
+    pub outputs: Vec<String>, // declared named outputs, e.g. `flow Total(total) { ... }`
     pub actions: Vec<Action>,
 }
 
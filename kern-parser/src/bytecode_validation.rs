@@ -364,6 +364,7 @@ mod tests {
                         name: "validate_farmer".to_string(),
                         arguments: vec![Term::Identifier("Farmer".to_string())],
                     })],
+                    location: SourceLocation::default(),
                 }),
             ],
         };
@@ -387,6 +388,7 @@ mod tests {
                     right: Box::new(Term::Number(0)),
                 }),
                 actions: vec![],
+                location: SourceLocation::default(),
             })],
         };
 
@@ -408,6 +408,7 @@ mod tests {
                         name: "validate_farmer".to_string(),
                         arguments: vec![Term::Identifier("Farmer".to_string())],
                     })],
+                    location: SourceLocation::default(),
                 }),
             ],
         };
@@ -443,6 +444,7 @@ mod tests {
                         arguments: vec![],
                     })),
                     actions: vec![],
+                    location: SourceLocation::default(),
                 }),
                 Definition::Rule(RuleDef {
                     name: "RuleB".to_string(),
@@ -451,6 +453,7 @@ mod tests {
                         arguments: vec![],
                     })),
                     actions: vec![],
+                    location: SourceLocation::default(),
                 }),
             ],
         };
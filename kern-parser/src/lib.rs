@@ -5,6 +5,7 @@ pub mod symbol_table;
 pub mod dependency_analysis;
 pub mod rule_conflict_detection;
 pub mod bytecode_validation;
+pub mod pretty_printer;
 
 pub use ast::*;
 pub use parser::{Parser, ParseError, ParseErrorType};
@@ -12,4 +13,5 @@ pub use type_checker::{TypeChecker, Type, TypeError};
 pub use symbol_table::{SymbolTable, Symbol, SymbolKind};
 pub use dependency_analysis::{DependencyAnalyzer, DependencyGraph, Dependency, DependencyKind};
 pub use rule_conflict_detection::{RuleConflictDetector, RuleConflict, ConflictType};
-pub use bytecode_validation::{BytecodeCompatibilityValidator, BytecodeValidationError};
\ No newline at end of file
+pub use bytecode_validation::{BytecodeCompatibilityValidator, BytecodeValidationError};
+pub use pretty_printer::pretty_print;
\ No newline at end of file
@@ -169,13 +169,59 @@ impl Parser {
         false
     }
 
+    /// Parses the whole input, failing with every error found if any
+    /// definition is malformed, including errors inside terms and comparators.
     pub fn parse_program(&mut self) -> Result<Program, Vec<ParseError>> {
+        let (program, errors) = self.parse_program_with_diagnostics();
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(program)
+    }
+
+    /// Parses as much of the input as possible, returning the definitions that
+    /// parsed cleanly together with every error found along the way, in source
+    /// order. With recovery enabled a malformed definition is skipped up to the
+    /// next `entity`/`rule`/`flow`/`constraint` keyword, so it doesn't hide the
+    /// definitions after it.
+    pub fn parse_program_with_diagnostics(&mut self) -> (Program, Vec<ParseError>) {
+        let (program, unrecorded) = self.parse_definitions();
+        let mut errors = self.errors.clone();
+        errors.extend(unrecorded);
+        errors.sort_by_key(|e| e.position);
+        (program, errors)
+    }
+
+    // Parses definitions until EOF, returning them with the errors that failed
+    // a definition without being pushed to `self.errors`
+    fn parse_definitions(&mut self) -> (Program, Vec<ParseError>) {
         let mut definitions = Vec::new();
+        let mut unrecorded = Vec::new();
 
         while !self.is_at_end() {
-            if let Ok(Some(definition)) = self.parse_definition() {
-                definitions.push(definition);
-            } else if self.recovery_enabled {
+            match self.parse_definition() {
+                Ok(Some(definition)) => {
+                    definitions.push(definition);
+                    continue;
+                }
+                Ok(None) => {}
+                Err(errors) => {
+                    // Failure paths that push their error return it too
+                    for error in errors {
+                        let recorded = self
+                            .errors
+                            .iter()
+                            .any(|e| e.position == error.position && e.message == error.message);
+                        if !recorded {
+                            unrecorded.push(error);
+                        }
+                    }
+                }
+            }
+
+            if self.recovery_enabled {
                 // If we couldn't parse a definition, skip tokens until we find the start of another definition
                 self.skip_until(&[
                     TokenType::Entity,
@@ -189,11 +235,7 @@ impl Parser {
             }
         }
 
-        if !self.errors.is_empty() {
-            return Err(self.errors.clone());
-        }
-
-        Ok(Program { definitions })
+        (Program { definitions }, unrecorded)
     }
 
     fn parse_definition(&mut self) -> Result<Option<Definition>, Vec<ParseError>> {
@@ -325,6 +367,10 @@ impl Parser {
     }
 
     fn parse_rule_def(&mut self) -> Result<RuleDef, Vec<ParseError>> {
+        let location = SourceLocation {
+            line: self.current_token.line,
+            column: self.current_token.column,
+        };
         self.expect_token(TokenType::Rule)?;
 
         let _name_token = self.current_token.clone();
@@ -398,6 +444,7 @@ impl Parser {
             name,
             condition,
             actions,
+            location,
         })
     }
 
@@ -741,6 +788,8 @@ impl Parser {
         };
         self.next_token(); // consume identifier
 
+        let outputs = self.parse_flow_outputs()?;
+
         if !self.is_current_token(&TokenType::LeftBrace) {
             return Err(vec![ParseError {
                 message: format!(
@@ -769,7 +818,47 @@ impl Parser {
         }
         self.next_token(); // consume '}'
 
-        Ok(FlowDef { name, actions })
+        Ok(FlowDef {
+            name,
+            outputs,
+            actions,
+        })
+    }
+
+    fn parse_flow_outputs(&mut self) -> Result<Vec<String>, Vec<ParseError>> {
+        // Declared outputs are optional: `flow Name(out1, out2) { ... }`
+        let mut outputs = Vec::new();
+        if !self.is_current_token(&TokenType::LeftParen) {
+            return Ok(outputs);
+        }
+        self.next_token(); // consume '('
+
+        while !self.is_current_token(&TokenType::RightParen) {
+            if let TokenType::Identifier(output) = &self.current_token.token_type {
+                outputs.push(output.clone());
+            } else {
+                return Err(vec![ParseError {
+                    message: format!(
+                        "Expected identifier for flow output, got {:?}",
+                        self.current_token.token_type
+                    ),
+                    line: self.current_token.line,
+                    column: self.current_token.column,
+                    position: self.current_token.position,
+                }]);
+            }
+            self.next_token(); // consume identifier
+
+            if self.is_current_token(&TokenType::Comma) {
+                self.next_token(); // consume comma
+            } else {
+                break;
+            }
+        }
+
+        self.expect_token(TokenType::RightParen)?;
+
+        Ok(outputs)
     }
 
     fn parse_constraint_def(&mut self) -> Result<ConstraintDef, Vec<ParseError>> {
@@ -910,6 +999,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_flow_outputs() {
+        let input = "flow Totals(total, count) { total = 5 }";
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program().unwrap();
+
+        if let Definition::Flow(flow) = &program.definitions[0] {
+            assert_eq!(flow.outputs, vec!["total".to_string(), "count".to_string()]);
+            assert_eq!(flow.actions.len(), 1);
+        } else {
+            panic!("Expected flow definition");
+        }
+    }
+
     #[test]
     fn test_parse_constraint() {
         let input = "constraint TestConstraint: value > 0";
@@ -0,0 +1,240 @@
+use crate::ast::*;
+
+const INDENT: &str = "    ";
+
+/// Re-emit a program as canonical KERN source.
+///
+/// Definitions are separated by a blank line, entity fields and flow or
+/// loop bodies are indented by four spaces, and rule actions stay on the
+/// rule line. Conditions are printed without parentheses, so `and` binds
+/// tighter than `or` exactly as the parser reads them back. An inline `if`
+/// action takes every action after it, so it only round-trips as the last
+/// action of its list, which is the only place the parser produces one.
+pub fn pretty_print(program: &Program) -> String {
+    let mut printer = PrettyPrinter::default();
+    for (index, definition) in program.definitions.iter().enumerate() {
+        if index > 0 {
+            printer.out.push('\n');
+        }
+        printer.definition(definition);
+    }
+    printer.out
+}
+
+#[derive(Default)]
+struct PrettyPrinter {
+    out: String,
+    indent: usize,
+}
+
+impl PrettyPrinter {
+    fn definition(&mut self, definition: &Definition) {
+        match definition {
+            Definition::Entity(entity) => self.entity(entity),
+            Definition::Rule(rule) => {
+                self.out.push_str(&format!("rule {}: if ", rule.name));
+                self.condition(&rule.condition);
+                self.out.push_str(" then ");
+                self.inline_actions(&rule.actions);
+                self.out.push('\n');
+            }
+            Definition::Flow(flow) => {
+                self.out.push_str(&format!("flow {}", flow.name));
+                if !flow.outputs.is_empty() {
+                    self.out.push_str(&format!("({})", flow.outputs.join(", ")));
+                }
+                self.out.push(' ');
+                self.block(&flow.actions);
+                self.out.push('\n');
+            }
+            Definition::Constraint(constraint) => {
+                self.out
+                    .push_str(&format!("constraint {}: ", constraint.name));
+                self.condition(&constraint.condition);
+                self.out.push('\n');
+            }
+        }
+    }
+
+    fn entity(&mut self, entity: &EntityDef) {
+        if entity.fields.is_empty() {
+            self.out.push_str(&format!("entity {} {{}}\n", entity.name));
+            return;
+        }
+        self.out.push_str(&format!("entity {} {{\n", entity.name));
+        for field in &entity.fields {
+            self.out.push_str(&format!("{}{}\n", INDENT, field.name));
+        }
+        self.out.push_str("}\n");
+    }
+
+    fn condition(&mut self, condition: &Condition) {
+        match condition {
+            Condition::Expression(expression) => self.expression(expression),
+            Condition::LogicalOp(left, op, right) => {
+                self.condition(left);
+                self.out.push_str(match op {
+                    LogicalOp::And => " and ",
+                    LogicalOp::Or => " or ",
+                });
+                self.condition(right);
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Comparison { left, op, right } => {
+                let op = match op {
+                    Comparator::Equal => "==",
+                    Comparator::NotEqual => "!=",
+                    Comparator::Greater => ">",
+                    Comparator::Less => "<",
+                    Comparator::GreaterEqual => ">=",
+                    Comparator::LessEqual => "<=",
+                };
+                self.out
+                    .push_str(&format!("{} {} {}", term(left), op, term(right)));
+            }
+            Expression::Predicate(predicate) => self.out.push_str(&predicate_call(predicate)),
+        }
+    }
+
+    // Actions separated by ", " on the current line
+    fn inline_actions(&mut self, actions: &[Action]) {
+        for (index, action) in actions.iter().enumerate() {
+            if index > 0 {
+                self.out.push_str(", ");
+            }
+            self.action(action);
+        }
+    }
+
+    // `{`, one action per line at the next indent level, then `}`
+    fn block(&mut self, actions: &[Action]) {
+        if actions.is_empty() {
+            self.out.push_str("{}");
+            return;
+        }
+        self.out.push_str("{\n");
+        self.indent += 1;
+        for (index, action) in actions.iter().enumerate() {
+            self.out.push_str(&INDENT.repeat(self.indent));
+            self.action(action);
+            if index + 1 < actions.len() {
+                self.out.push(',');
+            }
+            self.out.push('\n');
+        }
+        self.indent -= 1;
+        self.out.push_str(&INDENT.repeat(self.indent));
+        self.out.push('}');
+    }
+
+    fn action(&mut self, action: &Action) {
+        match action {
+            Action::Predicate(predicate) => self.out.push_str(&predicate_call(predicate)),
+            Action::Assignment(assignment) => self.out.push_str(&format!(
+                "{} = {}",
+                assignment.variable,
+                term(&assignment.value)
+            )),
+            Action::Control(ControlAction::If(if_action)) => {
+                self.out.push_str("if ");
+                self.condition(&if_action.condition);
+                self.out.push_str(" then ");
+                self.inline_actions(&if_action.then_actions);
+                if let Some(else_actions) = &if_action.else_actions {
+                    self.out.push_str(" else ");
+                    self.inline_actions(else_actions);
+                }
+            }
+            Action::Control(ControlAction::Loop(loop_action)) => {
+                self.out.push_str("loop ");
+                self.block(&loop_action.actions);
+            }
+            Action::Control(ControlAction::Halt(_)) => self.out.push_str("halt"),
+        }
+    }
+}
+
+fn term(term: &Term) -> String {
+    match term {
+        Term::Identifier(name) => name.clone(),
+        Term::Number(value) => value.to_string(),
+        Term::QualifiedRef(entity, field) => format!("{}.{}", entity, field),
+    }
+}
+
+fn predicate_call(predicate: &Predicate) -> String {
+    let arguments: Vec<String> = predicate.arguments.iter().map(term).collect();
+    format!("{}({})", predicate.name, arguments.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    fn parse(source: &str) -> Program {
+        let mut program = Parser::new(source)
+            .parse_program()
+            .expect("source should parse");
+        // Rule locations depend on layout, not structure
+        for definition in &mut program.definitions {
+            if let Definition::Rule(rule) = definition {
+                rule.location = SourceLocation::default();
+            }
+        }
+        program
+    }
+
+    #[test]
+    fn test_pretty_print_canonical_layout() {
+        let program = parse(
+            "entity Farmer { id   location }
+             rule Check:if farmer.id>0 and ready or x==1 then approve(farmer,  1),count=2
+             flow Pipeline(total){load(x),loop{step(),halt}}
+             constraint Positive:x>=0",
+        );
+
+        assert_eq!(
+            pretty_print(&program),
+            "entity Farmer {
+    id
+    location
+}
+
+rule Check: if farmer.id > 0 and ready() or x == 1 then approve(farmer, 1), count = 2
+
+flow Pipeline(total) {
+    load(x),
+    loop {
+        step(),
+        halt
+    }
+}
+
+constraint Positive: x >= 0
+"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_round_trips() {
+        let program = parse(
+            "entity Farmer { id location produce }
+             entity Empty { }
+             rule Approve: if farmer.location == valid or farmer.id != 0 and check(farmer) then approve(farmer), status = 1
+             rule Nested: if x < 10 then loop { x = 1 }, if y <= 2 then halt else mark(y)
+             flow Process { load(farmer), validate(farmer) }
+             flow Total(total, count) { total = 0, if x > 1 then halt }
+             constraint ValidId: farmer.id > 0 and farmer.id < 1000",
+        );
+        assert_eq!(program.definitions.len(), 7);
+
+        let printed = pretty_print(&program);
+        assert_eq!(parse(&printed), program);
+        assert_eq!(pretty_print(&parse(&printed)), printed);
+    }
+}
@@ -14,6 +14,8 @@ pub enum ConflictType {
 pub struct RuleConflict {
     pub rule1: String,
     pub rule2: String,
+    pub rule1_location: SourceLocation,
+    pub rule2_location: SourceLocation,
     pub conflict_type: ConflictType,
     pub description: String,
 }
@@ -75,6 +77,8 @@ impl RuleConflictDetector {
             self.conflicts.push(RuleConflict {
                 rule1: rule1.name.clone(),
                 rule2: rule2.name.clone(),
+                rule1_location: rule1.location,
+                rule2_location: rule2.location,
                 conflict_type: ConflictType::ContradictoryConditions,
                 description: format!(
                     "Rules '{}' and '{}' have equivalent conditions",
@@ -100,6 +104,8 @@ impl RuleConflictDetector {
                     self.conflicts.push(RuleConflict {
                         rule1: rule1.name.clone(),
                         rule2: rule2.name.clone(),
+                        rule1_location: rule1.location,
+                        rule2_location: rule2.location,
                         conflict_type: ConflictType::ConflictingActions,
                         description: format!(
                             "Rules '{}' and '{}' both assign to variable '{}'",
@@ -122,6 +128,8 @@ impl RuleConflictDetector {
                     self.conflicts.push(RuleConflict {
                         rule1: rule1.name.clone(),
                         rule2: rule2.name.clone(),
+                        rule1_location: rule1.location,
+                        rule2_location: rule2.location,
                         conflict_type: ConflictType::StateConflict,
                         description: format!(
                             "Rules '{}' and '{}' both modify entity '{}'",
@@ -270,6 +278,7 @@ mod tests {
                         name: "action_a".to_string(),
                         arguments: vec![],
                     })],
+                    location: SourceLocation::default(),
                 }),
                 Definition::Rule(RuleDef {
                     name: "RuleB".to_string(),
@@ -282,6 +291,7 @@ mod tests {
                         name: "action_b".to_string(),
                         arguments: vec![],
                     })],
+                    location: SourceLocation::default(),
                 }),
             ],
         };
@@ -308,6 +318,7 @@ mod tests {
                         variable: "result".to_string(),
                         value: Term::Number(1),
                     })],
+                    location: SourceLocation::default(),
                 }),
                 Definition::Rule(RuleDef {
                     name: "RuleB".to_string(),
@@ -320,6 +331,7 @@ mod tests {
                         variable: "result".to_string(),
                         value: Term::Number(2),
                     })],
+                    location: SourceLocation::default(),
                 }),
             ],
         };
@@ -552,6 +552,7 @@ mod tests {
                         name: "validate_farmer".to_string(),
                         arguments: vec![Term::Identifier("Farmer".to_string())],
                     })],
+                    location: SourceLocation::default(),
                 }),
             ],
         };
@@ -575,6 +576,7 @@ mod tests {
                     right: Box::new(Term::Identifier("undefined_var".to_string())), // This variable doesn't exist
                 }),
                 actions: vec![],
+                location: SourceLocation::default(),
             })],
         };
 
@@ -632,6 +634,7 @@ mod tests {
                     right: Box::new(Term::Number(42)),
                 }),
                 actions: vec![],
+                location: SourceLocation::default(),
             })],
         };
 
@@ -670,6 +673,7 @@ mod tests {
                         right: Box::new(Term::Number(42)),
                     }),
                     actions: vec![],
+                    location: SourceLocation::default(),
                 }),
             ],
         };
@@ -12,11 +12,10 @@ fn test_entity_definition_recovery() {
     "#;
 
     let mut parser = Parser::new(input);
-    let result = parser.parse_program();
+    let (program, errors) = parser.parse_program_with_diagnostics();
 
     // The parser should still be able to parse the complete entity after the malformed one
-    assert!(result.is_ok());
-    let program = result.unwrap();
+    assert!(!errors.is_empty());
 
     // Should have recovered and parsed the complete entity
     assert_eq!(program.definitions.len(), 1);
@@ -37,11 +36,10 @@ fn test_rule_definition_recovery() {
     "#;
 
     let mut parser = Parser::new(input);
-    let result = parser.parse_program();
+    let (program, errors) = parser.parse_program_with_diagnostics();
 
     // The parser should still be able to parse the complete rule after the malformed one
-    assert!(result.is_ok());
-    let program = result.unwrap();
+    assert!(!errors.is_empty());
 
     // Should have recovered and parsed the complete rule
     assert_eq!(program.definitions.len(), 1);
@@ -64,11 +62,10 @@ fn test_mixed_recovery() {
     "#;
 
     let mut parser = Parser::new(input);
-    let result = parser.parse_program();
+    let (program, errors) = parser.parse_program_with_diagnostics();
 
     // The parser should recover from multiple errors and parse valid definitions
-    assert!(result.is_ok());
-    let program = result.unwrap();
+    assert!(!errors.is_empty());
 
     // Should have recovered and parsed the valid definitions
     assert_eq!(program.definitions.len(), 2);
@@ -102,11 +99,10 @@ fn test_error_recovery_with_valid_definitions_after_error() {
     "#;
 
     let mut parser = Parser::new(input);
-    let result = parser.parse_program();
+    let (program, errors) = parser.parse_program_with_diagnostics();
 
     // Should parse the first entity and the valid rule after the error
-    assert!(result.is_ok());
-    let program = result.unwrap();
+    assert!(!errors.is_empty());
 
     // Should have recovered and parsed the valid definitions
     assert_eq!(program.definitions.len(), 3);
@@ -131,6 +127,52 @@ fn test_error_recovery_with_valid_definitions_after_error() {
     }
 }
 
+#[test]
+fn test_independent_errors_reported_in_one_pass() {
+    let input = r#"
+        rule Broken: if value > then action()
+        entity Farmer { id name }
+        rule AlsoBroken if value == 1 then action()
+        entity Plot { size }
+    "#;
+
+    let mut parser = Parser::new(input);
+    let (program, errors) = parser.parse_program_with_diagnostics();
+
+    // Both malformed rules are reported, in source order
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].message.contains("Expected term"));
+    assert!(errors[1].message.contains("Expected ':' after rule name"));
+    assert!(errors[0].position < errors[1].position);
+
+    // Neither error hides the entities after it
+    let names: Vec<&str> = program
+        .definitions
+        .iter()
+        .filter_map(|def| match def {
+            Definition::Entity(entity) => Some(entity.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, vec!["Farmer", "Plot"]);
+}
+
+#[test]
+fn test_parse_program_rejects_malformed_term() {
+    let input = r#"
+        rule Broken: if value > then action()
+        entity Farmer { id }
+    "#;
+
+    // The malformed comparison fails the whole parse instead of silently
+    // dropping the rule
+    let errors = Parser::new(input).parse_program().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].message.contains("Expected term"));
+
+    assert!(Parser::new("entity Farmer { id }").parse_program().is_ok());
+}
+
 #[test]
 fn test_recovery_disabled_behavior() {
     let input = r#"
@@ -191,7 +191,7 @@ mod integration_tests {
         }
 
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
 
         flow ProcessFarmers {
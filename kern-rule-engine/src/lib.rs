@@ -1,9 +1,13 @@
 // Re-export common types from the types module
 pub use types::*;
 
-use kern_graph_builder::{EdgeType, ExecutionGraph, GraphNode, SpecializedNode};
+use kern_graph_builder::{
+    EdgeType, ExecutionGraph, GraphNode, SpecializedNode, ValueNode, LOOP_EXIT_EDGE,
+};
 use kern_parser::Comparator;
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
 
 mod conflict_resolver;
 mod pattern_matcher;
@@ -11,6 +15,7 @@ mod priority_manager;
 mod recursion_guard;
 mod rule_engine;
 mod scheduler;
+mod snapshot;
 
 mod implementation;
 mod types;
@@ -20,6 +25,7 @@ pub use pattern_matcher::*;
 pub use priority_manager::*;
 pub use recursion_guard::*;
 pub use scheduler::*;
+pub use snapshot::*;
 
 #[cfg(test)]
 mod tests;
@@ -29,10 +35,13 @@ pub struct RuleEngine {
     pub context: ExecutionContext,
     pub step_count: u32,
     pub max_steps: u32,
-    pub priority_queue: Vec<u32>, // Node IDs sorted by priority
+    pub priority_queue: Vec<u32>, // Node IDs by ascending priority; the next to fire is last
     pub rule_priorities: HashMap<u32, RulePriority>, // Map of rule ID to priority
     pub activation_records: Vec<u32>, // List of activated rule nodes
     pub priority_strategy: PriorityStrategy, // Strategy for determining rule priority
+    pub tie_break: TieBreak,      // Order among rules with equal computed priority
+    queue_insertion: HashMap<u32, u64>, // When each queued node was added, for TieBreak::InsertionOrder
+    next_insertion: u64,
     pub execution_path: Vec<u32>, // Track the current execution path to detect recursion
     pub max_recursion_depth: u32, // Maximum allowed recursion depth
     pub rule_execution_counts: HashMap<u32, u32>, // Track how many times each rule has been executed in the current path
@@ -41,10 +50,44 @@ pub struct RuleEngine {
     pub rule_registry: HashMap<u32, RuleExecutionInfo>,
     pub execution_graph: Option<ExecutionGraph>,
     pub program_state: HashMap<String, Value>,
+
+    // Lazy evaluation cache keyed by (node ID, hash of input register values)
+    pub lazy_cache: HashMap<(u32, u64), Value>,
+    pub lazy_cache_order: VecDeque<(u32, u64)>, // Insertion order, oldest entry evicted first
+    pub max_lazy_cache_entries: usize,
+
+    pub max_subflow_workers: usize, // Upper bound on threads used by execute_subflows_parallel
+
+    pub metrics: RuleEngineMetrics,
+
+    pub max_pattern_depth: u32, // Maximum nesting depth for pattern matching
+
+    pub symbol_table: HashMap<u32, String>, // Symbol id -> interned name, used by LOAD_SYM
+
+    // Provenance of derived facts, recorded while record_provenance is set
+    pub record_provenance: bool,
+    pub provenance: HashMap<String, FactProvenance>,
+    active_support: Vec<FactProvenance>, // Rules currently running their actions
+
+    pub transactional_actions: bool, // Roll back a rule's writes if any of its actions fails
+
+    pub reject_cyclic_flows: bool, // Refuse graphs with a control cycle that can't reach HALT
+
+    // Verbose condition tracing, scoped to a single rule
+    traced_rule: Option<u32>,
+    rule_trace: Vec<RuleTraceEntry>,
+
+    // Unique-assertion mode: Some(policy) flags facts asserted to a new value
+    pub assertion_conflict_policy: Option<AssertionConflictPolicy>,
+    pub events: Vec<EngineEvent>,
 }
 
 impl RuleEngine {
     pub fn new(graph: Option<ExecutionGraph>) -> Self {
+        let symbol_table = graph
+            .as_ref()
+            .map(|graph| graph.symbol_table.clone())
+            .unwrap_or_default();
         RuleEngine {
             context: ExecutionContext::new(),
             step_count: 0,
@@ -53,13 +96,151 @@ impl RuleEngine {
             rule_priorities: HashMap::new(),
             activation_records: Vec::new(),
             priority_strategy: PriorityStrategy::Standard,
+            tie_break: TieBreak::default(),
+            queue_insertion: HashMap::new(),
+            next_insertion: 0,
             execution_path: Vec::new(),
             max_recursion_depth: 100, // Default maximum recursion depth
             rule_execution_counts: HashMap::new(),
             rule_registry: HashMap::new(),
             execution_graph: graph,
             program_state: HashMap::new(),
+            lazy_cache: HashMap::new(),
+            lazy_cache_order: VecDeque::new(),
+            max_lazy_cache_entries: 1024, // Bound memory used by lazy evaluation
+            max_subflow_workers: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            metrics: RuleEngineMetrics::new(),
+            max_pattern_depth: 64,
+            symbol_table,
+            record_provenance: false,
+            provenance: HashMap::new(),
+            active_support: Vec::new(),
+            transactional_actions: false,
+            reject_cyclic_flows: false,
+            traced_rule: None,
+            rule_trace: Vec::new(),
+            assertion_conflict_policy: None,
+            events: Vec::new(),
+        }
+    }
+
+    /// Clears the context, queues, counters, caches and recorded output in place,
+    /// keeping allocated capacity, so one engine can be reused across runs.
+    /// Configuration (graph, limits, strategy, rule priorities, symbol table and
+    /// modes) is kept; only the activation counts and conflict scores gathered
+    /// while running are zeroed in the rule priorities.
+    pub fn reset(&mut self) {
+        self.context.clear();
+        self.step_count = 0;
+        self.priority_queue.clear();
+        self.queue_insertion.clear();
+        self.next_insertion = 0;
+        for rule_priority in self.rule_priorities.values_mut() {
+            rule_priority.activation_count = 0;
+            rule_priority.conflict_score = 0;
+        }
+        self.activation_records.clear();
+        self.execution_path.clear();
+        self.rule_execution_counts.clear();
+        self.rule_registry.clear();
+        self.program_state.clear();
+        self.clear_lazy_cache();
+        self.metrics.condition_stats.clear();
+        self.provenance.clear();
+        self.active_support.clear();
+        self.rule_trace.clear();
+        self.events.clear();
+    }
+
+    /// Replaces the symbol table used to resolve LOAD_SYM operands
+    pub fn set_symbol_table(&mut self, symbols: HashMap<u32, String>) {
+        self.symbol_table = symbols;
+    }
+
+    pub fn define_symbol(&mut self, id: u32, name: &str) {
+        self.symbol_table.insert(id, name.to_string());
+    }
+
+    /// Adds the symbols a graph was built with, keeping any already defined
+    fn load_graph_symbols(&mut self, graph: &ExecutionGraph) {
+        for (id, name) in &graph.symbol_table {
+            self.symbol_table.entry(*id).or_insert_with(|| name.clone());
+        }
+    }
+
+    /// Turns recording of derived-fact provenance on or off
+    pub fn set_record_provenance(&mut self, enabled: bool) {
+        self.record_provenance = enabled;
+    }
+
+    /// Returns the rule and supporting facts that derived `fact_name`, if recorded
+    pub fn provenance(&self, fact_name: &str) -> Option<FactProvenance> {
+        self.provenance.get(fact_name).cloned()
+    }
+
+    /// Asserts a fact. When called from a firing rule's actions with provenance
+    /// recording enabled, the rule and the facts its condition read are recorded.
+    ///
+    /// In unique-assertion mode, changing an existing fact's value records an
+    /// `EngineEvent::ConflictingAssertion` and is then resolved by the policy.
+    pub fn assert_fact(&mut self, name: &str, value: Value) -> Result<(), RuleEngineError> {
+        if let Some(policy) = self.assertion_conflict_policy {
+            if let Some(old) = self.context.facts.get(name).filter(|old| **old != value) {
+                self.events.push(EngineEvent::ConflictingAssertion {
+                    fact: name.to_string(),
+                    old: old.clone(),
+                    new: value.clone(),
+                    rule: self.execution_path.last().copied(),
+                });
+                match policy {
+                    AssertionConflictPolicy::KeepOld => return Ok(()),
+                    AssertionConflictPolicy::TakeNew => {}
+                    AssertionConflictPolicy::Error => {
+                        return Err(RuleEngineError::ConflictingAssertion(name.to_string()))
+                    }
+                }
+            }
+        }
+
+        self.context.facts.insert(name.to_string(), value);
+        if !self.record_provenance {
+            return Ok(());
         }
+        if let Some(support) = self.active_support.last() {
+            self.provenance.insert(name.to_string(), support.clone());
+        }
+        Ok(())
+    }
+
+    /// Enables unique-assertion mode with the given conflict policy, or
+    /// disables it (plain overwrite) with `None`
+    pub fn set_unique_assertions(&mut self, policy: Option<AssertionConflictPolicy>) {
+        self.assertion_conflict_policy = policy;
+    }
+
+    /// Selects transactional (all-or-nothing) or immediate evaluation of rule actions
+    pub fn set_transactional_actions(&mut self, enabled: bool) {
+        self.transactional_actions = enabled;
+    }
+
+    /// Makes execute_graph fail with `CyclicFlow` instead of running a graph
+    /// whose control cycle has no HALT reachable from it
+    pub fn set_reject_cyclic_flows(&mut self, enabled: bool) {
+        self.reject_cyclic_flows = enabled;
+    }
+
+    /// Traces every term fetch and comparison made while evaluating `rule_id`'s
+    /// condition. Replaces any previously traced rule and clears the log.
+    pub fn trace_rule(&mut self, rule_id: u32) {
+        self.traced_rule = Some(rule_id);
+        self.rule_trace.clear();
+    }
+
+    /// The trace recorded for the rule selected with `trace_rule`, in evaluation order
+    pub fn rule_trace_log(&self) -> &[RuleTraceEntry] {
+        &self.rule_trace
     }
 
     /// Sets the priority for a specific rule
@@ -86,6 +267,11 @@ impl RuleEngine {
         self.priority_strategy = strategy;
     }
 
+    /// Sets how rules with equal computed priority are ordered
+    pub fn set_tie_break(&mut self, tie_break: TieBreak) {
+        self.tie_break = tie_break;
+    }
+
     /// Updates the activation count for a rule
     pub fn increment_rule_activation(&mut self, rule_id: u32) {
         if let Some(rule_priority) = self.rule_priorities.get_mut(&rule_id) {
@@ -139,7 +325,21 @@ impl RuleEngine {
                     // Use custom priority function
                     priority_fn(rule_priority)
                 }
+                PriorityStrategy::Provider(provider) => {
+                    provider.priority(rule_priority, &self.context, self.step_count)
+                }
             }
+        } else if let PriorityStrategy::Provider(provider) = &self.priority_strategy {
+            // Providers also rank rules that have no explicit priority settings
+            let unset = RulePriority {
+                rule_id,
+                priority: 0,
+                specificity: 0,
+                recency: 0,
+                activation_count: 0,
+                conflict_score: 0,
+            };
+            provider.priority(&unset, &self.context, self.step_count)
         } else {
             0 // Default priority
         }
@@ -153,19 +353,7 @@ impl RuleEngine {
         }
 
         self.priority_queue.push(node_id);
-
-        // Sort the queue based on priority (higher priority first)
-        // Clone the queue to avoid simultaneous borrows of self
-        let queue_ids = self.priority_queue.clone();
-        let mut priority_pairs: Vec<(u32, u32)> = queue_ids
-            .into_iter()
-            .map(|id| (id, self.get_rule_priority(id)))
-            .collect();
-
-        priority_pairs.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by priority descending
-
-        // Update the priority queue with the sorted order
-        self.priority_queue = priority_pairs.into_iter().map(|(id, _)| id).collect();
+        self.sort_priority_queue();
     }
 
     /// Adds a node to the priority queue using the current strategy
@@ -176,18 +364,43 @@ impl RuleEngine {
         }
 
         self.priority_queue.push(node_id);
+        self.queue_insertion.insert(node_id, self.next_insertion);
+        self.next_insertion += 1;
+        self.sort_priority_queue();
+    }
 
-        // Sort the queue based on the current priority strategy
-        let queue_ids = self.priority_queue.clone();
-        let mut priority_pairs: Vec<(u32, u32)> = queue_ids
-            .into_iter()
-            .map(|id| (id, self.get_rule_priority(id)))
+    /// Orders the queue by ascending priority, so the highest priority sits at the
+    /// end where `select_next_node` pops it. Among equal priorities the node
+    /// `tie_break` favours is placed last. Nodes pushed straight onto the queue
+    /// (e.g. dependents reached over an edge) are stamped here in push order.
+    fn sort_priority_queue(&mut self) {
+        for &id in &self.priority_queue {
+            self.queue_insertion.entry(id).or_insert_with(|| {
+                let stamp = self.next_insertion;
+                self.next_insertion += 1;
+                stamp
+            });
+        }
+
+        let mut keyed: Vec<(u32, u32, u32, u64)> = self
+            .priority_queue
+            .iter()
+            .map(|&id| {
+                let recency = self.rule_priorities.get(&id).map_or(0, |p| p.recency);
+                let inserted = self.queue_insertion.get(&id).copied().unwrap_or(u64::MAX);
+                (id, self.get_rule_priority(id), recency, inserted)
+            })
             .collect();
 
-        priority_pairs.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by priority descending
+        keyed.sort_by(|a, b| {
+            a.1.cmp(&b.1).then_with(|| match self.tie_break {
+                TieBreak::LowestId => b.0.cmp(&a.0),
+                TieBreak::HighestRecency => a.2.cmp(&b.2).then(b.3.cmp(&a.3)),
+                TieBreak::InsertionOrder => b.3.cmp(&a.3),
+            })
+        });
 
-        // Update the priority queue with the sorted order
-        self.priority_queue = priority_pairs.into_iter().map(|(id, _)| id).collect();
+        self.priority_queue = keyed.into_iter().map(|(id, ..)| id).collect();
     }
 
     /// Selects the next node to execute based on priority and scheduling strategy
@@ -196,24 +409,12 @@ impl RuleEngine {
             return None;
         }
 
-        // Sort the priority queue based on the current priority strategy
-        // To avoid borrow checker issues, we'll create a vector of (node_id, priority) pairs
-        let mut priority_pairs: Vec<(u32, u32)> = self
-            .priority_queue
-            .iter()
-            .map(|&node_id| (node_id, self.get_rule_priority(node_id)))
-            .collect();
-
-        priority_pairs.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by priority descending
-
-        // Update the priority queue with the sorted order
-        self.priority_queue = priority_pairs
-            .into_iter()
-            .map(|(node_id, _)| node_id)
-            .collect();
+        self.sort_priority_queue();
 
-        // Return the highest priority node
-        self.priority_queue.pop() // pop from the end since it's sorted in descending order
+        // The highest priority node is last; equal priorities follow the tie-break
+        let node_id = self.priority_queue.pop()?;
+        self.queue_insertion.remove(&node_id);
+        Some(node_id)
     }
 
     /// Schedules a rule for execution based on its eligibility
@@ -253,24 +454,22 @@ impl RuleEngine {
             self.resolve_conflicts(&conflicts);
 
             // Re-sort the priority queue based on conflict resolution strategy
-            // To avoid borrow checker issues, we'll create a vector of (node_id, priority) pairs
-            let mut priority_pairs: Vec<(u32, u32)> = self
-                .priority_queue
-                .iter()
-                .map(|&node_id| (node_id, self.get_rule_priority(node_id)))
-                .collect();
-
-            priority_pairs.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by priority descending
-
-            // Update the priority queue with the sorted order
-            self.priority_queue = priority_pairs
-                .into_iter()
-                .map(|(node_id, _)| node_id)
-                .collect();
+            self.sort_priority_queue();
         }
     }
 
     pub fn execute_graph(&mut self, graph: &ExecutionGraph) -> Result<(), RuleEngineError> {
+        graph.validate().map_err(RuleEngineError::InvalidGraph)?;
+        self.load_graph_symbols(graph);
+        if self.reject_cyclic_flows {
+            if let Some(cycle) = Self::detect_cycles(graph)
+                .into_iter()
+                .find(|cycle| !Self::reaches_halt(graph, cycle))
+            {
+                return Err(RuleEngineError::CyclicFlow(cycle));
+            }
+        }
+
         // Initialize the priority queue with entry points
         for entry_point in &graph.entry_points {
             self.add_to_priority_queue_with_strategy(entry_point.node_id);
@@ -306,18 +505,23 @@ impl RuleEngine {
         Ok(())
     }
 
-    /// Executes a flow pipeline with demand-driven evaluation
+    /// Executes a flow pipeline with demand-driven evaluation.
+    ///
+    /// Returns the flow's declared outputs (see `ExecutionGraph::flow_outputs`),
+    /// read from their registers once execution finishes. Outputs that were
+    /// never written are omitted, as is anything the flow did not declare.
     pub fn execute_flow_pipeline(
         &mut self,
         graph: &ExecutionGraph,
         flow_node_id: u32,
-    ) -> Result<(), RuleEngineError> {
+    ) -> Result<FlowResult, RuleEngineError> {
         // Find the flow node in the graph
         let flow_specialized_node = graph
             .nodes
             .iter()
             .find(|n| n.get_base().id == flow_node_id)
             .ok_or(RuleEngineError::InvalidNodeType)?;
+        self.load_graph_symbols(graph);
 
         // Get all connected nodes for this flow
         let connected_nodes = self.get_connected_nodes_for_flow(flow_node_id, graph);
@@ -330,7 +534,23 @@ impl RuleEngine {
             }
         }
 
-        Ok(())
+        Ok(self.collect_flow_result(flow_node_id, graph))
+    }
+
+    /// Reads a flow's declared outputs from the context registers
+    fn collect_flow_result(&self, flow_node_id: u32, graph: &ExecutionGraph) -> FlowResult {
+        let mut result = FlowResult::default();
+        for output in graph.flow_outputs.get(&flow_node_id).into_iter().flatten() {
+            let value = self
+                .context
+                .registers
+                .get(output.register as usize)
+                .and_then(|value| value.clone());
+            if let Some(value) = value {
+                result.outputs.insert(output.name.clone(), value);
+            }
+        }
+        result
     }
 
     /// Executes a node with demand-driven evaluation
@@ -412,22 +632,34 @@ impl RuleEngine {
         Ok(())
     }
 
-    /// Gets all connected nodes for a specific flow
+    /// Gets all connected nodes for a specific flow, in execution order.
+    ///
+    /// Nodes come in post-order: a node's successors (the operands it reads,
+    /// such as the load feeding a MOVE) precede it, and sibling actions keep
+    /// their source order.
     fn get_connected_nodes_for_flow(&self, flow_node_id: u32, graph: &ExecutionGraph) -> Vec<u32> {
         let mut connected_nodes = Vec::new();
         let mut visited = std::collections::HashSet::new();
-
-        // Start with the flow node
-        let mut queue = vec![flow_node_id];
         visited.insert(flow_node_id);
 
-        while let Some(current_node_id) = queue.pop() {
-            // Add all directly connected nodes
-            for edge in &graph.edges {
-                if edge.from_node == current_node_id && !visited.contains(&edge.to_node) {
-                    connected_nodes.push(edge.to_node);
-                    queue.push(edge.to_node);
-                    visited.insert(edge.to_node);
+        // Each stack entry is a node and whether its successors are already queued
+        let mut stack = Self::successors(flow_node_id, graph)
+            .rev()
+            .map(|node_id| (node_id, false))
+            .collect::<Vec<_>>();
+
+        while let Some((node_id, expanded)) = stack.pop() {
+            if expanded {
+                connected_nodes.push(node_id);
+                continue;
+            }
+            if !visited.insert(node_id) {
+                continue;
+            }
+            stack.push((node_id, true));
+            for successor in Self::successors(node_id, graph).rev() {
+                if !visited.contains(&successor) {
+                    stack.push((successor, false));
                 }
             }
         }
@@ -435,30 +667,44 @@ impl RuleEngine {
         connected_nodes
     }
 
-    /// Implements lazy evaluation for a node
+    fn successors(
+        node_id: u32,
+        graph: &ExecutionGraph,
+    ) -> impl DoubleEndedIterator<Item = u32> + '_ {
+        graph
+            .edges
+            .iter()
+            .filter(move |edge| edge.from_node == node_id)
+            .map(|edge| edge.to_node)
+    }
+
+    /// Implements lazy evaluation for a node.
+    ///
+    /// Results are memoized by node ID together with a hash of the node's current
+    /// input register values, so a changed input forces recomputation. The cache
+    /// holds at most `max_lazy_cache_entries` results, evicting the oldest first.
     pub fn evaluate_lazy(
         &mut self,
         node_id: u32,
         graph: &ExecutionGraph,
     ) -> Result<Value, RuleEngineError> {
-        // Check if the result is already computed and cached
-        let cache_key = format!("lazy_result_{}", node_id);
-        if let Some(cached_result) = self.context.variables.get(&cache_key) {
-            return Ok(cached_result.clone());
-        }
-
-        // Execute the node to get the result
         let specialized_node = graph
             .nodes
             .iter()
             .find(|n| n.get_base().id == node_id)
             .ok_or(RuleEngineError::InvalidNodeType)?;
+        let node = specialized_node.get_base();
+
+        // Check if the result is already computed for the current inputs
+        let cache_key = (node_id, self.hash_node_inputs(node));
+        if let Some(cached_result) = self.lazy_cache.get(&cache_key) {
+            return Ok(cached_result.clone());
+        }
 
         // Execute the node and get its result
         self.execute_node_from_specialized(specialized_node, graph)?;
 
         // Get the result from the output register
-        let node = specialized_node.get_base();
         let result = if !node.output_regs.is_empty() && node.output_regs[0] != 0 {
             let reg_idx = node.output_regs[0] as usize;
             if reg_idx < self.context.registers.len() {
@@ -476,24 +722,87 @@ impl RuleEngine {
         };
 
         // Cache the result for future use
-        self.context.variables.insert(cache_key, result.clone());
+        self.cache_lazy_result(cache_key, result.clone());
 
         Ok(result)
     }
 
+    /// Hashes the values currently held in a node's input registers
+    fn hash_node_inputs(&self, node: &GraphNode) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for &reg in &node.input_regs {
+            reg.hash(&mut hasher);
+            self.context
+                .registers
+                .get(reg as usize)
+                .cloned()
+                .flatten()
+                .hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Stores a lazy result, evicting the oldest entries once the cache is full
+    fn cache_lazy_result(&mut self, key: (u32, u64), value: Value) {
+        if self.max_lazy_cache_entries == 0 {
+            return;
+        }
+        while self.lazy_cache.len() >= self.max_lazy_cache_entries {
+            match self.lazy_cache_order.pop_front() {
+                Some(oldest) => {
+                    self.lazy_cache.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+        if self.lazy_cache.insert(key, value).is_none() {
+            self.lazy_cache_order.push_back(key);
+        }
+    }
+
+    /// Drops every cached lazy result for `node_id`, whatever inputs it was
+    /// computed from
+    pub fn invalidate_lazy(&mut self, node_id: u32) {
+        self.lazy_cache.retain(|&(id, _), _| id != node_id);
+        self.lazy_cache_order.retain(|&(id, _)| id != node_id);
+    }
+
+    /// Drops all cached lazy results
+    pub fn clear_lazy_cache(&mut self) {
+        self.lazy_cache.clear();
+        self.lazy_cache_order.clear();
+    }
+
     /// Implements lazy evaluation for a graph with dependencies
+    ///
+    /// Data edge predecessors are evaluated first, transitively. A node reached
+    /// again while it is still being evaluated fails with `CyclicDependency`.
     pub fn evaluate_lazy_with_dependencies(
         &mut self,
         node_id: u32,
         graph: &ExecutionGraph,
     ) -> Result<Value, RuleEngineError> {
+        self.evaluate_lazy_dependencies(node_id, graph, &mut HashSet::new())
+    }
+
+    fn evaluate_lazy_dependencies(
+        &mut self,
+        node_id: u32,
+        graph: &ExecutionGraph,
+        visited: &mut HashSet<u32>,
+    ) -> Result<Value, RuleEngineError> {
+        // `visited` holds the nodes on the current evaluation stack
+        if !visited.insert(node_id) {
+            return Err(RuleEngineError::CyclicDependency(node_id));
+        }
+
         // First, evaluate all dependencies lazily
         for edge in &graph.edges {
             if edge.to_node == node_id && edge.edge_type == EdgeType::Data {
-                // Evaluate the dependency node
-                self.evaluate_lazy(edge.from_node, graph)?;
+                self.evaluate_lazy_dependencies(edge.from_node, graph, visited)?;
             }
         }
+        visited.remove(&node_id);
 
         // Then evaluate the target node
         self.evaluate_lazy(node_id, graph)
@@ -503,8 +812,8 @@ impl RuleEngine {
     pub fn create_context(&mut self) -> ExecutionContext {
         ExecutionContext {
             registers: vec![None; 16],
-            variables: HashMap::new(),
-            facts: HashMap::new(),
+            variables: BTreeMap::new(),
+            facts: BTreeMap::new(),
             rule_results: HashMap::new(),
             current_node_id: self.context.current_node_id,
         }
@@ -539,7 +848,216 @@ impl RuleEngine {
         // Restore the original context
         self.context = original_context;
 
-        result
+        result.map(|_| ())
+    }
+
+    /// Executes independent subflows in parallel on a simple fan-out pool.
+    ///
+    /// At most `max_subflow_workers` threads pull subflow ids from one shared
+    /// queue until it is empty; there are no per-worker queues and no stealing.
+    /// Each worker clones the current context once and runs its subflows on that
+    /// copy, handing back only the outputs a subflow wrote (registers, variables,
+    /// facts and rule results) and resetting them before the next one. Once all
+    /// have finished, the outputs are merged back in subflow-id order. Two
+    /// subflows writing the same output is reported as a `SubflowConflict` and
+    /// nothing is merged. Workers run with this engine's configuration.
+    pub fn execute_subflows_parallel(
+        &mut self,
+        subflows: &[u32],
+        graph: &ExecutionGraph,
+    ) -> Result<(), RuleEngineError> {
+        let mut subflow_ids = subflows.to_vec();
+        subflow_ids.sort_unstable();
+        subflow_ids.dedup();
+
+        let queue = std::sync::Mutex::new(subflow_ids.iter().copied().collect::<VecDeque<u32>>());
+        let results = std::sync::Mutex::new(Vec::with_capacity(subflow_ids.len()));
+        let worker_count = self.max_subflow_workers.clamp(1, subflow_ids.len().max(1));
+
+        let parent = &*self;
+        let base_context = &self.context;
+
+        std::thread::scope(|scope| {
+            for _ in 0..worker_count {
+                scope.spawn(|| {
+                    // Cloned once per worker; each subflow's writes are undone after it
+                    let mut context = base_context.clone();
+
+                    loop {
+                        let next = queue.lock().unwrap().pop_front();
+                        let Some(subflow_id) = next else { break };
+
+                        let mut worker = parent.subflow_worker();
+                        worker.context = context;
+                        worker.context.current_node_id = Some(subflow_id);
+
+                        let result = worker.execute_flow_pipeline(graph, subflow_id);
+
+                        context = worker.context;
+                        let writes = Self::context_writes(base_context, &context);
+                        let written = Self::take_writes(&mut context, base_context, &writes);
+                        context.current_node_id = base_context.current_node_id;
+
+                        let result = result.map(|_| (writes, written));
+                        results.lock().unwrap().push((subflow_id, result));
+                    }
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(subflow_id, _)| *subflow_id);
+
+        // Collect the outputs written by each subflow, failing on the first error
+        let mut outputs = Vec::with_capacity(results.len());
+        for (subflow_id, result) in results {
+            outputs.push((subflow_id, result?));
+        }
+
+        // Detect subflows writing the same output before merging anything
+        let mut writers: HashMap<ContextOutput, u32> = HashMap::new();
+        let mut merges = Vec::with_capacity(outputs.len());
+        for (subflow_id, (writes, sub_context)) in outputs {
+            for key in &writes {
+                if let Some(&first) = writers.get(key) {
+                    return Err(RuleEngineError::SubflowConflict(
+                        first,
+                        subflow_id,
+                        key.to_string(),
+                    ));
+                }
+                writers.insert(key.clone(), subflow_id);
+            }
+            merges.push((writes, sub_context));
+        }
+
+        // Merge outputs deterministically in subflow-id order
+        for (writes, mut sub_context) in merges {
+            for key in writes {
+                match key {
+                    ContextOutput::Register(idx) => {
+                        self.context.registers[idx] = sub_context.registers[idx].take();
+                    }
+                    ContextOutput::Variable(name) => {
+                        if let Some(value) = sub_context.variables.remove(&name) {
+                            self.context.variables.insert(name, value);
+                        }
+                    }
+                    ContextOutput::Fact(name) => {
+                        if let Some(value) = sub_context.facts.remove(&name) {
+                            self.context.facts.insert(name, value);
+                        }
+                    }
+                    ContextOutput::RuleResult(name) => {
+                        if let Some(value) = sub_context.rule_results.remove(&name) {
+                            self.context.rule_results.insert(name, value);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds an engine for one parallel subflow, with this engine's limits,
+    /// strategy, rule priorities, symbol table and modes but fresh run state
+    fn subflow_worker(&self) -> RuleEngine {
+        let mut worker = RuleEngine::new(None);
+        worker.max_steps = self.max_steps;
+        worker.max_recursion_depth = self.max_recursion_depth;
+        worker.rule_priorities = self.rule_priorities.clone();
+        worker.priority_strategy = self.priority_strategy.clone();
+        worker.tie_break = self.tie_break;
+        worker.max_lazy_cache_entries = self.max_lazy_cache_entries;
+        worker.max_pattern_depth = self.max_pattern_depth;
+        worker.symbol_table = self.symbol_table.clone();
+        worker.transactional_actions = self.transactional_actions;
+        worker.reject_cyclic_flows = self.reject_cyclic_flows;
+        worker.assertion_conflict_policy = self.assertion_conflict_policy;
+        worker
+    }
+
+    /// Lists the outputs a subflow context changed relative to its base context
+    fn context_writes(base: &ExecutionContext, sub: &ExecutionContext) -> Vec<ContextOutput> {
+        let mut writes = Vec::new();
+        for (idx, value) in sub.registers.iter().enumerate() {
+            if idx < base.registers.len() && base.registers[idx] != *value {
+                writes.push(ContextOutput::Register(idx));
+            }
+        }
+        for (name, value) in &sub.variables {
+            if base.variables.get(name) != Some(value) {
+                writes.push(ContextOutput::Variable(name.clone()));
+            }
+        }
+        for (name, value) in &sub.facts {
+            if base.facts.get(name) != Some(value) {
+                writes.push(ContextOutput::Fact(name.clone()));
+            }
+        }
+        for (name, value) in &sub.rule_results {
+            if base.rule_results.get(name) != Some(value) {
+                writes.push(ContextOutput::RuleResult(name.clone()));
+            }
+        }
+        writes.sort();
+        writes
+    }
+
+    /// Moves the outputs listed in `writes` out of `context` into an otherwise
+    /// empty context, putting back the values `base` holds for them
+    fn take_writes(
+        context: &mut ExecutionContext,
+        base: &ExecutionContext,
+        writes: &[ContextOutput],
+    ) -> ExecutionContext {
+        let mut written = ExecutionContext::new();
+        for key in writes {
+            match key {
+                ContextOutput::Register(idx) => {
+                    written.registers[*idx] = std::mem::replace(
+                        &mut context.registers[*idx],
+                        base.registers[*idx].clone(),
+                    );
+                }
+                ContextOutput::Variable(name) => {
+                    let restored = base.variables.get(name).cloned();
+                    if let Some(value) = Self::restore_entry(&mut context.variables, name, restored)
+                    {
+                        written.variables.insert(name.clone(), value);
+                    }
+                }
+                ContextOutput::Fact(name) => {
+                    let restored = base.facts.get(name).cloned();
+                    if let Some(value) = Self::restore_entry(&mut context.facts, name, restored) {
+                        written.facts.insert(name.clone(), value);
+                    }
+                }
+                ContextOutput::RuleResult(name) => {
+                    let value = match base.rule_results.get(name) {
+                        Some(&restored) => context.rule_results.insert(name.clone(), restored),
+                        None => context.rule_results.remove(name),
+                    };
+                    if let Some(value) = value {
+                        written.rule_results.insert(name.clone(), value);
+                    }
+                }
+            }
+        }
+        written
+    }
+
+    // Puts `restored` back under `name` (or removes the entry), returning what was there
+    fn restore_entry(
+        entries: &mut BTreeMap<String, Value>,
+        name: &str,
+        restored: Option<Value>,
+    ) -> Option<Value> {
+        match restored {
+            Some(value) => entries.insert(name.to_string(), value),
+            None => entries.remove(name),
+        }
     }
 
     fn execute_node_from_specialized(
@@ -549,11 +1067,22 @@ impl RuleEngine {
     ) -> Result<(), RuleEngineError> {
         let base_node = node.get_base();
         match base_node.node_type {
-            kern_graph_builder::GraphNodeType::Op => self.execute_op_node(base_node),
+            // Value nodes carry the constant their load produces
+            kern_graph_builder::GraphNodeType::Op => match node {
+                SpecializedNode::Value(value_node) if base_node.opcode == 0x11 => {
+                    let value = load_num_value(value_node)?;
+                    self.write_output(base_node, Value::Num(value));
+                    Ok(())
+                }
+                _ => self.execute_op_node(base_node),
+            },
             kern_graph_builder::GraphNodeType::Rule => self.execute_rule_node(base_node, graph),
-            kern_graph_builder::GraphNodeType::Control => {
-                self.execute_control_node(base_node, graph)
-            }
+            kern_graph_builder::GraphNodeType::Control => match node {
+                SpecializedNode::Loop(loop_node) => {
+                    self.execute_loop_node(base_node, loop_node.iteration_limit, graph)
+                }
+                _ => self.execute_control_node(base_node, graph),
+            },
             kern_graph_builder::GraphNodeType::Graph => self.execute_graph_node(base_node),
             kern_graph_builder::GraphNodeType::Io => self.execute_io_node(base_node),
         }
@@ -575,10 +1104,11 @@ impl RuleEngine {
 
     fn execute_op_node(&mut self, node: &GraphNode) -> Result<(), RuleEngineError> {
         match node.opcode {
-            0x10 => self.execute_load_sym(node), // LOAD_SYM
-            0x11 => self.execute_load_num(node), // LOAD_NUM
-            0x12 => self.execute_move(node),     // MOVE
-            0x13 => self.execute_compare(node),  // COMPARE
+            0x10 => self.execute_load_sym(node),   // LOAD_SYM
+            0x11 => self.execute_load_num(node),   // LOAD_NUM
+            0x12 => self.execute_move(node),       // MOVE
+            0x13 => self.execute_compare(node),    // COMPARE
+            0x62 => self.execute_set_symbol(node), // SET_SYMBOL
             _ => {
                 // For other opcodes, we'll implement as needed
                 println!("Executing operation node with opcode: {}", node.opcode);
@@ -588,23 +1118,52 @@ impl RuleEngine {
     }
 
     fn execute_load_sym(&mut self, node: &GraphNode) -> Result<(), RuleEngineError> {
-        // Extract the symbol name from metadata or context
-        // For now, we'll use a placeholder approach
+        // The symbol id is carried in the node's flags (see GraphBuilder::symbol_table)
+        let symbol_id = node.flags as u32;
+        let name = self
+            .symbol_table
+            .get(&symbol_id)
+            .ok_or(RuleEngineError::UndefinedIdentifier(symbol_id))?;
+
         let dest_reg = node.output_regs[0] as usize;
         if dest_reg < self.context.registers.len() {
-            // In a real implementation, we'd get the symbol name from metadata or elsewhere
-            self.context.registers[dest_reg] = Some(Value::Sym(format!("symbol_{}", node.id)));
+            self.context.registers[dest_reg] = Some(Value::Sym(name.clone()));
         }
         Ok(())
     }
 
+    fn execute_set_symbol(&mut self, node: &GraphNode) -> Result<(), RuleEngineError> {
+        // Asserts the fact named by the symbol id in flags from the input register
+        let symbol_id = node.flags as u32;
+        let name = self
+            .symbol_table
+            .get(&symbol_id)
+            .cloned()
+            .ok_or(RuleEngineError::UndefinedIdentifier(symbol_id))?;
+
+        let src_reg = node.input_regs[0];
+        let value = self
+            .context
+            .registers
+            .get(src_reg as usize)
+            .cloned()
+            .flatten()
+            .ok_or(RuleEngineError::MissingRegisterValue(src_reg))?;
+
+        self.assert_fact(&name, value)
+    }
+
     fn execute_load_num(&mut self, node: &GraphNode) -> Result<(), RuleEngineError> {
+        // Only a ValueNode carries the constant to load
+        Err(RuleEngineError::MissingConstant(node.id))
+    }
+
+    /// Stores `value` in the node's first output register, if it exists
+    fn write_output(&mut self, node: &GraphNode, value: Value) {
         let dest_reg = node.output_regs[0] as usize;
         if dest_reg < self.context.registers.len() {
-            // In a real implementation, we'd get the number from metadata or elsewhere
-            self.context.registers[dest_reg] = Some(Value::Num(node.id as i64));
+            self.context.registers[dest_reg] = Some(value);
         }
-        Ok(())
     }
 
     fn execute_move(&mut self, node: &GraphNode) -> Result<(), RuleEngineError> {
@@ -636,14 +1195,9 @@ impl RuleEngine {
             &self.context.registers[reg_a],
             &self.context.registers[reg_b],
         ) {
-            let result = match node.flags as u8 {
-                0 => Value::Bool(self.compare_values(val_a, val_b, &Comparator::Equal)?), // ==
-                1 => Value::Bool(self.compare_values(val_a, val_b, &Comparator::NotEqual)?), // !=
-                2 => Value::Bool(self.compare_values(val_a, val_b, &Comparator::Greater)?), // >
-                3 => Value::Bool(self.compare_values(val_a, val_b, &Comparator::Less)?),  // <
-                4 => Value::Bool(self.compare_values(val_a, val_b, &Comparator::GreaterEqual)?), // >=
-                5 => Value::Bool(self.compare_values(val_a, val_b, &Comparator::LessEqual)?), // <=
-                _ => Value::Bool(false), // Default to false for unknown comparators
+            let result = match Self::comparator_from_flags(node.flags) {
+                Some(op) => Value::Bool(self.compare_values(val_a, val_b, &op)?),
+                None => Value::Bool(false), // Default to false for unknown comparators
             };
 
             // Store result in output register
@@ -658,6 +1212,19 @@ impl RuleEngine {
         Ok(())
     }
 
+    /// Maps COMPARE node flags to the comparator they encode
+    fn comparator_from_flags(flags: u16) -> Option<Comparator> {
+        match flags as u8 {
+            0 => Some(Comparator::Equal),        // ==
+            1 => Some(Comparator::NotEqual),     // !=
+            2 => Some(Comparator::Greater),      // >
+            3 => Some(Comparator::Less),         // <
+            4 => Some(Comparator::GreaterEqual), // >=
+            5 => Some(Comparator::LessEqual),    // <=
+            _ => None,
+        }
+    }
+
     fn compare_values(
         &self,
         val_a: &Value,
@@ -700,8 +1267,30 @@ impl RuleEngine {
         let condition_result = self.evaluate_rule_condition(node, graph)?;
 
         if condition_result {
+            if self.record_provenance {
+                let supporting_facts = self.supporting_facts(node, graph);
+                self.active_support.push(FactProvenance {
+                    rule_id: node.id,
+                    supporting_facts,
+                });
+            }
+
+            // In transactional mode the actions run against a staged copy of the
+            // context, which replaces the committed one only if every action succeeds
+            let committed = self
+                .transactional_actions
+                .then(|| (self.context.clone(), self.provenance.clone()));
+
             // Execute the rule's actions if the condition is satisfied
-            self.execute_rule_actions(node, graph)?;
+            let result = self.execute_rule_actions(node, graph);
+            if self.record_provenance {
+                self.active_support.pop();
+            }
+            if let (Err(_), Some((context, provenance))) = (&result, committed) {
+                self.context = context;
+                self.provenance = provenance;
+            }
+            result?;
         }
 
         // Add connected nodes to the priority queue
@@ -736,28 +1325,397 @@ impl RuleEngine {
 
         // For now, we'll evaluate each condition node and return true if any condition is met
         // In a real implementation, we'd properly evaluate the logical expressions
+        let tracing = self.traced_rule == Some(rule_node.id);
+        let mut passed = rule_node.flags & RULE_FLAG_UNCONDITIONAL != 0;
         for condition_specialized_node in condition_nodes {
+            if passed {
+                break;
+            }
             let condition_node = condition_specialized_node.get_base();
             if condition_node.node_type == kern_graph_builder::GraphNodeType::Op
                 && condition_node.opcode == 0x13
             {
                 // COMPARE
+                let operands = if tracing {
+                    self.trace_compare_terms(condition_node, graph)
+                } else {
+                    Vec::new()
+                };
+
                 // Execute the comparison operation
                 self.execute_compare(condition_node)?;
 
                 // Check if the comparison result is true
                 let result_reg = condition_node.output_regs[0] as usize;
-                if result_reg < self.context.registers.len() {
-                    if let Some(Value::Bool(result)) = &self.context.registers[result_reg] {
-                        if *result {
-                            return Ok(true);
-                        }
+                let result = matches!(
+                    self.context.registers.get(result_reg),
+                    Some(Some(Value::Bool(true)))
+                );
+                if let [Some(left), Some(right)] = operands.as_slice() {
+                    self.rule_trace.push(RuleTraceEntry::CompareEval {
+                        op: Self::comparator_from_flags(condition_node.flags),
+                        left: left.clone(),
+                        right: right.clone(),
+                        result,
+                    });
+                }
+                if result {
+                    passed = true;
+                    break;
+                }
+            }
+        }
+
+        if tracing {
+            self.rule_trace.push(RuleTraceEntry::Verdict { passed });
+        }
+        self.metrics.record_condition(rule_node.id, passed);
+        Ok(passed)
+    }
+
+    /// Logs a TermEval for each COMPARE operand and returns the operand values.
+    /// A term is named after the LOAD_SYM that produced its register, if any.
+    fn trace_compare_terms(
+        &mut self,
+        compare: &GraphNode,
+        graph: &ExecutionGraph,
+    ) -> Vec<Option<Value>> {
+        let mut values = Vec::new();
+        for &reg in &compare.input_regs[..2] {
+            let term = Self::compare_term_name(compare, reg, graph);
+            let value = self.context.registers.get(reg as usize).cloned().flatten();
+            self.rule_trace.push(RuleTraceEntry::TermEval {
+                term,
+                value: value.clone(),
+            });
+            values.push(value);
+        }
+        values
+    }
+
+    /// Names a COMPARE operand register after the LOAD_SYM that produced it,
+    /// falling back to the register name
+    fn compare_term_name(compare: &GraphNode, reg: u16, graph: &ExecutionGraph) -> String {
+        Self::data_children(graph, compare.id)
+            .filter_map(|id| graph.nodes.iter().find(|n| n.get_base().id == id))
+            .find_map(|operand| match operand {
+                SpecializedNode::Value(value_node)
+                    if value_node.base.output_regs[0] == reg
+                        && !value_node.value_sym.is_empty() =>
+                {
+                    Some(value_node.value_sym.clone())
+                }
+                _ => None,
+            })
+            .unwrap_or_else(|| format!("R{}", reg))
+    }
+
+    /// Facts read by the operands of a rule's comparisons, sorted by name
+    fn supporting_facts(&self, rule_node: &GraphNode, graph: &ExecutionGraph) -> Vec<String> {
+        let mut names: Vec<String> = Self::data_children(graph, rule_node.id)
+            .filter(|&id| Self::find_node(graph, id).is_some_and(|n| n.opcode == 0x13))
+            .flat_map(|compare_id| Self::data_children(graph, compare_id))
+            .filter_map(|operand_id| {
+                let operand = graph.nodes.iter().find(|n| n.get_base().id == operand_id)?;
+                if operand.get_base().opcode != 0x10 {
+                    return None;
+                }
+                match operand {
+                    SpecializedNode::Value(value_node) if !value_node.value_sym.is_empty() => {
+                        Some(value_node.value_sym.clone())
+                    }
+                    _ => self
+                        .symbol_table
+                        .get(&(operand.get_base().flags as u32))
+                        .cloned(),
+                }
+            })
+            .filter(|name| self.context.facts.contains_key(name))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Returns how often a rule's condition was evaluated and how often it passed
+    pub fn condition_stats(&self, rule_id: u32) -> Option<(u64, u64)> {
+        self.metrics.condition_stats.get(&rule_id).copied()
+    }
+
+    /// Partially evaluates rule conditions against facts known ahead of time.
+    ///
+    /// A COMPARE is folded when one operand loads a known fact and the other is a
+    /// known fact or a literal (numbers, and bare identifiers, which name symbols).
+    /// As at runtime, a rule passes if any of its comparisons does: a statically
+    /// true comparison marks the rule `RULE_FLAG_UNCONDITIONAL`, and a rule whose
+    /// comparisons are all statically false is dropped along with its actions.
+    /// Folded comparisons are removed from the returned graph.
+    pub fn partially_evaluate(
+        &self,
+        graph: &ExecutionGraph,
+        known_facts: &HashMap<String, Value>,
+    ) -> ExecutionGraph {
+        let mut result = graph.clone();
+        let mut removed = HashSet::new();
+
+        for rule in graph.nodes.iter().map(|n| n.get_base()) {
+            if rule.node_type != kern_graph_builder::GraphNodeType::Rule {
+                continue;
+            }
+
+            let comparisons: Vec<u32> = Self::data_children(graph, rule.id)
+                .filter(|&id| {
+                    Self::find_node(graph, id).is_some_and(|n| {
+                        n.node_type == kern_graph_builder::GraphNodeType::Op && n.opcode == 0x13
+                    })
+                })
+                .collect();
+
+            let mut folded = Vec::new();
+            let mut statically_true = false;
+            for &compare_id in &comparisons {
+                match self.fold_comparison(graph, compare_id, known_facts) {
+                    Some(true) => {
+                        statically_true = true;
+                        folded.push(compare_id);
+                    }
+                    Some(false) => folded.push(compare_id),
+                    None => {}
+                }
+            }
+
+            if folded.is_empty() {
+                continue;
+            }
+
+            if !statically_true && folded.len() == comparisons.len() {
+                // Statically false: drop the rule and everything only it reaches
+                removed.extend(Self::exclusively_reachable(graph, rule.id));
+                continue;
+            }
+
+            // Folded comparisons (and their operand loads) are no longer needed
+            for compare_id in folded {
+                removed.insert(compare_id);
+                removed.extend(Self::data_children(graph, compare_id));
+            }
+
+            if statically_true {
+                for node in result.nodes.iter_mut() {
+                    let base = match node {
+                        SpecializedNode::Rule(rule_node) => &mut rule_node.base,
+                        SpecializedNode::Base(base) => base,
+                        _ => continue,
+                    };
+                    if base.id == rule.id {
+                        base.flags |= RULE_FLAG_UNCONDITIONAL;
                     }
                 }
             }
         }
 
-        Ok(false)
+        result.nodes.retain(|n| !removed.contains(&n.get_base().id));
+        result
+            .edges
+            .retain(|e| !removed.contains(&e.from_node) && !removed.contains(&e.to_node));
+        result
+            .entry_points
+            .retain(|entry| !removed.contains(&entry.node_id));
+        result.node_count = result.nodes.len() as u32;
+        result.edge_count = result.edges.len() as u32;
+        result.entry_count = result.entry_points.len() as u16;
+        result
+    }
+
+    /// Statically evaluates a COMPARE node, or returns None if it depends on runtime values
+    fn fold_comparison(
+        &self,
+        graph: &ExecutionGraph,
+        compare_id: u32,
+        known_facts: &HashMap<String, Value>,
+    ) -> Option<bool> {
+        let compare = Self::find_node(graph, compare_id)?;
+        let op = Self::comparator_from_flags(compare.flags)?;
+
+        let operands: Vec<(Option<Value>, bool)> = Self::data_children(graph, compare_id)
+            .map(|id| Self::static_operand(graph, id, known_facts))
+            .collect();
+        let [(Some(left), left_is_fact), (Some(right), right_is_fact)] = operands.as_slice() else {
+            return None;
+        };
+
+        // Only fold comparisons that actually depend on a known fact
+        if !left_is_fact && !right_is_fact {
+            return None;
+        }
+
+        self.compare_values(left, right, &op).ok()
+    }
+
+    /// Resolves an operand load to a static value, noting whether it came from a known fact
+    fn static_operand(
+        graph: &ExecutionGraph,
+        node_id: u32,
+        known_facts: &HashMap<String, Value>,
+    ) -> (Option<Value>, bool) {
+        let Some(SpecializedNode::Value(value_node)) =
+            graph.nodes.iter().find(|n| n.get_base().id == node_id)
+        else {
+            return (None, false);
+        };
+
+        match value_node.base.opcode {
+            0x11 => (load_num_value(value_node).ok().map(Value::Num), false), // LOAD_NUM
+            0x10 => match known_facts.get(&value_node.value_sym) {
+                Some(value) => (Some(value.clone()), true),
+                // Unknown qualified references are runtime facts
+                None if value_node.value_sym.contains('.') => (None, false),
+                None => (Some(Value::Sym(value_node.value_sym.clone())), false),
+            },
+            _ => (None, false),
+        }
+    }
+
+    fn find_node(graph: &ExecutionGraph, node_id: u32) -> Option<&GraphNode> {
+        graph
+            .nodes
+            .iter()
+            .map(|n| n.get_base())
+            .find(|n| n.id == node_id)
+    }
+
+    fn data_children(graph: &ExecutionGraph, node_id: u32) -> impl Iterator<Item = u32> + '_ {
+        graph
+            .edges
+            .iter()
+            .filter(move |e| e.from_node == node_id && e.edge_type == EdgeType::Data)
+            .map(|e| e.to_node)
+    }
+
+    /// Strongly connected components of the control-flow graph (control and
+    /// condition edges) that contain a cycle, i.e. more than one node or a
+    /// self-loop. Each component is sorted, and components are ordered by
+    /// their lowest node ID.
+    pub fn detect_cycles(graph: &ExecutionGraph) -> Vec<Vec<u32>> {
+        let control: Vec<(u32, u32)> = graph
+            .edges
+            .iter()
+            .filter(|e| e.edge_type != EdgeType::Data)
+            .map(|e| (e.from_node, e.to_node))
+            .collect();
+        let mut successors: HashMap<u32, Vec<u32>> = HashMap::new();
+        let mut predecessors: HashMap<u32, Vec<u32>> = HashMap::new();
+        for &(from, to) in &control {
+            successors.entry(from).or_default().push(to);
+            predecessors.entry(to).or_default().push(from);
+        }
+        let mut ids: Vec<u32> = control.iter().flat_map(|&(from, to)| [from, to]).collect();
+        ids.sort_unstable();
+        ids.dedup();
+
+        // Kosaraju: order nodes by DFS finish time, then collect components
+        // on the reversed graph in reverse finish order
+        let mut finished = Vec::with_capacity(ids.len());
+        let mut visited = HashSet::new();
+        for &root in &ids {
+            if !visited.insert(root) {
+                continue;
+            }
+            let mut stack = vec![(root, 0)];
+            while let Some((id, next)) = stack.pop() {
+                let children = successors.get(&id).map_or(&[][..], |s| s.as_slice());
+                if let Some(&child) = children.get(next) {
+                    stack.push((id, next + 1));
+                    if visited.insert(child) {
+                        stack.push((child, 0));
+                    }
+                } else {
+                    finished.push(id);
+                }
+            }
+        }
+
+        let mut assigned = HashSet::new();
+        let mut cycles = Vec::new();
+        for &root in finished.iter().rev() {
+            if !assigned.insert(root) {
+                continue;
+            }
+            let mut component = vec![root];
+            let mut stack = vec![root];
+            while let Some(id) = stack.pop() {
+                for &pred in predecessors.get(&id).into_iter().flatten() {
+                    if assigned.insert(pred) {
+                        component.push(pred);
+                        stack.push(pred);
+                    }
+                }
+            }
+            if component.len() > 1 || control.contains(&(root, root)) {
+                component.sort_unstable();
+                cycles.push(component);
+            }
+        }
+        cycles.sort();
+        cycles
+    }
+
+    /// Whether a HALT node is reachable from any node of `cycle`
+    fn reaches_halt(graph: &ExecutionGraph, cycle: &[u32]) -> bool {
+        let mut seen: HashSet<u32> = HashSet::new();
+        let mut stack = cycle.to_vec();
+        while let Some(id) = stack.pop() {
+            if !seen.insert(id) {
+                continue;
+            }
+            if Self::find_node(graph, id).is_some_and(|node| {
+                node.node_type == kern_graph_builder::GraphNodeType::Control && node.opcode == 0x03
+            }) {
+                return true;
+            }
+            stack.extend(
+                graph
+                    .edges
+                    .iter()
+                    .filter(|e| e.from_node == id && e.edge_type != EdgeType::Data)
+                    .map(|e| e.to_node),
+            );
+        }
+        false
+    }
+
+    /// Nodes reachable from `root` that no other entry point reaches
+    fn exclusively_reachable(graph: &ExecutionGraph, root: u32) -> HashSet<u32> {
+        let reach = |starts: Vec<u32>, skip: Option<u32>| {
+            let mut seen: HashSet<u32> = HashSet::new();
+            let mut stack = starts;
+            while let Some(id) = stack.pop() {
+                if Some(id) == skip || !seen.insert(id) {
+                    continue;
+                }
+                stack.extend(
+                    graph
+                        .edges
+                        .iter()
+                        .filter(|e| e.from_node == id)
+                        .map(|e| e.to_node),
+                );
+            }
+            seen
+        };
+
+        let others: Vec<u32> = graph
+            .entry_points
+            .iter()
+            .map(|entry| entry.node_id)
+            .filter(|&id| id != root)
+            .collect();
+        let shared = reach(others, Some(root));
+
+        reach(vec![root], None)
+            .into_iter()
+            .filter(|id| !shared.contains(id))
+            .collect()
     }
 
     /// Executes the action part of a rule
@@ -910,10 +1868,12 @@ impl RuleEngine {
         Ok(())
     }
 
-    /// Executes a loop control node
+    /// Executes a loop control node. The limit comes from the register in
+    /// input_regs[1] when it holds a number, otherwise from the LoopNode.
     fn execute_loop_node(
         &mut self,
         node: &GraphNode,
+        iteration_limit: u32,
         graph: &ExecutionGraph,
     ) -> Result<(), RuleEngineError> {
         // Get the iteration counter register
@@ -927,36 +1887,30 @@ impl RuleEngine {
             }
         }
 
-        // Check the counter against the limit
-        if counter_reg < self.context.registers.len() && limit_reg < self.context.registers.len() {
-            if let (Some(Value::Num(counter)), Some(Value::Num(limit))) = (
-                &self.context.registers[counter_reg],
-                &self.context.registers[limit_reg],
-            ) {
-                if *counter < *limit {
-                    // Continue the loop - add the loop body to the queue
-                    for edge in &graph.edges {
-                        if edge.from_node == node.id && edge.edge_type == EdgeType::Control {
-                            if !self.priority_queue.contains(&edge.to_node) {
-                                self.priority_queue.push(edge.to_node);
-                            }
-                        }
-                    }
+        let limit = match self.context.registers.get(limit_reg) {
+            Some(Some(Value::Num(limit))) => *limit,
+            _ => iteration_limit as i64,
+        };
 
-                    // Increment the counter
-                    self.context.registers[counter_reg] = Some(Value::Num(counter + 1));
-                } else {
-                    // Loop finished - add exit nodes to the queue
-                    for edge in &graph.edges {
-                        if edge.from_node == node.id && edge.edge_type == EdgeType::Control {
-                            // In a real implementation, we'd distinguish between body and exit edges
-                            if !self.priority_queue.contains(&edge.to_node) {
-                                self.priority_queue.push(edge.to_node);
-                            }
-                        }
-                    }
+        // Check the counter against the limit
+        if let Some(Some(Value::Num(counter))) = self.context.registers.get(counter_reg) {
+            let counter = *counter;
+            // Body edges while iterating, exit edges once the limit is reached
+            let exiting = counter >= limit;
+            for edge in &graph.edges {
+                if edge.from_node == node.id
+                    && edge.edge_type == EdgeType::Control
+                    && (edge.condition_flag & LOOP_EXIT_EDGE != 0) == exiting
+                    && !self.priority_queue.contains(&edge.to_node)
+                {
+                    self.priority_queue.push(edge.to_node);
                 }
             }
+
+            if !exiting {
+                // Increment the counter
+                self.context.registers[counter_reg] = Some(Value::Num(counter + 1));
+            }
         }
 
         Ok(())
@@ -1013,6 +1967,44 @@ impl RuleEngine {
         self.context.variables.get(name)
     }
 
+    /// Bulk-loads facts from a JSON object of name -> value. Integers map to
+    /// `Num`, booleans to `Bool`, strings to `Sym` and arrays to `Vec`. Nothing
+    /// is loaded if any value can't be represented.
+    pub fn load_facts_json(&mut self, json: &str) -> Result<(), ContextError> {
+        let parsed: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| ContextError::InvalidJson(e.to_string()))?;
+        let serde_json::Value::Object(entries) = parsed else {
+            return Err(ContextError::NotAnObject);
+        };
+
+        let mut facts = Vec::with_capacity(entries.len());
+        for (name, value) in entries {
+            let value = Self::value_from_json(&value)
+                .map_err(|reason| ContextError::UnrepresentableValue(name.clone(), reason))?;
+            facts.push((name, value));
+        }
+        self.context.facts.extend(facts);
+        Ok(())
+    }
+
+    fn value_from_json(json: &serde_json::Value) -> Result<Value, String> {
+        match json {
+            serde_json::Value::Bool(b) => Ok(Value::Bool(*b)),
+            serde_json::Value::Number(n) => n
+                .as_i64()
+                .map(Value::Num)
+                .ok_or_else(|| format!("{} is not a 64-bit integer", n)),
+            serde_json::Value::String(s) => Ok(Value::Sym(s.clone())),
+            serde_json::Value::Array(items) => items
+                .iter()
+                .map(Self::value_from_json)
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Vec),
+            serde_json::Value::Null => Err("null has no fact value".to_string()),
+            serde_json::Value::Object(_) => Err("objects have no fact value".to_string()),
+        }
+    }
+
     /// Matches a pattern against the current context
     pub fn match_pattern(
         &self,
@@ -1020,10 +2012,9 @@ impl RuleEngine {
         value: &Value,
     ) -> Option<HashMap<String, Value>> {
         let mut bindings = HashMap::new();
-        if self.match_pattern_with_bindings(pattern, value, &mut bindings) {
-            Some(bindings)
-        } else {
-            None
+        match self.match_pattern_with_bindings(pattern, value, &mut bindings, 0) {
+            Ok(true) => Some(bindings),
+            _ => None,
         }
     }
 
@@ -1033,8 +2024,13 @@ impl RuleEngine {
         pattern: &Pattern,
         value: &Value,
         bindings: &mut HashMap<String, Value>,
-    ) -> bool {
-        match pattern {
+        depth: u32,
+    ) -> Result<bool, RuleEngineError> {
+        if depth > self.max_pattern_depth {
+            return Err(RuleEngineError::PatternTooDeep(self.max_pattern_depth));
+        }
+
+        let matched = match pattern {
             Pattern::Value(expected) => {
                 // Direct value comparison
                 expected == value
@@ -1057,7 +2053,7 @@ impl RuleEngine {
                         // Match entity.field pattern against symbol value
                         if let Some(expected_field) = pattern_parts.get(0) {
                             if let Pattern::Value(Value::Sym(expected)) = expected_field {
-                                return field_value == expected;
+                                return Ok(field_value == expected);
                             }
                         }
                         false
@@ -1066,7 +2062,7 @@ impl RuleEngine {
                         // Match entity pattern against symbol value
                         if let Some(expected_entity) = pattern_parts.get(0) {
                             if let Pattern::Value(Value::Sym(expected)) = expected_entity {
-                                return entity_value == expected;
+                                return Ok(entity_value == expected);
                             }
                         }
                         false
@@ -1081,8 +2077,9 @@ impl RuleEngine {
                                     pattern_part,
                                     field_value,
                                     bindings,
-                                ) {
-                                    return false;
+                                    depth + 1,
+                                )? {
+                                    return Ok(false);
                                 }
                             }
                             true
@@ -1093,7 +2090,9 @@ impl RuleEngine {
                     _ => false,
                 }
             }
-        }
+        };
+
+        Ok(matched)
     }
 
     /// Enhanced rule matching algorithm that matches against facts in the context
@@ -1207,7 +2206,10 @@ impl RuleEngine {
         }
     }
 
-    /// Matches a pattern against the graph nodes
+    /// Matches a pattern against the graph nodes.
+    ///
+    /// Matches are returned in ascending `matched_node` order so that enumeration
+    /// (and any "first match" logic built on it) is reproducible.
     pub fn match_graph_pattern(
         &mut self,
         pattern: &Pattern,
@@ -1229,6 +2231,7 @@ impl RuleEngine {
             }
         }
 
+        matches.sort_by_key(|m| m.matched_node);
         matches
     }
 
@@ -1238,11 +2241,23 @@ impl RuleEngine {
         pattern: &Pattern,
         value: &Value,
     ) -> Option<HashMap<String, Value>> {
+        self.try_match_complex_pattern(pattern, value)
+            .ok()
+            .flatten()
+    }
+
+    /// Like `match_complex_pattern`, but reports `PatternTooDeep` when the pattern
+    /// nests deeper than `max_pattern_depth` instead of treating it as a non-match
+    pub fn try_match_complex_pattern(
+        &self,
+        pattern: &Pattern,
+        value: &Value,
+    ) -> Result<Option<HashMap<String, Value>>, RuleEngineError> {
         let mut bindings = HashMap::new();
-        if self.match_complex_pattern_with_bindings(pattern, value, &mut bindings) {
-            Some(bindings)
+        if self.match_complex_pattern_with_bindings(pattern, value, &mut bindings, 0)? {
+            Ok(Some(bindings))
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -1252,8 +2267,13 @@ impl RuleEngine {
         pattern: &Pattern,
         value: &Value,
         bindings: &mut HashMap<String, Value>,
-    ) -> bool {
-        match pattern {
+        depth: u32,
+    ) -> Result<bool, RuleEngineError> {
+        if depth > self.max_pattern_depth {
+            return Err(RuleEngineError::PatternTooDeep(self.max_pattern_depth));
+        }
+
+        let matched = match pattern {
             Pattern::Value(expected) => {
                 // Direct value comparison
                 expected == value
@@ -1275,7 +2295,7 @@ impl RuleEngine {
                     ("entity.field", Value::Sym(field_value)) => {
                         if let Some(expected_field) = pattern_parts.get(0) {
                             if let Pattern::Value(Value::Sym(expected)) = expected_field {
-                                return field_value == expected;
+                                return Ok(field_value == expected);
                             }
                         }
                         false
@@ -1284,7 +2304,7 @@ impl RuleEngine {
                     ("entity", Value::Sym(entity_value)) => {
                         if let Some(expected_entity) = pattern_parts.get(0) {
                             if let Pattern::Value(Value::Sym(expected)) = expected_entity {
-                                return entity_value == expected;
+                                return Ok(entity_value == expected);
                             }
                         }
                         false
@@ -1299,8 +2319,9 @@ impl RuleEngine {
                                     pattern_part,
                                     field_value,
                                     bindings,
-                                ) {
-                                    return false;
+                                    depth + 1,
+                                )? {
+                                    return Ok(false);
                                 }
                             }
                             true
@@ -1318,8 +2339,9 @@ impl RuleEngine {
                                     pattern_part,
                                     vec_value,
                                     bindings,
-                                ) {
-                                    return false;
+                                    depth + 1,
+                                )? {
+                                    return Ok(false);
                                 }
                             }
                             true
@@ -1327,6 +2349,46 @@ impl RuleEngine {
                             false
                         }
                     }
+                    // Match negated pattern. The sub-pattern is tried against a
+                    // copy of the bindings so nothing it binds leaks out
+                    ("not", _) => match pattern_parts.as_slice() {
+                        [sub_pattern] => {
+                            let mut scratch = bindings.clone();
+                            !self.match_complex_pattern_with_bindings(
+                                sub_pattern,
+                                value,
+                                &mut scratch,
+                                depth + 1,
+                            )?
+                        }
+                        _ => false,
+                    },
+                    // Match the first alternative that fits. Each one is tried
+                    // against a copy of the bindings, kept only if it matches
+                    ("or", _) => {
+                        let mut matched = false;
+                        for alternative in pattern_parts {
+                            let mut scratch = bindings.clone();
+                            if self.match_complex_pattern_with_bindings(
+                                alternative,
+                                value,
+                                &mut scratch,
+                                depth + 1,
+                            )? {
+                                *bindings = scratch;
+                                matched = true;
+                                break;
+                            }
+                        }
+                        matched
+                    }
+                    // Match a number within the inclusive range [lo, hi]
+                    ("range", Value::Num(n)) => match pattern_parts.as_slice() {
+                        [Pattern::Value(Value::Num(lo)), Pattern::Value(Value::Num(hi))] => {
+                            lo <= n && n <= hi
+                        }
+                        _ => false,
+                    },
                     // Match any pattern (wildcard)
                     ("any", _) => true,
                     // Match type pattern
@@ -1348,10 +2410,16 @@ impl RuleEngine {
                     _ => false,
                 }
             }
-        }
+        };
+
+        Ok(matched)
     }
 
-    /// Matches multiple patterns against a set of values (for rule conditions)
+    /// Matches multiple patterns against a set of values (for rule conditions).
+    ///
+    /// Bindings are shared across patterns in order: once an earlier pattern binds a
+    /// variable, later patterns must agree with that value rather than rebinding it.
+    /// The returned vector holds the accumulated bindings after each pattern.
     pub fn match_multiple_patterns(
         &self,
         patterns: &[Pattern],
@@ -1366,7 +2434,9 @@ impl RuleEngine {
 
         for (pattern, value) in patterns.iter().zip(values.iter()) {
             let mut local_bindings = global_bindings.clone();
-            if self.match_pattern_with_bindings(pattern, value, &mut local_bindings) {
+            if let Ok(true) =
+                self.match_pattern_with_bindings(pattern, value, &mut local_bindings, 0)
+            {
                 // Update global bindings with new bindings
                 global_bindings = local_bindings;
                 all_bindings.push(global_bindings.clone());
@@ -1404,6 +2474,91 @@ impl RuleEngine {
         }
     }
 
+    /// Lists every rule in the graph, ordered by id. Names are resolved through
+    /// the symbol table, and priorities set on the engine override the graph's.
+    pub fn list_rules(&self, graph: &ExecutionGraph) -> Vec<RuleInfo> {
+        let mut rules: Vec<RuleInfo> = graph
+            .nodes
+            .iter()
+            .filter(|node| node.get_base().node_type == kern_graph_builder::GraphNodeType::Rule)
+            .map(|node| {
+                let base = node.get_base();
+                let (name_symbol, graph_priority) = match node {
+                    SpecializedNode::Rule(rule) => (rule.name_symbol, rule.priority as u32),
+                    _ => (None, 0),
+                };
+                let name = name_symbol
+                    .and_then(|id| self.symbol_table.get(&id).cloned())
+                    .unwrap_or_else(|| format!("rule_{}", base.id));
+                let priority = self
+                    .rule_priorities
+                    .get(&base.id)
+                    .map_or(graph_priority, |p| p.priority);
+
+                RuleInfo {
+                    id: base.id,
+                    name,
+                    priority,
+                    condition_summary: self.condition_summary(base, graph),
+                    action_count: self.get_rule_actions(base, graph).len(),
+                }
+            })
+            .collect();
+        rules.sort_by_key(|rule| rule.id);
+        rules
+    }
+
+    /// Renders a rule's comparisons the way evaluate_rule_condition reads
+    /// them: the rule fires if any one holds
+    fn condition_summary(&self, rule_node: &GraphNode, graph: &ExecutionGraph) -> String {
+        if rule_node.flags & RULE_FLAG_UNCONDITIONAL != 0 {
+            return "true".to_string();
+        }
+        let comparisons: Vec<String> = Self::data_children(graph, rule_node.id)
+            .filter_map(|id| Self::find_node(graph, id))
+            .filter(|node| node.opcode == 0x13)
+            .map(|compare| Self::describe_comparison(compare, graph))
+            .collect();
+        comparisons.join(" or ")
+    }
+
+    /// Renders a COMPARE node as `left op right`, naming operands after their loads
+    fn describe_comparison(compare: &GraphNode, graph: &ExecutionGraph) -> String {
+        format!(
+            "{} {} {}",
+            Self::compare_term_name(compare, compare.input_regs[0], graph),
+            Self::comparator_symbol(compare.flags),
+            Self::compare_term_name(compare, compare.input_regs[1], graph)
+        )
+    }
+
+    /// Like `describe_comparison`, but shows a static right-hand side as its value
+    fn describe_constant_comparison(compare: &GraphNode, graph: &ExecutionGraph) -> String {
+        let right = match Self::compare_constant(compare, graph) {
+            Some(Value::Num(n)) => n.to_string(),
+            Some(Value::Sym(s)) => s,
+            _ => return Self::describe_comparison(compare, graph),
+        };
+        format!(
+            "{} {} {}",
+            Self::compare_term_name(compare, compare.input_regs[0], graph),
+            Self::comparator_symbol(compare.flags),
+            right
+        )
+    }
+
+    fn comparator_symbol(flags: u16) -> &'static str {
+        match Self::comparator_from_flags(flags) {
+            Some(Comparator::Equal) => "==",
+            Some(Comparator::NotEqual) => "!=",
+            Some(Comparator::Greater) => ">",
+            Some(Comparator::Less) => "<",
+            Some(Comparator::GreaterEqual) => ">=",
+            Some(Comparator::LessEqual) => "<=",
+            None => "?",
+        }
+    }
+
     /// Detects conflicts between rules based on their conditions and actions
     pub fn detect_rule_conflicts(&self, graph: &ExecutionGraph) -> Vec<RuleConflict> {
         let mut conflicts = Vec::new();
@@ -1437,27 +2592,73 @@ impl RuleEngine {
         rule2: &SpecializedNode,
         graph: &ExecutionGraph,
     ) -> Option<RuleConflict> {
-        // For now, we'll implement a basic check
-        // In a real implementation, this would be more sophisticated
+        let (id1, id2) = (rule1.get_base().id, rule2.get_base().id);
+        let conflict = |conflict_type, description| RuleConflict {
+            rule1_id: id1,
+            rule2_id: id2,
+            conflict_type,
+            description,
+        };
+
+        // Rules whose comparisons on the same term exclude each other
+        let compares = |rule: &SpecializedNode| -> Vec<&GraphNode> {
+            Self::data_children(graph, rule.get_base().id)
+                .filter_map(|id| Self::find_node(graph, id))
+                .filter(|node| node.opcode == 0x13)
+                .collect()
+        };
+        for compare1 in compares(rule1) {
+            for compare2 in compares(rule2) {
+                if Self::comparisons_exclusive(compare1, compare2, graph) {
+                    return Some(conflict(
+                        ConflictType::ContradictoryConditions,
+                        format!(
+                            "Rules {} and {} have contradictory conditions: {} and {}",
+                            id1,
+                            id2,
+                            Self::describe_constant_comparison(compare1, graph),
+                            Self::describe_constant_comparison(compare2, graph)
+                        ),
+                    ));
+                }
+            }
+        }
 
-        // Check if rules modify the same variables or entities
         let rule1_actions = self.get_rule_actions(rule1.get_base(), graph);
         let rule2_actions = self.get_rule_actions(rule2.get_base(), graph);
 
+        // Moves into the same register from different sources
+        for action1 in &rule1_actions {
+            for action2 in &rule2_actions {
+                let (move1, move2) = (action1.get_base(), action2.get_base());
+                if move1.opcode == 0x12
+                    && move2.opcode == 0x12
+                    && move1.output_regs[0] == move2.output_regs[0]
+                    && move1.input_regs[0] != move2.input_regs[0]
+                {
+                    return Some(conflict(
+                        ConflictType::StateConflict,
+                        format!(
+                            "Rules {} and {} both write R{} (from R{} and R{})",
+                            id1,
+                            id2,
+                            move1.output_regs[0],
+                            move1.input_regs[0],
+                            move2.input_regs[0]
+                        ),
+                    ));
+                }
+            }
+        }
+
         // Check for conflicting assignments
         for action1 in &rule1_actions {
             for action2 in &rule2_actions {
                 if self.actions_conflict(action1, action2) {
-                    return Some(RuleConflict {
-                        rule1_id: rule1.get_base().id,
-                        rule2_id: rule2.get_base().id,
-                        conflict_type: ConflictType::ActionConflict,
-                        description: format!(
-                            "Rules {} and {} have conflicting actions",
-                            rule1.get_base().id,
-                            rule2.get_base().id
-                        ),
-                    });
+                    return Some(conflict(
+                        ConflictType::ActionConflict,
+                        format!("Rules {} and {} have conflicting actions", id1, id2),
+                    ));
                 }
             }
         }
@@ -1465,6 +2666,80 @@ impl RuleEngine {
         None
     }
 
+    /// Whether two comparisons test the same term against constants in a way
+    /// that no single value can satisfy both
+    fn comparisons_exclusive(
+        compare1: &GraphNode,
+        compare2: &GraphNode,
+        graph: &ExecutionGraph,
+    ) -> bool {
+        let term1 = Self::compare_term_name(compare1, compare1.input_regs[0], graph);
+        let term2 = Self::compare_term_name(compare2, compare2.input_regs[0], graph);
+        if term1 != term2 {
+            return false;
+        }
+        let (Some(op1), Some(op2)) = (
+            Self::comparator_from_flags(compare1.flags),
+            Self::comparator_from_flags(compare2.flags),
+        ) else {
+            return false;
+        };
+        let (Some(value1), Some(value2)) = (
+            Self::compare_constant(compare1, graph),
+            Self::compare_constant(compare2, graph),
+        ) else {
+            return false;
+        };
+
+        match (&value1, &value2) {
+            (Value::Num(k1), Value::Num(k2)) => {
+                match (
+                    Self::comparator_range(&op1, *k1),
+                    Self::comparator_range(&op2, *k2),
+                ) {
+                    (Some((lo1, hi1)), Some((lo2, hi2))) => hi1 < lo2 || hi2 < lo1,
+                    // x != k only excludes x == k
+                    _ => {
+                        matches!(
+                            (op1, op2),
+                            (Comparator::Equal, Comparator::NotEqual)
+                                | (Comparator::NotEqual, Comparator::Equal)
+                        ) && k1 == k2
+                    }
+                }
+            }
+            _ => match (op1, op2) {
+                (Comparator::Equal, Comparator::Equal) => value1 != value2,
+                (Comparator::Equal, Comparator::NotEqual)
+                | (Comparator::NotEqual, Comparator::Equal) => value1 == value2,
+                _ => false,
+            },
+        }
+    }
+
+    /// The constant on the right-hand side of a COMPARE, if its load is static
+    fn compare_constant(compare: &GraphNode, graph: &ExecutionGraph) -> Option<Value> {
+        Self::data_children(graph, compare.id).find_map(|id| {
+            let node = Self::find_node(graph, id)?;
+            if node.output_regs[0] != compare.input_regs[1] {
+                return None;
+            }
+            Self::static_operand(graph, id, &HashMap::new()).0
+        })
+    }
+
+    /// The inclusive range of integers satisfying `x op k`; None for `!=`
+    fn comparator_range(op: &Comparator, k: i64) -> Option<(i64, i64)> {
+        match op {
+            Comparator::Equal => Some((k, k)),
+            Comparator::NotEqual => None,
+            Comparator::Greater => Some((k.saturating_add(1), i64::MAX)),
+            Comparator::GreaterEqual => Some((k, i64::MAX)),
+            Comparator::Less => Some((i64::MIN, k.saturating_sub(1))),
+            Comparator::LessEqual => Some((i64::MIN, k)),
+        }
+    }
+
     /// Gets the actions associated with a rule node
     fn get_rule_actions(
         &self,
@@ -1632,3 +2907,34 @@ pub enum ConflictType {
     /// Rules modify the same state in conflicting ways
     StateConflict = 4,
 }
+
+/// An output slot of an execution context that a subflow may write
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum ContextOutput {
+    Register(usize),
+    Variable(String),
+    Fact(String),
+    RuleResult(String),
+}
+
+impl std::fmt::Display for ContextOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContextOutput::Register(idx) => write!(f, "R{}", idx),
+            ContextOutput::Variable(name) => write!(f, "variable {}", name),
+            ContextOutput::Fact(name) => write!(f, "fact {}", name),
+            ContextOutput::RuleResult(name) => write!(f, "rule result {}", name),
+        }
+    }
+}
+
+/// The constant a LOAD_NUM value node loads. Rule engine numbers are `i64`, so
+/// a payload with a fractional part or outside the `i64` range is rejected
+/// rather than truncated.
+fn load_num_value(value_node: &ValueNode) -> Result<i64, RuleEngineError> {
+    let value = value_node.value_num;
+    if value.fract() != 0.0 || value < i64::MIN as f64 || value >= i64::MAX as f64 {
+        return Err(RuleEngineError::NonIntegralConstant(value_node.base.id));
+    }
+    Ok(value as i64)
+}
@@ -165,6 +165,8 @@ mod tests {
                 build_hash: 0,
                 version: 0,
             },
+            flow_outputs: HashMap::new(),
+            symbol_table: HashMap::new(),
         };
 
         let mut rule_engine = RuleEngine::new(Some(graph.clone()));
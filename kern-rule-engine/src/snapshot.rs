@@ -0,0 +1,84 @@
+use crate::types::Value;
+use crate::RuleEngine;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A point-in-time copy of the engine's fact base and variables
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EngineSnapshot {
+    pub facts: BTreeMap<String, Value>,
+    pub variables: BTreeMap<String, Value>,
+}
+
+/// Keys added, removed or changed between two maps
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MapDiff {
+    pub added: BTreeMap<String, Value>,
+    pub removed: BTreeMap<String, Value>,
+    pub changed: BTreeMap<String, (Value, Value)>, // key -> (before, after)
+}
+
+/// What changed in the fact base and variables between two snapshots
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FactDiff {
+    pub facts: MapDiff,
+    pub variables: MapDiff,
+}
+
+impl EngineSnapshot {
+    /// Returns the changes needed to go from `self` to `other`
+    pub fn diff(&self, other: &EngineSnapshot) -> FactDiff {
+        FactDiff {
+            facts: MapDiff::between(&self.facts, &other.facts),
+            variables: MapDiff::between(&self.variables, &other.variables),
+        }
+    }
+}
+
+impl MapDiff {
+    fn between(before: &BTreeMap<String, Value>, after: &BTreeMap<String, Value>) -> Self {
+        let added = after
+            .iter()
+            .filter(|(key, _)| !before.contains_key(*key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let removed = before
+            .iter()
+            .filter(|(key, _)| !after.contains_key(*key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let changed = before
+            .iter()
+            .filter_map(|(key, old)| match after.get(key) {
+                Some(new) if new != old => Some((key.clone(), (old.clone(), new.clone()))),
+                _ => None,
+            })
+            .collect();
+
+        MapDiff {
+            added,
+            removed,
+            changed,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl FactDiff {
+    pub fn is_empty(&self) -> bool {
+        self.facts.is_empty() && self.variables.is_empty()
+    }
+}
+
+impl RuleEngine {
+    /// Captures the current facts and variables for later diffing
+    pub fn snapshot(&self) -> EngineSnapshot {
+        EngineSnapshot {
+            facts: self.context.facts.clone(),
+            variables: self.context.variables.clone(),
+        }
+    }
+}
@@ -1,6 +1,16 @@
-use crate::types::{Pattern, Value};
-use crate::RuleEngine;
-use kern_graph_builder::{ContextPool, ExecutionGraph, GraphMeta, Register, RegisterSet};
+use crate::types::{
+    AssertionConflictPolicy, ContextError, EngineEvent, ExecutionContext, FactProvenance,
+    FlowResult, Pattern, PriorityProvider, PriorityStrategy, RuleEngineError, RuleInfo,
+    RulePriority, RuleTraceEntry, TieBreak, Value, RULE_FLAG_UNCONDITIONAL,
+};
+use crate::{ConflictType, RuleEngine};
+use kern_graph_builder::{
+    ContextPool, EntryPoint, ExecutionGraph, FlowOutput, GraphEdge, GraphError, GraphMeta,
+    GraphNode, GraphNodeType, LoopNode, NodeMeta, Register, RegisterSet, RuleNode, SpecializedNode,
+    ValueNode,
+};
+use kern_parser::Comparator;
+use std::collections::HashMap;
 
 #[cfg(test)]
 mod tests {
@@ -25,9 +35,160 @@ mod tests {
                 build_hash: 0,
                 version: 0,
             },
+            flow_outputs: HashMap::new(),
+            symbol_table: HashMap::new(),
         }
     }
 
+    fn create_move_node(id: u32, src: u16, dst: u16) -> SpecializedNode {
+        SpecializedNode::Base(GraphNode {
+            id,
+            node_type: GraphNodeType::Op,
+            opcode: 0x12, // MOVE
+            flags: 0,
+            input_regs: [src, 0, 0, 0],
+            output_regs: [dst, 0],
+            first_edge: 0,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: 0,
+                cost_hint: 1,
+            },
+        })
+    }
+
+    fn create_flow_node(id: u32) -> SpecializedNode {
+        SpecializedNode::Base(GraphNode {
+            id,
+            node_type: GraphNodeType::Control,
+            opcode: 0x00, // NOP
+            flags: 0,
+            input_regs: [0; 4],
+            output_regs: [0; 2],
+            first_edge: 0,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: 0,
+                cost_hint: 1,
+            },
+        })
+    }
+
+    fn create_rule_node(id: u32) -> SpecializedNode {
+        SpecializedNode::Base(GraphNode {
+            id,
+            node_type: GraphNodeType::Rule,
+            opcode: 0x31,
+            flags: 0,
+            input_regs: [0; 4],
+            output_regs: [0; 2],
+            first_edge: 0,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: 0,
+                cost_hint: 1,
+            },
+        })
+    }
+
+    fn create_compare_node(id: u32, left: u16, right: u16, dst: u16) -> SpecializedNode {
+        SpecializedNode::Base(GraphNode {
+            id,
+            node_type: GraphNodeType::Op,
+            opcode: 0x13, // COMPARE
+            flags: 0,     // ==
+            input_regs: [left, right, 0, 0],
+            output_regs: [dst, 0],
+            first_edge: 0,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: 0,
+                cost_hint: 1,
+            },
+        })
+    }
+
+    fn create_load_sym_node(id: u32, name: &str) -> SpecializedNode {
+        let base = GraphNode {
+            id,
+            node_type: GraphNodeType::Op,
+            opcode: 0x10, // LOAD_SYM
+            flags: 0,
+            input_regs: [0; 4],
+            output_regs: [0; 2],
+            first_edge: 0,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: 0,
+                cost_hint: 1,
+            },
+        };
+        SpecializedNode::Value(ValueNode::new_sym(base, name.to_string()))
+    }
+
+    // Rule `id` fires `id + 3` when farmer.location == `expected`
+    fn add_location_rule(graph: &mut ExecutionGraph, id: u32, expected: &str) {
+        graph.nodes.push(create_rule_node(id));
+        graph.nodes.push(create_compare_node(id + 1, 1, 2, 3));
+        graph
+            .nodes
+            .push(create_load_sym_node(id + 2, "farmer.location"));
+        graph.nodes.push(create_move_node(id + 3, 1, 4));
+        graph.nodes.push(create_load_sym_node(id + 4, expected));
+        graph.edges.push(GraphEdge::new_data(id, id + 1));
+        graph.edges.push(GraphEdge::new_data(id + 1, id + 2));
+        graph.edges.push(GraphEdge::new_data(id + 1, id + 4));
+        graph.edges.push(GraphEdge::new_data(id, id + 3));
+        graph.entry_points.push(EntryPoint {
+            node_id: id,
+            entry_type: 0,
+        });
+    }
+
+    // Rule `id` compares R1 against the constant `k` with the comparator in `flags`
+    // and moves `src` into R4
+    fn add_threshold_rule(graph: &mut ExecutionGraph, id: u32, flags: u16, k: f64, src: u16) {
+        let mut compare = create_compare_node(id + 1, 1, 2, 3);
+        if let SpecializedNode::Base(node) = &mut compare {
+            node.flags = flags;
+        }
+        let load = GraphNode {
+            id: id + 2,
+            node_type: GraphNodeType::Op,
+            opcode: 0x11, // LOAD_NUM
+            flags: 0,
+            input_regs: [0; 4],
+            output_regs: [2, 0],
+            first_edge: 0,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: 0,
+                cost_hint: 1,
+            },
+        };
+        graph.nodes.push(create_rule_node(id));
+        graph.nodes.push(compare);
+        graph
+            .nodes
+            .push(SpecializedNode::Value(ValueNode::new_num(load, k)));
+        graph.nodes.push(create_move_node(id + 3, src, 4));
+        graph.edges.push(GraphEdge::new_data(id, id + 1));
+        graph.edges.push(GraphEdge::new_data(id + 1, id + 2));
+        graph.edges.push(GraphEdge::new_data(id, id + 3));
+    }
+
+    // Two flows, each moving its own input register into its own output register
+    fn create_subflow_graph(second_dst: u16) -> ExecutionGraph {
+        let mut graph = create_mock_graph();
+        graph.nodes.push(create_flow_node(10));
+        graph.nodes.push(create_move_node(11, 1, 2));
+        graph.nodes.push(create_flow_node(20));
+        graph.nodes.push(create_move_node(21, 3, second_dst));
+        graph.edges.push(GraphEdge::new_control(10, 11));
+        graph.edges.push(GraphEdge::new_control(20, 21));
+        graph
+    }
+
     #[test]
     fn test_rule_engine_initialization() {
         let graph = create_mock_graph();
@@ -60,4 +221,1378 @@ mod tests {
             assert!(result.is_ok());
         }
     }
+
+    #[test]
+    fn test_lazy_cache_recomputes_on_changed_input() {
+        let mut graph = create_mock_graph();
+        graph.nodes.push(create_move_node(1, 1, 2));
+        let mut engine = RuleEngine::new(None);
+
+        engine.context.registers[1] = Some(Value::Num(5));
+        assert_eq!(engine.evaluate_lazy(1, &graph).unwrap(), Value::Num(5));
+
+        // A changed input must miss the cache and yield the new value
+        engine.context.registers[1] = Some(Value::Num(7));
+        assert_eq!(engine.evaluate_lazy(1, &graph).unwrap(), Value::Num(7));
+
+        // Unchanged inputs hit the cache even if the output register is clobbered
+        engine.context.registers[2] = Some(Value::Num(0));
+        assert_eq!(engine.evaluate_lazy(1, &graph).unwrap(), Value::Num(7));
+        assert_eq!(engine.lazy_cache.len(), 2);
+    }
+
+    #[test]
+    fn test_invalidate_lazy_forces_reevaluation() {
+        let mut graph = create_mock_graph();
+        graph.nodes.push(create_move_node(1, 1, 2));
+        graph.nodes.push(create_move_node(2, 3, 4));
+        let mut engine = RuleEngine::new(None);
+
+        engine.context.registers[1] = Some(Value::Num(5));
+        engine.context.registers[3] = Some(Value::Num(9));
+        assert_eq!(engine.evaluate_lazy(1, &graph).unwrap(), Value::Num(5));
+        assert_eq!(engine.evaluate_lazy(2, &graph).unwrap(), Value::Num(9));
+
+        // An input change yields the new result
+        engine.context.registers[1] = Some(Value::Num(6));
+        assert_eq!(engine.evaluate_lazy(1, &graph).unwrap(), Value::Num(6));
+        assert_eq!(engine.lazy_cache.len(), 3);
+
+        // Invalidation drops every entry for that node only
+        engine.invalidate_lazy(1);
+        assert_eq!(engine.lazy_cache.len(), 1);
+        assert_eq!(engine.lazy_cache_order.len(), 1);
+        engine.context.registers[2] = Some(Value::Num(0));
+        assert_eq!(engine.evaluate_lazy(1, &graph).unwrap(), Value::Num(6));
+
+        engine.clear_lazy_cache();
+        assert!(engine.lazy_cache.is_empty());
+        assert!(engine.lazy_cache_order.is_empty());
+    }
+
+    #[test]
+    fn test_lazy_data_cycle_is_reported() {
+        let mut graph = create_mock_graph();
+        graph.nodes.push(create_move_node(1, 1, 2));
+        graph.nodes.push(create_move_node(2, 2, 3));
+        graph.nodes.push(create_move_node(3, 3, 4));
+        graph.edges.push(GraphEdge::new_data(1, 2));
+        graph.edges.push(GraphEdge::new_data(2, 1));
+        graph.edges.push(GraphEdge::new_data(2, 3));
+        let mut engine = RuleEngine::new(None);
+        engine.context.registers[1] = Some(Value::Num(5));
+
+        assert!(matches!(
+            engine.evaluate_lazy_with_dependencies(1, &graph),
+            Err(RuleEngineError::CyclicDependency(1))
+        ));
+        assert!(matches!(
+            engine.evaluate_lazy_with_dependencies(3, &graph),
+            Err(RuleEngineError::CyclicDependency(2))
+        ));
+
+        // Without the back edge the chain evaluates in dependency order
+        graph.edges.remove(1);
+        assert_eq!(
+            engine.evaluate_lazy_with_dependencies(3, &graph).unwrap(),
+            Value::Num(5)
+        );
+    }
+
+    #[test]
+    fn test_lazy_cache_is_bounded() {
+        let mut graph = create_mock_graph();
+        graph.nodes.push(create_move_node(1, 1, 2));
+        let mut engine = RuleEngine::new(None);
+        engine.max_lazy_cache_entries = 2;
+
+        for i in 0..5 {
+            engine.context.registers[1] = Some(Value::Num(i));
+            assert_eq!(engine.evaluate_lazy(1, &graph).unwrap(), Value::Num(i));
+        }
+        assert_eq!(engine.lazy_cache.len(), 2);
+        assert_eq!(engine.lazy_cache_order.len(), 2);
+    }
+
+    #[test]
+    fn test_parallel_subflows_match_sequential_run() {
+        let graph = create_subflow_graph(4);
+
+        let mut sequential = RuleEngine::new(None);
+        sequential.context.registers[1] = Some(Value::Num(5));
+        sequential.context.registers[3] = Some(Value::Num(9));
+        sequential.execute_flow_pipeline(&graph, 10).unwrap();
+        sequential.execute_flow_pipeline(&graph, 20).unwrap();
+
+        let mut parallel = RuleEngine::new(None);
+        parallel.context.registers[1] = Some(Value::Num(5));
+        parallel.context.registers[3] = Some(Value::Num(9));
+        parallel
+            .execute_subflows_parallel(&[20, 10], &graph)
+            .unwrap();
+
+        assert_eq!(parallel.context.registers[2], Some(Value::Num(5)));
+        assert_eq!(parallel.context.registers[4], Some(Value::Num(9)));
+        assert_eq!(parallel.context.registers, sequential.context.registers);
+    }
+
+    #[test]
+    fn test_flow_result_contains_declared_outputs() {
+        let mut graph = create_subflow_graph(4);
+        graph.flow_outputs.insert(
+            10,
+            vec![FlowOutput {
+                name: "total".to_string(),
+                register: 2,
+            }],
+        );
+
+        let mut engine = RuleEngine::new(None);
+        engine.context.registers[1] = Some(Value::Num(5));
+        engine.context.registers[3] = Some(Value::Num(9));
+
+        let result = engine.execute_flow_pipeline(&graph, 10).unwrap();
+
+        // R1 and R3 are set too, but only the declared output is returned
+        let mut expected = FlowResult::default();
+        expected.outputs.insert("total".to_string(), Value::Num(5));
+        assert_eq!(result, expected);
+
+        // A flow without declared outputs returns an empty result
+        let result = engine.execute_flow_pipeline(&graph, 20).unwrap();
+        assert!(result.outputs.is_empty());
+    }
+
+    #[test]
+    fn test_flow_output_assignment_from_source() {
+        let program = kern_parser::Parser::new("flow Total(total) { total = 5 }")
+            .parse_program()
+            .unwrap();
+        let graph = kern_graph_builder::GraphBuilder::new().build_execution_graph(&program);
+        let flow_node_id = graph.entry_points[0].node_id;
+
+        let mut engine = RuleEngine::new(None);
+        let result = engine.execute_flow_pipeline(&graph, flow_node_id).unwrap();
+
+        let mut expected = FlowResult::default();
+        expected.outputs.insert("total".to_string(), Value::Num(5));
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_load_sym_resolves_symbols_from_built_graph() {
+        let program = kern_parser::Parser::new("flow Label(out) { out = farmer }")
+            .parse_program()
+            .unwrap();
+        let graph = kern_graph_builder::GraphBuilder::new().build_execution_graph(&program);
+        let flow_node_id = graph.entry_points[0].node_id;
+
+        // The engine picks up the builder's symbol table from the graph
+        let mut engine = RuleEngine::new(Some(graph.clone()));
+        assert_eq!(engine.symbol_table, graph.symbol_table);
+        let result = engine.execute_flow_pipeline(&graph, flow_node_id).unwrap();
+        assert_eq!(
+            result.outputs.get("out"),
+            Some(&Value::Sym("farmer".to_string()))
+        );
+
+        // Including when the graph is only passed at execution time
+        let mut engine = RuleEngine::new(None);
+        let result = engine.execute_flow_pipeline(&graph, flow_node_id).unwrap();
+        assert_eq!(
+            result.outputs.get("out"),
+            Some(&Value::Sym("farmer".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_partially_evaluate_known_facts() {
+        let mut graph = create_mock_graph();
+        add_location_rule(&mut graph, 10, "valid");
+        add_location_rule(&mut graph, 20, "invalid");
+
+        let mut known_facts = HashMap::new();
+        known_facts.insert(
+            "farmer.location".to_string(),
+            Value::Sym("valid".to_string()),
+        );
+
+        let engine = RuleEngine::new(None);
+        let folded = engine.partially_evaluate(&graph, &known_facts);
+        let node_ids: Vec<u32> = folded.nodes.iter().map(|n| n.get_base().id).collect();
+
+        // Rule 10 becomes unconditional: its comparison is gone, its action stays
+        let rule = folded.nodes.iter().find(|n| n.get_base().id == 10).unwrap();
+        assert_ne!(rule.get_base().flags & RULE_FLAG_UNCONDITIONAL, 0);
+        assert_eq!(node_ids, vec![10, 13]);
+
+        // Rule 20 is statically false and dropped with its action
+        assert_eq!(folded.entry_points.len(), 1);
+        assert_eq!(folded.entry_points[0].node_id, 10);
+        assert_eq!(folded.edges.len(), 1);
+        assert_eq!(folded.node_count, 2);
+
+        // Unknown facts leave the graph untouched
+        let untouched = engine.partially_evaluate(&graph, &HashMap::new());
+        assert_eq!(untouched.nodes.len(), graph.nodes.len());
+    }
+
+    #[test]
+    fn test_unconditional_rule_fires_without_comparisons() {
+        let mut graph = create_mock_graph();
+        add_location_rule(&mut graph, 10, "valid");
+
+        let mut known_facts = HashMap::new();
+        known_facts.insert(
+            "farmer.location".to_string(),
+            Value::Sym("valid".to_string()),
+        );
+
+        let mut engine = RuleEngine::new(None);
+        let folded = engine.partially_evaluate(&graph, &known_facts);
+        engine.context.registers[1] = Some(Value::Num(7));
+        engine.execute_graph(&folded).unwrap();
+
+        assert_eq!(engine.condition_stats(10), Some((1, 1)));
+        assert_eq!(engine.context.registers[4], Some(Value::Num(7)));
+    }
+
+    #[test]
+    fn test_provenance_records_deriving_rule_and_supporting_facts() {
+        let mut graph = create_mock_graph();
+        add_location_rule(&mut graph, 10, "valid");
+        // Rule 10 also asserts farmer.eligible from R1
+        graph.nodes.push(SpecializedNode::Base(GraphNode {
+            id: 15,
+            node_type: GraphNodeType::Op,
+            opcode: 0x62, // SET_SYMBOL
+            flags: 1,     // symbol id
+            input_regs: [1, 0, 0, 0],
+            output_regs: [0; 2],
+            first_edge: 0,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: 0,
+                cost_hint: 1,
+            },
+        }));
+        graph.edges.push(GraphEdge::new_data(10, 15));
+
+        let mut engine = RuleEngine::new(None);
+        engine.set_record_provenance(true);
+        engine.define_symbol(1, "farmer.eligible");
+        engine.context.facts.insert(
+            "farmer.location".to_string(),
+            Value::Sym("valid".to_string()),
+        );
+        engine.context.registers[1] = Some(Value::Sym("valid".to_string()));
+        engine.context.registers[2] = Some(Value::Sym("valid".to_string()));
+        engine.execute_graph(&graph).unwrap();
+
+        assert_eq!(
+            engine.context.facts.get("farmer.eligible"),
+            Some(&Value::Sym("valid".to_string()))
+        );
+        assert_eq!(
+            engine.provenance("farmer.eligible"),
+            Some(FactProvenance {
+                rule_id: 10,
+                supporting_facts: vec!["farmer.location".to_string()],
+            })
+        );
+        // Facts asserted outside a rule have no provenance
+        assert_eq!(engine.provenance("farmer.location"), None);
+    }
+
+    #[test]
+    fn test_pattern_match_bindings_are_sorted() {
+        let mut graph = create_mock_graph();
+        graph.nodes.push(create_move_node(7, 1, 2));
+
+        let mut engine = RuleEngine::new(None);
+        engine.context.registers[2] = Some(Value::Vec(vec![
+            Value::Num(1),
+            Value::Sym("north".to_string()),
+        ]));
+
+        // Variables are deliberately named out of key order
+        let pattern = Pattern::Composite(
+            "entity.fields".to_string(),
+            vec![
+                Pattern::Variable("zone".to_string()),
+                Pattern::Variable("amount".to_string()),
+            ],
+        );
+        let matches = engine.match_graph_pattern(&pattern, &graph);
+        assert_eq!(matches.len(), 1);
+
+        let sorted: Vec<(&str, &Value)> = matches[0]
+            .bindings_sorted()
+            .into_iter()
+            .map(|(name, value)| (name.as_str(), value))
+            .collect();
+        assert_eq!(
+            sorted,
+            vec![
+                ("amount", &Value::Sym("north".to_string())),
+                ("zone", &Value::Num(1)),
+            ]
+        );
+        assert_eq!(
+            matches[0].to_string(),
+            "node 7 { amount = Sym(\"north\"), zone = Num(1) }"
+        );
+        assert_eq!(
+            matches[0].to_json(),
+            r#"{"matched_node":7,"bindings":{"amount":{"Sym":"north"},"zone":{"Num":1}}}"#
+        );
+    }
+
+    // Rule 10 fires unconditionally: it moves R1 into R4, then asserts a fact
+    // through an undefined symbol, which fails
+    fn create_failing_action_graph() -> ExecutionGraph {
+        let mut graph = create_mock_graph();
+        let mut rule = create_rule_node(10);
+        if let SpecializedNode::Base(rule_node) = &mut rule {
+            rule_node.flags |= RULE_FLAG_UNCONDITIONAL;
+        }
+        graph.nodes.push(rule);
+        graph.nodes.push(create_move_node(11, 1, 4));
+        graph.nodes.push(SpecializedNode::Base(GraphNode {
+            id: 12,
+            node_type: GraphNodeType::Op,
+            opcode: 0x62, // SET_SYMBOL
+            flags: 99,    // not in the symbol table
+            input_regs: [1, 0, 0, 0],
+            output_regs: [0; 2],
+            first_edge: 0,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: 0,
+                cost_hint: 1,
+            },
+        }));
+        graph.edges.push(GraphEdge::new_data(10, 11));
+        graph.edges.push(GraphEdge::new_data(10, 12));
+        graph.entry_points.push(EntryPoint {
+            node_id: 10,
+            entry_type: 0,
+        });
+        graph
+    }
+
+    #[test]
+    fn test_transactional_actions_roll_back_on_failure() {
+        let graph = create_failing_action_graph();
+
+        let mut engine = RuleEngine::new(None);
+        engine.context.registers[1] = Some(Value::Num(5));
+        assert!(engine.execute_graph(&graph).is_err());
+        // Immediate mode keeps the first action's write
+        assert_eq!(engine.context.registers[4], Some(Value::Num(5)));
+
+        let mut engine = RuleEngine::new(None);
+        engine.set_transactional_actions(true);
+        engine.context.registers[1] = Some(Value::Num(5));
+        let result = engine.execute_graph(&graph);
+        assert!(matches!(
+            result,
+            Err(RuleEngineError::UndefinedIdentifier(99))
+        ));
+        assert_eq!(engine.context.registers[4], None);
+    }
+
+    // Unconditional rules 10 and 20 assert symbol 1 from R1 and R2 respectively
+    fn create_conflicting_assertion_graph() -> ExecutionGraph {
+        let mut graph = create_mock_graph();
+        for (rule_id, src) in [(10, 1), (20, 2)] {
+            let mut rule = create_rule_node(rule_id);
+            if let SpecializedNode::Base(rule_node) = &mut rule {
+                rule_node.flags |= RULE_FLAG_UNCONDITIONAL;
+            }
+            graph.nodes.push(rule);
+            graph.nodes.push(SpecializedNode::Base(GraphNode {
+                id: rule_id + 1,
+                node_type: GraphNodeType::Op,
+                opcode: 0x62, // SET_SYMBOL
+                flags: 1,
+                input_regs: [src, 0, 0, 0],
+                output_regs: [0; 2],
+                first_edge: 0,
+                edge_count: 0,
+                meta: NodeMeta {
+                    source_ref: 0,
+                    cost_hint: 1,
+                },
+            }));
+            graph.edges.push(GraphEdge::new_data(rule_id, rule_id + 1));
+            graph.entry_points.push(EntryPoint {
+                node_id: rule_id,
+                entry_type: 0,
+            });
+        }
+        graph
+    }
+
+    fn run_conflicting_assertions(
+        policy: Option<AssertionConflictPolicy>,
+    ) -> (RuleEngine, Result<(), RuleEngineError>) {
+        let mut engine = RuleEngine::new(None);
+        engine.set_unique_assertions(policy);
+        engine.define_symbol(1, "x");
+        engine.context.registers[1] = Some(Value::Num(3));
+        engine.context.registers[2] = Some(Value::Num(5));
+        let result = engine.execute_graph(&create_conflicting_assertion_graph());
+        (engine, result)
+    }
+
+    #[test]
+    fn test_conflicting_assertions_are_reported_in_unique_mode() {
+        // The rules tie and rule 10 was queued first, so it asserts x = 3; rule 20
+        // then asserts x = 5
+        let conflict = EngineEvent::ConflictingAssertion {
+            fact: "x".to_string(),
+            old: Value::Num(3),
+            new: Value::Num(5),
+            rule: Some(20),
+        };
+
+        let (engine, result) = run_conflicting_assertions(Some(AssertionConflictPolicy::KeepOld));
+        assert!(result.is_ok());
+        assert_eq!(engine.events.first(), Some(&conflict));
+        assert_eq!(engine.context.facts.get("x"), Some(&Value::Num(3)));
+
+        let (engine, result) = run_conflicting_assertions(Some(AssertionConflictPolicy::TakeNew));
+        assert!(result.is_ok());
+        assert_eq!(engine.events.first(), Some(&conflict));
+        assert_eq!(engine.context.facts.get("x"), Some(&Value::Num(5)));
+
+        let (engine, result) = run_conflicting_assertions(Some(AssertionConflictPolicy::Error));
+        assert!(
+            matches!(result, Err(RuleEngineError::ConflictingAssertion(ref fact)) if fact == "x")
+        );
+        assert_eq!(engine.events, vec![conflict]);
+
+        // Without the mode the second rule silently overwrites
+        let (engine, result) = run_conflicting_assertions(None);
+        assert!(result.is_ok());
+        assert!(engine.events.is_empty());
+        assert_eq!(engine.context.facts.get("x"), Some(&Value::Num(5)));
+    }
+
+    // Boosts the given rules while `fact` is present in the context
+    #[derive(Clone)]
+    struct FactBoost {
+        fact: String,
+        rules: Vec<u32>,
+    }
+
+    impl PriorityProvider for FactBoost {
+        fn priority(&self, rule: &RulePriority, ctx: &ExecutionContext, _step: u32) -> u32 {
+            let boosted = self.rules.contains(&rule.rule_id) && ctx.facts.contains_key(&self.fact);
+            if boosted {
+                100
+            } else {
+                0
+            }
+        }
+    }
+
+    #[test]
+    fn test_priority_provider_reorders_firing() {
+        // Under KeepOld, x keeps the value asserted by whichever rule fires first
+        let first_to_fire = |boosted: u32, flagged: bool| {
+            let mut engine = RuleEngine::new(None);
+            engine.set_unique_assertions(Some(AssertionConflictPolicy::KeepOld));
+            engine.set_priority_strategy(PriorityStrategy::Provider(Box::new(FactBoost {
+                fact: "farmer.flagged".to_string(),
+                rules: vec![boosted],
+            })));
+            engine.define_symbol(1, "x");
+            if flagged {
+                engine
+                    .context
+                    .facts
+                    .insert("farmer.flagged".to_string(), Value::Bool(true));
+            }
+            engine.context.registers[1] = Some(Value::Num(10));
+            engine.context.registers[2] = Some(Value::Num(20));
+            engine
+                .execute_graph(&create_conflicting_assertion_graph())
+                .unwrap();
+            engine.context.facts.get("x").cloned()
+        };
+
+        // Whichever rule the provider boosts fires first once the fact is present
+        assert_eq!(first_to_fire(10, true), Some(Value::Num(10)));
+        assert_eq!(first_to_fire(20, true), Some(Value::Num(20)));
+        // Without it the boost doesn't apply and both rules tie
+        assert_eq!(first_to_fire(10, false), first_to_fire(20, false));
+    }
+
+    #[test]
+    fn test_tie_break_orders_equal_priority_rules() {
+        let order = |tie_break: TieBreak| {
+            let mut engine = RuleEngine::new(None);
+            engine.set_tie_break(tie_break);
+            // All three compute to 200 under the standard strategy
+            engine.set_rule_priority(5, 0, 0, 20);
+            engine.set_rule_priority(7, 0, 1, 10);
+            engine.set_rule_priority(3, 0, 2, 0);
+            for id in [7, 3, 5] {
+                engine.add_to_priority_queue_with_strategy(id);
+            }
+            std::iter::from_fn(|| engine.select_next_node()).collect::<Vec<u32>>()
+        };
+
+        assert_eq!(order(TieBreak::InsertionOrder), vec![7, 3, 5]);
+        assert_eq!(order(TieBreak::LowestId), vec![3, 5, 7]);
+        assert_eq!(order(TieBreak::HighestRecency), vec![5, 7, 3]);
+    }
+
+    #[test]
+    fn test_detect_cycles_finds_two_node_control_cycle() {
+        let mut graph = create_mock_graph();
+        graph.nodes.push(create_flow_node(1));
+        graph.nodes.push(create_flow_node(2));
+        graph.nodes.push(create_move_node(3, 1, 2));
+        graph.edges.push(GraphEdge::new_control(1, 2));
+        graph.edges.push(GraphEdge::new_control(2, 1));
+        // Data edges never form control cycles
+        graph.edges.push(GraphEdge::new_data(2, 3));
+        graph.edges.push(GraphEdge::new_data(3, 2));
+        graph.entry_points.push(EntryPoint {
+            node_id: 1,
+            entry_type: 0,
+        });
+
+        assert_eq!(RuleEngine::detect_cycles(&graph), vec![vec![1, 2]]);
+
+        let mut engine = RuleEngine::new(None);
+        engine.set_reject_cyclic_flows(true);
+        assert!(matches!(
+            engine.execute_graph(&graph),
+            Err(RuleEngineError::CyclicFlow(ref cycle)) if cycle == &[1, 2]
+        ));
+
+        // A HALT reachable from the cycle gives it a way out
+        let mut halt = create_flow_node(4);
+        if let SpecializedNode::Base(node) = &mut halt {
+            node.opcode = 0x03;
+        }
+        graph.nodes.push(halt);
+        graph.edges.push(GraphEdge::new_condition(2, 4, 1));
+        assert_eq!(RuleEngine::detect_cycles(&graph), vec![vec![1, 2]]);
+        let mut engine = RuleEngine::new(None);
+        engine.set_reject_cyclic_flows(true);
+        engine.context.registers[1] = Some(Value::Num(1));
+        let result = engine.execute_graph(&graph);
+        assert!(result.is_ok(), "{:?}", result);
+    }
+
+    #[test]
+    fn test_reset_matches_fresh_engine_and_keeps_capacity() {
+        let mut graph = create_mock_graph();
+        add_location_rule(&mut graph, 10, "valid");
+        let seed = |engine: &mut RuleEngine| {
+            engine.context.facts.insert(
+                "farmer.location".to_string(),
+                Value::Sym("valid".to_string()),
+            );
+            engine.context.registers[1] = Some(Value::Sym("valid".to_string()));
+            engine.context.registers[2] = Some(Value::Sym("valid".to_string()));
+        };
+
+        let mut reused = RuleEngine::new(None);
+        seed(&mut reused);
+        reused.execute_graph(&graph).unwrap();
+        let activations_capacity = reused.activation_records.capacity();
+
+        reused.reset();
+        assert!(reused.context.facts.is_empty());
+        assert!(reused.context.registers.iter().all(|reg| reg.is_none()));
+        assert_eq!(reused.step_count, 0);
+        assert!(reused.activation_records.is_empty());
+        assert_eq!(reused.activation_records.capacity(), activations_capacity);
+
+        seed(&mut reused);
+        reused.execute_graph(&graph).unwrap();
+        let mut fresh = RuleEngine::new(None);
+        seed(&mut fresh);
+        fresh.execute_graph(&graph).unwrap();
+
+        assert_eq!(reused.context.registers, fresh.context.registers);
+        assert_eq!(reused.context.facts, fresh.context.facts);
+        assert_eq!(reused.step_count, fresh.step_count);
+        assert_eq!(reused.activation_records, fresh.activation_records);
+        assert_eq!(
+            reused.metrics.condition_stats,
+            fresh.metrics.condition_stats
+        );
+    }
+
+    #[test]
+    fn test_list_rules_reports_names_and_action_counts() {
+        let mut graph = create_mock_graph();
+        add_location_rule(&mut graph, 10, "valid");
+        add_location_rule(&mut graph, 20, "invalid");
+        // Rule 20 gets a second action
+        graph.nodes.push(create_move_node(30, 2, 5));
+        graph.edges.push(GraphEdge::new_data(20, 30));
+
+        // Give both rules a name symbol and a graph-level priority
+        for node in graph.nodes.iter_mut() {
+            let base = node.get_base().clone();
+            if base.node_type == GraphNodeType::Rule {
+                let mut rule = RuleNode::new(base.clone(), base.id, 5, 0);
+                rule.name_symbol = Some(base.id);
+                *node = SpecializedNode::Rule(rule);
+            }
+        }
+
+        let mut engine = RuleEngine::new(None);
+        engine.define_symbol(10, "ApproveFarmer");
+        engine.define_symbol(20, "RejectFarmer");
+        engine.set_rule_priority(20, 9, 0, 0);
+
+        assert_eq!(
+            engine.list_rules(&graph),
+            vec![
+                RuleInfo {
+                    id: 10,
+                    name: "ApproveFarmer".to_string(),
+                    priority: 5,
+                    condition_summary: "R1 == R2".to_string(),
+                    action_count: 1,
+                },
+                RuleInfo {
+                    id: 20,
+                    name: "RejectFarmer".to_string(),
+                    priority: 9,
+                    condition_summary: "R1 == R2".to_string(),
+                    action_count: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_execute_graph_rejects_dangling_entry_point() {
+        let mut graph = create_mock_graph();
+        add_location_rule(&mut graph, 10, "valid");
+        graph.entry_points.push(EntryPoint {
+            node_id: 42,
+            entry_type: 0,
+        });
+
+        let mut engine = RuleEngine::new(None);
+        let result = engine.execute_graph(&graph);
+        assert!(matches!(
+            result,
+            Err(RuleEngineError::InvalidGraph(ref errors))
+                if errors == &vec![GraphError::DanglingEntryPoint(42)]
+        ));
+        assert_eq!(engine.condition_stats(10), None);
+    }
+
+    #[test]
+    fn test_trace_rule_logs_terms_and_comparisons() {
+        let mut graph = create_mock_graph();
+        // Rule 10: farmer.location (R1) == expected (R2), with named operand loads
+        add_location_rule(&mut graph, 10, "south");
+        for node in graph.nodes.iter_mut() {
+            if let SpecializedNode::Value(value_node) = node {
+                value_node.base.output_regs[0] = if value_node.base.id == 12 { 1 } else { 2 };
+            }
+        }
+        // Rule 20 is evaluated too, but not traced
+        add_location_rule(&mut graph, 20, "east");
+
+        let mut engine = RuleEngine::new(None);
+        engine.trace_rule(10);
+        engine.context.registers[1] = Some(Value::Sym("north".to_string()));
+        engine.context.registers[2] = Some(Value::Sym("south".to_string()));
+        engine.execute_graph(&graph).unwrap();
+
+        assert_eq!(engine.condition_stats(20), Some((1, 0)));
+        assert_eq!(
+            engine.rule_trace_log(),
+            &[
+                RuleTraceEntry::TermEval {
+                    term: "farmer.location".to_string(),
+                    value: Some(Value::Sym("north".to_string())),
+                },
+                RuleTraceEntry::TermEval {
+                    term: "south".to_string(),
+                    value: Some(Value::Sym("south".to_string())),
+                },
+                RuleTraceEntry::CompareEval {
+                    op: Some(Comparator::Equal),
+                    left: Value::Sym("north".to_string()),
+                    right: Value::Sym("south".to_string()),
+                    result: false,
+                },
+                RuleTraceEntry::Verdict { passed: false },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_facts_json() {
+        let mut engine = RuleEngine::new(None);
+        engine
+            .load_facts_json(
+                r#"{
+                    "farmer.age": 42,
+                    "farmer.approved": true,
+                    "farmer.location": "north",
+                    "farmer.crops": ["wheat", 3, [false]]
+                }"#,
+            )
+            .unwrap();
+
+        let facts = &engine.context.facts;
+        assert_eq!(facts.get("farmer.age"), Some(&Value::Num(42)));
+        assert_eq!(facts.get("farmer.approved"), Some(&Value::Bool(true)));
+        assert_eq!(
+            facts.get("farmer.location"),
+            Some(&Value::Sym("north".to_string()))
+        );
+        assert_eq!(
+            facts.get("farmer.crops"),
+            Some(&Value::Vec(vec![
+                Value::Sym("wheat".to_string()),
+                Value::Num(3),
+                Value::Vec(vec![Value::Bool(false)]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_load_facts_json_rejects_bad_input() {
+        let mut engine = RuleEngine::new(None);
+        assert!(matches!(
+            engine.load_facts_json("{ not json"),
+            Err(ContextError::InvalidJson(_))
+        ));
+        assert_eq!(
+            engine.load_facts_json("[1, 2]"),
+            Err(ContextError::NotAnObject)
+        );
+        let err = engine
+            .load_facts_json(r#"{"ok": 1, "ratio": 0.5}"#)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "fact 'ratio' can't be represented: 0.5 is not a 64-bit integer"
+        );
+        // Nothing was loaded from the rejected object
+        assert!(engine.context.facts.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_diff_reports_added_and_changed_facts() {
+        let mut engine = RuleEngine::new(None);
+        engine.context.facts.insert(
+            "farmer.location".to_string(),
+            Value::Sym("pending".to_string()),
+        );
+        engine.set_variable("count", Value::Num(1));
+        let before = engine.snapshot();
+
+        engine
+            .context
+            .facts
+            .insert("farmer.approved".to_string(), Value::Bool(true));
+        engine.context.facts.insert(
+            "farmer.location".to_string(),
+            Value::Sym("valid".to_string()),
+        );
+        let after = engine.snapshot();
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.facts.added.len(), 1);
+        assert_eq!(diff.facts.added["farmer.approved"], Value::Bool(true));
+        assert_eq!(diff.facts.changed.len(), 1);
+        assert_eq!(
+            diff.facts.changed["farmer.location"],
+            (
+                Value::Sym("pending".to_string()),
+                Value::Sym("valid".to_string())
+            )
+        );
+        assert!(diff.facts.removed.is_empty());
+        assert!(diff.variables.is_empty());
+
+        // The diff can be logged as JSON
+        let json = serde_json::to_string(&diff).unwrap();
+        assert!(json.contains("farmer.approved"));
+        assert!(after.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn test_load_sym_resolves_symbol_table() {
+        let mut graph = create_mock_graph();
+        graph.nodes.push(create_flow_node(10));
+        graph.nodes.push(SpecializedNode::Base(GraphNode {
+            id: 11,
+            node_type: GraphNodeType::Op,
+            opcode: 0x10, // LOAD_SYM
+            flags: 3,     // symbol id
+            input_regs: [0; 4],
+            output_regs: [1, 0],
+            first_edge: 0,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: 0,
+                cost_hint: 1,
+            },
+        }));
+        graph.edges.push(GraphEdge::new_control(10, 11));
+
+        let mut engine = RuleEngine::new(None);
+        engine.define_symbol(3, "farmer");
+        engine.execute_flow_pipeline(&graph, 10).unwrap();
+        assert_eq!(
+            engine.context.registers[1],
+            Some(Value::Sym("farmer".to_string()))
+        );
+
+        let mut engine = RuleEngine::new(None);
+        engine.define_symbol(4, "other");
+        let result = engine.execute_flow_pipeline(&graph, 10);
+        assert!(matches!(
+            result,
+            Err(RuleEngineError::UndefinedIdentifier(3))
+        ));
+        assert_eq!(engine.context.registers[1], None);
+    }
+
+    #[test]
+    fn test_parallel_subflows_on_one_worker_start_from_the_base_context() {
+        // Subflow 10 writes R2, which subflow 20 reads
+        let mut graph = create_mock_graph();
+        graph.nodes.push(create_flow_node(10));
+        graph.nodes.push(create_move_node(11, 1, 2));
+        graph.nodes.push(create_flow_node(20));
+        graph.nodes.push(create_move_node(21, 2, 4));
+        graph.edges.push(GraphEdge::new_control(10, 11));
+        graph.edges.push(GraphEdge::new_control(20, 21));
+
+        let mut engine = RuleEngine::new(None);
+        engine.max_subflow_workers = 1;
+        engine.context.registers[1] = Some(Value::Num(5));
+        engine.context.registers[2] = Some(Value::Num(7));
+        engine.execute_subflows_parallel(&[10, 20], &graph).unwrap();
+
+        // Subflow 20 ran after 10 on the same worker but still saw the base R2
+        assert_eq!(engine.context.registers[2], Some(Value::Num(5)));
+        assert_eq!(engine.context.registers[4], Some(Value::Num(7)));
+    }
+
+    #[test]
+    fn test_parallel_subflows_detect_conflicting_outputs() {
+        // Both subflows write R2
+        let graph = create_subflow_graph(2);
+
+        let mut engine = RuleEngine::new(None);
+        engine.context.registers[1] = Some(Value::Num(5));
+        engine.context.registers[3] = Some(Value::Num(9));
+
+        let result = engine.execute_subflows_parallel(&[10, 20], &graph);
+        assert!(matches!(
+            result,
+            Err(RuleEngineError::SubflowConflict(10, 20, ref output)) if output == "R2"
+        ));
+        assert_eq!(engine.context.registers[2], None);
+    }
+
+    #[test]
+    fn test_parallel_subflow_workers_inherit_engine_configuration() {
+        // Subflow 10 asserts x from R1 over an existing, different value
+        let mut graph = create_mock_graph();
+        graph.nodes.push(create_flow_node(10));
+        graph.nodes.push(SpecializedNode::Base(GraphNode {
+            id: 11,
+            node_type: GraphNodeType::Op,
+            opcode: 0x62, // SET_SYMBOL
+            flags: 1,
+            input_regs: [1, 0, 0, 0],
+            output_regs: [0; 2],
+            first_edge: 0,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: 0,
+                cost_hint: 1,
+            },
+        }));
+        graph.edges.push(GraphEdge::new_control(10, 11));
+
+        let mut engine = RuleEngine::new(None);
+        engine.set_priority_strategy(PriorityStrategy::SpecificityFirst);
+        engine.tie_break = TieBreak::LowestId;
+        engine.max_pattern_depth = 8;
+        engine.set_unique_assertions(Some(AssertionConflictPolicy::Error));
+        engine.define_symbol(1, "x");
+        engine.context.facts.insert("x".to_string(), Value::Num(3));
+        engine.context.registers[1] = Some(Value::Num(5));
+
+        let worker = engine.subflow_worker();
+        assert_eq!(worker.priority_strategy, PriorityStrategy::SpecificityFirst);
+        assert_eq!(worker.tie_break, TieBreak::LowestId);
+        assert_eq!(worker.max_pattern_depth, 8);
+
+        // The worker applies the parent's assertion policy instead of overwriting
+        let result = engine.execute_subflows_parallel(&[10], &graph);
+        assert!(
+            matches!(result, Err(RuleEngineError::ConflictingAssertion(ref fact)) if fact == "x")
+        );
+        assert_eq!(engine.context.facts.get("x"), Some(&Value::Num(3)));
+    }
+
+    #[test]
+    fn test_condition_stats_track_evaluations_and_passes() {
+        let mut graph = create_mock_graph();
+        // Rule 1 compares R1 == R2 (always fails), rule 3 compares R1 == R1 (always passes)
+        graph.nodes.push(create_rule_node(1));
+        graph.nodes.push(create_compare_node(2, 1, 2, 5));
+        graph.nodes.push(create_rule_node(3));
+        graph.nodes.push(create_compare_node(4, 1, 1, 6));
+        graph.edges.push(GraphEdge::new_data(1, 2));
+        graph.edges.push(GraphEdge::new_data(3, 4));
+        for node_id in [1, 3] {
+            graph.entry_points.push(EntryPoint {
+                node_id,
+                entry_type: 0,
+            });
+        }
+
+        let mut engine = RuleEngine::new(None);
+        engine.context.registers[1] = Some(Value::Num(1));
+        engine.context.registers[2] = Some(Value::Num(2));
+        engine.execute_graph(&graph).unwrap();
+        engine.execute_graph(&graph).unwrap();
+
+        let (evaluated, passed) = engine.condition_stats(1).unwrap();
+        assert!(evaluated > 0);
+        assert_eq!(passed, 0);
+        assert_eq!(engine.condition_stats(3), Some((evaluated, evaluated)));
+        assert_eq!(engine.condition_stats(2), None);
+    }
+
+    #[test]
+    fn test_graph_pattern_matches_sorted_by_node_id() {
+        let mut graph = create_mock_graph();
+        graph.nodes.push(create_flow_node(9));
+        graph.nodes.push(create_rule_node(2));
+        graph.nodes.push(create_flow_node(5));
+
+        let mut engine = RuleEngine::new(None);
+        let matches = engine.match_graph_pattern(&Pattern::Variable("x".to_string()), &graph);
+
+        let ids: Vec<u32> = matches.iter().map(|m| m.matched_node).collect();
+        assert_eq!(ids, vec![2, 5, 9]);
+    }
+
+    #[test]
+    fn test_multiple_patterns_earlier_bindings_take_precedence() {
+        let engine = RuleEngine::new(None);
+        let x = Pattern::Variable("x".to_string());
+        let y = Pattern::Variable("y".to_string());
+
+        // A later pattern cannot rebind x to a different value
+        assert!(engine
+            .match_multiple_patterns(&[x.clone(), x.clone()], &[Value::Num(1), Value::Num(2)])
+            .is_none());
+
+        let bindings = engine
+            .match_multiple_patterns(
+                &[x.clone(), y, x],
+                &[Value::Num(1), Value::Num(2), Value::Num(1)],
+            )
+            .unwrap();
+        let last = bindings.last().unwrap();
+        assert_eq!(last.get("x"), Some(&Value::Num(1)));
+        assert_eq!(last.get("y"), Some(&Value::Num(2)));
+    }
+
+    #[test]
+    fn test_multiple_patterns_require_consistent_rebinding() {
+        let engine = RuleEngine::new(None);
+        let patterns = [
+            Pattern::Variable("x".to_string()),
+            Pattern::Variable("x".to_string()),
+        ];
+
+        let bindings = engine
+            .match_multiple_patterns(&patterns, &[Value::Num(5), Value::Num(5)])
+            .unwrap();
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(bindings[1].get("x"), Some(&Value::Num(5)));
+        assert!(engine
+            .match_multiple_patterns(&patterns, &[Value::Num(5), Value::Num(6)])
+            .is_none());
+    }
+
+    #[test]
+    fn test_pattern_nested_beyond_limit_reports_depth_error() {
+        let mut engine = RuleEngine::new(None);
+        engine.max_pattern_depth = 8;
+
+        // Build vec(vec(...vec(x)...)) patterns and values nested 20 levels deep
+        let mut pattern = Pattern::Variable("x".to_string());
+        let mut value = Value::Num(1);
+        for _ in 0..20 {
+            pattern = Pattern::Composite("vec".to_string(), vec![pattern]);
+            value = Value::Vec(vec![value]);
+        }
+
+        let result = engine.try_match_complex_pattern(&pattern, &value);
+        assert!(matches!(result, Err(RuleEngineError::PatternTooDeep(8))));
+        assert!(engine.match_complex_pattern(&pattern, &value).is_none());
+
+        // The same pattern matches once the limit allows it
+        engine.max_pattern_depth = 32;
+        let bindings = engine.try_match_complex_pattern(&pattern, &value).unwrap();
+        assert_eq!(bindings.unwrap().get("x"), Some(&Value::Num(1)));
+    }
+
+    #[test]
+    fn test_conflicts_report_contradictory_conditions() {
+        let mut graph = create_mock_graph();
+        add_threshold_rule(&mut graph, 10, 2, 5.0, 1); // R1 > 5
+        add_threshold_rule(&mut graph, 20, 3, 3.0, 1); // R1 < 3
+        add_threshold_rule(&mut graph, 30, 3, 9.0, 1); // R1 < 9
+
+        let conflicts = RuleEngine::new(None).detect_rule_conflicts(&graph);
+        let contradictory: Vec<(u32, u32)> = conflicts
+            .iter()
+            .filter(|c| c.conflict_type == ConflictType::ContradictoryConditions)
+            .map(|c| (c.rule1_id, c.rule2_id))
+            .collect();
+        // R1 < 3 and R1 < 9 overlap, so rules 20 and 30 are not contradictory
+        assert_eq!(contradictory, vec![(10, 20)]);
+        assert_eq!(
+            conflicts[0].description,
+            "Rules 10 and 20 have contradictory conditions: R1 > 5 and R1 < 3"
+        );
+    }
+
+    #[test]
+    fn test_conflicts_report_state_conflict_for_different_sources() {
+        let mut graph = create_mock_graph();
+        add_threshold_rule(&mut graph, 10, 0, 1.0, 5); // R1 == 1, R4 <- R5
+        add_threshold_rule(&mut graph, 20, 0, 1.0, 6); // R1 == 1, R4 <- R6
+        add_threshold_rule(&mut graph, 30, 0, 1.0, 6); // R1 == 1, R4 <- R6
+
+        let conflicts = RuleEngine::new(None).detect_rule_conflicts(&graph);
+        let kinds: Vec<(u32, u32, ConflictType)> = conflicts
+            .iter()
+            .map(|c| (c.rule1_id, c.rule2_id, c.conflict_type))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (10, 20, ConflictType::StateConflict),
+                (10, 30, ConflictType::StateConflict),
+                (20, 30, ConflictType::ActionConflict),
+            ]
+        );
+        assert_eq!(
+            conflicts[0].description,
+            "Rules 10 and 20 both write R4 (from R5 and R6)"
+        );
+    }
+
+    #[test]
+    fn test_range_pattern_matches_inclusive_bounds() {
+        let engine = RuleEngine::new(None);
+        let range = Pattern::Composite(
+            "range".to_string(),
+            vec![
+                Pattern::Value(Value::Num(1)),
+                Pattern::Value(Value::Num(10)),
+            ],
+        );
+
+        let in_range = |n| {
+            engine
+                .match_complex_pattern(&range, &Value::Num(n))
+                .is_some()
+        };
+
+        assert!(in_range(5));
+        assert!(in_range(1) && in_range(10));
+        assert!(!in_range(0) && !in_range(11));
+        assert!(engine
+            .match_complex_pattern(&range, &Value::Sym("5".to_string()))
+            .is_none());
+    }
+
+    #[test]
+    fn test_not_pattern_excludes_without_leaking_bindings() {
+        let engine = RuleEngine::new(None);
+        let not_north = Pattern::Composite(
+            "not".to_string(),
+            vec![Pattern::Value(Value::Sym("north".to_string()))],
+        );
+        assert!(engine
+            .match_complex_pattern(&not_north, &Value::Sym("north".to_string()))
+            .is_none());
+        assert!(engine
+            .match_complex_pattern(&not_north, &Value::Sym("south".to_string()))
+            .is_some());
+
+        // not(vec(x, 1)) against vec(7, 2): the inner match binds x before
+        // failing on 2, and that binding must not survive
+        let pattern = Pattern::Composite(
+            "vec".to_string(),
+            vec![
+                Pattern::Composite(
+                    "not".to_string(),
+                    vec![Pattern::Composite(
+                        "vec".to_string(),
+                        vec![
+                            Pattern::Variable("x".to_string()),
+                            Pattern::Value(Value::Num(1)),
+                        ],
+                    )],
+                ),
+                Pattern::Variable("x".to_string()),
+            ],
+        );
+        let value = Value::Vec(vec![
+            Value::Vec(vec![Value::Num(7), Value::Num(2)]),
+            Value::Num(3),
+        ]);
+        let bindings = engine.match_complex_pattern(&pattern, &value).unwrap();
+        assert_eq!(bindings.get("x"), Some(&Value::Num(3)));
+    }
+
+    #[test]
+    fn test_or_pattern_first_match_wins() {
+        let engine = RuleEngine::new(None);
+        let pattern = Pattern::Composite(
+            "or".to_string(),
+            vec![
+                Pattern::Composite("type.ref".to_string(), vec![]),
+                Pattern::Variable("x".to_string()),
+                Pattern::Variable("y".to_string()),
+            ],
+        );
+
+        let bindings = engine
+            .match_complex_pattern(&pattern, &Value::Num(4))
+            .unwrap();
+        assert_eq!(bindings.get("x"), Some(&Value::Num(4)));
+        assert_eq!(bindings.get("y"), None);
+
+        let bindings = engine
+            .match_complex_pattern(&pattern, &Value::Ref("sensor".to_string()))
+            .unwrap();
+        assert!(bindings.is_empty());
+    }
+
+    #[test]
+    fn test_or_pattern_discards_failed_alternative_bindings() {
+        let engine = RuleEngine::new(None);
+        // The first alternative binds x to 7 and then fails on the second element
+        let pattern = Pattern::Composite(
+            "or".to_string(),
+            vec![
+                Pattern::Composite(
+                    "vec".to_string(),
+                    vec![
+                        Pattern::Variable("x".to_string()),
+                        Pattern::Value(Value::Num(1)),
+                    ],
+                ),
+                Pattern::Value(Value::Vec(vec![Value::Num(7), Value::Num(2)])),
+            ],
+        );
+        let value = Value::Vec(vec![Value::Num(7), Value::Num(2)]);
+        let bindings = engine.match_complex_pattern(&pattern, &value).unwrap();
+        assert_eq!(bindings.get("x"), None);
+
+        // A variable alternative only binds when it is the branch taken
+        let pattern = Pattern::Composite(
+            "or".to_string(),
+            vec![
+                Pattern::Value(Value::Num(1)),
+                Pattern::Variable("x".to_string()),
+            ],
+        );
+        let bindings = engine
+            .match_complex_pattern(&pattern, &Value::Num(1))
+            .unwrap();
+        assert!(bindings.is_empty());
+        let bindings = engine
+            .match_complex_pattern(&pattern, &Value::Num(2))
+            .unwrap();
+        assert_eq!(bindings.get("x"), Some(&Value::Num(2)));
+    }
+
+    #[test]
+    fn test_load_num_loads_value_node_constant() {
+        let load = |id: u32, dst: u16| GraphNode {
+            id,
+            node_type: GraphNodeType::Op,
+            opcode: 0x11, // LOAD_NUM
+            flags: 0,
+            input_regs: [0; 4],
+            output_regs: [dst, 0],
+            first_edge: 0,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: 0,
+                cost_hint: 1,
+            },
+        };
+        let run = |node: SpecializedNode| {
+            let mut graph = create_mock_graph();
+            graph.entry_points.push(EntryPoint {
+                node_id: node.id(),
+                entry_type: 0,
+            });
+            graph.nodes.push(node);
+            let mut engine = RuleEngine::new(None);
+            engine
+                .execute_graph(&graph)
+                .map(|_| engine.context.registers[2].clone())
+        };
+
+        let loaded = run(SpecializedNode::Value(ValueNode::new_num(load(1, 2), 99.0)));
+        assert_eq!(loaded.unwrap(), Some(Value::Num(99)));
+
+        // Without a payload there is nothing to load
+        assert!(matches!(
+            run(SpecializedNode::Base(load(7, 2))),
+            Err(RuleEngineError::MissingConstant(7))
+        ));
+
+        // A fractional constant isn't truncated to fit an integer register
+        assert!(matches!(
+            run(SpecializedNode::Value(ValueNode::new_num(load(8, 2), 2.5))),
+            Err(RuleEngineError::NonIntegralConstant(8))
+        ));
+    }
+
+    #[test]
+    fn test_loop_runs_body_until_limit_then_exit_once() {
+        let unconditional_rule = |id: u32| {
+            let mut rule = create_rule_node(id);
+            if let SpecializedNode::Base(node) = &mut rule {
+                node.flags = RULE_FLAG_UNCONDITIONAL;
+            }
+            rule
+        };
+        let mut loop_base = create_flow_node(1).get_base().clone();
+        loop_base.input_regs = [5, 6, 0, 0]; // counter R5, limit R6 (unset)
+
+        let mut graph = create_mock_graph();
+        graph
+            .nodes
+            .push(SpecializedNode::Loop(LoopNode::new(loop_base, 3)));
+        graph.nodes.push(unconditional_rule(2));
+        graph.nodes.push(unconditional_rule(3));
+        graph.edges.push(GraphEdge::new_control(1, 2));
+        graph.edges.push(GraphEdge::new_control(2, 1));
+        graph.edges.push(GraphEdge::new_loop_exit(1, 3));
+        graph.entry_points.push(EntryPoint {
+            node_id: 1,
+            entry_type: 0,
+        });
+
+        let mut engine = RuleEngine::new(None);
+        engine.execute_graph(&graph).unwrap();
+
+        assert_eq!(engine.condition_stats(2), Some((3, 3)));
+        assert_eq!(engine.condition_stats(3), Some((1, 1)));
+        assert_eq!(engine.context.registers[5], Some(Value::Num(3)));
+    }
+
+    #[test]
+    fn test_variable_and_fact_iteration_is_sorted() {
+        let names = ["zeta", "alpha", "mid", "beta"];
+        let build = |order: &[&str]| {
+            let mut engine = RuleEngine::new(None);
+            for (i, name) in order.iter().enumerate() {
+                engine.set_variable(name, Value::Num(i as i64));
+                engine
+                    .context
+                    .facts
+                    .insert(format!("fact.{}", name), Value::Bool(true));
+            }
+            engine
+        };
+        let keys = |engine: &RuleEngine| {
+            let variables: Vec<String> = engine.context.variables.keys().cloned().collect();
+            let facts: Vec<String> = engine.context.facts.keys().cloned().collect();
+            (variables, facts)
+        };
+
+        let first = build(&names);
+        let mut reversed = names;
+        reversed.reverse();
+        let second = build(&reversed);
+
+        let (variables, facts) = keys(&first);
+        assert_eq!(variables, vec!["alpha", "beta", "mid", "zeta"]);
+        assert_eq!(
+            facts,
+            vec!["fact.alpha", "fact.beta", "fact.mid", "fact.zeta"]
+        );
+        assert_eq!(keys(&second), (variables, facts));
+        assert_eq!(first.get_variable("zeta"), Some(&Value::Num(0)));
+    }
+
+    #[test]
+    fn test_reset_keeps_priority_configuration() {
+        let mut graph = create_mock_graph();
+        add_location_rule(&mut graph, 10, "valid");
+        let run = |engine: &mut RuleEngine, location: &str| {
+            engine.context.facts.insert(
+                "farmer.location".to_string(),
+                Value::Sym(location.to_string()),
+            );
+            engine.context.registers[1] = Some(Value::Sym(location.to_string()));
+            engine.context.registers[2] = Some(Value::Sym("valid".to_string()));
+            engine.execute_graph(&graph).unwrap();
+        };
+
+        let mut engine = RuleEngine::new(None);
+        engine.set_rule_priority(10, 7, 3, 1);
+        engine.set_priority_strategy(PriorityStrategy::SpecificityFirst);
+        engine.max_recursion_depth = 4;
+        run(&mut engine, "valid");
+        let first_steps = engine.step_count;
+        assert!(first_steps > 0);
+
+        engine.reset();
+        assert_eq!(engine.step_count, 0);
+        assert!(engine.context.facts.is_empty());
+        assert!(engine.activation_records.is_empty());
+        assert!(engine.execution_path.is_empty());
+        assert!(engine.rule_execution_counts.is_empty());
+
+        run(&mut engine, "elsewhere");
+        let mut fresh = RuleEngine::new(None);
+        run(&mut fresh, "elsewhere");
+        assert_eq!(engine.step_count, fresh.step_count);
+        assert_eq!(
+            engine.context.facts.get("farmer.location"),
+            Some(&Value::Sym("elsewhere".to_string()))
+        );
+
+        let priority = &engine.rule_priorities[&10];
+        assert_eq!(
+            (priority.priority, priority.specificity, priority.recency),
+            (7, 3, 1)
+        );
+        assert!(matches!(
+            engine.priority_strategy,
+            PriorityStrategy::SpecificityFirst
+        ));
+        assert_eq!(engine.max_recursion_depth, 4);
+    }
 }
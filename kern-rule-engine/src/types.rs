@@ -1,13 +1,15 @@
+use kern_graph_builder::GraphError;
 use kern_parser::Comparator;
-use std::collections::HashMap;
+use serde::ser::SerializeStruct;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
 // Define the rule engine execution context
 #[derive(Debug, Clone)]
 pub struct ExecutionContext {
     pub registers: Vec<Option<Value>>, // R0-R15, using Option for uninitialized values
-    pub variables: HashMap<String, Value>,
-    pub facts: HashMap<String, Value>,
+    pub variables: BTreeMap<String, Value>, // Ordered so iteration is deterministic
+    pub facts: BTreeMap<String, Value>,
     pub rule_results: HashMap<String, bool>,
     pub current_node_id: Option<u32>,
 }
@@ -16,12 +18,21 @@ impl ExecutionContext {
     pub fn new() -> Self {
         ExecutionContext {
             registers: (0..16).map(|_| None).collect(), // Initialize with 16 registers (R0-R15)
-            variables: HashMap::new(),
-            facts: HashMap::new(),
+            variables: BTreeMap::new(),
+            facts: BTreeMap::new(),
             rule_results: HashMap::new(),
             current_node_id: None,
         }
     }
+
+    /// Return to the state of `new()`, keeping the register and rule result allocations
+    pub fn clear(&mut self) {
+        self.registers.iter_mut().for_each(|reg| *reg = None);
+        self.variables.clear();
+        self.facts.clear();
+        self.rule_results.clear();
+        self.current_node_id = None;
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -59,7 +70,43 @@ pub struct PatternMatch {
     pub matched_node: u32,                // The node that matched
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl PatternMatch {
+    /// Bindings in key order, for reproducible output
+    pub fn bindings_sorted(&self) -> Vec<(&String, &Value)> {
+        let mut bindings: Vec<_> = self.bindings.iter().collect();
+        bindings.sort_by(|a, b| a.0.cmp(b.0));
+        bindings
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("pattern matches are always serializable")
+    }
+}
+
+impl serde::Serialize for PatternMatch {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bindings: BTreeMap<&String, &Value> = self.bindings.iter().collect();
+        let mut state = serializer.serialize_struct("PatternMatch", 2)?;
+        state.serialize_field("matched_node", &self.matched_node)?;
+        state.serialize_field("bindings", &bindings)?;
+        state.end()
+    }
+}
+
+impl fmt::Display for PatternMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "node {} {{", self.matched_node)?;
+        for (i, (name, value)) in self.bindings_sorted().into_iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, " {} = {:?}", name, value)?;
+        }
+        write!(f, " }}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
 pub enum Value {
     Sym(String),
     Num(i64),
@@ -75,8 +122,39 @@ pub enum RuleEngineError {
     InvalidComparison(Comparator, Value, Value),
     InvalidPredicate(String),
     ExecutionLimitExceeded,
+    SubflowConflict(u32, u32, String), // Two subflows wrote the same output
+    PatternTooDeep(u32),               // Pattern nesting exceeded max_pattern_depth
+    UndefinedIdentifier(u32),          // LOAD_SYM referenced a symbol id missing from the table
+    InvalidGraph(Vec<GraphError>),     // Graph failed ExecutionGraph::validate
+    ConflictingAssertion(String),      // Fact asserted to a new value under the Error policy
+    CyclicFlow(Vec<u32>),              // Control cycle with no reachable HALT, sorted node IDs
+    CyclicDependency(u32),             // Lazy evaluation re-entered a node through Data edges
+    MissingConstant(u32),              // LOAD_NUM node carries no constant payload
+    NonIntegralConstant(u32),          // LOAD_NUM constant isn't an integer that fits in i64
+}
+
+// Errors loading external data into the execution context
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContextError {
+    InvalidJson(String),
+    NotAnObject,
+    UnrepresentableValue(String, String), // Fact name, reason
 }
 
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContextError::InvalidJson(err) => write!(f, "invalid JSON: {}", err),
+            ContextError::NotAnObject => write!(f, "facts must be a JSON object"),
+            ContextError::UnrepresentableValue(name, reason) => {
+                write!(f, "fact '{}' can't be represented: {}", name, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ContextError {}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct RulePriority {
     pub rule_id: u32,
@@ -88,13 +166,13 @@ pub struct RulePriority {
 }
 
 // Define a custom trait for cloning boxed functions
-pub trait CloneableFn: Fn(&RulePriority) -> u32 {
+pub trait CloneableFn: Fn(&RulePriority) -> u32 + Send + Sync {
     fn clone_box(&self) -> Box<dyn CloneableFn>;
 }
 
 impl<T> CloneableFn for T
 where
-    T: Fn(&RulePriority) -> u32 + Clone + 'static,
+    T: Fn(&RulePriority) -> u32 + Clone + Send + Sync + 'static,
 {
     fn clone_box(&self) -> Box<dyn CloneableFn> {
         Box::new(self.clone())
@@ -121,6 +199,33 @@ impl PartialEq for Box<dyn CloneableFn> {
     }
 }
 
+/// Computes a rule's priority with access to engine-wide state: the current
+/// execution context and step count. Higher values fire first. Providers are
+/// shared with the workers of `execute_subflows_parallel`, hence `Send + Sync`.
+pub trait PriorityProvider: ClonePriorityProvider + Send + Sync {
+    fn priority(&self, rule: &RulePriority, ctx: &ExecutionContext, step: u32) -> u32;
+}
+
+// Lets PriorityStrategy stay Clone; implemented for every Clone provider
+pub trait ClonePriorityProvider {
+    fn clone_box(&self) -> Box<dyn PriorityProvider>;
+}
+
+impl<T> ClonePriorityProvider for T
+where
+    T: PriorityProvider + Clone + 'static,
+{
+    fn clone_box(&self) -> Box<dyn PriorityProvider> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn PriorityProvider> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
 #[derive(Clone)]
 pub enum PriorityStrategy {
     /// Standard priority based on explicit settings
@@ -135,6 +240,8 @@ pub enum PriorityStrategy {
     ConflictResolution,
     /// Custom priority function
     Custom(Box<dyn CloneableFn>),
+    /// Priority computed by a provider that can also inspect the execution context
+    Provider(Box<dyn PriorityProvider>),
 }
 
 impl fmt::Debug for PriorityStrategy {
@@ -146,6 +253,7 @@ impl fmt::Debug for PriorityStrategy {
             PriorityStrategy::FrequencyBased => write!(f, "FrequencyBased"),
             PriorityStrategy::ConflictResolution => write!(f, "ConflictResolution"),
             PriorityStrategy::Custom(_) => write!(f, "Custom"),
+            PriorityStrategy::Provider(_) => write!(f, "Provider"),
         }
     }
 }
@@ -159,11 +267,105 @@ impl PartialEq for PriorityStrategy {
             (PriorityStrategy::FrequencyBased, PriorityStrategy::FrequencyBased) => true,
             (PriorityStrategy::ConflictResolution, PriorityStrategy::ConflictResolution) => true,
             (PriorityStrategy::Custom(_), PriorityStrategy::Custom(_)) => true, // Consider all custom functions as equal
+            (PriorityStrategy::Provider(_), PriorityStrategy::Provider(_)) => true,
             _ => false,
         }
     }
 }
 
+// Why a derived fact holds: the rule that asserted it and the facts its condition read
+#[derive(Debug, Clone, PartialEq)]
+pub struct FactProvenance {
+    pub rule_id: u32,
+    pub supporting_facts: Vec<String>,
+}
+
+// How the scheduler orders rules whose computed priorities are equal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    LowestId,
+    HighestRecency, // Ties on recency fall back to insertion order
+    #[default]
+    InsertionOrder, // Earliest queued fires first
+}
+
+// What to do when a fact is asserted to a value different from the one it holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssertionConflictPolicy {
+    KeepOld,
+    TakeNew,
+    Error,
+}
+
+// Notable occurrences recorded by the engine while executing
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineEvent {
+    ConflictingAssertion {
+        fact: String,
+        old: Value,
+        new: Value,
+        rule: Option<u32>, // None when asserted outside a firing rule
+    },
+}
+
+// One step of a traced rule's condition evaluation (see RuleEngine::trace_rule)
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleTraceEntry {
+    TermEval {
+        term: String,
+        value: Option<Value>, // None if the register was unset
+    },
+    CompareEval {
+        op: Option<Comparator>,
+        left: Value,
+        right: Value,
+        result: bool,
+    },
+    Verdict {
+        passed: bool,
+    },
+}
+
+// Rule node flag: the condition is statically true, so the rule always fires
+pub const RULE_FLAG_UNCONDITIONAL: u16 = 0x0001;
+
+// Named outputs produced by executing a flow
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FlowResult {
+    pub outputs: HashMap<String, Value>,
+}
+
+// Runtime metrics collected while executing rules
+#[derive(Debug, Clone, Default)]
+pub struct RuleEngineMetrics {
+    pub condition_stats: HashMap<u32, (u64, u64)>, // rule ID -> (evaluated, passed)
+}
+
+impl RuleEngineMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one evaluation of a rule's condition and whether it passed
+    pub fn record_condition(&mut self, rule_id: u32, passed: bool) {
+        let stats = self.condition_stats.entry(rule_id).or_insert((0, 0));
+        stats.0 += 1;
+        if passed {
+            stats.1 += 1;
+        }
+    }
+}
+
+// A rule as listed by RuleEngine::list_rules
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleInfo {
+    pub id: u32,
+    pub name: String, // "rule_<id>" when the graph doesn't name the rule
+    pub priority: u32,
+    pub condition_summary: String, // e.g. "farmer.location == valid"
+    pub action_count: usize,
+}
+
 // Rule execution metadata
 #[derive(Debug, Clone)]
 pub struct RuleExecutionInfo {
@@ -392,7 +392,7 @@ mod tests {
         }
 
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
 
         flow ProcessFarmers {
@@ -5,7 +5,8 @@
 use crate::resolver::Resolver;
 use crate::symbol::SymbolKind;
 use kern_parser::{
-    Action, Comparator, Condition, Definition, Expression, Predicate, Program, RuleDef, Term,
+    Action, Comparator, Condition, Definition, Expression, Predicate, Program, RuleDef,
+    SourceLocation, Term,
 };
 use std::collections::HashSet;
 
@@ -21,6 +22,8 @@ pub enum ConflictType {
 pub struct Conflict {
     pub rule_a: String,
     pub rule_b: String,
+    pub location_a: SourceLocation,
+    pub location_b: SourceLocation,
     pub conflict_type: ConflictType,
     pub severity: ConflictSeverity,
     pub description: String,
@@ -81,6 +84,8 @@ impl ConflictDetector {
                 let conflict = Conflict {
                     rule_a: rule_a.name.clone(),
                     rule_b: rule_b.name.clone(),
+                    location_a: rule_a.location,
+                    location_b: rule_b.location,
                     conflict_type: ConflictType::OverlappingConditions,
                     severity: ConflictSeverity::Warning,
                     description: format!(
@@ -96,6 +101,8 @@ impl ConflictDetector {
                 let conflict = Conflict {
                     rule_a: rule_a.name.clone(),
                     rule_b: rule_b.name.clone(),
+                    location_a: rule_a.location,
+                    location_b: rule_b.location,
                     conflict_type: ConflictType::ConflictingAttributeWrites,
                     severity: ConflictSeverity::Error,
                     description: format!(
@@ -320,7 +327,7 @@ mod tests {
         }
 
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
 
         rule CheckId:
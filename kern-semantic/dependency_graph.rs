@@ -193,12 +193,7 @@ impl DependencyGraph {
             }
             Expression::Predicate(predicate) => {
                 // Add predicate as a dependency if it's a rule or function
-                if self
-                    .resolver
-                    .scope_manager()
-                    .resolve_symbol(&predicate.name)
-                    .is_some()
-                {
+                if self.is_known_symbol(&predicate.name) {
                     dependencies.push(predicate.name.clone());
                 }
 
@@ -214,7 +209,10 @@ impl DependencyGraph {
         match term {
             Term::Identifier(name) => {
                 // Check if this identifier refers to a symbol that should be a dependency
-                if let Some(symbol) = self.resolver.scope_manager().resolve_symbol(name) {
+                if self.nodes.contains_key(name) {
+                    // Another top-level definition in this program
+                    dependencies.push(name.clone());
+                } else if let Some(symbol) = self.resolver.scope_manager().resolve_symbol(name) {
                     match symbol.kind {
                         SymbolKind::Rule
                         | SymbolKind::Flow
@@ -233,28 +231,24 @@ impl DependencyGraph {
             }
             Term::QualifiedRef(entity, _field) => {
                 // The entity part might be a dependency
-                if self
-                    .resolver
-                    .scope_manager()
-                    .resolve_symbol(entity)
-                    .is_some()
-                {
+                if self.is_known_symbol(entity) {
                     dependencies.push(entity.clone());
                 }
             }
         }
     }
 
+    /// True for top-level definitions of this program and symbols the resolver knows
+    fn is_known_symbol(&self, name: &str) -> bool {
+        self.nodes.contains_key(name)
+            || self.resolver.scope_manager().resolve_symbol(name).is_some()
+    }
+
     fn collect_action_dependencies(&mut self, action: &Action, dependencies: &mut Vec<String>) {
         match action {
             Action::Predicate(predicate) => {
                 // Add predicate as a dependency if it's a rule or function
-                if self
-                    .resolver
-                    .scope_manager()
-                    .resolve_symbol(&predicate.name)
-                    .is_some()
-                {
+                if self.is_known_symbol(&predicate.name) {
                     dependencies.push(predicate.name.clone());
                 }
 
@@ -426,6 +420,37 @@ impl DependencyGraph {
             .map(|node| node.depends_on.as_slice())
     }
 
+    /// Definitions that depend on `name` directly, sorted by name
+    pub fn direct_dependents_of(&self, name: &str) -> Vec<&DependencyNode> {
+        let mut dependents: Vec<&DependencyNode> = self
+            .nodes
+            .values()
+            .filter(|node| node.depends_on.iter().any(|dep| dep == name))
+            .collect();
+        dependents.sort_by(|a, b| a.symbol_id.cmp(&b.symbol_id));
+        dependents
+    }
+
+    /// Definitions that depend on `name` directly or transitively, sorted by name
+    pub fn dependents_of(&self, name: &str) -> Vec<&DependencyNode> {
+        let mut found: HashSet<&str> = HashSet::new();
+        let mut pending = vec![name];
+        while let Some(current) = pending.pop() {
+            for dependent in self.direct_dependents_of(current) {
+                if dependent.symbol_id != name && found.insert(&dependent.symbol_id) {
+                    pending.push(&dependent.symbol_id);
+                }
+            }
+        }
+
+        let mut dependents: Vec<&DependencyNode> = found
+            .into_iter()
+            .filter_map(|id| self.nodes.get(id))
+            .collect();
+        dependents.sort_by(|a, b| a.symbol_id.cmp(&b.symbol_id));
+        dependents
+    }
+
     /// Gets the resolver (for access to symbols)
     pub fn resolver(&self) -> &Resolver {
         &self.resolver
@@ -459,7 +484,7 @@ mod tests {
         }
 
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
 
         flow ProcessFarmers {
@@ -489,6 +514,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_dependents_of_entity() {
+        let input = r#"
+        entity Farmer {
+            id
+            location
+        }
+
+        rule ApproveFarmer:
+            if Farmer.id > 0
+            then approve(Farmer)
+
+        rule FlagFarmer:
+            if Farmer.location == 0
+            then flag(Farmer)
+
+        flow Review {
+            ApproveFarmer()
+        }
+        "#;
+
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program().expect("Failed to parse program");
+
+        let mut dep_graph = DependencyGraph::new(Resolver::new());
+        dep_graph
+            .build_graph(&program)
+            .expect("Failed to build dependency graph");
+
+        let names = |nodes: Vec<&DependencyNode>| -> Vec<String> {
+            nodes.iter().map(|node| node.symbol_id.clone()).collect()
+        };
+        assert_eq!(
+            names(dep_graph.direct_dependents_of("Farmer")),
+            vec!["ApproveFarmer", "FlagFarmer"]
+        );
+        // The flow depends on Farmer through ApproveFarmer
+        assert_eq!(
+            names(dep_graph.dependents_of("Farmer")),
+            vec!["ApproveFarmer", "FlagFarmer", "Review"]
+        );
+        assert!(dep_graph.dependents_of("Review").is_empty());
+    }
+
     #[test]
     fn test_topological_sort() {
         let input = r#"
@@ -31,15 +31,22 @@ pub use types::{TypeChecker as TypeCheckerUtil, TypeDescriptor, TypeKind};
 /// The main semantic analysis pipeline for KERN programs
 pub struct SemanticAnalyzer {
     diagnostic_reporter: DiagnosticReporter,
+    source_file: String,
 }
 
 impl SemanticAnalyzer {
     pub fn new() -> Self {
         SemanticAnalyzer {
             diagnostic_reporter: DiagnosticReporter::new(),
+            source_file: "unknown".to_string(),
         }
     }
 
+    /// Sets the file name diagnostics are reported against
+    pub fn set_source_file(&mut self, source_file: impl Into<String>) {
+        self.source_file = source_file.into();
+    }
+
     /// Performs complete semantic analysis on a KERN program
     pub fn analyze(&mut self, program: &kern_parser::Program) -> Result<(), Vec<String>> {
         // Reset diagnostic reporter
@@ -63,39 +70,7 @@ impl SemanticAnalyzer {
                                 match conflict_detector.detect_conflicts(program) {
                                     Ok(conflicts) => {
                                         // Report conflicts as warnings/errors
-                                        for conflict in conflicts {
-                                            let location = DiagnosticSourceLocation::new(
-                                                "unknown".to_string(),
-                                                0,
-                                                0,
-                                            );
-                                            let message =
-                                                format!("Rule conflict: {}", conflict.description);
-
-                                            match conflict.severity {
-                                                ConflictSeverity::Error => {
-                                                    self.diagnostic_reporter.error(
-                                                        DiagnosticCode::RULE_CONFLICT,
-                                                        message,
-                                                        location,
-                                                    );
-                                                }
-                                                ConflictSeverity::Warning => {
-                                                    self.diagnostic_reporter.warning(
-                                                        DiagnosticCode::RULE_CONFLICT,
-                                                        message,
-                                                        location,
-                                                    );
-                                                }
-                                                ConflictSeverity::Info => {
-                                                    self.diagnostic_reporter.info(
-                                                        DiagnosticCode::RULE_CONFLICT,
-                                                        message,
-                                                        location,
-                                                    );
-                                                }
-                                            }
-                                        }
+                                        self.report_conflicts(&conflicts);
 
                                         // Step 5: Validate for bytecode generation
                                         let resolver = conflict_detector.resolver().clone();
@@ -125,6 +100,39 @@ impl SemanticAnalyzer {
         }
     }
 
+    /// Reports each conflict at the second of its two rules
+    pub fn report_conflicts(&mut self, conflicts: &[Conflict]) {
+        for conflict in conflicts {
+            let location = DiagnosticSourceLocation::new(
+                self.source_file.clone(),
+                conflict.location_b.line,
+                conflict.location_b.column,
+            );
+            let message = format!("Rule conflict: {}", conflict.description);
+
+            match conflict.severity {
+                ConflictSeverity::Error => {
+                    self.diagnostic_reporter.error(
+                        DiagnosticCode::RULE_CONFLICT,
+                        message,
+                        location,
+                    );
+                }
+                ConflictSeverity::Warning => {
+                    self.diagnostic_reporter.warning(
+                        DiagnosticCode::RULE_CONFLICT,
+                        message,
+                        location,
+                    );
+                }
+                ConflictSeverity::Info => {
+                    self.diagnostic_reporter
+                        .info(DiagnosticCode::RULE_CONFLICT, message, location);
+                }
+            }
+        }
+    }
+
     /// Gets the diagnostic reporter
     pub fn diagnostic_reporter(&self) -> &DiagnosticReporter {
         &self.diagnostic_reporter
@@ -157,7 +165,7 @@ mod tests {
         }
 
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
 
         flow ProcessFarmers {
@@ -184,4 +192,25 @@ mod tests {
         // There should be no errors reported
         assert!(!analyzer.diagnostic_reporter().has_errors());
     }
+
+    #[test]
+    fn test_conflict_diagnostic_points_at_second_rule() {
+        let input = "entity Farmer {\n    id\n}\n\nrule Approve:\n    if farmer.id > 0\n    then approve(farmer)\n\nrule Review:\n    if farmer.id > 10\n    then review(farmer)\n";
+        let program = Parser::new(input).parse_program().unwrap();
+        let conflicts = ConflictDetector::new(Resolver::new())
+            .detect_conflicts(&program)
+            .unwrap();
+        assert_eq!(conflicts.len(), 1);
+
+        let mut analyzer = SemanticAnalyzer::new();
+        analyzer.set_source_file("farmers.kern");
+        analyzer.report_conflicts(&conflicts);
+
+        let diagnostics = analyzer.diagnostic_reporter().diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].location,
+            DiagnosticSourceLocation::new("farmers.kern".to_string(), 9, 1)
+        );
+    }
 }
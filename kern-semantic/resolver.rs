@@ -402,7 +402,7 @@ mod tests {
         }
 
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
 
         flow ProcessFarmers {
@@ -0,0 +1,275 @@
+//! JSON-RPC Debug Server for KERN VM
+//!
+//! Drives a `VirtualMachine` on behalf of an external debugger (e.g. an
+//! editor's debug adapter). Requests are JSON-RPC 2.0 objects, one per line;
+//! each gets one response line. Supported methods:
+//!
+//! - `step`: execute one instruction
+//! - `continue`: run until a breakpoint, HALT, the end of the program, or
+//!   `max_steps` instructions (default `VirtualMachine::max_steps`)
+//! - `readRegisters`: current PC, flags and general purpose registers
+//! - `setBreakpoint` / `clearBreakpoint`: `{"pc": n}`
+//!
+//! `step` and `continue` report the new PC and the registers they changed.
+
+use crate::{VirtualMachine, VmError};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeSet;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+// JSON-RPC 2.0 error codes
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const VM_ERROR: i64 = -32000; // The VM itself reported an error
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<VmError> for RpcError {
+    fn from(error: VmError) -> Self {
+        RpcError::new(VM_ERROR, format!("{:?}", error))
+    }
+}
+
+pub struct DebugServer {
+    pub vm: VirtualMachine,
+    breakpoints: BTreeSet<u32>,
+}
+
+impl DebugServer {
+    pub fn new(vm: VirtualMachine) -> Self {
+        DebugServer {
+            vm,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    /// Handles one request line and returns the response line
+    pub fn handle_request(&mut self, line: &str) -> String {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                return error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string()))
+            }
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return error_response(id, RpcError::new(INVALID_REQUEST, "missing method"));
+        };
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        match self.dispatch(method, &params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string(),
+            Err(error) => error_response(id, error),
+        }
+    }
+
+    /// Runs a single method against the VM
+    pub fn dispatch(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "step" => self.step(),
+            "continue" => {
+                let max_steps = match params.get("max_steps") {
+                    Some(value) => value.as_u64().ok_or_else(|| {
+                        RpcError::new(INVALID_PARAMS, "max_steps must be a number")
+                    })?,
+                    None => self.vm.max_steps as u64,
+                };
+                self.run(max_steps)
+            }
+            "readRegisters" => Ok(json!({
+                "pc": self.vm.registers.pc,
+                "flag": self.vm.registers.flag,
+                "registers": self.vm.registers.r.to_vec(),
+            })),
+            "setBreakpoint" => {
+                let pc = pc_param(params)?;
+                self.breakpoints.insert(pc);
+                Ok(json!({ "breakpoints": self.breakpoints.iter().collect::<Vec<_>>() }))
+            }
+            "clearBreakpoint" => {
+                let pc = pc_param(params)?;
+                self.breakpoints.remove(&pc);
+                Ok(json!({ "breakpoints": self.breakpoints.iter().collect::<Vec<_>>() }))
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method '{}'", method),
+            )),
+        }
+    }
+
+    /// Accepts connections one at a time, serving each until it disconnects
+    pub fn serve(&mut self, listener: &TcpListener) -> std::io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            self.serve_stream(BufReader::new(stream.try_clone()?), stream)?;
+        }
+        Ok(())
+    }
+
+    /// Answers newline-delimited requests from `reader` until end of input
+    pub fn serve_stream<R: BufRead, W: Write>(
+        &mut self,
+        reader: R,
+        mut writer: W,
+    ) -> std::io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            writeln!(writer, "{}", self.handle_request(&line))?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn step(&mut self) -> Result<Value, RpcError> {
+        let pc = self.vm.registers.pc;
+        let before = self.vm.registers.r;
+        let instruction = self.vm.program.get(pc as usize).cloned();
+        self.vm.step()?;
+
+        let mut result = self.stop_state(&before);
+        if let Some(instruction) = instruction {
+            result["executed"] = json!({
+                "pc": pc,
+                "opcode": instruction.opcode,
+                "arg1": instruction.arg1,
+                "arg2": instruction.arg2,
+                "arg3": instruction.arg3,
+                "flags": instruction.flags,
+            });
+        }
+        Ok(result)
+    }
+
+    fn run(&mut self, max_steps: u64) -> Result<Value, RpcError> {
+        let before = self.vm.registers.r;
+        let mut steps = 0;
+        let reason = loop {
+            if self.vm.registers.is_halt_requested() {
+                break "halted";
+            }
+            if self.vm.registers.pc as usize >= self.vm.program.len() {
+                break "end";
+            }
+            // The breakpoint at the starting PC was already reported
+            if steps > 0 && self.breakpoints.contains(&self.vm.registers.pc) {
+                break "breakpoint";
+            }
+            if steps == max_steps {
+                break "max_steps";
+            }
+            self.vm.step()?;
+            steps += 1;
+        };
+
+        let mut result = self.stop_state(&before);
+        result["reason"] = json!(reason);
+        result["steps"] = json!(steps);
+        Ok(result)
+    }
+
+    // The PC and the registers that differ from `before`
+    fn stop_state(&self, before: &[i64; 16]) -> Value {
+        let mut changed = Map::new();
+        for (i, (old, new)) in before.iter().zip(self.vm.registers.r.iter()).enumerate() {
+            if old != new {
+                changed.insert(format!("R{}", i), json!(new));
+            }
+        }
+        json!({
+            "pc": self.vm.registers.pc,
+            "halted": self.vm.registers.is_halt_requested(),
+            "changed_registers": changed,
+        })
+    }
+}
+
+fn pc_param(params: &Value) -> Result<u32, RpcError> {
+    params
+        .get("pc")
+        .and_then(Value::as_u64)
+        .and_then(|pc| u32::try_from(pc).ok())
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "expected {\"pc\": <instruction index>}"))
+}
+
+fn error_response(id: Value, error: RpcError) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kern_bytecode::Instruction;
+
+    fn server() -> DebugServer {
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::new(0x11, 2, 5, 0, 0), // LOAD_NUM R2, 5
+            Instruction::new(0x11, 3, 7, 0, 0), // LOAD_NUM R3, 7
+            Instruction::new(0x11, 4, 9, 0, 0), // LOAD_NUM R4, 9
+        ]);
+        DebugServer::new(vm)
+    }
+
+    #[test]
+    fn test_step_request_reports_executed_instruction_and_registers() {
+        let mut server = server();
+        let response: Value = serde_json::from_str(
+            &server.handle_request(r#"{"jsonrpc":"2.0","id":1,"method":"step"}"#),
+        )
+        .unwrap();
+
+        assert_eq!(response["id"], 1);
+        let result = &response["result"];
+        assert_eq!(result["pc"], 1);
+        assert_eq!(result["executed"]["pc"], 0);
+        assert_eq!(result["executed"]["opcode"], 0x11);
+        assert_eq!(result["changed_registers"], json!({ "R2": 5 }));
+        assert_eq!(server.vm.get_register(2), Some(5));
+    }
+
+    #[test]
+    fn test_continue_stops_at_breakpoint() {
+        let mut server = server();
+        server
+            .dispatch("setBreakpoint", &json!({ "pc": 2 }))
+            .unwrap();
+
+        let result = server.dispatch("continue", &Value::Null).unwrap();
+        assert_eq!(result["reason"], "breakpoint");
+        assert_eq!(result["pc"], 2);
+        assert_eq!(result["changed_registers"], json!({ "R2": 5, "R3": 7 }));
+
+        let result = server.dispatch("continue", &Value::Null).unwrap();
+        assert_eq!(result["reason"], "end");
+        assert_eq!(result["steps"], 1);
+
+        let response: Value =
+            serde_json::from_str(&server.handle_request(r#"{"id":2,"method":"nope"}"#)).unwrap();
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    }
+}
@@ -0,0 +1,38 @@
+//! Input Sources for KERN VM
+//!
+//! READ_IO pulls values from an `IoInput` installed on the VM, so hosts decide
+//! where external input comes from and tests can replay it deterministically.
+
+use std::collections::{HashMap, VecDeque};
+
+/// Supplies the values READ_IO reads, one channel at a time
+pub trait IoInput: Send {
+    /// Next value on `channel`, or `None` when the channel has nothing to read
+    fn read(&mut self, channel: &str) -> Option<i64>;
+}
+
+/// Serves pre-loaded values from a FIFO queue per channel
+#[derive(Debug, Clone, Default)]
+pub struct QueueInput {
+    queues: HashMap<String, VecDeque<i64>>,
+}
+
+impl QueueInput {
+    pub fn new() -> Self {
+        QueueInput::default()
+    }
+
+    /// Appends `value` to the end of `channel`'s queue
+    pub fn push(&mut self, channel: &str, value: i64) {
+        self.queues
+            .entry(channel.to_string())
+            .or_default()
+            .push_back(value);
+    }
+}
+
+impl IoInput for QueueInput {
+    fn read(&mut self, channel: &str) -> Option<i64> {
+        self.queues.get_mut(channel)?.pop_front()
+    }
+}
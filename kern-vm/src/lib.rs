@@ -1,7 +1,16 @@
-use kern_bytecode::{BytecodeModule, Instruction, Opcode, Constant};
-use std::collections::HashMap;
+use kern_bytecode::{BytecodeModule, Instruction, Opcode, Constant, Symbol};
+use kern_bytecode::COMPARE_TYPED;
+use kern_bytecode::operand_utils::{decode_compare, decode_move};
+use kern_graph_builder::{ExecutionGraph, GraphEdge, GraphNode, GraphNodeType, NodeMeta, SpecializedNode};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 pub mod vm_safety;
+pub mod output;
+pub mod input;
+pub mod debug_server;
+
+use output::{HumanFormatter, OutputEvent, OutputFormatter};
+use input::IoInput;
 
 use vm_safety::{
     memory_limits::{MemoryLimits, MemoryManager, MemoryRegion},
@@ -13,7 +22,7 @@ use vm_safety::{
 };
 
 // Define the KERN VM registers
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct VmRegisters {
     pub r: [i64; 16], // General purpose registers R0-R15
     pub pc: u32,      // Program Counter (instruction index, not byte offset)
@@ -96,7 +105,7 @@ impl VmRegisters {
 }
 
 // Define the KERN VM execution context
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct VmContext {
     pub id: u64,
     pub registers: VmRegisters,
@@ -113,6 +122,15 @@ impl VmContext {
             variables: HashMap::new(),
         }
     }
+
+    /// Return to the state of `VmContext::new(id)` without reallocating
+    pub fn reset(&mut self, id: u64) {
+        self.id = id;
+        self.registers = VmRegisters::new();
+        self.memory.clear();
+        self.memory.resize(1024, 0);
+        self.variables.clear();
+    }
 }
 
 // Memory regions for the KERN VM
@@ -135,8 +153,38 @@ impl MemoryRegions {
             meta: vec![0; 1024], // 1KB metadata
         }
     }
+
+    pub fn region_mut(&mut self, region: MemoryRegion) -> &mut Vec<u8> {
+        match region {
+            MemoryRegion::Code => &mut self.code,
+            MemoryRegion::Const => &mut self.constants,
+            MemoryRegion::Stack => &mut self.stack,
+            MemoryRegion::Heap => &mut self.heap,
+            MemoryRegion::Meta => &mut self.meta,
+        }
+    }
+
+    /// Zero every region in place, as freshly created by `new`
+    pub fn reset(&mut self) {
+        self.code.clear();
+        self.constants.clear();
+        for (region, size) in [(&mut self.stack, 4096), (&mut self.heap, 1024 * 100), (&mut self.meta, 1024)] {
+            region.clear();
+            region.resize(size, 0);
+        }
+    }
+}
+
+// Behaviour of EXT_CALL when the called function is not registered with the VM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownExternPolicy {
+    Trap,       // Fail with VmError::UnknownExternalFunction
+    Nop,        // Continue without doing anything
+    ReturnZero, // Continue with 0 written to R0 as the call result
 }
 
+pub use kern_bytecode::ArithmeticMode;
+
 // VM Configuration object as specified in the safety layer
 #[derive(Debug, Clone)]
 pub struct VMConfig {
@@ -144,6 +192,14 @@ pub struct VMConfig {
     pub execution_limits: ExecutionLimits,
     pub sandbox_policy: SandboxPolicy,
     pub perf_flags: bool, // Whether to enable performance monitoring
+    pub unknown_extern_policy: UnknownExternPolicy,
+    pub max_trace_entries: usize, // Execution trace keeps only the most recent entries
+    pub trace_full: bool, // Keep every trace entry, ignoring max_trace_entries
+    pub arithmetic_mode: ArithmeticMode,
+    pub trap_on_div_zero: bool, // DIV/MOD by zero fail with DivisionByZero; otherwise yield 0 and set the error flag
+    pub max_context_depth: usize, // Most contexts PUSH_CTX may stack, including the root
+    pub echo_output: bool, // Also print WRITE_IO output to stdout through the output formatter
+    pub max_output_events: usize, // Output sink keeps only the most recent undrained events
 }
 
 impl VMConfig {
@@ -153,6 +209,14 @@ impl VMConfig {
             execution_limits: ExecutionLimits::default(),
             sandbox_policy: SandboxPolicy::new(),
             perf_flags: true,
+            unknown_extern_policy: UnknownExternPolicy::Nop,
+            max_trace_entries: 10_000,
+            trace_full: false,
+            arithmetic_mode: ArithmeticMode::Checked,
+            trap_on_div_zero: true,
+            max_context_depth: 256,
+            echo_output: false,
+            max_output_events: 10_000,
         }
     }
 }
@@ -162,15 +226,32 @@ pub struct VirtualMachine {
     pub registers: VmRegisters,
     pub contexts: Vec<VmContext>,
     pub current_context: usize,
+    max_context_depth: usize, // Most contexts on the stack at once, including the root
     pub memory: MemoryRegions,
     pub program: Vec<Instruction>,
     pub running: bool,
     pub max_steps: u32, // Maximum execution steps to prevent infinite loops
     pub step_count: u32,
     pub external_functions: HashMap<String, fn(&mut VirtualMachine) -> Result<(), String>>,
-    pub execution_trace: Vec<ExecutionTraceEntry>, // For PSI introspection
+    pub function_names: HashMap<u64, String>, // CALL_EXTERN function id -> declared name
+    pub execution_trace: VecDeque<ExecutionTraceEntry>, // For PSI introspection, bounded by config.max_trace_entries
     pub constant_pool: Vec<Constant>,
+    pub opcode_coverage: HashSet<u8>, // Opcodes dispatched over the VM's lifetime
+    pc_hit_counts: Vec<u64>, // Executions per program index, parallel to `program`
+    pub exec_counts: HashMap<u32, u64>, // INCREMENT_EXEC_COUNT counters, keyed by loop/recursion id
+    pub call_stack: Vec<u32>, // Return addresses pushed by CALL_RULE
+    pub try_frames: Vec<TryFrame>, // Innermost TRY last
+    pub loop_frames: Vec<LoopFrame>, // Innermost LOOP_BEGIN last
+    pub vm_graph: ExecutionGraph, // Nodes and edges built by the graph opcodes (0x40-0x43)
+    next_graph_node: u32,         // Id for the next CREATE_NODE; ids are never reused
+    graph_node_records: HashMap<u32, usize>, // Heap offset of each live node's record
+    free_graph_records: Vec<usize>, // Offsets of deleted nodes' records, reused first
+    pending_memory_diff: Vec<MemoryChange>, // Writes made by the instruction being executed
     jumped: bool, // Track if the last instruction was a jump
+    ext_operand: Option<u64>, // Pending EXT payload for the instruction being executed
+    pub output_sink: VecDeque<OutputEvent>, // WRITE_IO output not yet drained by take_output, bounded by config.max_output_events
+    pub output_formatter: Box<dyn OutputFormatter>, // Renders WRITE_IO output when config.echo_output is set
+    pub io_input: Option<Box<dyn IoInput>>, // Source READ_IO reads from
 
     // Safety layer components
     pub memory_manager: MemoryManager,
@@ -181,21 +262,41 @@ pub struct VirtualMachine {
 }
 
 // Execution trace entry for PSI introspection
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ExecutionTraceEntry {
     pub pc_before: u32,
     pub opcode: u8,
     pub operands: u64,
     pub register_diff: [i64; 16], // Difference in general purpose registers
-    pub memory_diff: Vec<u8>,     // Memory changes
+    pub memory_diff: Vec<MemoryChange>, // Memory changes, in write order
 }
 
+// One contiguous run of bytes an instruction changed
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MemoryChange {
+    pub region: MemoryRegion,
+    pub offset: usize,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+}
+
+// Each graph node created by CREATE_NODE owns a heap record of this size:
+//   bytes 0-3  node id (little-endian)
+//   byte  4    1 while the node is live, 0 once DELETE_NODE or MERGE_NODES removed it
+//   bytes 5-7  reserved, zero
+// Records are packed from heap offset 0. A removed node's slot goes on a free
+// list and the next CREATE_NODE takes it over, so the heap only ever holds as
+// many records as there were nodes live at once.
+pub const GRAPH_NODE_RECORD_SIZE: usize = 8;
+
 #[derive(Debug)]
 pub enum VmError {
     InvalidOpcode(u8),
     InvalidRegister(u16),
     InvalidAddress(u32),
-    ExecutionLimitExceeded,
+    StepLimitExceeded,   // More instructions than ExecutionLimits::max_steps
+    RuleLimitExceeded,   // More rule invocations than ExecutionLimits::max_rule_invocations
+    LoopLimitExceeded,   // A loop passed its own cap or ExecutionLimits::max_loop_iterations
     DivisionByZero,
     StackOverflow,
     StackUnderflow,
@@ -206,15 +307,94 @@ pub enum VmError {
     SecurityError(vm_safety::security::SecurityError),
     SandboxViolation,
     LimitError(vm_safety::limit_errors::LimitError),
+    UnknownExternalFunction(String), // EXT_CALL target not registered (Trap policy)
+    ExternalCallFailed(String),      // Registered external function returned an error
+    InvalidState(String),            // import_state input could not be restored
+    UncaughtThrow(u64),              // THROW with no enclosing TRY; carries the error code
+    ArithmeticOverflow,              // ADD/SUB/MUL overflowed under ArithmeticMode::Checked
+    InvalidComparison,               // Typed COMPARE ordering non-numeric constants
+    ContextDepthExceeded,            // PUSH_CTX past VMConfig::max_context_depth
+}
+
+// A TRY block awaiting completion, pushed by TRY
+#[derive(Debug, Clone, PartialEq)]
+pub struct TryFrame {
+    pub catch_pc: u32,
+    pub saved_registers: [i64; 16], // R0-R15 as they were at TRY, restored on THROW
+    pub call_depth: usize,           // call_stack length at TRY; deeper rule calls are abandoned on THROW
+    pub context_depth: usize,        // contexts length at TRY; contexts pushed since are popped on THROW
+    pub loop_depth: usize,           // loop_frames length at TRY; loops opened since are closed on THROW
+    pub handling: bool,              // A THROW has landed on catch_pc; popped by CLEAR_ERR
+}
+
+// A counted loop opened by LOOP_BEGIN
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopFrame {
+    pub body_pc: u32,        // First instruction of the body, where LOOP_END jumps back to
+    pub max_iterations: u64, // Passes over the body before LOOP_END falls through
+    pub iterations: u64,     // Passes completed so far
+}
+
+// Serializable VM state written by export_state and read back by import_state
+#[derive(serde::Serialize, serde::Deserialize)]
+struct VmState {
+    registers: VmRegisters,
+    contexts: Vec<VmContext>,
+    current_context: usize,
+}
+
+/// Split `all_opcodes` into (covered, uncovered) opcodes, both sorted ascending
+pub fn coverage_report(coverage: &HashSet<u8>, all_opcodes: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut opcodes = all_opcodes.to_vec();
+    opcodes.sort_unstable();
+    opcodes.dedup();
+    opcodes.into_iter().partition(|op| coverage.contains(op))
+}
+
+// Error codes a caught runtime fault leaves in the error register. They sit
+// above the 16-bit range THROW can produce.
+pub const FAULT_DIVISION_BY_ZERO: u64 = 0x1_0001;
+pub const FAULT_INVALID_REGISTER: u64 = 0x1_0002;
+pub const FAULT_INVALID_ADDRESS: u64 = 0x1_0003;
+pub const FAULT_INVALID_INSTRUCTION: u64 = 0x1_0004;
+pub const FAULT_UNDEFINED_SYMBOL: u64 = 0x1_0005;
+pub const FAULT_STACK_UNDERFLOW: u64 = 0x1_0006;
+pub const FAULT_ARITHMETIC_OVERFLOW: u64 = 0x1_0007;
+
+impl VmError {
+    /// The error code for faults a TRY block can catch. Limit, security and
+    /// sandbox errors are never catchable.
+    pub fn fault_code(&self) -> Option<u64> {
+        match self {
+            VmError::DivisionByZero => Some(FAULT_DIVISION_BY_ZERO),
+            VmError::InvalidRegister(_) => Some(FAULT_INVALID_REGISTER),
+            VmError::InvalidAddress(_) => Some(FAULT_INVALID_ADDRESS),
+            VmError::InvalidInstruction => Some(FAULT_INVALID_INSTRUCTION),
+            VmError::UndefinedSymbol => Some(FAULT_UNDEFINED_SYMBOL),
+            VmError::StackUnderflow => Some(FAULT_STACK_UNDERFLOW),
+            VmError::ArithmeticOverflow => Some(FAULT_ARITHMETIC_OVERFLOW),
+            _ => None,
+        }
+    }
+}
+
+impl From<vm_safety::step_limits::StepLimitError> for VmError {
+    fn from(step_limit_error: vm_safety::step_limits::StepLimitError) -> Self {
+        match step_limit_error {
+            vm_safety::step_limits::StepLimitError::StepLimitExceeded => VmError::StepLimitExceeded,
+            vm_safety::step_limits::StepLimitError::RuleLimitExceeded => VmError::RuleLimitExceeded,
+            vm_safety::step_limits::StepLimitError::LoopLimitExceeded => VmError::LoopLimitExceeded,
+        }
+    }
 }
 
 impl From<vm_safety::limit_errors::LimitError> for VmError {
     fn from(limit_error: vm_safety::limit_errors::LimitError) -> Self {
         match limit_error {
             vm_safety::limit_errors::LimitError::MemoryLimitExceeded(_) => VmError::MemoryLimitExceeded,
-            vm_safety::limit_errors::LimitError::StepLimitExceeded => VmError::ExecutionLimitExceeded,
-            vm_safety::limit_errors::LimitError::RuleLimitExceeded => VmError::ExecutionLimitExceeded,
-            vm_safety::limit_errors::LimitError::LoopLimitExceeded => VmError::ExecutionLimitExceeded,
+            vm_safety::limit_errors::LimitError::StepLimitExceeded => VmError::StepLimitExceeded,
+            vm_safety::limit_errors::LimitError::RuleLimitExceeded => VmError::RuleLimitExceeded,
+            vm_safety::limit_errors::LimitError::LoopLimitExceeded => VmError::LoopLimitExceeded,
             vm_safety::limit_errors::LimitError::SandboxViolation => VmError::SandboxViolation,
             vm_safety::limit_errors::LimitError::SecurityViolation => VmError::SecurityError(
                 vm_safety::security::SecurityError::SecurityViolation
@@ -237,15 +417,32 @@ impl VirtualMachine {
             registers: VmRegisters::new(),
             contexts: vec![VmContext::new(0)], // Initialize with one context
             current_context: 0,
+            max_context_depth: 1,
             memory: MemoryRegions::new(),
             program: Vec::new(),
             running: false,
             max_steps: 100000, // 100k max steps
             step_count: 0,
             external_functions: HashMap::new(),
-            execution_trace: Vec::new(),
+            function_names: HashMap::new(),
+            execution_trace: VecDeque::new(),
             jumped: false,
+            ext_operand: None,
+            output_sink: VecDeque::new(),
+            output_formatter: Box::new(HumanFormatter),
+            io_input: None,
             constant_pool: Vec::new(),
+            opcode_coverage: HashSet::new(),
+            pc_hit_counts: Vec::new(),
+            exec_counts: HashMap::new(),
+            call_stack: Vec::new(),
+            try_frames: Vec::new(),
+            loop_frames: Vec::new(),
+            vm_graph: ExecutionGraph::empty(),
+            next_graph_node: 0,
+            graph_node_records: HashMap::new(),
+            free_graph_records: Vec::new(),
+            pending_memory_diff: Vec::new(),
 
             // Safety layer components
             memory_manager,
@@ -269,15 +466,32 @@ impl VirtualMachine {
             registers: VmRegisters::new(),
             contexts: vec![VmContext::new(0)], // Initialize with one context
             current_context: 0,
+            max_context_depth: 1,
             memory: MemoryRegions::new(),
             program: Vec::new(),
             running: false,
             max_steps: config.execution_limits.max_steps.min(1_000_000) as u32, // Cap at reasonable value for legacy field
             step_count: 0,
             external_functions: HashMap::new(),
-            execution_trace: Vec::new(),
+            function_names: HashMap::new(),
+            execution_trace: VecDeque::new(),
             jumped: false,
+            ext_operand: None,
+            output_sink: VecDeque::new(),
+            output_formatter: Box::new(HumanFormatter),
+            io_input: None,
             constant_pool: Vec::new(),
+            opcode_coverage: HashSet::new(),
+            pc_hit_counts: Vec::new(),
+            exec_counts: HashMap::new(),
+            call_stack: Vec::new(),
+            try_frames: Vec::new(),
+            loop_frames: Vec::new(),
+            vm_graph: ExecutionGraph::empty(),
+            next_graph_node: 0,
+            graph_node_records: HashMap::new(),
+            free_graph_records: Vec::new(),
+            pending_memory_diff: Vec::new(),
 
             // Safety layer components
             memory_manager,
@@ -288,16 +502,103 @@ impl VirtualMachine {
         }
     }
 
+    /// Clear registers, contexts, memory, traces and counters in place so the VM
+    /// can be reused without reallocating. Afterwards it behaves like a freshly
+    /// constructed VM; the loaded program, constant pool, registered external
+    /// functions and configuration are kept.
+    pub fn reset(&mut self) {
+        self.registers = VmRegisters::new();
+        self.contexts.truncate(1);
+        match self.contexts.first_mut() {
+            Some(context) => context.reset(0),
+            None => self.contexts.push(VmContext::new(0)),
+        }
+        self.current_context = 0;
+        self.max_context_depth = 1;
+        self.memory.reset();
+        self.running = false;
+        self.step_count = 0;
+        self.execution_trace.clear();
+        self.opcode_coverage.clear();
+        self.pc_hit_counts.fill(0);
+        self.exec_counts.clear();
+        self.call_stack.clear();
+        self.try_frames.clear();
+        self.loop_frames.clear();
+        self.vm_graph = ExecutionGraph::empty();
+        self.next_graph_node = 0;
+        self.graph_node_records.clear();
+        self.free_graph_records.clear();
+        self.pending_memory_diff.clear();
+        self.jumped = false;
+        self.ext_operand = None;
+        self.output_sink.clear();
+
+        self.memory_manager.reset();
+        self.step_limiter.reset();
+        self.security_context.sandbox.reset();
+        self.performance_monitor.reset();
+    }
+
+    /// Load a compiled module: its instructions, the constant pool LOAD_SYM,
+    /// LOAD_CONST and WRITE_IO refer to, and its extern function names
+    pub fn load_module(&mut self, module: BytecodeModule) {
+        self.constant_pool = module.constant_pool;
+        self.load_function_names(&module.symbol_table);
+        self.load_program(module.instruction_stream);
+    }
+
+    /// Drain the events WRITE_IO has produced since the last call. Only the
+    /// most recent `config.max_output_events` are kept between calls.
+    pub fn take_output(&mut self) -> Vec<OutputEvent> {
+        self.output_sink.drain(..).collect()
+    }
+
     pub fn load_program(&mut self, program: Vec<Instruction>) {
+        self.pc_hit_counts = vec![0; program.len()];
         self.program = program;
         self.registers.pc = 0;
     }
 
+    /// Serialize registers and contexts (including their variables) to JSON.
+    /// Unlike an in-memory checkpoint this can be written out and restored in another process.
+    pub fn export_state(&self) -> String {
+        let state = VmState {
+            registers: self.registers.clone(),
+            contexts: self.contexts.clone(),
+            current_context: self.current_context,
+        };
+        serde_json::to_string_pretty(&state).expect("VM state is always serializable")
+    }
+
+    /// Restore state previously produced by `export_state`
+    pub fn import_state(&mut self, json: &str) -> Result<(), VmError> {
+        let state: VmState = serde_json::from_str(json)
+            .map_err(|e| VmError::InvalidState(e.to_string()))?;
+        if state.current_context >= state.contexts.len() {
+            return Err(VmError::InvalidState(format!(
+                "current context {} out of range ({} contexts)",
+                state.current_context,
+                state.contexts.len()
+            )));
+        }
+
+        self.registers = state.registers;
+        self.contexts = state.contexts;
+        self.current_context = state.current_context;
+        self.max_context_depth = self.max_context_depth.max(self.contexts.len());
+        Ok(())
+    }
+
     /// Execute the program using the canonical fetch-decode-execute cycle with safety checks
     pub fn execute(&mut self) -> Result<(), VmError> {
         self.running = true;
         self.step_count = 0;
         self.step_limiter.reset(); // Reset step counters
+        self.exec_counts.clear();
+        self.call_stack.clear();
+        self.try_frames.clear();
+        self.loop_frames.clear();
 
         // Validate the entire program before execution
         self.security_context.validate_instructions(&self.program)
@@ -308,36 +609,18 @@ impl VirtualMachine {
         {
             // Check step limits before each step
             if let Some(step_limit_error) = self.step_limiter.exceeds_limit() {
-                let limit_error = match step_limit_error {
-                    vm_safety::step_limits::StepLimitError::StepLimitExceeded =>
-                        vm_safety::limit_errors::LimitError::StepLimitExceeded,
-                    vm_safety::step_limits::StepLimitError::RuleLimitExceeded =>
-                        vm_safety::limit_errors::LimitError::RuleLimitExceeded,
-                    vm_safety::step_limits::StepLimitError::LoopLimitExceeded =>
-                        vm_safety::limit_errors::LimitError::LoopLimitExceeded,
-                };
-                return Err(VmError::from(limit_error));
+                return Err(VmError::from(step_limit_error));
             }
 
             self.step()?;
 
             // Increment step counter after successful step
-            if let Err(_) = self.step_limiter.increment_step() {
-                return Err(VmError::ExecutionLimitExceeded);
-            }
+            self.step_limiter.increment_step()?;
         }
 
         // Check if execution was halted due to limit violation
         if let Some(step_limit_error) = self.step_limiter.exceeds_limit() {
-            let limit_error = match step_limit_error {
-                vm_safety::step_limits::StepLimitError::StepLimitExceeded =>
-                    vm_safety::limit_errors::LimitError::StepLimitExceeded,
-                vm_safety::step_limits::StepLimitError::RuleLimitExceeded =>
-                    vm_safety::limit_errors::LimitError::RuleLimitExceeded,
-                vm_safety::step_limits::StepLimitError::LoopLimitExceeded =>
-                    vm_safety::limit_errors::LimitError::LoopLimitExceeded,
-            };
-            return Err(VmError::from(limit_error));
+            return Err(VmError::from(step_limit_error));
         }
 
         // Set running to false when execution completes
@@ -351,8 +634,23 @@ impl VirtualMachine {
         // Reset the jump flag at the beginning of each step
         self.jumped = false;
 
-        // Fetch instruction
-        let instruction = self.fetch()?;
+        // Fetch instruction, folding an EXT prefix into the instruction it extends
+        let mut instruction = self.fetch()?;
+        let mut width = 1;
+        self.ext_operand = None;
+        if instruction.opcode == Opcode::Ext as u8 {
+            self.security_context.validate_instruction(&instruction)
+                .map_err(VmError::SecurityError)?;
+
+            let next_pc = self.registers.pc as usize + 1;
+            let next = self.program.get(next_pc).cloned().ok_or(VmError::InvalidInstruction)?;
+            if next.opcode == Opcode::Ext as u8 {
+                return Err(VmError::InvalidInstruction);
+            }
+            self.ext_operand = Some(instruction.ext_payload());
+            instruction = next;
+            width = 2;
+        }
 
         // Validate the instruction for security compliance
         self.security_context.validate_instruction(&instruction)
@@ -363,19 +661,33 @@ impl VirtualMachine {
             self.performance_monitor.record_instruction(instruction.opcode.into());
         }
 
+        // Record opcode coverage (kept across executions)
+        self.opcode_coverage.insert(instruction.opcode);
+
         // Save state before execution for trace
+        self.pending_memory_diff.clear();
         let pc_before = self.registers.pc;
         let mut register_diff = [0i64; 16];
         for i in 0..16 {
             register_diff[i] = self.registers.r[i]; // Save original values
         }
 
-        // Execute instruction
-        self.execute_instruction(&instruction)?;
+        // Execute instruction; runtime faults inside a try block go to its catch target
+        if let Err(error) = self.execute_instruction(&instruction) {
+            match error.fault_code() {
+                Some(code) if self.unwind_to_catch(code) => self.ext_operand = None,
+                _ => return Err(error),
+            }
+        }
+
+        // Only instructions that understand wide operands may be prefixed
+        if self.ext_operand.take().is_some() {
+            return Err(VmError::InvalidInstruction);
+        }
 
         // Calculate register differences for trace
         for i in 0..16 {
-            register_diff[i] = self.registers.r[i] - register_diff[i];
+            register_diff[i] = self.registers.r[i].wrapping_sub(register_diff[i]);
         }
 
         // Add to execution trace for PSI introspection
@@ -384,13 +696,23 @@ impl VirtualMachine {
             opcode: instruction.opcode as u8,
             operands: (instruction.arg1 as u64) | ((instruction.arg2 as u64) << 16) | ((instruction.arg3 as u64) << 32) | ((instruction.flags as u64) << 48),
             register_diff,
-            memory_diff: Vec::new(), // Simplified for now
+            memory_diff: std::mem::take(&mut self.pending_memory_diff),
         };
-        self.execution_trace.push(trace_entry);
+        self.execution_trace.push_back(trace_entry);
+        // `program` is public, so it may have been replaced without load_program
+        if self.pc_hit_counts.len() != self.program.len() {
+            self.pc_hit_counts.resize(self.program.len(), 0);
+        }
+        self.pc_hit_counts[pc_before as usize] += 1;
+        if !self.config.trace_full {
+            while self.execution_trace.len() > self.config.max_trace_entries {
+                self.execution_trace.pop_front();
+            }
+        }
 
-        // Increment PC if no jump occurred in the instruction
+        // Increment PC past the instruction (and any prefix) if no jump occurred
         if !self.jumped {
-            self.registers.pc += 1;
+            self.registers.pc += width;
         }
 
         Ok(())
@@ -405,17 +727,14 @@ impl VirtualMachine {
     }
 
     fn execute_instruction(&mut self, instruction: &Instruction) -> Result<(), VmError> {
-        // Record the instruction for performance monitoring
-        if self.config.perf_flags {
-            self.performance_monitor.record_instruction(instruction.opcode.into());
-        }
-
         match instruction.opcode as u8 {
             // Control Flow Instructions
             0x00 => self.op_nop(),                // NOP
             0x01 => self.op_jmp(instruction)?,    // JMP
             0x02 => self.op_jmp_if(instruction)?, // JMP_IF
             0x03 => self.op_halt(),               // HALT
+            0x05 => self.op_loop_begin(instruction)?, // LOOP_BEGIN
+            0x06 => self.op_loop_end()?,              // LOOP_END
 
             // Data & Symbol Instructions
             0x10 => self.op_load_sym(instruction)?, // LOAD_SYM
@@ -423,6 +742,8 @@ impl VirtualMachine {
             0x12 => self.op_load_bool(instruction)?, // LOAD_BOOL
             0x13 => self.op_move(instruction)?,     // MOVE
             0x14 => self.op_compare(instruction)?,  // COMPARE
+            0x15 => self.op_load_const(instruction)?, // LOAD_CONST
+            0x16 => self.op_read_steps(instruction)?, // READ_STEPS
 
             // Arithmetic Instructions
             0x20 => self.op_add(instruction)?,      // ADD
@@ -439,31 +760,31 @@ impl VirtualMachine {
             // Graph Instructions
             0x40 => self.op_graph_node_create(instruction)?, // GRAPH_NODE_CREATE
             0x41 => self.op_graph_edge_create(instruction)?, // GRAPH_EDGE_CREATE
-            // 0x42 => self.op_graph_merge(instruction)?,
-            // 0x43 => self.op_graph_delete(instruction)?,
+            0x42 => self.op_graph_merge(instruction)?,       // MERGE
+            0x43 => self.op_graph_delete(instruction)?,      // DELETE_NODE
 
             // Rule Execution Instructions
-            // 0x50 => self.op_rule_call(instruction)?,
-            // 0x51 => self.op_rule_return(instruction)?,
+            0x50 => self.op_rule_call(instruction)?,   // CALL_RULE
+            0x51 => self.op_rule_return()?,            // RETURN_RULE
             // 0x52 => self.op_rule_check(instruction)?,
-            // 0x53 => self.op_rule_inc(instruction)?,
+            0x53 => self.op_increment_exec_count(instruction)?, // INCREMENT_EXEC_COUNT
 
             // Context & State Instructions
-            // 0x60 => self.op_ctx_push(instruction)?,
-            // 0x61 => self.op_ctx_pop(instruction)?,
-            // 0x62 => self.op_ctx_set_sym(instruction)?,
-            // 0x63 => self.op_ctx_get_sym(instruction)?,
+            0x60 => self.op_ctx_push()?,                 // PUSH_CTX
+            0x61 => self.op_ctx_pop()?,                  // POP_CTX
+            0x62 => self.op_ctx_set_sym(instruction)?,   // SET_SYMBOL
+            0x63 => self.op_ctx_get_sym(instruction)?,   // GET_SYMBOL
             0x64 => self.op_ctx_clone(instruction)?,  // CTX_CLONE
 
             // Error Handling Instructions
             0x70 => self.op_err_set(instruction)?,   // THROW (reused err_set)
-            // 0x71 => self.op_try(instruction)?,    // TRY
+            0x71 => self.op_try(instruction)?,       // TRY
             0x72 => self.op_err_check(instruction)?, // CATCH (reused err_check)
             0x73 => self.op_err_clear(),             // CLEAR_ERR
 
             // External Interface Instructions
             0x80 => self.op_ext_call(instruction)?, // EXT_CALL
-            // 0x81 => self.op_ext_read(instruction)?,
+            0x81 => self.op_ext_read(instruction)?, // READ_IO
             0x82 => self.op_output(instruction)?,   // WRITE_IO (reused output)
 
             _ => {
@@ -478,9 +799,21 @@ impl VirtualMachine {
     }
 
 
+    /// Validate a register operand taken from bytecode. Every handler goes
+    /// through this before indexing `registers.r`.
+    fn decode_reg(&self, reg: u16) -> Result<usize, VmError> {
+        let index = reg as usize;
+        if index < self.registers.r.len() {
+            Ok(index)
+        } else {
+            Err(VmError::InvalidRegister(reg))
+        }
+    }
+
     // Context management methods
     fn push_context(&mut self, new_context: VmContext) {
         self.contexts.push(new_context);
+        self.max_context_depth = self.max_context_depth.max(self.contexts.len());
     }
 
     fn pop_context(&mut self) -> Option<VmContext> {
@@ -514,6 +847,36 @@ impl VirtualMachine {
         Err(VmError::InvalidPc)
     }
 
+    fn op_loop_begin(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // Open a counted loop over the instructions up to the matching LOOP_END.
+        // The body always runs at least once.
+        // operand: arg1 = register holding the iteration count
+        let count_reg = self.decode_reg(instruction.arg1)?;
+        self.loop_frames.push(LoopFrame {
+            body_pc: self.registers.pc + 1,
+            max_iterations: self.registers.r[count_reg].max(1) as u64,
+            iterations: 0,
+        });
+        Ok(())
+    }
+
+    fn op_loop_end(&mut self) -> Result<(), VmError> {
+        // Finish a pass over the innermost loop's body; every pass counts
+        // towards max_loop_iterations
+        let frame = self.loop_frames.last_mut().ok_or(VmError::StackUnderflow)?;
+        frame.iterations += 1;
+        let (body_pc, exhausted) = (frame.body_pc, frame.iterations >= frame.max_iterations);
+
+        self.step_limiter.increment_loop_iteration()?;
+        if exhausted {
+            self.loop_frames.pop();
+        } else {
+            self.registers.pc = body_pc;
+            self.jumped = true;
+        }
+        Ok(())
+    }
+
     fn op_jmp_if(&mut self, instruction: &Instruction) -> Result<(), VmError> {
         // Conditional jump based on flag register
         // operand: target address
@@ -539,11 +902,7 @@ impl VirtualMachine {
         // Load a symbol into a register
         // operand: (symbol_id << 32) | dest_reg
         let symbol_id = (instruction.arg1 as u32) | ((instruction.arg2 as u32) << 16);
-        let dest_reg = instruction.arg3 as usize;
-
-        if dest_reg >= self.registers.r.len() {
-            return Err(VmError::InvalidRegister(dest_reg as u16));
-        }
+        let dest_reg = self.decode_reg(instruction.arg3)?;
 
         // In a real implementation, this would load a symbol from the symbol table
         self.registers.r[dest_reg] = symbol_id as i64;
@@ -553,32 +912,48 @@ impl VirtualMachine {
     fn op_load_num(&mut self, instruction: &Instruction) -> Result<(), VmError> {
         // Load a number into a register
         // operand: (dest_reg << 32) | value (where value is in lower 32 bits)
-        let dest_reg = instruction.arg1 as usize;
-        let value = instruction.arg2 as i64; // Extract lower 32 bits as unsigned value
+        // With an EXT prefix, arg2 holds the low 16 bits of a full 64-bit value
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let value = match self.ext_operand.take() {
+            Some(payload) => Instruction::wide_operand(payload, instruction.arg2) as i64,
+            None => instruction.arg2 as i64, // Extract lower 32 bits as unsigned value
+        };
 
-        if dest_reg >= self.registers.r.len() {
-            return Err(VmError::InvalidRegister(dest_reg as u16));
-        }
+        self.registers.r[dest_reg] = value;
+        Ok(())
+    }
+
+    fn op_load_const(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // Load a constant pool entry into a register
+        // operand: arg1 = dest_reg, arg2 = constant pool index
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let const_idx = instruction.arg2 as usize;
+
+        let value = match self.constant_pool.get(const_idx) {
+            Some(Constant::Num(n)) => *n,
+            Some(Constant::Bool(b)) => *b as i64,
+            Some(_) => return Err(VmError::InvalidInstruction),
+            None => return Err(VmError::InvalidAddress(const_idx as u32)),
+        };
 
         self.registers.r[dest_reg] = value;
         Ok(())
     }
 
+    fn op_read_steps(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // Read the number of steps executed so far into a register
+        // operand: arg1 = dest_reg
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+
+        self.registers.r[dest_reg] = self.step_limiter.counters.step_count as i64;
+        Ok(())
+    }
+
     fn op_move(&mut self, instruction: &Instruction) -> Result<(), VmError> {
         // Move value from one register to another
-        // operand: (src_reg << 32) | dest_reg
-        let src_reg = instruction.arg1 as usize;
-        let dest_reg = instruction.arg2 as usize;
-
-        if dest_reg >= self.registers.r.len() || src_reg >= self.registers.r.len() {
-            return Err(VmError::InvalidRegister(
-                if dest_reg >= self.registers.r.len() {
-                    dest_reg as u16
-                } else {
-                    src_reg as u16
-                },
-            ));
-        }
+        let (src_reg, dest_reg) = decode_move(instruction.operand());
+        let src_reg = self.decode_reg(src_reg)?;
+        let dest_reg = self.decode_reg(dest_reg)?;
 
         self.registers.r[dest_reg] = self.registers.r[src_reg];
         Ok(())
@@ -586,156 +961,171 @@ impl VirtualMachine {
 
     fn op_compare(&mut self, instruction: &Instruction) -> Result<(), VmError> {
         // Compare two registers and set flags
-        // operand: (reg_a << 32) | (reg_b << 16) | result_reg
-        let reg_a = instruction.arg1 as usize;
-        let reg_b = instruction.arg2 as usize;
-        let result_reg = instruction.arg3 as usize;
-
-        if reg_a >= self.registers.r.len()
-            || reg_b >= self.registers.r.len()
-            || result_reg >= self.registers.r.len()
-        {
-            return Err(VmError::InvalidRegister(reg_a as u16));
-        }
+        let (reg_a, reg_b, result_reg) = decode_compare(instruction.operand());
+        let reg_a = self.decode_reg(reg_a)?;
+        let reg_b = self.decode_reg(reg_b)?;
+        let result_reg = self.decode_reg(result_reg)?;
 
         let val_a = self.registers.r[reg_a];
         let val_b = self.registers.r[reg_b];
 
+        // Typed comparisons look the registers up in the constant pool. Any
+        // two constants can be tested for equality; only numbers are ordered.
+        let comparator = instruction.flags & !COMPARE_TYPED;
+        let (equal, ordering) = if instruction.flags & COMPARE_TYPED != 0 {
+            let a = self.pool_constant(val_a)?;
+            let b = self.pool_constant(val_b)?;
+            match (a, b) {
+                (Constant::Num(a), Constant::Num(b)) => (a == b, Some(a.cmp(b))),
+                _ => (a == b, None),
+            }
+        } else {
+            (val_a == val_b, Some(val_a.cmp(&val_b)))
+        };
+
         // Set flags based on comparison
-        let result = match instruction.flags {
-            0 => val_a == val_b, // Equal
-            1 => val_a != val_b, // Not Equal
-            2 => val_a > val_b,  // Greater
-            3 => val_a < val_b,  // Less
-            4 => val_a >= val_b, // Greater or Equal
-            5 => val_a <= val_b, // Less or Equal
+        let result = match (comparator, ordering) {
+            (0, _) => equal,  // Equal
+            (1, _) => !equal, // Not Equal
+            (2..=5, None) => return Err(VmError::InvalidComparison),
+            (2, Some(ordering)) => ordering.is_gt(), // Greater
+            (3, Some(ordering)) => ordering.is_lt(), // Less
+            (4, Some(ordering)) => ordering.is_ge(), // Greater or Equal
+            (5, Some(ordering)) => ordering.is_le(), // Less or Equal
             _ => false,
         };
 
         // Update flags
-        self.registers.set_zero_flag(val_a == val_b);
-        self.registers.set_negative_flag(val_a < val_b);
+        self.registers.set_zero_flag(equal);
+        self.registers.set_negative_flag(ordering.is_some_and(|o| o.is_lt()));
         self.registers.set_compare_true_flag(result);
 
-        if result_reg < self.registers.r.len() {
-            self.registers.r[result_reg] = if result { 1 } else { 0 };
-        }
+        self.registers.r[result_reg] = if result { 1 } else { 0 };
 
         Ok(())
     }
 
     // Arithmetic Instructions
     fn op_add(&mut self, instruction: &Instruction) -> Result<(), VmError> {
-        let dest_reg = instruction.arg1 as usize;
-        let left_reg = instruction.arg2 as usize;
-        let right_reg = instruction.arg3 as usize;
-
-        if dest_reg >= self.registers.r.len() || left_reg >= self.registers.r.len() || right_reg >= self.registers.r.len() {
-             return Err(VmError::InvalidRegister(dest_reg as u16));
-        }
-
-        self.registers.r[dest_reg] = self.registers.r[left_reg] + self.registers.r[right_reg];
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let left_reg = self.decode_reg(instruction.arg2)?;
+        let right_reg = self.decode_reg(instruction.arg3)?;
+        let (left, right) = (self.registers.r[left_reg], self.registers.r[right_reg]);
+
+        self.registers.r[dest_reg] = match self.config.arithmetic_mode {
+            ArithmeticMode::Wrapping => left.wrapping_add(right),
+            ArithmeticMode::Saturating => left.saturating_add(right),
+            ArithmeticMode::Checked => self.checked_result(left.checked_add(right))?,
+        };
         Ok(())
     }
 
     fn op_sub(&mut self, instruction: &Instruction) -> Result<(), VmError> {
-        let dest_reg = instruction.arg1 as usize;
-        let left_reg = instruction.arg2 as usize;
-        let right_reg = instruction.arg3 as usize;
-
-        if dest_reg >= self.registers.r.len() || left_reg >= self.registers.r.len() || right_reg >= self.registers.r.len() {
-             return Err(VmError::InvalidRegister(dest_reg as u16));
-        }
-
-        self.registers.r[dest_reg] = self.registers.r[left_reg] - self.registers.r[right_reg];
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let left_reg = self.decode_reg(instruction.arg2)?;
+        let right_reg = self.decode_reg(instruction.arg3)?;
+        let (left, right) = (self.registers.r[left_reg], self.registers.r[right_reg]);
+
+        self.registers.r[dest_reg] = match self.config.arithmetic_mode {
+            ArithmeticMode::Wrapping => left.wrapping_sub(right),
+            ArithmeticMode::Saturating => left.saturating_sub(right),
+            ArithmeticMode::Checked => self.checked_result(left.checked_sub(right))?,
+        };
         Ok(())
     }
 
     fn op_mul(&mut self, instruction: &Instruction) -> Result<(), VmError> {
-        let dest_reg = instruction.arg1 as usize;
-        let left_reg = instruction.arg2 as usize;
-        let right_reg = instruction.arg3 as usize;
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let left_reg = self.decode_reg(instruction.arg2)?;
+        let right_reg = self.decode_reg(instruction.arg3)?;
+        let (left, right) = (self.registers.r[left_reg], self.registers.r[right_reg]);
+
+        self.registers.r[dest_reg] = match self.config.arithmetic_mode {
+            ArithmeticMode::Wrapping => left.wrapping_mul(right),
+            ArithmeticMode::Saturating => left.saturating_mul(right),
+            ArithmeticMode::Checked => self.checked_result(left.checked_mul(right))?,
+        };
+        Ok(())
+    }
 
-        if dest_reg >= self.registers.r.len() || left_reg >= self.registers.r.len() || right_reg >= self.registers.r.len() {
-             return Err(VmError::InvalidRegister(dest_reg as u16));
-        }
+    // Unwraps a checked arithmetic result, flagging the error on overflow
+    fn checked_result(&mut self, result: Option<i64>) -> Result<i64, VmError> {
+        result.ok_or_else(|| {
+            self.registers.set_error_flag(true);
+            VmError::ArithmeticOverflow
+        })
+    }
 
-        self.registers.r[dest_reg] = self.registers.r[left_reg] * self.registers.r[right_reg];
+    // Zero divisor: trap, or with trap_on_div_zero off write 0 and set the error flag
+    fn division_by_zero(&mut self, dest_reg: usize) -> Result<(), VmError> {
+        self.registers.set_error_flag(true);
+        if self.config.trap_on_div_zero {
+            return Err(VmError::DivisionByZero);
+        }
+        self.registers.r[dest_reg] = 0;
         Ok(())
     }
 
     fn op_div(&mut self, instruction: &Instruction) -> Result<(), VmError> {
-        let dest_reg = instruction.arg1 as usize;
-        let left_reg = instruction.arg2 as usize;
-        let right_reg = instruction.arg3 as usize;
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let left_reg = self.decode_reg(instruction.arg2)?;
+        let right_reg = self.decode_reg(instruction.arg3)?;
 
-        if dest_reg >= self.registers.r.len() || left_reg >= self.registers.r.len() || right_reg >= self.registers.r.len() {
-             return Err(VmError::InvalidRegister(dest_reg as u16));
-        }
+        let (left, right) = (self.registers.r[left_reg], self.registers.r[right_reg]);
 
-        if self.registers.r[right_reg] == 0 {
-             // Handle divide by zero - set error flag? Or return error?
-             // For now, return 0 or handle gracefully
-             self.registers.r[dest_reg] = 0;
-             self.registers.set_error_flag(true);
-        } else {
-             self.registers.r[dest_reg] = self.registers.r[left_reg] / self.registers.r[right_reg];
+        if right == 0 {
+            return self.division_by_zero(dest_reg);
         }
+        // i64::MIN / -1 is the only quotient that overflows
+        self.registers.r[dest_reg] = match self.config.arithmetic_mode {
+            ArithmeticMode::Wrapping => left.wrapping_div(right),
+            ArithmeticMode::Saturating => left.saturating_div(right),
+            ArithmeticMode::Checked => self.checked_result(left.checked_div(right))?,
+        };
         Ok(())
     }
 
     fn op_mod(&mut self, instruction: &Instruction) -> Result<(), VmError> {
-        let dest_reg = instruction.arg1 as usize;
-        let left_reg = instruction.arg2 as usize;
-        let right_reg = instruction.arg3 as usize;
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let left_reg = self.decode_reg(instruction.arg2)?;
+        let right_reg = self.decode_reg(instruction.arg3)?;
 
-        if dest_reg >= self.registers.r.len() || left_reg >= self.registers.r.len() || right_reg >= self.registers.r.len() {
-             return Err(VmError::InvalidRegister(dest_reg as u16));
-        }
+        let (left, right) = (self.registers.r[left_reg], self.registers.r[right_reg]);
 
-        if self.registers.r[right_reg] == 0 {
-             self.registers.r[dest_reg] = 0;
-             self.registers.set_error_flag(true);
-        } else {
-             self.registers.r[dest_reg] = self.registers.r[left_reg] % self.registers.r[right_reg];
+        if right == 0 {
+            return self.division_by_zero(dest_reg);
         }
+        // i64::MIN % -1 overflows in the hardware division; its true value is 0,
+        // which is also what wrapping gives, so only Checked treats it as an error
+        self.registers.r[dest_reg] = match self.config.arithmetic_mode {
+            ArithmeticMode::Wrapping | ArithmeticMode::Saturating => left.wrapping_rem(right),
+            ArithmeticMode::Checked => self.checked_result(left.checked_rem(right))?,
+        };
         Ok(())
     }
 
     // Logical Instructions
     fn op_and(&mut self, instruction: &Instruction) -> Result<(), VmError> {
-        let dest_reg = instruction.arg1 as usize;
-        let left_reg = instruction.arg2 as usize;
-        let right_reg = instruction.arg3 as usize;
-
-        if dest_reg >= self.registers.r.len() || left_reg >= self.registers.r.len() || right_reg >= self.registers.r.len() {
-             return Err(VmError::InvalidRegister(dest_reg as u16));
-        }
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let left_reg = self.decode_reg(instruction.arg2)?;
+        let right_reg = self.decode_reg(instruction.arg3)?;
 
         self.registers.r[dest_reg] = self.registers.r[left_reg] & self.registers.r[right_reg];
         Ok(())
     }
 
     fn op_or(&mut self, instruction: &Instruction) -> Result<(), VmError> {
-        let dest_reg = instruction.arg1 as usize;
-        let left_reg = instruction.arg2 as usize;
-        let right_reg = instruction.arg3 as usize;
-
-        if dest_reg >= self.registers.r.len() || left_reg >= self.registers.r.len() || right_reg >= self.registers.r.len() {
-             return Err(VmError::InvalidRegister(dest_reg as u16));
-        }
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let left_reg = self.decode_reg(instruction.arg2)?;
+        let right_reg = self.decode_reg(instruction.arg3)?;
 
         self.registers.r[dest_reg] = self.registers.r[left_reg] | self.registers.r[right_reg];
         Ok(())
     }
 
     fn op_not(&mut self, instruction: &Instruction) -> Result<(), VmError> {
-        let dest_reg = instruction.arg1 as usize;
-        let src_reg = instruction.arg2 as usize;
-
-        if dest_reg >= self.registers.r.len() || src_reg >= self.registers.r.len() {
-             return Err(VmError::InvalidRegister(dest_reg as u16));
-        }
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let src_reg = self.decode_reg(instruction.arg2)?;
 
         // Logical NOT for boolean-ish values (0 is false, anything else is true)
         // If src is 0, result is 1. If src is != 0, result is 0.
@@ -747,83 +1137,242 @@ impl VirtualMachine {
 
     fn op_load_bool(&mut self, instruction: &Instruction) -> Result<(), VmError> {
         // Load a boolean value into a register
-        let dest_reg = instruction.arg1 as usize;
+        let dest_reg = self.decode_reg(instruction.arg1)?;
         let value = instruction.arg2; // 0 or 1
-        
-        if dest_reg >= self.registers.r.len() {
-            return Err(VmError::InvalidRegister(dest_reg as u16));
-        }
 
         self.registers.r[dest_reg] = value as i64;
         Ok(())
     }
 
 
-    // Graph Instructions (simplified for this implementation)
-    fn op_graph_node_create(&mut self, _instruction: &Instruction) -> Result<(), VmError> {
-        // Create a graph node
-        println!("Creating graph node");
+    // Graph Instructions
+    // Nodes live in vm_graph and are referred to by the id CREATE_NODE wrote
+    // to its destination register.
+    fn op_graph_node_create(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // operand: arg1 = dest_reg for the new node's id
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let id = self.next_graph_node;
+
+        // The heap record is written before the node exists, so a rejected
+        // write leaves neither a node nor an allocation behind
+        self.memory_manager.allocate(MemoryRegion::Heap, GRAPH_NODE_RECORD_SIZE)
+            .map_err(|_| VmError::MemoryLimitExceeded)?;
+        // Every slot handed out so far is either live or on the free list
+        let reused = self.free_graph_records.pop();
+        let offset = reused.unwrap_or(
+            (self.graph_node_records.len() + self.free_graph_records.len()) * GRAPH_NODE_RECORD_SIZE,
+        );
+        let mut record = [0u8; GRAPH_NODE_RECORD_SIZE];
+        record[..4].copy_from_slice(&id.to_le_bytes());
+        record[4] = 1;
+        if let Err(err) = self.write_memory(MemoryRegion::Heap, offset, &record) {
+            self.memory_manager.deallocate(MemoryRegion::Heap, GRAPH_NODE_RECORD_SIZE);
+            self.free_graph_records.extend(reused);
+            return Err(err);
+        }
+        self.graph_node_records.insert(id, offset);
+        self.next_graph_node += 1;
+
+        self.vm_graph.nodes.push(SpecializedNode::Base(GraphNode {
+            id,
+            node_type: GraphNodeType::Graph,
+            opcode: instruction.opcode,
+            flags: instruction.flags as u16,
+            input_regs: [0; 4],
+            output_regs: [0; 2],
+            first_edge: self.vm_graph.edges.len() as u32,
+            edge_count: 0,
+            meta: NodeMeta {
+                source_ref: self.registers.pc,
+                cost_hint: 0,
+            },
+        }));
+        self.vm_graph.node_count = self.vm_graph.nodes.len() as u32;
+        self.registers.r[dest_reg] = id as i64;
         Ok(())
     }
 
-    fn op_graph_edge_create(&mut self, _instruction: &Instruction) -> Result<(), VmError> {
-        // Create a graph edge
-        println!("Creating graph edge");
+    fn op_graph_edge_create(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // operand: arg1 = reg holding the source node, arg2 = reg holding the target node
+        let from = self.graph_node_operand(instruction.arg1)?;
+        let to = self.graph_node_operand(instruction.arg2)?;
+
+        self.vm_graph.edges.push(GraphEdge::new_data(from, to));
+        self.vm_graph.edge_count = self.vm_graph.edges.len() as u32;
+        Ok(())
+    }
+
+    fn op_graph_merge(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // operand: arg1 = reg holding the node to keep, arg2 = reg holding the node merged into it
+        let keep = self.graph_node_operand(instruction.arg1)?;
+        let merged = self.graph_node_operand(instruction.arg2)?;
+        if keep == merged {
+            return Ok(());
+        }
+
+        // Move the merged node's edges onto the kept node; edges that ran
+        // between the two would become self-loops and are dropped
+        for edge in &mut self.vm_graph.edges {
+            if edge.from_node == merged {
+                edge.from_node = keep;
+            }
+            if edge.to_node == merged {
+                edge.to_node = keep;
+            }
+        }
+        self.vm_graph
+            .edges
+            .retain(|edge| !(edge.from_node == keep && edge.to_node == keep));
+        self.remove_graph_node(merged)
+    }
+
+    fn op_graph_delete(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // operand: arg1 = reg holding the node to delete
+        let id = self.graph_node_operand(instruction.arg1)?;
+        self.vm_graph
+            .edges
+            .retain(|edge| edge.from_node != id && edge.to_node != id);
+        self.remove_graph_node(id)
+    }
+
+    // Reads a node id from a register, failing if vm_graph has no such node
+    fn graph_node_operand(&self, reg: u16) -> Result<u32, VmError> {
+        let value = self.registers.r[self.decode_reg(reg)?];
+        let id = u32::try_from(value).map_err(|_| VmError::InvalidAddress(value as u32))?;
+        if self.vm_graph.nodes.iter().any(|node| node.get_base().id == id) {
+            Ok(id)
+        } else {
+            Err(VmError::InvalidAddress(id))
+        }
+    }
+
+    fn remove_graph_node(&mut self, id: u32) -> Result<(), VmError> {
+        self.vm_graph.nodes.retain(|node| node.get_base().id != id);
+        self.vm_graph.node_count = self.vm_graph.nodes.len() as u32;
+        self.vm_graph.edge_count = self.vm_graph.edges.len() as u32;
+
+        // Mark the node's heap record dead and free its slot for the next CREATE_NODE
+        let Some(offset) = self.graph_node_records.remove(&id) else {
+            return Ok(());
+        };
+        self.memory_manager.deallocate(MemoryRegion::Heap, GRAPH_NODE_RECORD_SIZE);
+        self.free_graph_records.push(offset);
+        self.write_memory(MemoryRegion::Heap, offset + 4, &[0])
+    }
+
+    // Writes `bytes` at `offset` in `region`, recording the changed range for the trace
+    fn write_memory(&mut self, region: MemoryRegion, offset: usize, bytes: &[u8]) -> Result<(), VmError> {
+        if !self.security_context.sandbox.policy.is_write_allowed(region) {
+            return Err(VmError::SandboxViolation);
+        }
+        let memory = self.memory.region_mut(region);
+        let target = offset
+            .checked_add(bytes.len())
+            .and_then(|end| memory.get_mut(offset..end))
+            .ok_or(VmError::InvalidAddress(offset as u32))?;
+
+        // Only the span between the first and last differing byte is recorded
+        let Some(first) = target.iter().zip(bytes).position(|(old, new)| old != new) else {
+            return Ok(());
+        };
+        let last = target.iter().zip(bytes).rposition(|(old, new)| old != new).unwrap_or(first);
+        let old = target[first..=last].to_vec();
+        target.copy_from_slice(bytes);
+        self.pending_memory_diff.push(MemoryChange {
+            region,
+            offset: offset + first,
+            old,
+            new: bytes[first..=last].to_vec(),
+        });
         Ok(())
     }
 
     fn op_graph_match(&mut self, _instruction: &Instruction) -> Result<(), VmError> {
         // Perform graph pattern matching
-        println!("Performing graph match");
         Ok(())
     }
 
     fn op_graph_traverse(&mut self, _instruction: &Instruction) -> Result<(), VmError> {
         // Traverse the graph
-        println!("Traversing graph");
         Ok(())
     }
 
     // Rule Execution Instructions
     fn op_rule_load(&mut self, _instruction: &Instruction) -> Result<(), VmError> {
         // Load a rule for execution
-        println!("Loading rule");
         Ok(())
     }
 
+    fn op_rule_call(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // Jump to a rule's entry, remembering where to resume
+        // operand: arg1 = entry pc (arg2 names the rule until the call is resolved)
+        if instruction.flags & kern_bytecode::CALL_RULE_UNRESOLVED != 0 {
+            return Err(VmError::InvalidInstruction);
+        }
+        let target = instruction.arg1 as u32;
+        if target >= self.program.len() as u32 {
+            return Err(VmError::InvalidPc);
+        }
+        // Each return address takes 4 bytes of stack
+        if (self.call_stack.len() + 1) * 4 > self.config.memory_limits.max_stack_bytes {
+            return Err(VmError::StackOverflow);
+        }
+
+        self.step_limiter.increment_rule_invocation()?;
+        self.call_stack.push(self.registers.pc + 1);
+        self.registers.pc = target;
+        self.jumped = true;
+        Ok(())
+    }
+
+    fn op_rule_return(&mut self) -> Result<(), VmError> {
+        let return_pc = self.call_stack.pop().ok_or(VmError::StackUnderflow)?;
+        self.registers.pc = return_pc;
+        self.jumped = true;
+        Ok(())
+    }
+
+    fn op_increment_exec_count(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // Count one iteration of the loop (or recursion) identified by arg1
+        // operand: arg1 = loop id
+        let loop_id = instruction.arg1 as u32;
+        let count = self.exec_counts.entry(loop_id).or_insert(0);
+        *count += 1;
+        let count = *count;
+
+        self.step_limiter.increment_loop_iteration()
+            .and_then(|_| self.step_limiter.check_loop_count(loop_id, count))
+            .map_err(VmError::from)
+    }
+
     fn op_rule_eval(&mut self, _instruction: &Instruction) -> Result<(), VmError> {
         // Evaluate a rule with limit checking
         // Increment rule invocation counter
-        self.step_limiter.increment_rule_invocation()
-            .map_err(|_| VmError::ExecutionLimitExceeded)?;
+        self.step_limiter.increment_rule_invocation()?;
 
         // Record for performance monitoring
         if self.config.perf_flags {
             self.performance_monitor.record_rule_invocation("anonymous_rule_eval");
         }
 
-        println!("Evaluating rule");
         Ok(())
     }
 
     fn op_rule_fire(&mut self, _instruction: &Instruction) -> Result<(), VmError> {
         // Fire a rule with limit checking
         // Increment rule invocation counter
-        self.step_limiter.increment_rule_invocation()
-            .map_err(|_| VmError::ExecutionLimitExceeded)?;
+        self.step_limiter.increment_rule_invocation()?;
 
         // Record for performance monitoring
         if self.config.perf_flags {
             self.performance_monitor.record_rule_invocation("anonymous_rule");
         }
 
-        println!("Firing rule");
         Ok(())
     }
 
     fn op_rule_priority_set(&mut self, _instruction: &Instruction) -> Result<(), VmError> {
         // Set rule priority
-        println!("Setting rule priority");
         Ok(())
     }
 
@@ -835,10 +1384,8 @@ impl VirtualMachine {
         self.contexts.push(new_ctx);
 
         // Store the context ID in the specified register
-        let dest_reg = instruction.arg1 as usize;
-        if dest_reg < self.registers.r.len() {
-            self.registers.r[dest_reg] = new_ctx_id as i64;
-        }
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        self.registers.r[dest_reg] = new_ctx_id as i64;
 
         Ok(())
     }
@@ -846,16 +1393,75 @@ impl VirtualMachine {
     fn op_ctx_switch(&mut self, instruction: &Instruction) -> Result<(), VmError> {
         // Switch to a different execution context
         // operand: reg containing ctx_id
-        let reg = instruction.arg1 as usize;
-        if reg < self.registers.r.len() {
-            let ctx_id = self.registers.r[reg] as usize;
-            if ctx_id < self.contexts.len() {
-                self.current_context = ctx_id;
-            }
+        let reg = self.decode_reg(instruction.arg1)?;
+        let ctx_id = self.registers.r[reg] as usize;
+        if ctx_id < self.contexts.len() {
+            self.current_context = ctx_id;
         }
         Ok(())
     }
 
+    fn op_ctx_push(&mut self) -> Result<(), VmError> {
+        // Push a fresh context and make it current
+        if self.contexts.len() >= self.config.max_context_depth {
+            return Err(VmError::ContextDepthExceeded);
+        }
+        let new_ctx_id = self.contexts.len() as u64;
+        self.push_context(VmContext::new(new_ctx_id));
+        self.current_context = new_ctx_id as usize;
+        self.registers.ctx = new_ctx_id;
+        Ok(())
+    }
+
+    fn op_ctx_pop(&mut self) -> Result<(), VmError> {
+        // Discard the top context and return to the one below it; the root stays
+        self.pop_context().ok_or(VmError::StackUnderflow)?;
+        self.current_context = self.contexts.len() - 1;
+        self.registers.ctx = self.contexts[self.current_context].id;
+        Ok(())
+    }
+
+    fn op_ctx_set_sym(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // Store a register under a symbol in the current context
+        // operand: arg1 = constant pool index of the symbol name, arg2 = src_reg
+        let name = self.symbol_name(instruction.arg1)?;
+        let src_reg = self.decode_reg(instruction.arg2)?;
+        let value = self.registers.r[src_reg];
+
+        self.contexts[self.current_context].variables.insert(name, value);
+        Ok(())
+    }
+
+    fn op_ctx_get_sym(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // Load a symbol of the current context into a register
+        // operand: arg1 = dest_reg, arg2 = constant pool index of the symbol name
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let name = self.symbol_name(instruction.arg2)?;
+        let value = *self.contexts[self.current_context]
+            .variables
+            .get(&name)
+            .ok_or(VmError::UndefinedSymbol)?;
+
+        self.registers.r[dest_reg] = value;
+        Ok(())
+    }
+
+    fn pool_constant(&self, index: i64) -> Result<&Constant, VmError> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.constant_pool.get(i))
+            .ok_or(VmError::InvalidAddress(index as u32))
+    }
+
+    // Resolves a symbol name stored in the constant pool
+    fn symbol_name(&self, const_idx: u16) -> Result<String, VmError> {
+        match self.constant_pool.get(const_idx as usize) {
+            Some(Constant::Sym(name)) => Ok(name.clone()),
+            Some(_) => Err(VmError::InvalidInstruction),
+            None => Err(VmError::InvalidAddress(const_idx as u32)),
+        }
+    }
+
     fn op_ctx_clone(&mut self, instruction: &Instruction) -> Result<(), VmError> {
         // Clone an execution context
         // operand: source context ID
@@ -886,17 +1492,73 @@ impl VirtualMachine {
 
     // Error Handling Instructions
     fn op_err_set(&mut self, instruction: &Instruction) -> Result<(), VmError> {
-        // Set error register
+        // Set error register and unwind to the nearest TRY's catch target
         // operand: error code
-        self.registers.err = instruction.arg1 as u64;
+        let code = instruction.arg1 as u64;
+        self.registers.err = code;
         self.registers.set_error_flag(true);
+        if self.unwind_to_catch(code) {
+            Ok(())
+        } else {
+            Err(VmError::UncaughtThrow(code))
+        }
+    }
+
+    // Transfers control to the innermost open try block's catch target with
+    // `code` in the error register. Returns false if no try block is open.
+    fn unwind_to_catch(&mut self, code: u64) -> bool {
+        // A throw from inside a handler leaves that handler's block too
+        while self.try_frames.last().is_some_and(|frame| frame.handling) {
+            self.try_frames.pop();
+        }
+        let Some(frame) = self.try_frames.last_mut() else {
+            return false;
+        };
+        frame.handling = true;
+        let frame = frame.clone();
+
+        // Leave every rule call, context and loop entered inside the try block
+        self.call_stack.truncate(frame.call_depth);
+        self.loop_frames.truncate(frame.loop_depth);
+        if self.contexts.len() > frame.context_depth {
+            while self.contexts.len() > frame.context_depth && self.pop_context().is_some() {}
+            self.current_context = self.contexts.len() - 1;
+            self.registers.ctx = self.contexts[self.current_context].id;
+        }
+
+        self.registers.r = frame.saved_registers;
+        self.registers.pc = frame.catch_pc;
+        self.registers.err = code;
+        self.registers.set_error_flag(true);
+        self.jumped = true;
+        true
+    }
+
+    fn op_try(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // Open a try block
+        // operand: catch target address
+        let catch_pc = instruction.arg1 as u32;
+        if catch_pc >= self.program.len() as u32 {
+            return Err(VmError::InvalidPc);
+        }
+        self.try_frames.push(TryFrame {
+            catch_pc,
+            saved_registers: self.registers.r,
+            call_depth: self.call_stack.len(),
+            context_depth: self.contexts.len(),
+            loop_depth: self.loop_frames.len(),
+            handling: false,
+        });
         Ok(())
     }
 
     fn op_err_clear(&mut self) {
-        // Clear error register
+        // Clear error register, ending the handler of the current try block
         self.registers.err = 0;
         self.registers.set_error_flag(false);
+        if self.try_frames.last().is_some_and(|frame| frame.handling) {
+            self.try_frames.pop();
+        }
     }
 
     fn op_err_check(&mut self, instruction: &Instruction) -> Result<(), VmError> {
@@ -909,6 +1571,9 @@ impl VirtualMachine {
                 self.jumped = true;
                 return Ok(());
             }
+        } else if self.try_frames.last().is_some_and(|frame| !frame.handling) {
+            // Reaching CATCH without an error ends the try block successfully
+            self.try_frames.pop();
         }
         Ok(())
     }
@@ -916,19 +1581,33 @@ impl VirtualMachine {
     // External Interface Instructions
     fn op_ext_call(&mut self, instruction: &Instruction) -> Result<(), VmError> {
         // Call an external function with sandbox validation
-        // operand: function ID
+        // operand: function ID (a symbol table id)
         let fn_id = instruction.arg1 as u64;
 
-        // Convert function ID to a name for sandbox validation
-        let fn_name = format!("extern_fn_{}", fn_id);
+        // The sandbox and the registered functions both go by declared name
+        let fn_name = self
+            .function_names
+            .get(&fn_id)
+            .cloned()
+            .ok_or(VmError::UndefinedSymbol)?;
 
         // Validate against sandbox policy
         self.security_context.sandbox.execute_external_call(&fn_name)
             .map_err(|e| VmError::SecurityError(vm_safety::security::SecurityError::SandboxViolation(e)))?;
 
-        let arg0 = self.registers.r[0];
-        println!("Calling external function with ID: {} (Arg0: {})", fn_id, arg0);
-        Ok(())
+        if let Some(func) = self.external_functions.get(&fn_name).copied() {
+            return func(self).map_err(VmError::ExternalCallFailed);
+        }
+
+        // The function passed the sandbox check but isn't registered with the VM
+        match self.config.unknown_extern_policy {
+            UnknownExternPolicy::Trap => Err(VmError::UnknownExternalFunction(fn_name)),
+            UnknownExternPolicy::Nop => Ok(()),
+            UnknownExternPolicy::ReturnZero => {
+                self.registers.r[0] = 0;
+                Ok(())
+            }
+        }
     }
 
     fn op_ext_bind(&mut self, _instruction: &Instruction) -> Result<(), VmError> {
@@ -937,7 +1616,6 @@ impl VirtualMachine {
         self.security_context.sandbox.execute_io_operation("extern_bind")
             .map_err(|e| VmError::SecurityError(vm_safety::security::SecurityError::SandboxViolation(e)))?;
 
-        println!("Binding external function");
         Ok(())
     }
 
@@ -951,32 +1629,44 @@ impl VirtualMachine {
     fn op_output(&mut self, instruction: &Instruction) -> Result<(), VmError> {
         // Output a value with IO validation
         // operand: register index
-        let reg = instruction.arg1 as usize;
+        let reg = self.decode_reg(instruction.arg1)?;
 
         // Validate against sandbox policy for stdout access
         self.security_context.sandbox.execute_io_operation("stdout")
             .map_err(|e| VmError::SecurityError(vm_safety::security::SecurityError::SandboxViolation(e)))?;
 
-        if reg < self.registers.r.len() {
-            let val = self.registers.r[reg];
-
-            // If the VM has a constant pool and the register value indexes it, prefer printing the constant
-            if val >= 0 {
-                let idx = val as usize;
-                if idx < self.constant_pool.len() {
-                    match &self.constant_pool[idx] {
-                        Constant::Num(n) => println!("Output: {}", n),
-                        Constant::Bool(b) => println!("Output: {}", b),
-                        Constant::Sym(s) => println!("Output: {}", s),
-                        Constant::Vec(v) => println!("Output: {:?}", v),
-                    }
-                    return Ok(());
-                }
-            }
+        let val = self.registers.r[reg];
+
+        // If the VM has a constant pool and the register value indexes it, prefer printing the constant
+        let event = match usize::try_from(val).ok().and_then(|idx| self.constant_pool.get(idx)) {
+            Some(constant) => OutputEvent::from_constant(constant),
+            None => OutputEvent::Num(val), // Fallback to numeric output
+        };
+        if self.config.echo_output {
+            println!("{}", self.output_formatter.format(&event));
+        }
+        self.output_sink.push_back(event);
+        while self.output_sink.len() > self.config.max_output_events {
+            self.output_sink.pop_front();
+        }
+        Ok(())
+    }
+
+    fn op_ext_read(&mut self, instruction: &Instruction) -> Result<(), VmError> {
+        // Read a value from the installed input source with IO validation
+        // operand: arg1 = dest_reg, arg2 = constant pool index of the channel name
+        let dest_reg = self.decode_reg(instruction.arg1)?;
+        let channel = self.symbol_name(instruction.arg2)?;
 
-            // Fallback to numeric output
-            println!("Output: {}", self.registers.r[reg]);
+        self.security_context.sandbox.execute_io_operation(&channel)
+            .map_err(|e| VmError::SecurityError(vm_safety::security::SecurityError::SandboxViolation(e)))?;
+
+        // No input source, or nothing left on the channel: 0 with the error flag set
+        let value = self.io_input.as_mut().and_then(|input| input.read(&channel));
+        if value.is_none() {
+            self.registers.set_error_flag(true);
         }
+        self.registers.r[dest_reg] = value.unwrap_or(0);
         Ok(())
     }
 
@@ -1017,6 +1707,22 @@ impl VirtualMachine {
         self.external_functions.insert(name.to_string(), func);
     }
 
+    /// Name CALL_EXTERN function ids after a module's symbol table
+    pub fn load_function_names(&mut self, symbols: &[Symbol]) {
+        self.function_names
+            .extend(symbols.iter().map(|symbol| (symbol.id as u64, symbol.name.clone())));
+    }
+
+    /// Merge this VM's opcode coverage into an accumulator shared across runs
+    pub fn accumulate_coverage(&self, accumulator: &mut HashSet<u8>) {
+        accumulator.extend(self.opcode_coverage.iter().copied());
+    }
+
+    /// Split `all_opcodes` into (covered, uncovered) according to this VM's coverage
+    pub fn coverage_report(&self, all_opcodes: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        coverage_report(&self.opcode_coverage, all_opcodes)
+    }
+
     // Helper function to get register value
     pub fn get_register(&self, reg: usize) -> Option<i64> {
         if reg < self.registers.r.len() {
@@ -1040,6 +1746,23 @@ impl VirtualMachine {
         self.performance_monitor.get_snapshot()
     }
 
+    /// Times each opcode has been executed since the metrics were last reset,
+    /// as opposed to how often it appears in the program
+    pub fn get_opcode_execution_counts(&self) -> HashMap<u8, u64> {
+        self.performance_monitor
+            .metrics
+            .per_opcode_count
+            .iter()
+            .map(|(opcode, count)| (*opcode as u8, *count))
+            .collect()
+    }
+
+    /// Times the instruction at each program index has been executed, indexed
+    /// by PC. An EXT-prefixed instruction is counted at its prefix.
+    pub fn get_pc_hit_counts(&self) -> &[u64] {
+        &self.pc_hit_counts
+    }
+
     pub fn generate_performance_report(&self) -> String {
         self.performance_monitor.generate_report()
     }
@@ -1053,6 +1776,17 @@ impl VirtualMachine {
         &self.memory_manager.usage
     }
 
+    /// The most contexts that have been on the stack at once, including the
+    /// root, since the VM was created or reset
+    pub fn get_max_context_depth(&self) -> usize {
+        self.max_context_depth
+    }
+
+    /// The most memory each region has held at once since the last reset
+    pub fn get_peak_memory_usage(&self) -> &vm_safety::memory_limits::MemoryUsage {
+        &self.memory_manager.peak_usage
+    }
+
     pub fn get_memory_limits(&self) -> &vm_safety::memory_limits::MemoryLimits {
         &self.memory_manager.limits
     }
@@ -1168,6 +1902,442 @@ mod tests {
         assert_eq!(vm.execution_trace.len(), 3);
     }
 
+    #[test]
+    fn test_execution_trace_keeps_most_recent_entries() {
+        let program: Vec<Instruction> = (0..10)
+            .map(|i| Instruction::new(0x11, 0, i, 0, 0)) // LOAD_NUM R0, i
+            .collect();
+
+        let mut config = VMConfig::new();
+        config.max_trace_entries = 4;
+        let mut vm = VirtualMachine::with_config(config.clone());
+        vm.load_program(program.clone());
+        for _ in 0..10 {
+            vm.step().unwrap();
+        }
+        let pcs: Vec<u32> = vm.execution_trace.iter().map(|e| e.pc_before).collect();
+        assert_eq!(pcs, vec![6, 7, 8, 9]);
+
+        // trace_full opts back into keeping everything
+        config.trace_full = true;
+        let mut vm = VirtualMachine::with_config(config);
+        vm.load_program(program);
+        for _ in 0..10 {
+            vm.step().unwrap();
+        }
+        assert_eq!(vm.execution_trace.len(), 10);
+    }
+
+    #[test]
+    fn test_graph_opcodes_merge_and_delete_nodes() {
+        let program = vec![
+            Instruction::new(0x40, 1, 0, 0, 0), // CREATE_NODE R1
+            Instruction::new(0x40, 2, 0, 0, 0), // CREATE_NODE R2
+            Instruction::new(0x40, 3, 0, 0, 0), // CREATE_NODE R3
+            Instruction::new(0x41, 1, 2, 0, 0), // CONNECT R1 -> R2
+            Instruction::new(0x41, 2, 3, 0, 0), // CONNECT R2 -> R3
+            Instruction::new(0x41, 3, 1, 0, 0), // CONNECT R3 -> R1
+            Instruction::new(0x42, 1, 2, 0, 0), // MERGE R2 into R1
+        ];
+        let mut vm = VirtualMachine::new();
+        vm.load_program(program);
+        vm.execute().unwrap();
+
+        // R1 -> R2 became a self-loop and was dropped; R2 -> R3 now leaves R1
+        let edges: Vec<(u32, u32)> = vm
+            .vm_graph
+            .edges
+            .iter()
+            .map(|e| (e.from_node, e.to_node))
+            .collect();
+        assert_eq!(edges, vec![(0, 2), (2, 0)]);
+        assert_eq!(vm.vm_graph.node_count, 2);
+
+        vm.program.push(Instruction::new(0x43, 3, 0, 0, 0)); // DELETE_NODE R3
+        vm.step().unwrap();
+        assert_eq!(vm.vm_graph.node_count, 1);
+        assert_eq!(vm.vm_graph.edge_count, 0);
+
+        // R2 still holds the id of the node merged away
+        vm.program.push(Instruction::new(0x43, 2, 0, 0, 0)); // DELETE_NODE R2
+        assert!(matches!(vm.step(), Err(VmError::InvalidAddress(1))));
+    }
+
+    #[test]
+    fn test_trace_records_memory_diffs() {
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::new(0x40, 1, 0, 0, 0), // CREATE_NODE R1
+            Instruction::new(0x40, 2, 0, 0, 0), // CREATE_NODE R2
+            Instruction::new(0x41, 1, 2, 0, 0), // CONNECT R1 -> R2
+            Instruction::new(0x43, 2, 0, 0, 0), // DELETE_NODE R2
+        ]);
+        vm.execute().unwrap();
+
+        let diffs: Vec<&Vec<MemoryChange>> =
+            vm.execution_trace.iter().map(|e| &e.memory_diff).collect();
+        // Node 0's record is all zero except the live byte
+        assert_eq!(
+            diffs[0],
+            &vec![MemoryChange {
+                region: MemoryRegion::Heap,
+                offset: 4,
+                old: vec![0],
+                new: vec![1],
+            }]
+        );
+        assert_eq!(diffs[1][0].offset, GRAPH_NODE_RECORD_SIZE);
+        assert_eq!(diffs[1][0].new, vec![1, 0, 0, 0, 1]);
+        assert!(diffs[2].is_empty());
+        assert_eq!(diffs[3][0].offset, GRAPH_NODE_RECORD_SIZE + 4);
+        assert_eq!(diffs[3][0].new, vec![0]);
+        assert_eq!(vm.memory.heap[GRAPH_NODE_RECORD_SIZE], 1);
+    }
+
+    #[test]
+    fn test_deleted_graph_node_records_are_reused() {
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::new(0x40, 1, 0, 0, 0), // CREATE_NODE R1
+            Instruction::new(0x40, 2, 0, 0, 0), // CREATE_NODE R2
+            Instruction::new(0x43, 1, 0, 0, 0), // DELETE_NODE R1
+            Instruction::new(0x40, 3, 0, 0, 0), // CREATE_NODE R3 takes node 0's slot
+        ]);
+        vm.execute().unwrap();
+
+        assert_eq!(vm.get_register(3), Some(2));
+        assert_eq!(&vm.memory.heap[..5], &[2, 0, 0, 0, 1]);
+        assert_eq!(&vm.memory.heap[GRAPH_NODE_RECORD_SIZE..GRAPH_NODE_RECORD_SIZE + 5], &[1, 0, 0, 0, 1]);
+        assert_eq!(vm.memory.heap[2 * GRAPH_NODE_RECORD_SIZE + 4], 0);
+
+        // Far more create/delete cycles than the heap has record slots
+        let cycles = vm.memory.heap.len() / GRAPH_NODE_RECORD_SIZE + 100;
+        for _ in 0..cycles {
+            vm.program.push(Instruction::new(0x40, 4, 0, 0, 0)); // CREATE_NODE R4
+            vm.program.push(Instruction::new(0x43, 4, 0, 0, 0)); // DELETE_NODE R4
+        }
+        vm.execute().unwrap();
+        assert_eq!(vm.vm_graph.node_count, 2);
+        assert_eq!(vm.memory_manager.usage.heap_used, 2 * GRAPH_NODE_RECORD_SIZE);
+    }
+
+    #[test]
+    fn test_sandbox_rejects_writes_to_read_only_regions() {
+        let program = vec![
+            Instruction::new(0x40, 1, 0, 0, 0), // CREATE_NODE R1 writes its heap record
+        ];
+
+        let mut vm = VirtualMachine::new();
+        vm.load_program(program.clone());
+        assert!(vm.execute().is_ok());
+        assert!(matches!(
+            vm.write_memory(MemoryRegion::Const, 0, &[1]),
+            Err(VmError::SandboxViolation)
+        ));
+
+        let mut config = VMConfig::new();
+        config.sandbox_policy.deny_write(MemoryRegion::Heap);
+        let mut vm = VirtualMachine::with_config(config);
+        vm.load_program(program);
+        assert!(matches!(vm.execute(), Err(VmError::SandboxViolation)));
+        assert!(vm.memory.heap.iter().all(|&b| b == 0));
+        // The rejected CREATE_NODE left no node or heap allocation behind
+        assert!(vm.vm_graph.nodes.is_empty());
+        assert_eq!(vm.memory_manager.usage.heap_used, 0);
+    }
+
+    #[test]
+    fn test_context_symbols_are_scoped_to_their_context() {
+        let program = vec![
+            Instruction::new(0x11, 1, 7, 0, 0), // LOAD_NUM R1, 7
+            Instruction::new(0x60, 0, 0, 0, 0), // PUSH_CTX
+            Instruction::new(0x62, 0, 1, 0, 0), // SET_SYMBOL "total", R1
+            Instruction::new(0x63, 2, 0, 0, 0), // GET_SYMBOL R2, "total"
+            Instruction::new(0x61, 0, 0, 0, 0), // POP_CTX
+            Instruction::new(0x63, 3, 0, 0, 0), // GET_SYMBOL R3, "total"
+        ];
+        let mut vm = VirtualMachine::new();
+        vm.load_program(program);
+        vm.constant_pool = vec![Constant::Sym("total".to_string())];
+
+        for _ in 0..4 {
+            vm.step().unwrap();
+        }
+        assert_eq!(vm.get_register(2), Some(7));
+        assert_eq!(vm.current_context, 1);
+
+        vm.step().unwrap();
+        assert_eq!(vm.current_context, 0);
+        assert!(matches!(vm.step(), Err(VmError::UndefinedSymbol)));
+
+        // Only the root context is left
+        vm.program.push(Instruction::new(0x61, 0, 0, 0, 0)); // POP_CTX
+        vm.registers.pc = vm.program.len() as u32 - 1;
+        assert!(matches!(vm.step(), Err(VmError::StackUnderflow)));
+    }
+
+    #[test]
+    fn test_max_context_depth_watermark_and_limit() {
+        let program = vec![
+            Instruction::new(0x60, 0, 0, 0, 0), // PUSH_CTX
+            Instruction::new(0x60, 0, 0, 0, 0), // PUSH_CTX
+            Instruction::new(0x61, 0, 0, 0, 0), // POP_CTX
+            Instruction::new(0x61, 0, 0, 0, 0), // POP_CTX
+            Instruction::new(0x60, 0, 0, 0, 0), // PUSH_CTX
+        ];
+        let mut vm = VirtualMachine::new();
+        vm.load_program(program.clone());
+        assert_eq!(vm.get_max_context_depth(), 1);
+        vm.execute().unwrap();
+        // The watermark stays at its peak after the pops
+        assert_eq!(vm.contexts.len(), 2);
+        assert_eq!(vm.get_max_context_depth(), 3);
+        vm.reset();
+        assert_eq!(vm.get_max_context_depth(), 1);
+
+        let mut config = VMConfig::new();
+        config.max_context_depth = 2;
+        let mut vm = VirtualMachine::with_config(config);
+        vm.load_program(program);
+        vm.step().unwrap();
+        assert!(matches!(vm.step(), Err(VmError::ContextDepthExceeded)));
+        assert_eq!(vm.contexts.len(), 2);
+        assert_eq!(vm.get_max_context_depth(), 2);
+    }
+
+    #[test]
+    fn test_arithmetic_mode_on_add_overflow() {
+        let run = |mode: ArithmeticMode| {
+            let mut config = VMConfig::new();
+            config.arithmetic_mode = mode;
+            let mut vm = VirtualMachine::with_config(config);
+            vm.load_program(vec![Instruction::new(0x20, 2, 0, 1, 0)]); // ADD R2, R0, R1
+            vm.set_register(0, i64::MAX).unwrap();
+            vm.set_register(1, 1).unwrap();
+            let result = vm.step();
+            (vm, result)
+        };
+
+        let (vm, result) = run(ArithmeticMode::Wrapping);
+        assert!(result.is_ok());
+        assert_eq!(vm.get_register(2), Some(i64::MIN));
+
+        let (vm, result) = run(ArithmeticMode::Saturating);
+        assert!(result.is_ok());
+        assert_eq!(vm.get_register(2), Some(i64::MAX));
+
+        let (vm, result) = run(ArithmeticMode::Checked);
+        assert!(matches!(result, Err(VmError::ArithmeticOverflow)));
+        assert!(vm.registers.has_error());
+        assert_eq!(vm.get_register(2), Some(0));
+        assert_eq!(VMConfig::new().arithmetic_mode, ArithmeticMode::Checked);
+    }
+
+    #[test]
+    fn test_arithmetic_mode_on_min_divided_by_minus_one() {
+        let run = |opcode: u8, mode: ArithmeticMode| {
+            let mut config = VMConfig::new();
+            config.arithmetic_mode = mode;
+            let mut vm = VirtualMachine::with_config(config);
+            vm.load_program(vec![Instruction::new(opcode, 2, 0, 1, 0)]); // DIV/MOD R2, R0, R1
+            vm.set_register(0, i64::MIN).unwrap();
+            vm.set_register(1, -1).unwrap();
+            let result = vm.step();
+            (vm, result)
+        };
+
+        // DIV
+        let (vm, result) = run(0x23, ArithmeticMode::Wrapping);
+        assert!(result.is_ok());
+        assert_eq!(vm.get_register(2), Some(i64::MIN));
+
+        let (vm, result) = run(0x23, ArithmeticMode::Saturating);
+        assert!(result.is_ok());
+        assert_eq!(vm.get_register(2), Some(i64::MAX));
+
+        let (vm, result) = run(0x23, ArithmeticMode::Checked);
+        assert!(matches!(result, Err(VmError::ArithmeticOverflow)));
+        assert!(vm.registers.has_error());
+
+        // MOD
+        for mode in [ArithmeticMode::Wrapping, ArithmeticMode::Saturating] {
+            let (vm, result) = run(0x24, mode);
+            assert!(result.is_ok());
+            assert_eq!(vm.get_register(2), Some(0));
+        }
+
+        let (vm, result) = run(0x24, ArithmeticMode::Checked);
+        assert!(matches!(result, Err(VmError::ArithmeticOverflow)));
+        assert!(vm.registers.has_error());
+    }
+
+    #[test]
+    fn test_division_by_zero_traps_unless_disabled() {
+        for opcode in [0x23, 0x24] {
+            // DIV / MOD R2, R0, R1 with R1 = 0
+            let program = vec![Instruction::new(opcode, 2, 0, 1, 0)];
+
+            let mut vm = VirtualMachine::new();
+            vm.load_program(program.clone());
+            vm.set_register(0, 10).unwrap();
+            vm.set_register(2, 5).unwrap();
+            assert!(matches!(vm.execute(), Err(VmError::DivisionByZero)));
+            assert_eq!(vm.get_register(2), Some(5));
+
+            let mut config = VMConfig::new();
+            config.trap_on_div_zero = false;
+            let mut vm = VirtualMachine::with_config(config);
+            vm.load_program(program);
+            vm.set_register(0, 10).unwrap();
+            vm.set_register(2, 5).unwrap();
+            assert!(vm.execute().is_ok());
+            assert_eq!(vm.get_register(2), Some(0));
+            assert!(vm.registers.has_error());
+        }
+    }
+
+    #[test]
+    fn test_typed_compare_uses_constant_values() {
+        let mut vm = VirtualMachine::new();
+        vm.constant_pool = vec![
+            Constant::Sym("farmer".to_string()),
+            Constant::Sym("farmer".to_string()),
+            Constant::Vec(vec![Constant::Num(1), Constant::Num(2)]),
+            Constant::Vec(vec![Constant::Num(1), Constant::Num(3)]),
+        ];
+        vm.load_program(vec![
+            Instruction::new(0x11, 0, 0, 0, 0), // LOAD_NUM R0, 0
+            Instruction::new(0x11, 1, 1, 0, 0), // LOAD_NUM R1, 1
+            Instruction::new(0x14, 0, 1, 4, COMPARE_TYPED), // R4 = const[R0] == const[R1]
+            Instruction::new(0x11, 2, 2, 0, 0), // LOAD_NUM R2, 2
+            Instruction::new(0x11, 3, 3, 0, 0), // LOAD_NUM R3, 3
+            Instruction::new(0x14, 2, 3, 5, COMPARE_TYPED), // R5 = const[R2] == const[R3]
+            Instruction::new(0x14, 2, 3, 6, COMPARE_TYPED | 1), // R6 = const[R2] != const[R3]
+            Instruction::new(0x14, 0, 1, 7, COMPARE_TYPED | 3), // const[R0] < const[R1]
+        ]);
+
+        for _ in 0..7 {
+            vm.step().unwrap();
+        }
+        // Untyped, the symbols' indices differ; typed, their names match
+        assert_eq!(vm.get_register(4), Some(1));
+        assert_eq!(vm.get_register(5), Some(0));
+        assert_eq!(vm.get_register(6), Some(1));
+        assert!(matches!(vm.step(), Err(VmError::InvalidComparison)));
+    }
+
+    #[test]
+    fn test_read_io_pulls_from_input_queue() {
+        let mut config = VMConfig::new();
+        config.sandbox_policy.allow_io_channel("sensor");
+        let mut vm = VirtualMachine::with_config(config);
+        vm.constant_pool = vec![Constant::Sym("sensor".to_string())];
+        vm.load_program(vec![
+            Instruction::new(0x81, 1, 0, 0, 0), // READ_IO R1, "sensor"
+            Instruction::new(0x81, 2, 0, 0, 0), // READ_IO R2, "sensor"
+            Instruction::new(0x81, 3, 0, 0, 0), // READ_IO R3, "sensor" (drained)
+        ]);
+
+        let mut input = input::QueueInput::new();
+        input.push("sensor", 17);
+        input.push("sensor", -4);
+        vm.io_input = Some(Box::new(input));
+
+        vm.step().unwrap();
+        vm.step().unwrap();
+        assert_eq!(vm.get_register(1), Some(17));
+        assert_eq!(vm.get_register(2), Some(-4));
+        assert!(!vm.registers.has_error());
+
+        vm.set_register(3, 9).unwrap();
+        vm.step().unwrap();
+        assert_eq!(vm.get_register(3), Some(0));
+        assert!(vm.registers.has_error());
+        assert_eq!(vm.security_context.sandbox.get_io_operation_count("sensor"), 3);
+    }
+
+    #[test]
+    fn test_throw_inside_try_lands_on_catch_handler() {
+        let program = vec![
+            Instruction::new(0x71, 5, 0, 0, 0), // TRY, catch at 5
+            Instruction::new(0x11, 0, 100, 0, 0), // LOAD_NUM R0, 100
+            Instruction::new(0x70, 9, 0, 0, 0), // THROW 9
+            Instruction::new(0x11, 1, 1, 0, 0), // LOAD_NUM R1, 1 (skipped)
+            Instruction::new(0x72, 5, 0, 0, 0), // CATCH -> 5
+            Instruction::new(0x11, 2, 1, 0, 0), // LOAD_NUM R2, 1 (handler)
+            Instruction::new(0x73, 0, 0, 0, 0), // CLEAR_ERR
+            Instruction::new(0x70, 4, 0, 0, 0), // THROW 4, uncaught
+        ];
+        let mut vm = VirtualMachine::new();
+        vm.load_program(program);
+
+        for _ in 0..3 {
+            vm.step().unwrap();
+        }
+        // The handler runs with the registers saved at TRY
+        assert_eq!(vm.registers.pc, 5);
+        assert_eq!(vm.registers.err, 9);
+        assert_eq!(vm.get_register(0), Some(0));
+        assert_eq!(vm.get_register(1), Some(0));
+        assert_eq!(vm.try_frames.len(), 1);
+
+        vm.step().unwrap();
+        vm.step().unwrap();
+        assert!(vm.try_frames.is_empty());
+        assert!(!vm.registers.has_error());
+        assert!(matches!(vm.step(), Err(VmError::UncaughtThrow(4))));
+
+        // Runtime faults are caught like a THROW
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::new(0x71, 2, 0, 0, 0),  // TRY, catch at 2
+            Instruction::new(0x11, 99, 1, 0, 0), // LOAD_NUM R99, 1
+            Instruction::new(0x73, 0, 0, 0, 0),  // CLEAR_ERR
+        ]);
+        vm.step().unwrap();
+        vm.step().unwrap();
+        assert_eq!(vm.registers.pc, 2);
+        assert_eq!(vm.registers.err, FAULT_INVALID_REGISTER);
+
+        // A block that reaches CATCH without throwing pops its frame
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::new(0x71, 2, 0, 0, 0), // TRY, catch at 2
+            Instruction::new(0x72, 2, 0, 0, 0), // CATCH -> 2
+            Instruction::new(0x03, 0, 0, 0, 0), // HALT
+        ]);
+        vm.execute().unwrap();
+        assert!(vm.try_frames.is_empty());
+    }
+
+    #[test]
+    fn test_throw_from_called_rule_unwinds_to_try_depth() {
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::new(0x71, 3, 0, 0, 0),  // TRY, catch at 3
+            Instruction::new(0x50, 5, 0, 0, 0),  // CALL_RULE 5
+            Instruction::new(0x03, 0, 0, 0, 0),  // HALT (skipped)
+            Instruction::new(0x11, 2, 1, 0, 0),  // LOAD_NUM R2, 1 (handler)
+            Instruction::new(0x03, 0, 0, 0, 0),  // HALT
+            Instruction::new(0x60, 0, 0, 0, 0),  // PUSH_CTX (rule body)
+            Instruction::new(0x11, 0, 2, 0, 0),  // LOAD_NUM R0, 2
+            Instruction::new(0x05, 0, 0, 0, 0),  // LOOP_BEGIN R0
+            Instruction::new(0x70, 7, 0, 0, 0),  // THROW 7
+            Instruction::new(0x06, 0, 0, 0, 0),  // LOOP_END
+            Instruction::new(0x51, 0, 0, 0, 0),  // RETURN_RULE
+        ]);
+        vm.execute().unwrap();
+
+        // The handler ran at the depth the try block was entered at
+        assert_eq!(vm.get_register(2), Some(1));
+        assert_eq!(vm.registers.err, 7);
+        assert!(vm.call_stack.is_empty());
+        assert!(vm.loop_frames.is_empty());
+        assert_eq!(vm.contexts.len(), 1);
+        assert_eq!(vm.current_context, 0);
+        assert_eq!(vm.registers.ctx, vm.contexts[0].id);
+    }
+
     #[test]
     fn test_introspection_hooks() {
         let mut vm = VirtualMachine::new();
@@ -1256,11 +2426,31 @@ mod tests {
         let result = vm.execute();
         assert!(result.is_err());
         match result {
-            Err(VmError::ExecutionLimitExceeded) => {}, // Expected
-            _ => panic!("Expected ExecutionLimitExceeded error"),
+            Err(VmError::StepLimitExceeded) => {}, // Expected
+            _ => panic!("Expected StepLimitExceeded error"),
         }
     }
 
+    #[test]
+    fn test_vm_safety_rule_limit_exceeded() {
+        use vm_safety::step_limits::ExecutionLimits;
+
+        // Plenty of steps, but only 2 rule invocations
+        let mut config = VMConfig::new();
+        config.execution_limits = ExecutionLimits::new(1_000, 2, 10);
+        let mut vm = VirtualMachine::with_config(config);
+        vm.load_program(vec![
+            Instruction::new(0x50, 4, 0, 0, 0), // CALL_RULE 4
+            Instruction::new(0x50, 4, 0, 0, 0), // CALL_RULE 4
+            Instruction::new(0x50, 4, 0, 0, 0), // CALL_RULE 4 - exceeds the rule limit
+            Instruction::new(0x03, 0, 0, 0, 0), // HALT
+            Instruction::new(0x51, 0, 0, 0, 0), // RETURN_RULE
+        ]);
+
+        assert!(matches!(vm.execute(), Err(VmError::RuleLimitExceeded)));
+        assert_eq!(vm.step_limiter.counters.rule_invocation_count, 3);
+    }
+
     #[test]
     fn test_vm_safety_sandbox_policy() {
         use vm_safety::sandbox::SandboxPolicy;
@@ -1352,4 +2542,379 @@ mod tests {
         // Check that sandbox starts with no allowed functions
         assert_eq!(config.sandbox_policy.allowed_functions.len(), 0);
     }
+
+    #[test]
+    fn test_load_const_for_oversized_literal() {
+        use kern_bytecode::emitter::BytecodeEmitter;
+        use kern_bytecode::lir_builder::LirBuilder;
+        use kern_bytecode::register_allocator::LinearScanAllocator;
+
+        // 5_000_000_000 does not fit the 16-bit LOAD_NUM immediate
+        let mut builder = LirBuilder::new();
+        builder.load_num(5_000_000_000);
+        let lir_program = builder.build();
+        let allocation = LinearScanAllocator::new().allocate(&lir_program);
+        let mut emitter = BytecodeEmitter::new();
+        let program = emitter.emit_from_lir(&lir_program.instructions, &allocation);
+        assert_eq!(program[0].opcode, Opcode::LoadConst as u8);
+
+        let mut vm = VirtualMachine::new();
+        vm.constant_pool = emitter.constant_pool.clone();
+        let dest_reg = program[0].arg1 as usize;
+        vm.load_program(program);
+
+        assert!(vm.execute().is_ok());
+        assert_eq!(vm.get_register(dest_reg), Some(5_000_000_000));
+    }
+
+    #[test]
+    fn test_loaded_module_outputs_symbol_text() {
+        use crate::output::OutputEvent;
+        use kern_bytecode::lir_builder::LirBuilder;
+        use kern_bytecode::BytecodeCompiler;
+
+        let mut builder = LirBuilder::new();
+        builder.load_num(7_000_000_000); // Occupies the first pool slot
+        let status = builder.load_sym("approved");
+        builder.write_io("stdout", status);
+        let module = BytecodeCompiler::new().compile_lir(&builder.build());
+
+        // Round trip through the JSON the compiler CLI writes
+        let module: BytecodeModule =
+            serde_json::from_str(&serde_json::to_string(&module).unwrap()).unwrap();
+        assert!(module.constant_pool.contains(&Constant::Sym("approved".to_string())));
+
+        let mut config = VMConfig::new();
+        config.sandbox_policy.allow_io_channel("stdout");
+        let mut vm = VirtualMachine::with_config(config);
+        vm.load_module(module);
+        vm.execute().unwrap();
+
+        assert_eq!(vm.take_output(), vec![OutputEvent::Sym("approved".to_string())]);
+    }
+
+    #[test]
+    fn test_write_io_collects_output_events() {
+        use crate::output::OutputEvent;
+
+        let mut config = VMConfig::new();
+        config.sandbox_policy.allow_io_channel("stdout");
+        let mut vm = VirtualMachine::with_config(config);
+        vm.load_program(vec![
+            Instruction::new(0x11, 0, 3, 0, 0), // LOAD_NUM R0, 3
+            Instruction::new(0x82, 0, 0, 0, 0), // WRITE_IO R0
+            Instruction::new(0x11, 1, 9, 0, 0), // LOAD_NUM R1, 9
+            Instruction::new(0x82, 1, 0, 0, 0), // WRITE_IO R1
+        ]);
+        vm.execute().unwrap();
+
+        assert_eq!(vm.take_output(), vec![OutputEvent::Num(3), OutputEvent::Num(9)]);
+        assert!(vm.output_sink.is_empty());
+    }
+
+    #[test]
+    fn test_output_sink_keeps_most_recent_events() {
+        use crate::output::OutputEvent;
+
+        let mut config = VMConfig::new();
+        config.sandbox_policy.allow_io_channel("stdout");
+        config.max_output_events = 2;
+        let mut vm = VirtualMachine::with_config(config);
+        vm.load_program(
+            (1..=5)
+                .flat_map(|i| {
+                    [
+                        Instruction::new(0x11, 0, i, 0, 0), // LOAD_NUM R0, i
+                        Instruction::new(0x82, 0, 0, 0, 0), // WRITE_IO R0
+                    ]
+                })
+                .collect(),
+        );
+        vm.execute().unwrap();
+
+        assert_eq!(vm.take_output(), vec![OutputEvent::Num(4), OutputEvent::Num(5)]);
+    }
+
+    fn run_unknown_extern(policy: UnknownExternPolicy) -> (Result<(), VmError>, VirtualMachine) {
+        let mut config = VMConfig::new();
+        let mut sandbox = SandboxPolicy::new();
+        sandbox.allow_function("log_event");
+        config.sandbox_policy = sandbox;
+        config.unknown_extern_policy = policy;
+
+        let mut vm = VirtualMachine::with_config(config);
+        vm.function_names.insert(7, "log_event".to_string());
+        vm.load_program(vec![
+            Instruction::new(0x11, 0, 42, 0, 0), // LOAD_NUM R0, 42
+            Instruction::new(0x80, 7, 0, 0, 0),  // CALL_EXTERN fn 7 (not registered)
+            Instruction::new(0x11, 1, 1, 0, 0),  // LOAD_NUM R1, 1
+            Instruction::new(0x03, 0, 0, 0, 0),  // HALT
+        ]);
+        let result = vm.execute();
+        (result, vm)
+    }
+
+    #[test]
+    fn test_unknown_extern_policy_trap() {
+        let (result, vm) = run_unknown_extern(UnknownExternPolicy::Trap);
+        assert!(matches!(result, Err(VmError::UnknownExternalFunction(ref name)) if name == "log_event"));
+        assert_eq!(vm.get_register(1), Some(0));
+    }
+
+    #[test]
+    fn test_unknown_extern_policy_nop() {
+        let (result, vm) = run_unknown_extern(UnknownExternPolicy::Nop);
+        assert!(result.is_ok());
+        assert_eq!(vm.get_register(0), Some(42));
+        assert_eq!(vm.get_register(1), Some(1));
+    }
+
+    #[test]
+    fn test_extern_calls_are_sandboxed_by_declared_name() {
+        fn approve(vm: &mut VirtualMachine) -> Result<(), String> {
+            vm.registers.r[0] = 1;
+            Ok(())
+        }
+
+        let mut config = VMConfig::new();
+        let mut sandbox = SandboxPolicy::new();
+        sandbox.allow_function("approve_farmer");
+        config.sandbox_policy = sandbox;
+
+        let mut vm = VirtualMachine::with_config(config);
+        vm.add_external_function("approve_farmer", approve);
+        vm.load_function_names(&[
+            Symbol { id: 0, name: "farmer".to_string() },
+            Symbol { id: 1, name: "approve_farmer".to_string() },
+        ]);
+        vm.load_program(vec![
+            Instruction::new(0x80, 1, 0, 0, 0), // CALL_EXTERN approve_farmer
+            Instruction::new(0x80, 3, 0, 0, 0), // CALL_EXTERN 3 (no such symbol)
+        ]);
+
+        vm.step().unwrap();
+        assert_eq!(vm.get_register(0), Some(1));
+        assert_eq!(vm.security_context.sandbox.get_function_call_count("approve_farmer"), 1);
+        assert!(matches!(vm.step(), Err(VmError::UndefinedSymbol)));
+    }
+
+    #[test]
+    fn test_unknown_extern_policy_return_zero() {
+        let (result, vm) = run_unknown_extern(UnknownExternPolicy::ReturnZero);
+        assert!(result.is_ok());
+        assert_eq!(vm.get_register(0), Some(0));
+        assert_eq!(vm.get_register(1), Some(1));
+    }
+
+    #[test]
+    fn test_opcode_coverage_report() {
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::new(0x11, 0, 2, 0, 0), // LOAD_NUM R0, 2
+            Instruction::new(0x11, 1, 3, 0, 0), // LOAD_NUM R1, 3
+            Instruction::new(0x20, 2, 0, 1, 0), // ADD R2, R0, R1
+            Instruction::new(0x03, 0, 0, 0, 0), // HALT
+        ]);
+        assert!(vm.execute().is_ok());
+
+        let all_opcodes = vm.security_context.validator.allowed_opcodes.clone();
+        let (covered, uncovered) = vm.coverage_report(&all_opcodes);
+        assert_eq!(covered, vec![0x03, 0x11, 0x20]);
+        assert_eq!(uncovered.len(), all_opcodes.len() - 3);
+
+        let mut accumulator = HashSet::new();
+        vm.accumulate_coverage(&mut accumulator);
+        assert_eq!(accumulator.len(), 3);
+    }
+
+    #[test]
+    fn test_read_steps() {
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::new(0x11, 0, 1, 0, 0), // LOAD_NUM R0, 1
+            Instruction::new(0x11, 1, 2, 0, 0), // LOAD_NUM R1, 2
+            Instruction::new(0x00, 0, 0, 0, 0), // NOP
+            Instruction::new(0x16, 5, 0, 0, 0), // READ_STEPS R5
+            Instruction::new(0x03, 0, 0, 0, 0), // HALT
+        ]);
+        assert!(vm.execute().is_ok());
+
+        // Three steps completed before READ_STEPS ran
+        assert_eq!(vm.get_register(5), Some(3));
+    }
+
+    #[test]
+    fn test_export_and_import_state() {
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::new(0x11, 0, 7, 0, 0), // LOAD_NUM R0, 7
+            Instruction::new(0x11, 3, 9, 0, 0), // LOAD_NUM R3, 9
+            Instruction::new(0x03, 0, 0, 0, 0), // HALT
+        ]);
+        assert!(vm.execute().is_ok());
+        vm.contexts[0].variables.insert("count".to_string(), 4);
+        vm.contexts.push(VmContext::new(1));
+        vm.current_context = 1;
+
+        let json = vm.export_state();
+        let mut restored = VirtualMachine::new();
+        restored.import_state(&json).unwrap();
+
+        assert_eq!(restored.registers, vm.registers);
+        assert_eq!(restored.get_register(3), Some(9));
+        assert_eq!(restored.contexts, vm.contexts);
+        assert_eq!(restored.current_context, 1);
+
+        assert!(matches!(restored.import_state("{}"), Err(VmError::InvalidState(_))));
+    }
+
+    #[test]
+    fn test_reset_matches_fresh_vm_and_keeps_capacity() {
+        let program = vec![
+            Instruction::new(0x11, 1, 40, 0, 0), // LOAD_NUM R1, 40
+            Instruction::new(0x11, 2, 2, 0, 0),  // LOAD_NUM R2, 2
+            Instruction::new(0x20, 3, 1, 2, 0),  // ADD R3 = R1 + R2
+            Instruction::new(0x53, 7, 0, 0, 0),  // INCREMENT_EXEC_COUNT loop 7
+        ];
+
+        let mut reused = VirtualMachine::new();
+        reused.load_program(program.clone());
+        reused.execute().unwrap();
+        reused.contexts.push(VmContext::new(1));
+        let trace_capacity = reused.execution_trace.capacity();
+
+        reused.reset();
+        assert_eq!(reused.registers, VmRegisters::new());
+        assert_eq!(reused.contexts, vec![VmContext::new(0)]);
+        assert!(reused.execution_trace.is_empty());
+        assert!(reused.opcode_coverage.is_empty());
+        assert!(reused.execution_trace.capacity() >= trace_capacity);
+
+        reused.execute().unwrap();
+        let mut fresh = VirtualMachine::new();
+        fresh.load_program(program);
+        fresh.execute().unwrap();
+
+        assert_eq!(reused.registers, fresh.registers);
+        assert_eq!(reused.contexts, fresh.contexts);
+        assert_eq!(reused.exec_counts, fresh.exec_counts);
+        assert_eq!(reused.opcode_coverage, fresh.opcode_coverage);
+        assert_eq!(reused.execution_trace.len(), fresh.execution_trace.len());
+        assert_eq!(reused.step_limiter.counters.step_count, fresh.step_limiter.counters.step_count);
+    }
+
+    #[test]
+    fn test_out_of_range_register_is_rejected() {
+        // Register 20 in each register operand position of several opcodes
+        let programs = [
+            Instruction::new(0x11, 20, 5, 0, 0), // LOAD_NUM dst
+            Instruction::new(0x10, 1, 0, 20, 0), // LOAD_SYM dst
+            Instruction::new(0x13, 20, 1, 0, 0), // MOVE src
+            Instruction::new(0x13, 1, 20, 0, 0), // MOVE dst
+            Instruction::new(0x14, 1, 2, 20, 0), // COMPARE result
+            Instruction::new(0x20, 1, 2, 20, 0), // ADD rhs
+            Instruction::new(0x23, 1, 20, 2, 0), // DIV lhs
+            Instruction::new(0x32, 20, 1, 0, 0), // NOT dst
+            Instruction::new(0x82, 20, 0, 0, 0), // WRITE_IO src
+        ];
+        for instruction in programs {
+            let mut vm = VirtualMachine::new();
+            vm.load_program(vec![instruction.clone()]);
+            assert!(
+                matches!(vm.execute(), Err(VmError::InvalidRegister(20))),
+                "opcode 0x{:02X}",
+                instruction.opcode
+            );
+        }
+    }
+
+    #[test]
+    fn test_increment_exec_count_enforces_per_loop_limit() {
+        let mut vm = VirtualMachine::new();
+        vm.step_limiter.set_loop_limit(7, 5);
+        vm.load_program(vec![
+            Instruction::new(0x53, 7, 0, 0, 0), // INCREMENT_EXEC_COUNT loop 7
+            Instruction::new(0x53, 8, 0, 0, 0), // INCREMENT_EXEC_COUNT loop 8 (no own cap)
+            Instruction::new(0x01, 0, 0, 0, 0), // JMP 0
+        ]);
+
+        assert!(matches!(vm.execute(), Err(VmError::LoopLimitExceeded)));
+        // The sixth iteration of loop 7 trips its cap of 5
+        assert_eq!(vm.exec_counts.get(&7), Some(&6));
+        assert_eq!(vm.exec_counts.get(&8), Some(&5));
+        assert_eq!(vm.step_limiter.counters.loop_iteration_count, 11);
+    }
+
+    #[test]
+    fn test_read_steps_can_be_disallowed() {
+        let mut vm = VirtualMachine::new();
+        vm.security_context.validator.disallow_opcode(0x16);
+        vm.load_program(vec![Instruction::new(0x16, 5, 0, 0, 0)]);
+
+        assert!(matches!(vm.execute(), Err(VmError::SecurityError(_))));
+    }
+
+    #[test]
+    fn test_ext_prefix_loads_full_64_bit_value() {
+        let wide: u64 = 0x8123_4567_89AB_CDEF;
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::ext_prefix(wide),
+            Instruction::new(0x11, 2, wide as u16, 0, 0), // LOAD_NUM R2, low 16 bits
+            Instruction::new(0x16, 3, 0, 0, 0),           // READ_STEPS R3
+            Instruction::new(0x03, 0, 0, 0, 0),           // HALT
+        ]);
+        assert!(vm.execute().is_ok());
+
+        assert_eq!(vm.get_register(2), Some(wide as i64));
+        // The prefixed pair executes as a single step
+        assert_eq!(vm.get_register(3), Some(1));
+    }
+
+    #[test]
+    fn test_ext_prefix_rejected_on_unsupported_instruction() {
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::ext_prefix(1 << 40),
+            Instruction::new(0x20, 0, 1, 2, 0), // ADD
+        ]);
+
+        assert!(matches!(vm.execute(), Err(VmError::InvalidInstruction)));
+    }
+
+    fn counted_loop(count: u16) -> Vec<Instruction> {
+        vec![
+            Instruction::new(0x11, 1, count, 0, 0), // LOAD_NUM R1, count
+            Instruction::new(0x11, 2, 1, 0, 0),     // LOAD_NUM R2, 1
+            Instruction::new(0x05, 1, 0, 0, 0),     // LOOP_BEGIN R1
+            Instruction::new(0x20, 3, 3, 2, 0),     // ADD R3 = R3 + R2
+            Instruction::new(0x06, 0, 0, 0, 0),     // LOOP_END
+            Instruction::new(0x03, 0, 0, 0, 0),     // HALT
+        ]
+    }
+
+    #[test]
+    fn test_loop_runs_body_count_times() {
+        let mut vm = VirtualMachine::new();
+        vm.load_program(counted_loop(4));
+        assert!(vm.execute().is_ok());
+
+        assert_eq!(vm.get_register(3), Some(4));
+        assert!(vm.loop_frames.is_empty());
+        assert_eq!(vm.step_limiter.counters.loop_iteration_count, 4);
+    }
+
+    #[test]
+    fn test_loop_past_loop_limit_is_rejected() {
+        use vm_safety::step_limits::ExecutionLimits;
+
+        let mut config = VMConfig::new();
+        config.execution_limits = ExecutionLimits::new(1_000, 10, 5); // Only 5 loop iterations allowed
+        let mut vm = VirtualMachine::with_config(config);
+        vm.load_program(counted_loop(10));
+
+        assert!(matches!(vm.execute(), Err(VmError::LoopLimitExceeded)));
+        // The sixth LOOP_END tripped the limit
+        assert_eq!(vm.get_register(3), Some(6));
+    }
 }
@@ -0,0 +1,102 @@
+//! Output Rendering for KERN VM
+//!
+//! Separates what a program outputs (`OutputEvent`) from how it is presented,
+//! so hosts can choose human-readable text or machine-readable JSON lines.
+
+use kern_bytecode::Constant;
+
+/// A value written by the program via WRITE_IO
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputEvent {
+    Num(i64),
+    Bool(bool),
+    Sym(String),
+    Vec(Vec<Constant>),
+}
+
+impl OutputEvent {
+    pub fn from_constant(constant: &Constant) -> Self {
+        match constant {
+            Constant::Num(n) => OutputEvent::Num(*n),
+            Constant::Bool(b) => OutputEvent::Bool(*b),
+            Constant::Sym(s) => OutputEvent::Sym(s.clone()),
+            Constant::Vec(items) => OutputEvent::Vec(items.clone()),
+        }
+    }
+}
+
+/// Renders an output event as a single line (without the trailing newline)
+pub trait OutputFormatter: Send {
+    fn format(&self, event: &OutputEvent) -> String;
+}
+
+/// Default formatter: `Output: <value>`, with vectors as `[1, a]`
+pub struct HumanFormatter;
+
+impl OutputFormatter for HumanFormatter {
+    fn format(&self, event: &OutputEvent) -> String {
+        let value = match event {
+            OutputEvent::Num(n) => n.to_string(),
+            OutputEvent::Bool(b) => b.to_string(),
+            OutputEvent::Sym(s) => s.clone(),
+            OutputEvent::Vec(items) => human_constant(&Constant::Vec(items.clone())),
+        };
+        format!("Output: {}", value)
+    }
+}
+
+fn human_constant(constant: &Constant) -> String {
+    match constant {
+        Constant::Num(n) => n.to_string(),
+        Constant::Bool(b) => b.to_string(),
+        Constant::Sym(s) => s.clone(),
+        Constant::Vec(items) => {
+            let parts: Vec<String> = items.iter().map(human_constant).collect();
+            format!("[{}]", parts.join(", "))
+        }
+    }
+}
+
+/// JSON lines formatter: each event is one compact JSON value
+pub struct JsonFormatter;
+
+impl OutputFormatter for JsonFormatter {
+    fn format(&self, event: &OutputEvent) -> String {
+        let value = match event {
+            OutputEvent::Num(n) => serde_json::json!(n),
+            OutputEvent::Bool(b) => serde_json::json!(b),
+            OutputEvent::Sym(s) => serde_json::json!(s),
+            OutputEvent::Vec(items) => json_constant(&Constant::Vec(items.clone())),
+        };
+        value.to_string()
+    }
+}
+
+fn json_constant(constant: &Constant) -> serde_json::Value {
+    match constant {
+        Constant::Num(n) => serde_json::json!(n),
+        Constant::Bool(b) => serde_json::json!(b),
+        Constant::Sym(s) => serde_json::json!(s),
+        Constant::Vec(items) => serde_json::Value::Array(items.iter().map(json_constant).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formatters_render_vectors() {
+        let event = OutputEvent::Vec(vec![Constant::Num(1), Constant::Sym("a".to_string())]);
+
+        assert_eq!(HumanFormatter.format(&event), "Output: [1, a]");
+        assert_eq!(JsonFormatter.format(&event), r#"[1,"a"]"#);
+    }
+
+    #[test]
+    fn test_formatters_render_scalars() {
+        assert_eq!(HumanFormatter.format(&OutputEvent::Num(7)), "Output: 7");
+        assert_eq!(JsonFormatter.format(&OutputEvent::Sym("done".to_string())), r#""done""#);
+        assert_eq!(JsonFormatter.format(&OutputEvent::Bool(true)), "true");
+    }
+}
@@ -101,7 +101,7 @@ pub enum MemoryLimitType {
 }
 
 /// Memory region types
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum MemoryRegion {
     Code,
     Const,
@@ -115,6 +115,7 @@ pub enum MemoryRegion {
 pub struct MemoryManager {
     pub limits: MemoryLimits,
     pub usage: MemoryUsage,
+    pub peak_usage: MemoryUsage, // High-water mark of each region since the last reset
     pub region_usage: HashMap<MemoryRegion, usize>,
 }
 
@@ -130,6 +131,7 @@ impl MemoryManager {
         MemoryManager {
             limits,
             usage: MemoryUsage::new(),
+            peak_usage: MemoryUsage::new(),
             region_usage,
         }
     }
@@ -174,9 +176,29 @@ impl MemoryManager {
         }
 
         *current_usage = new_usage;
+        self.record_peak();
         Ok(())
     }
 
+    // Only allocation can raise usage, so only allocate needs to call this
+    fn record_peak(&mut self) {
+        let peak = &mut self.peak_usage;
+        peak.code_used = peak.code_used.max(self.usage.code_used);
+        peak.const_used = peak.const_used.max(self.usage.const_used);
+        peak.stack_used = peak.stack_used.max(self.usage.stack_used);
+        peak.heap_used = peak.heap_used.max(self.usage.heap_used);
+        peak.meta_used = peak.meta_used.max(self.usage.meta_used);
+    }
+
+    /// Zero all usage counters, keeping the limits
+    pub fn reset(&mut self) {
+        self.usage = MemoryUsage::new();
+        self.peak_usage = MemoryUsage::new();
+        for usage in self.region_usage.values_mut() {
+            *usage = 0;
+        }
+    }
+
     /// Deallocate memory in the specified region
     pub fn deallocate(&mut self, region: MemoryRegion, size: usize) {
         let current_usage = self.region_usage.get_mut(&region).unwrap();
@@ -263,6 +285,7 @@ mod tests {
         // Test successful deallocation
         manager.deallocate(MemoryRegion::Heap, 20);
         assert_eq!(manager.usage.heap_used, 30);
+        assert_eq!(manager.peak_usage.heap_used, 50);
     }
 
     #[test]
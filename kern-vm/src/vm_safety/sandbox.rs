@@ -2,7 +2,9 @@
 //! 
 //! Implements the sandboxed execution environment as specified in the safety layer.
 
-use std::collections::HashMap;
+use super::memory_limits::MemoryRegion;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
 /// Sandbox policy configuration structure
 #[derive(Debug, Clone)]
@@ -10,6 +12,8 @@ pub struct SandboxPolicy {
     pub allowed_functions: Vec<String>,
     pub allowed_io_channels: Vec<String>,
     pub max_calls_per_function: HashMap<String, u64>,
+    pub rate_limits: HashMap<String, (u64, Duration)>, // Most calls allowed within a trailing window
+    pub writable_regions: Vec<MemoryRegion>, // Regions the program may write; code and constants are read-only by default
 }
 
 impl SandboxPolicy {
@@ -18,6 +22,8 @@ impl SandboxPolicy {
             allowed_functions: Vec::new(),
             allowed_io_channels: Vec::new(),
             max_calls_per_function: HashMap::new(),
+            rate_limits: HashMap::new(),
+            writable_regions: vec![MemoryRegion::Stack, MemoryRegion::Heap, MemoryRegion::Meta],
         }
     }
 
@@ -40,6 +46,29 @@ impl SandboxPolicy {
         self.max_calls_per_function.insert(function_name.to_string(), max_calls);
     }
 
+    /// Allow at most `max_calls` calls to a function within any trailing `window`.
+    /// Independent of the total cap set by `set_max_calls_for_function`.
+    pub fn set_rate_limit_for_function(&mut self, function_name: &str, max_calls: u64, window: Duration) {
+        self.rate_limits.insert(function_name.to_string(), (max_calls, window));
+    }
+
+    /// Let the program write to a memory region
+    pub fn allow_write(&mut self, region: MemoryRegion) {
+        if !self.writable_regions.contains(&region) {
+            self.writable_regions.push(region);
+        }
+    }
+
+    /// Make a memory region read-only for the program
+    pub fn deny_write(&mut self, region: MemoryRegion) {
+        self.writable_regions.retain(|&r| r != region);
+    }
+
+    /// Check if the policy lets the program write to a memory region
+    pub fn is_write_allowed(&self, region: MemoryRegion) -> bool {
+        self.writable_regions.contains(&region)
+    }
+
     /// Check if a function is allowed by the policy
     pub fn is_function_allowed(&self, function_name: &str) -> bool {
         self.allowed_functions.contains(&function_name.to_string())
@@ -67,16 +96,49 @@ impl Default for SandboxPolicy {
     }
 }
 
+/// Time source for sandbox rate limits, replaceable so hosts and tests can
+/// control the clock
+pub trait SandboxClock: std::fmt::Debug + Send {
+    /// Time elapsed since a fixed, clock-specific origin
+    fn now(&self) -> Duration;
+}
+
+/// Monotonic wall clock measured from its creation
+#[derive(Debug)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock { origin: Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+impl SandboxClock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
 /// Function call tracker to enforce call limits
 #[derive(Debug, Clone)]
 pub struct FunctionCallTracker {
     pub call_counts: HashMap<String, u64>,
+    pub call_log: HashMap<String, VecDeque<Duration>>, // Times of accepted calls to rate-limited functions
 }
 
 impl FunctionCallTracker {
     pub fn new() -> Self {
         FunctionCallTracker {
             call_counts: HashMap::new(),
+            call_log: HashMap::new(),
         }
     }
 
@@ -99,6 +161,24 @@ impl FunctionCallTracker {
         Ok(())
     }
 
+    /// Check a call made at `now` against the function's rate limit, logging it
+    /// if accepted. Rejected calls are not logged, so they don't extend the throttle.
+    pub fn record_rate_limited_call(&mut self, function_name: &str, policy: &SandboxPolicy, now: Duration) -> Result<(), SandboxError> {
+        let Some(&(max_calls, window)) = policy.rate_limits.get(function_name) else {
+            return Ok(());
+        };
+
+        let log = self.call_log.entry(function_name.to_string()).or_default();
+        while log.front().is_some_and(|&time| now.saturating_sub(time) >= window) {
+            log.pop_front();
+        }
+        if log.len() as u64 >= max_calls {
+            return Err(SandboxError::RateLimitExceeded(function_name.to_string()));
+        }
+        log.push_back(now);
+        Ok(())
+    }
+
     /// Get the current call count for a function
     pub fn get_call_count(&self, function_name: &str) -> u64 {
         *self.call_counts.get(function_name).unwrap_or(&0)
@@ -144,6 +224,7 @@ pub struct SandboxEnvironment {
     pub policy: SandboxPolicy,
     pub function_tracker: FunctionCallTracker,
     pub io_tracker: IoOperationTracker,
+    pub clock: Box<dyn SandboxClock>, // Timestamps calls for rate limits
 }
 
 impl SandboxEnvironment {
@@ -152,12 +233,22 @@ impl SandboxEnvironment {
             policy,
             function_tracker: FunctionCallTracker::new(),
             io_tracker: IoOperationTracker::new(),
+            clock: Box::new(SystemClock::new()),
         }
     }
 
+    /// Clear call and IO counters, keeping the policy
+    pub fn reset(&mut self) {
+        self.function_tracker.call_counts.clear();
+        self.function_tracker.call_log.clear();
+        self.io_tracker.io_counts.clear();
+    }
+
     /// Execute an external function call within the sandbox
     pub fn execute_external_call(&mut self, function_name: &str) -> Result<(), SandboxError> {
-        self.function_tracker.record_call(function_name, &self.policy)
+        self.function_tracker.record_call(function_name, &self.policy)?;
+        let now = self.clock.now();
+        self.function_tracker.record_rate_limited_call(function_name, &self.policy, now)
     }
 
     /// Execute an IO operation within the sandbox
@@ -189,6 +280,7 @@ pub enum SandboxError {
     FunctionNotAllowed(String),
     IoChannelNotAllowed(String),
     CallLimitExceeded(String),
+    RateLimitExceeded(String),
 }
 
 impl std::fmt::Display for SandboxError {
@@ -197,6 +289,7 @@ impl std::fmt::Display for SandboxError {
             SandboxError::FunctionNotAllowed(name) => write!(f, "Function not allowed in sandbox: {}", name),
             SandboxError::IoChannelNotAllowed(name) => write!(f, "IO channel not allowed in sandbox: {}", name),
             SandboxError::CallLimitExceeded(name) => write!(f, "Call limit exceeded for function: {}", name),
+            SandboxError::RateLimitExceeded(name) => write!(f, "Rate limit exceeded for function: {}", name),
         }
     }
 }
@@ -224,6 +317,19 @@ mod tests {
         assert!(!policy.would_exceed_call_limit("print", 4));
     }
 
+    #[test]
+    fn test_write_permissions() {
+        let mut policy = SandboxPolicy::new();
+        assert!(!policy.is_write_allowed(MemoryRegion::Code));
+        assert!(!policy.is_write_allowed(MemoryRegion::Const));
+        assert!(policy.is_write_allowed(MemoryRegion::Meta));
+
+        policy.deny_write(MemoryRegion::Meta);
+        policy.allow_write(MemoryRegion::Const);
+        assert!(!policy.is_write_allowed(MemoryRegion::Meta));
+        assert!(policy.is_write_allowed(MemoryRegion::Const));
+    }
+
     #[test]
     fn test_function_call_tracker() {
         let mut policy = SandboxPolicy::new();
@@ -290,4 +396,43 @@ mod tests {
         assert_eq!(sandbox.get_function_call_count("print"), 3); // Includes the failed call
         assert_eq!(sandbox.get_io_operation_count("stdout"), 2);
     }
+
+    #[derive(Debug)]
+    struct MockClock(std::sync::Arc<std::sync::Mutex<Duration>>);
+
+    impl SandboxClock for MockClock {
+        fn now(&self) -> Duration {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_window() {
+        let mut policy = SandboxPolicy::new();
+        policy.allow_function("notify");
+        policy.set_rate_limit_for_function("notify", 2, Duration::from_secs(1));
+        policy.set_max_calls_for_function("notify", 6);
+
+        let time = std::sync::Arc::new(std::sync::Mutex::new(Duration::ZERO));
+        let mut sandbox = SandboxEnvironment::new(policy);
+        sandbox.clock = Box::new(MockClock(time.clone()));
+
+        // The window fills up
+        assert!(sandbox.execute_external_call("notify").is_ok());
+        *time.lock().unwrap() = Duration::from_millis(400);
+        assert!(sandbox.execute_external_call("notify").is_ok());
+        assert_eq!(sandbox.execute_external_call("notify"),
+                   Err(SandboxError::RateLimitExceeded("notify".to_string())));
+
+        // Once the first call leaves the trailing window there is room again
+        *time.lock().unwrap() = Duration::from_millis(1000);
+        assert!(sandbox.execute_external_call("notify").is_ok());
+        assert_eq!(sandbox.execute_external_call("notify"),
+                   Err(SandboxError::RateLimitExceeded("notify".to_string())));
+
+        // The total cap, which counts rejected calls too, still applies on its own
+        *time.lock().unwrap() = Duration::from_secs(10);
+        assert_eq!(sandbox.execute_external_call("notify"),
+                   Err(SandboxError::CallLimitExceeded("notify".to_string())));
+    }
 }
\ No newline at end of file
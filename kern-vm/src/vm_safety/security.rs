@@ -49,8 +49,10 @@ impl SecurityValidator {
             allow_dynamic_dispatch: false,
             allow_runtime_code_loading: false,
             allowed_opcodes: vec![
-                0x00, 0x01, 0x02, 0x03,  // Control Flow: NOP, JMP, JMP_IF, HALT
-                0x10, 0x11, 0x12, 0x13, 0x14,  // Data & Symbol: LOAD_SYM, LOAD_NUM, LOAD_BOOL, MOVE, COMPARE
+                0x00, 0x01, 0x02, 0x03, 0x04, // Control Flow: NOP, JMP, JMP_IF, HALT, EXT
+                0x05, 0x06,                    // Loops: LOOP_BEGIN, LOOP_END
+                0x10, 0x11, 0x12, 0x13, 0x14, 0x15, // Data & Symbol: LOAD_SYM, LOAD_NUM, LOAD_BOOL, MOVE, COMPARE, LOAD_CONST
+                0x16,                          // Introspection: READ_STEPS
                 0x20, 0x21, 0x22, 0x23, 0x24,  // Arithmetic: ADD, SUB, MUL, DIV, MOD
                 0x30, 0x31, 0x32,              // Logical: AND, OR, NOT
                 0x40, 0x41, 0x42, 0x43,        // Graph: CREATE_NODE, CONNECT, MERGE, DELETE_NODE
@@ -88,6 +90,11 @@ impl SecurityValidator {
         Ok(())
     }
 
+    /// Remove an opcode from the allowed set so programs using it are rejected
+    pub fn disallow_opcode(&mut self, opcode: u8) {
+        self.allowed_opcodes.retain(|&op| op != opcode);
+    }
+
     /// Validate bytecode module for security compliance
     pub fn validate_bytecode(&self, instructions: &[Instruction]) -> Result<(), SecurityError> {
         for instruction in instructions {
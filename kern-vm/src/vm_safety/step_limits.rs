@@ -2,6 +2,8 @@
 //! 
 //! Implements the execution step counting and limiting system as specified in the safety layer.
 
+use std::collections::HashMap;
+
 /// Execution limits configuration structure
 #[derive(Debug, Clone)]
 pub struct ExecutionLimits {
@@ -113,6 +115,7 @@ impl std::error::Error for StepLimitError {}
 pub struct StepLimiter {
     pub limits: ExecutionLimits,
     pub counters: ExecutionCounters,
+    pub loop_limits: HashMap<u32, u64>, // Per-loop iteration caps, keyed by loop id
 }
 
 impl StepLimiter {
@@ -120,6 +123,7 @@ impl StepLimiter {
         StepLimiter {
             limits,
             counters: ExecutionCounters::new(),
+            loop_limits: HashMap::new(),
         }
     }
 
@@ -143,6 +147,20 @@ impl StepLimiter {
         self.counters.exceeds_limit(&self.limits)
     }
 
+    /// Cap the iterations of one loop, independently of max_loop_iterations
+    pub fn set_loop_limit(&mut self, loop_id: u32, max_iterations: u64) {
+        self.loop_limits.insert(loop_id, max_iterations);
+    }
+
+    /// Check a loop's iteration count against its own cap (or max_loop_iterations if it has none)
+    pub fn check_loop_count(&self, loop_id: u32, count: u64) -> Result<(), StepLimitError> {
+        let limit = self.loop_limits.get(&loop_id).copied().unwrap_or(self.limits.max_loop_iterations);
+        if count > limit {
+            return Err(StepLimitError::LoopLimitExceeded);
+        }
+        Ok(())
+    }
+
     /// Reset all counters to zero
     pub fn reset(&mut self) {
         self.counters = ExecutionCounters::new();
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
 pub mod operator_engine;
@@ -7,7 +7,7 @@ pub mod language_mappings;
 pub mod multimodal_operators;
 
 // PSI Operator - the smallest unit of intelligence
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PSI_Operator {
     pub id: u16,
     pub name: String,
@@ -16,12 +16,16 @@ pub struct PSI_Operator {
     pub arity_in: u8,         // number of input parameters
     pub arity_out: u8,        // number of output parameters
     pub cost_hint: u16,       // estimated computational cost
+    #[serde(default)]
+    pub inputs: Vec<String>,  // names of the values this operator consumes
+    #[serde(default)]
+    pub outputs: Vec<String>, // names of the values this operator produces
     pub kern_template: String, // KERN source code template
     pub emissions: HashMap<String, String>, // language-specific templates (e.g., "rust", "python")
 }
 
 // PSI Meta-Program - compressed cognitive strategies
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PSI_MetaProgram {
     pub id: u16,
     pub name: String,
@@ -31,7 +35,7 @@ pub struct PSI_MetaProgram {
 }
 
 // PSI Heuristic - decision weighting
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PSI_Heuristic {
     pub id: u16,
     pub name: String,
@@ -41,7 +45,7 @@ pub struct PSI_Heuristic {
 }
 
 // PSI Language Mapping - abstract logic to syntax
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PSI_LanguageMap {
     pub language_id: String,
     pub abstract_operator_id: String,
@@ -49,7 +53,7 @@ pub struct PSI_LanguageMap {
 }
 
 // PSI Execution Graph
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PSI_Graph {
     pub id: u32,
     pub name: String,
@@ -57,7 +61,7 @@ pub struct PSI_Graph {
     pub edges: Vec<PSI_GraphEdge>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PSI_GraphNode {
     pub id: u32,
     pub operator_name: String,
@@ -65,7 +69,7 @@ pub struct PSI_GraphNode {
     pub outputs: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PSI_GraphEdge {
     pub from: u32,
     pub to: u32,
@@ -73,7 +77,7 @@ pub struct PSI_GraphEdge {
 }
 
 // PSI Context - isolated reasoning session
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PSI_Context {
     pub id: u32,
     pub name: String,
@@ -82,8 +86,78 @@ pub struct PSI_Context {
     pub constraints: Vec<String>,
 }
 
+// A set of operators whose inputs and outputs depend on each other in a loop
+#[derive(Debug, Clone, PartialEq)]
+pub struct CycleError {
+    pub operators: Vec<String>, // operators that could not be ordered
+}
+
+impl std::fmt::Display for CycleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cyclic operator dependency among: {}", self.operators.join(", "))
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+// A reference or identifier that doesn't hold up across the brain's tables
+#[derive(Debug, Clone, PartialEq)]
+pub enum BrainValidationError {
+    UnknownMetaProgramOperator { meta_program: String, operator: String },
+    UnknownPreferredOperator { heuristic: String, operator: String },
+    DuplicateOperatorName(String),
+    DuplicateOperatorId(u16),
+    DuplicateMetaProgramId(u16),
+    DuplicateHeuristicId(u16),
+}
+
+impl std::fmt::Display for BrainValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BrainValidationError::UnknownMetaProgramOperator { meta_program, operator } => {
+                write!(f, "Meta-program {} references unknown operator {}", meta_program, operator)
+            }
+            BrainValidationError::UnknownPreferredOperator { heuristic, operator } => {
+                write!(f, "Heuristic {} prefers unknown operator {}", heuristic, operator)
+            }
+            BrainValidationError::DuplicateOperatorName(name) => write!(f, "Duplicate operator name: {}", name),
+            BrainValidationError::DuplicateOperatorId(id) => write!(f, "Duplicate operator id: {}", id),
+            BrainValidationError::DuplicateMetaProgramId(id) => write!(f, "Duplicate meta-program id: {}", id),
+            BrainValidationError::DuplicateHeuristicId(id) => write!(f, "Duplicate heuristic id: {}", id),
+        }
+    }
+}
+
+impl std::error::Error for BrainValidationError {}
+
+// Why a graph could not be emitted as source
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmitError {
+    UnknownNode(u32),                                     // an edge endpoint the graph doesn't contain
+    UnknownOperator { node: u32, operator: String },
+    MissingEmission { operator: String, template: String }, // template is the language or mapped template id
+    Cycle(Vec<u32>),                                      // nodes that could not be ordered
+}
+
+impl std::fmt::Display for EmitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmitError::UnknownNode(id) => write!(f, "Edge refers to unknown node {}", id),
+            EmitError::UnknownOperator { node, operator } => {
+                write!(f, "Node {} uses unknown operator {}", node, operator)
+            }
+            EmitError::MissingEmission { operator, template } => {
+                write!(f, "Operator {} has no {} emission", operator, template)
+            }
+            EmitError::Cycle(nodes) => write!(f, "Graph has a cycle among nodes {:?}", nodes),
+        }
+    }
+}
+
+impl std::error::Error for EmitError {}
+
 // Main PSI Brain structure
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct PSI_Brain {
     pub name: String,
     pub version: String,
@@ -111,16 +185,30 @@ impl PSI_Brain {
         }
     }
 
-    pub fn add_operator(&mut self, operator: PSI_Operator) {
+    /// Adds `operator` and returns its id. An id of 0 is replaced by the lowest free one;
+    /// any other id is kept, but must not already be in use.
+    pub fn add_operator(&mut self, mut operator: PSI_Operator) -> Result<u16, BrainValidationError> {
+        let taken = self.operators.iter().map(|op| op.id).collect();
+        operator.id = assign_id(operator.id, &taken).ok_or(BrainValidationError::DuplicateOperatorId(operator.id))?;
         self.operators.push(operator);
+        Ok(self.operators.last().unwrap().id)
     }
 
-    pub fn add_meta_program(&mut self, meta_program: PSI_MetaProgram) {
+    /// Adds `meta_program` and returns its id, assigned as in `add_operator`
+    pub fn add_meta_program(&mut self, mut meta_program: PSI_MetaProgram) -> Result<u16, BrainValidationError> {
+        let taken = self.meta_programs.iter().map(|mp| mp.id).collect();
+        meta_program.id = assign_id(meta_program.id, &taken)
+            .ok_or(BrainValidationError::DuplicateMetaProgramId(meta_program.id))?;
         self.meta_programs.push(meta_program);
+        Ok(self.meta_programs.last().unwrap().id)
     }
 
-    pub fn add_heuristic(&mut self, heuristic: PSI_Heuristic) {
+    /// Adds `heuristic` and returns its id, assigned as in `add_operator`
+    pub fn add_heuristic(&mut self, mut heuristic: PSI_Heuristic) -> Result<u16, BrainValidationError> {
+        let taken = self.heuristics.iter().map(|h| h.id).collect();
+        heuristic.id = assign_id(heuristic.id, &taken).ok_or(BrainValidationError::DuplicateHeuristicId(heuristic.id))?;
         self.heuristics.push(heuristic);
+        Ok(self.heuristics.last().unwrap().id)
     }
 
     pub fn add_language_map(&mut self, language_map: PSI_LanguageMap) {
@@ -135,6 +223,165 @@ impl PSI_Brain {
         self.contexts.push(context);
     }
 
+    /// Checks that operator names and ids are unique and that meta-programs and
+    /// heuristics only refer to operators the brain defines
+    pub fn validate(&self) -> Result<(), Vec<BrainValidationError>> {
+        let mut errors = Vec::new();
+
+        let mut names = HashSet::new();
+        let mut ids = HashSet::new();
+        for op in &self.operators {
+            if !names.insert(op.name.as_str()) {
+                errors.push(BrainValidationError::DuplicateOperatorName(op.name.clone()));
+            }
+            if !ids.insert(op.id) {
+                errors.push(BrainValidationError::DuplicateOperatorId(op.id));
+            }
+        }
+
+        let mut meta_program_ids = HashSet::new();
+        for mp in self.meta_programs.iter().filter(|mp| !meta_program_ids.insert(mp.id)) {
+            errors.push(BrainValidationError::DuplicateMetaProgramId(mp.id));
+        }
+        let mut heuristic_ids = HashSet::new();
+        for heuristic in self.heuristics.iter().filter(|h| !heuristic_ids.insert(h.id)) {
+            errors.push(BrainValidationError::DuplicateHeuristicId(heuristic.id));
+        }
+
+        for mp in &self.meta_programs {
+            for operator in mp.operators.iter().filter(|name| !names.contains(name.as_str())) {
+                errors.push(BrainValidationError::UnknownMetaProgramOperator {
+                    meta_program: mp.name.clone(),
+                    operator: operator.clone(),
+                });
+            }
+        }
+        for heuristic in &self.heuristics {
+            for operator in heuristic.preferred_ops.iter().filter(|name| !names.contains(name.as_str())) {
+                errors.push(BrainValidationError::UnknownPreferredOperator {
+                    heuristic: heuristic.name.clone(),
+                    operator: operator.clone(),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Picks the meta-program favoured by the heuristics `command` triggers.
+    ///
+    /// A heuristic is triggered when the command mentions its name (without a
+    /// `_heuristic` suffix) or one of its preferred operators. Each meta-program scores
+    /// the triggering heuristic's weight for every preferred operator it uses; the first
+    /// highest scorer wins, and nothing is selected when no meta-program scores.
+    pub fn select_metaprogram_weighted(&self, command: &str) -> Option<&PSI_MetaProgram> {
+        let command = command.to_lowercase();
+        let triggered: Vec<&PSI_Heuristic> = self
+            .heuristics
+            .iter()
+            .filter(|h| {
+                let stem = h.name.strip_suffix("_heuristic").unwrap_or(&h.name).to_lowercase();
+                command.contains(&stem) || h.preferred_ops.iter().any(|op| command.contains(&op.to_lowercase()))
+            })
+            .collect();
+
+        let mut best: Option<(&PSI_MetaProgram, u32)> = None;
+        for mp in &self.meta_programs {
+            let score: u32 = triggered
+                .iter()
+                .map(|h| h.weight as u32 * mp.operators.iter().filter(|op| h.preferred_ops.contains(op)).count() as u32)
+                .sum();
+            if score > 0 && best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((mp, score));
+            }
+        }
+        best.map(|(mp, _)| mp)
+    }
+
+    /// Orders `operator_names` so every operator runs after the operators producing its inputs.
+    ///
+    /// Operators with no data dependency between them keep their list order. Names the
+    /// brain doesn't define have no declared inputs or outputs.
+    pub fn topological_operator_order(&self, operator_names: &[String]) -> Result<Vec<String>, CycleError> {
+        let declared: Vec<Option<&PSI_Operator>> = operator_names
+            .iter()
+            .map(|name| self.operators.iter().find(|op| &op.name == name))
+            .collect();
+        let depends_on = |consumer: usize, producer: usize| match (declared[consumer], declared[producer]) {
+            (Some(c), Some(p)) if consumer != producer => c.inputs.iter().any(|input| p.outputs.contains(input)),
+            _ => false,
+        };
+
+        let count = operator_names.len();
+        let mut placed = vec![false; count];
+        let mut order = Vec::with_capacity(count);
+        while order.len() < count {
+            let ready = (0..count)
+                .find(|&i| !placed[i] && (0..count).all(|j| placed[j] || !depends_on(i, j)));
+            match ready {
+                Some(i) => {
+                    placed[i] = true;
+                    order.push(operator_names[i].clone());
+                }
+                None => {
+                    return Err(CycleError {
+                        operators: (0..count).filter(|&i| !placed[i]).map(|i| operator_names[i].clone()).collect(),
+                    });
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    /// Emits `graph` as `language` source, one operator template per node in edge order.
+    ///
+    /// A language map for the operator and language selects which emission template is
+    /// used; otherwise it is the one keyed by `language`.
+    pub fn emit(&self, graph: &PSI_Graph, language: &str) -> Result<String, EmitError> {
+        let position = |id: u32| graph.nodes.iter().position(|node| node.id == id).ok_or(EmitError::UnknownNode(id));
+        let mut incoming = vec![0usize; graph.nodes.len()];
+        for edge in &graph.edges {
+            position(edge.from)?;
+            incoming[position(edge.to)?] += 1;
+        }
+
+        let mut placed = vec![false; graph.nodes.len()];
+        let mut sections = Vec::with_capacity(graph.nodes.len());
+        while sections.len() < graph.nodes.len() {
+            let Some(next) = (0..graph.nodes.len()).find(|&i| !placed[i] && incoming[i] == 0) else {
+                let remaining = graph.nodes.iter().zip(&placed).filter(|(_, &done)| !done).map(|(node, _)| node.id);
+                return Err(EmitError::Cycle(remaining.collect()));
+            };
+            placed[next] = true;
+            let node = &graph.nodes[next];
+            for edge in graph.edges.iter().filter(|edge| edge.from == node.id) {
+                incoming[position(edge.to)?] -= 1;
+            }
+
+            let operator = self
+                .operators
+                .iter()
+                .find(|op| op.name == node.operator_name)
+                .ok_or_else(|| EmitError::UnknownOperator { node: node.id, operator: node.operator_name.clone() })?;
+            let template = self
+                .language_maps
+                .iter()
+                .find(|map| map.language_id == language && map.abstract_operator_id == operator.name)
+                .map_or(language, |map| map.emission_template_id.as_str());
+            let emission = operator.emissions.get(template).ok_or_else(|| EmitError::MissingEmission {
+                operator: operator.name.clone(),
+                template: template.to_string(),
+            })?;
+            sections.push(emission.as_str());
+        }
+        Ok(sections.join("\n\n"))
+    }
+
+    /// JSON encoded as UTF-8 bytes; see `serialize_to_bincode` for the compact format
     pub fn serialize_to_binary(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
         let json_string = serde_json::to_string(self)?;
         Ok(json_string.into_bytes())
@@ -146,17 +393,59 @@ impl PSI_Brain {
         Ok(brain)
     }
 
+    pub fn serialize_to_bincode(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    pub fn deserialize_from_bincode(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(bincode::deserialize(data)?)
+    }
+
+    /// Writes bincode for `.psi` paths and pretty-printed JSON otherwise
     pub fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if is_bincode_path(path) {
+            std::fs::write(path, self.serialize_to_bincode()?)?;
+            return Ok(());
+        }
         let json_string = serde_json::to_string_pretty(self)?;
         std::fs::write(path, json_string)?;
         Ok(())
     }
 
+    /// Reads bincode for `.psi` paths and JSON otherwise
     pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if is_bincode_path(path) {
+            return Self::deserialize_from_bincode(&std::fs::read(path)?);
+        }
         let contents = std::fs::read_to_string(path)?;
         let brain: PSI_Brain = serde_json::from_str(&contents)?;
         Ok(brain)
     }
+
+    /// `load_from_file` followed by `validate`, reporting every validation error
+    pub fn load_from_file_validated(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let brain = Self::load_from_file(path)?;
+        if let Err(errors) = brain.validate() {
+            let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            return Err(format!("Invalid brain {}: {}", path, messages.join("; ")).into());
+        }
+        Ok(brain)
+    }
+}
+
+// Keeps a non-zero `id` that isn't taken, otherwise picks the lowest free one.
+// None means `id` is taken, or for 0 that every id is.
+fn assign_id(id: u16, taken: &HashSet<u16>) -> Option<u16> {
+    if id != 0 {
+        return (!taken.contains(&id)).then_some(id);
+    }
+    (1..=u16::MAX).find(|candidate| !taken.contains(candidate))
+}
+
+fn is_bincode_path(path: &str) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("psi"))
 }
 
 // Helper functions for creating common operators
@@ -176,6 +465,8 @@ impl PSI_Operator {
             arity_in: 1,
             arity_out: 1,
             cost_hint: 10,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
             kern_template: kern_template.to_string(),
             emissions,
         }
@@ -195,7 +486,7 @@ mod tests {
             "TestOperator", 
             "rule TestOperator: if 1 == 1 then log(\"Test\")"
         );
-        brain.add_operator(op);
+        brain.add_operator(op).unwrap();
         
         // Add a meta-program
         let mp = PSI_MetaProgram {
@@ -205,7 +496,7 @@ mod tests {
             domain: "testing".to_string(),
             adaptability: 50,
         };
-        brain.add_meta_program(mp);
+        brain.add_meta_program(mp).unwrap();
         
         assert_eq!(brain.operators.len(), 1);
         assert_eq!(brain.meta_programs.len(), 1);
@@ -218,7 +509,7 @@ mod tests {
             "SerializationTest", 
             "rule SerializationTest: if 1 == 1 then log(\"Serialization\")"
         );
-        brain.add_operator(op);
+        brain.add_operator(op).unwrap();
         
         // Test binary serialization
         let binary = brain.serialize_to_binary().unwrap();
@@ -227,4 +518,187 @@ mod tests {
         assert_eq!(deserialized.name, "serialization-test");
         assert_eq!(deserialized.operators.len(), 1);
     }
+
+    #[test]
+    fn test_validate_reports_dangling_operator_reference() {
+        let mut brain = PSI_Brain::new("validation-test");
+        brain.add_operator(PSI_Operator::new_simple("Known", "rule Known: if 1 == 1 then log(\"Known\")")).unwrap();
+        brain.add_meta_program(PSI_MetaProgram {
+            id: 1,
+            name: "Plan".to_string(),
+            operators: vec!["Known".to_string(), "Missing".to_string()],
+            domain: "testing".to_string(),
+            adaptability: 50,
+        }).unwrap();
+        brain.add_heuristic(PSI_Heuristic {
+            id: 1,
+            name: "Prefer".to_string(),
+            trigger_type: "request".to_string(),
+            weight: 5,
+            preferred_ops: vec!["Known".to_string()],
+        }).unwrap();
+
+        assert_eq!(
+            brain.validate().unwrap_err(),
+            vec![BrainValidationError::UnknownMetaProgramOperator {
+                meta_program: "Plan".to_string(),
+                operator: "Missing".to_string(),
+            }]
+        );
+
+        brain.meta_programs[0].operators.pop();
+        assert_eq!(brain.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_reports_duplicate_operator_id() {
+        let mut brain = PSI_Brain::new("validation-test");
+        let mut first = PSI_Operator::new_simple("First", "rule First: if 1 == 1 then log(\"First\")");
+        first.id = 7;
+        let mut second = PSI_Operator::new_simple("Second", "rule Second: if 1 == 1 then log(\"Second\")");
+        second.id = 7;
+        // add_operator refuses the clash, but a hand-edited brain file can still contain one
+        brain.add_operator(first).unwrap();
+        assert_eq!(
+            brain.add_operator(second.clone()).unwrap_err(),
+            BrainValidationError::DuplicateOperatorId(7)
+        );
+        brain.operators.push(second);
+
+        assert_eq!(brain.validate().unwrap_err(), vec![BrainValidationError::DuplicateOperatorId(7)]);
+
+        let path = std::env::temp_dir().join(format!("psi-validate-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        brain.save_to_file(path).unwrap();
+        assert!(PSI_Brain::load_from_file(path).is_ok());
+        let error = PSI_Brain::load_from_file_validated(path).unwrap_err();
+        assert!(error.to_string().contains("Duplicate operator id: 7"));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_add_assigns_unique_ids() {
+        let mut brain = PSI_Brain::new("id-test");
+        let ids: Vec<u16> = ["First", "Second", "Third"]
+            .iter()
+            .map(|name| {
+                let template = format!("rule {}: if 1 == 1 then log(\"{}\")", name, name);
+                brain.add_operator(PSI_Operator::new_simple(name, &template)).unwrap()
+            })
+            .collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        let stored: Vec<u16> = brain.operators.iter().map(|op| op.id).collect();
+        assert_eq!(stored, ids);
+
+        // An explicit id is kept, and later assignments skip it
+        let mut explicit = PSI_Operator::new_simple("Explicit", "rule Explicit: if 1 == 1 then log(\"Explicit\")");
+        explicit.id = 5;
+        assert_eq!(brain.add_operator(explicit), Ok(5));
+        let mut clash = PSI_Operator::new_simple("Clash", "rule Clash: if 1 == 1 then log(\"Clash\")");
+        clash.id = 2;
+        assert_eq!(brain.add_operator(clash), Err(BrainValidationError::DuplicateOperatorId(2)));
+        assert_eq!(brain.operators.len(), 4);
+        assert_eq!(brain.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_emit_graph_in_edge_order() {
+        let mut brain = PSI_Brain::new("emit-test");
+        for name in ["Define", "Use"] {
+            let mut op = PSI_Operator::new_simple(name, &format!("rule {}: if 1 == 1 then log(\"{}\")", name, name));
+            op.emissions = HashMap::from([("rust".to_string(), format!("// {} in Rust", name))]);
+            brain.add_operator(op).unwrap();
+        }
+        let node = |id: u32, operator: &str| PSI_GraphNode {
+            id,
+            operator_name: operator.to_string(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        };
+        // Listed out of order; the edge puts Define first
+        let graph = PSI_Graph {
+            id: 1,
+            name: "pipeline".to_string(),
+            nodes: vec![node(2, "Use"), node(1, "Define")],
+            edges: vec![PSI_GraphEdge { from: 1, to: 2, edge_type: "data".to_string() }],
+        };
+
+        assert_eq!(brain.emit(&graph, "rust").unwrap(), "// Define in Rust\n\n// Use in Rust");
+        assert_eq!(
+            brain.emit(&graph, "cobol").unwrap_err(),
+            EmitError::MissingEmission { operator: "Define".to_string(), template: "cobol".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_heuristic_weights_pick_metaprogram() {
+        let mut brain = PSI_Brain::new("selection-test");
+        let program = |name: &str, operators: &[&str]| PSI_MetaProgram {
+            id: 0,
+            name: name.to_string(),
+            operators: operators.iter().map(|op| op.to_string()).collect(),
+            domain: "testing".to_string(),
+            adaptability: 50,
+        };
+        brain.add_meta_program(program("Rewrite", &["Parse", "Rewrite", "Check"])).unwrap();
+        brain.add_meta_program(program("Patch", &["Parse", "Patch"])).unwrap();
+        let heuristic = |name: &str, weight: u8, preferred_ops: &[&str]| PSI_Heuristic {
+            id: 0,
+            name: name.to_string(),
+            trigger_type: "request".to_string(),
+            weight,
+            preferred_ops: preferred_ops.iter().map(|op| op.to_string()).collect(),
+        };
+        brain.add_heuristic(heuristic("rewrite_heuristic", 10, &["Rewrite", "Check"])).unwrap();
+        brain.add_heuristic(heuristic("patch_heuristic", 50, &["Patch"])).unwrap();
+        brain.add_heuristic(heuristic("verify_heuristic", 100, &["Check"])).unwrap();
+
+        // Both heuristics trigger; Rewrite scores 2 * 10, Patch scores 1 * 50
+        let command = "rewrite or patch the parser";
+        assert_eq!(brain.select_metaprogram_weighted(command).unwrap().name, "Patch");
+
+        brain.heuristics[0].weight = 30;
+        assert_eq!(brain.select_metaprogram_weighted(command).unwrap().name, "Rewrite");
+
+        assert!(brain.select_metaprogram_weighted("nothing relevant").is_none());
+    }
+
+    #[test]
+    fn test_bincode_round_trip() {
+        let mut brain = PSI_Brain::new("bincode-test");
+        for i in 0..20 {
+            let mut op = PSI_Operator::new_simple(
+                &format!("Operator{}", i),
+                &format!("rule Operator{}: if 1 == 1 then log(\"{}\")", i, i)
+            );
+            op.outputs = vec![format!("value{}", i)];
+            brain.add_operator(op).unwrap();
+        }
+        brain.add_meta_program(PSI_MetaProgram {
+            id: 1,
+            name: "Everything".to_string(),
+            operators: brain.operators.iter().map(|op| op.name.clone()).collect(),
+            domain: "testing".to_string(),
+            adaptability: 10,
+        }).unwrap();
+
+        let binary = brain.serialize_to_bincode().unwrap();
+        let deserialized = PSI_Brain::deserialize_from_bincode(&binary).unwrap();
+        assert_eq!(deserialized, brain);
+        assert!(binary.len() < serde_json::to_vec(&brain).unwrap().len());
+
+        // The extension picks the format
+        let dir = std::env::temp_dir();
+        let psi_path = dir.join(format!("psi-bincode-{}.psi", std::process::id()));
+        let json_path = psi_path.with_extension("json");
+        for path in [&psi_path, &json_path] {
+            let path = path.to_str().unwrap();
+            brain.save_to_file(path).unwrap();
+            assert_eq!(PSI_Brain::load_from_file(path).unwrap(), brain);
+        }
+        assert_eq!(std::fs::read(&psi_path).unwrap(), binary);
+        assert!(serde_json::from_slice::<serde_json::Value>(&std::fs::read(&json_path).unwrap()).is_ok());
+        let _ = std::fs::remove_file(&psi_path);
+        let _ = std::fs::remove_file(&json_path);
+    }
 }
\ No newline at end of file
@@ -6,7 +6,7 @@ use std::fs;
 use std::io::{self, Write};
 use std::process;
 
-use psi::{PSI_Brain, operator_engine::{OperatorEngine, OperatorExecutionContext, common_operators}, meta_programs::{select_metaprogram, create_generate_module_metaprogram, create_refactor_code_metaprogram, create_debug_issue_metaprogram, create_translate_code_metaprogram, create_explain_code_metaprogram, create_default_heuristics}, multimodal_operators::{multimodal_operators, MultiModalOperator}};
+use psi::{PSI_Brain, operator_engine::{ChainProgress, OperatorEngine, OperatorExecutionContext, common_operators}, meta_programs::{select_metaprogram, create_generate_module_metaprogram, create_refactor_code_metaprogram, create_debug_issue_metaprogram, create_translate_code_metaprogram, create_explain_code_metaprogram, create_default_heuristics}, multimodal_operators::{multimodal_operators, MultiModalOperator}};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "PSI CLI - Full Implementation", long_about = None)]
@@ -23,6 +23,10 @@ struct Args {
     #[arg(short, long)]
     load: Option<String>,
 
+    /// Reject a loaded brain with dangling operator references or duplicate operators
+    #[arg(long)]
+    validate: bool,
+
     /// Set default language for code generation
     #[arg(long, default_value = "rust")]
     language: String,
@@ -31,6 +35,10 @@ struct Args {
     #[arg(long)]
     debug: bool,
 
+    /// Print progress as each operator in a chain starts and finishes
+    #[arg(long)]
+    stream: bool,
+
     /// Enable explanations
     #[arg(long)]
     explain: bool,
@@ -73,7 +81,12 @@ fn main() {
 
     // Load brain if specified
     let mut brain = if let Some(brain_file) = &args.load {
-        match PSI_Brain::load_from_file(brain_file) {
+        let loaded = if args.validate {
+            PSI_Brain::load_from_file_validated(brain_file)
+        } else {
+            PSI_Brain::load_from_file(brain_file)
+        };
+        match loaded {
             Ok(b) => {
                 println!("Loaded PSI brain: {} ({} operators, {} meta-programs)", 
                     b.name, b.operators.len(), b.meta_programs.len());
@@ -91,16 +104,20 @@ fn main() {
 
     // Add default meta-programs and heuristics if not present
     if brain.meta_programs.is_empty() {
-        brain.add_meta_program(create_generate_module_metaprogram());
-        brain.add_meta_program(create_refactor_code_metaprogram());
-        brain.add_meta_program(create_debug_issue_metaprogram());
-        brain.add_meta_program(create_translate_code_metaprogram());
-        brain.add_meta_program(create_explain_code_metaprogram());
+        for mp in [
+            create_generate_module_metaprogram(),
+            create_refactor_code_metaprogram(),
+            create_debug_issue_metaprogram(),
+            create_translate_code_metaprogram(),
+            create_explain_code_metaprogram(),
+        ] {
+            brain.add_meta_program(mp).expect("default meta-program ids are unique");
+        }
     }
 
     if brain.heuristics.is_empty() {
         for h in create_default_heuristics() {
-            brain.add_heuristic(h);
+            brain.add_heuristic(h).expect("default heuristic ids are unique");
         }
     }
 
@@ -142,31 +159,33 @@ fn main() {
 fn create_default_brain() -> PSI_Brain {
     let mut brain = PSI_Brain::new("default-brain");
 
-    // Add common operators
-    brain.add_operator(common_operators::create_define_entities_operator());
-    brain.add_operator(common_operators::create_create_routes_operator());
-    brain.add_operator(common_operators::create_implement_auth_operator());
-    brain.add_operator(common_operators::create_write_tests_operator());
-
-    // Add some additional operators for other domains
-    brain.add_operator(create_analyze_patterns_operator());
-    brain.add_operator(create_optimize_queries_operator());
-    brain.add_operator(create_apply_refactor_operator());
-    brain.add_operator(create_validate_operator());
-    brain.add_operator(create_detect_race_conditions_operator());
-    brain.add_operator(create_suggest_fixes_operator());
-    brain.add_operator(create_parse_ast_operator());
-    brain.add_operator(create_map_to_language_templates_operator());
-    brain.add_operator(create_emit_code_operator());
-    brain.add_operator(create_map_operators_operator());
-    brain.add_operator(create_generate_explanation_operator());
-
-    // Add multi-modal operators
-    let mm_code_gen = multimodal_operators::create_code_generation_operator();
-    brain.add_operator(mm_code_gen.base_operator);
-
-    let mm_image_gen = multimodal_operators::create_image_generation_operator();
-    brain.add_operator(mm_image_gen.base_operator);
+    let operators = vec![
+        // Common operators
+        common_operators::create_define_entities_operator(),
+        common_operators::create_create_routes_operator(),
+        common_operators::create_implement_auth_operator(),
+        common_operators::create_write_tests_operator(),
+
+        // Some additional operators for other domains
+        create_analyze_patterns_operator(),
+        create_optimize_queries_operator(),
+        create_apply_refactor_operator(),
+        create_validate_operator(),
+        create_detect_race_conditions_operator(),
+        create_suggest_fixes_operator(),
+        create_parse_ast_operator(),
+        create_map_to_language_templates_operator(),
+        create_emit_code_operator(),
+        create_map_operators_operator(),
+        create_generate_explanation_operator(),
+
+        // Multi-modal operators
+        multimodal_operators::create_code_generation_operator().base_operator,
+        multimodal_operators::create_image_generation_operator().base_operator,
+    ];
+    for op in operators {
+        brain.add_operator(op).expect("default operator ids are unique");
+    }
 
     brain
 }
@@ -186,6 +205,8 @@ fn create_analyze_patterns_operator() -> psi::PSI_Operator {
         purity: 1,
         arity_in: 1,
         arity_out: 1,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
         cost_hint: 12,
         kern_template: r#"rule AnalyzePatterns: 
     if has_code() 
@@ -208,6 +229,8 @@ fn create_optimize_queries_operator() -> psi::PSI_Operator {
         purity: 1,
         arity_in: 1,
         arity_out: 1,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
         cost_hint: 15,
         kern_template: r#"rule OptimizeQueries: 
     if has_database_queries() 
@@ -230,6 +253,8 @@ fn create_apply_refactor_operator() -> psi::PSI_Operator {
         purity: 1,
         arity_in: 1,
         arity_out: 1,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
         cost_hint: 20,
         kern_template: r#"rule ApplyRefactor: 
     if refactoring_plan_ready() 
@@ -252,6 +277,8 @@ fn create_validate_operator() -> psi::PSI_Operator {
         purity: 1,
         arity_in: 1,
         arity_out: 1,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
         cost_hint: 8,
         kern_template: r#"rule Validate: 
     if code_exists() 
@@ -274,6 +301,8 @@ fn create_detect_race_conditions_operator() -> psi::PSI_Operator {
         purity: 1,
         arity_in: 1,
         arity_out: 1,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
         cost_hint: 25,
         kern_template: r#"rule DetectRaceConditions: 
     if has_concurrent_code() 
@@ -296,6 +325,8 @@ fn create_suggest_fixes_operator() -> psi::PSI_Operator {
         purity: 1,
         arity_in: 1,
         arity_out: 1,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
         cost_hint: 18,
         kern_template: r#"rule SuggestFixes: 
     if issues_found() 
@@ -318,6 +349,8 @@ fn create_parse_ast_operator() -> psi::PSI_Operator {
         purity: 1,
         arity_in: 1,
         arity_out: 1,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
         cost_hint: 10,
         kern_template: r#"rule ParseAST: 
     if has_source_code() 
@@ -340,6 +373,8 @@ fn create_map_to_language_templates_operator() -> psi::PSI_Operator {
         purity: 1,
         arity_in: 1,
         arity_out: 1,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
         cost_hint: 14,
         kern_template: r#"rule MapToLanguageTemplates: 
     if has_ast() 
@@ -362,6 +397,8 @@ fn create_emit_code_operator() -> psi::PSI_Operator {
         purity: 1,
         arity_in: 1,
         arity_out: 1,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
         cost_hint: 12,
         kern_template: r#"rule EmitCode: 
     if has_translated_ast() 
@@ -384,6 +421,8 @@ fn create_map_operators_operator() -> psi::PSI_Operator {
         purity: 1,
         arity_in: 1,
         arity_out: 1,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
         cost_hint: 9,
         kern_template: r#"rule MapOperators: 
     if has_ast() 
@@ -406,6 +445,8 @@ fn create_generate_explanation_operator() -> psi::PSI_Operator {
         purity: 1,
         arity_in: 1,
         arity_out: 1,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
         cost_hint: 16,
         kern_template: r#"rule GenerateExplanation: 
     if has_operators() 
@@ -451,8 +492,9 @@ fn process_command(engine: &mut OperatorEngine, brain: &PSI_Brain, cmd: &str, ar
     } else if lower_cmd.starts_with("explain") {
         handle_explain(engine, brain, cmd, args);
     } else {
-        // Try to select a meta-program based on the command
-        if let Some(metaprogram) = select_metaprogram(cmd, brain) {
+        // Let the brain's heuristics pick a meta-program, then fall back to name matching
+        let selected = brain.select_metaprogram_weighted(cmd).cloned().or_else(|| select_metaprogram(cmd, brain));
+        if let Some(metaprogram) = selected {
             execute_metaprogram(engine, brain, &metaprogram, cmd, args);
         } else {
             println!("Unrecognized command: {}", cmd);
@@ -607,7 +649,14 @@ fn execute_metaprogram_with_context(
         println!("[DEBUG] Operators in chain: {:?}", metaprogram.operators);
     }
     
-    match engine.execute_operator_chain(brain, &metaprogram.operators, context) {
+    // With --stream, report each operator as the chain reaches and completes it
+    let mut report_progress = |operator: &str, context: &OperatorExecutionContext| {
+        println!("[STREAM] {} ({} outputs so far)", operator, context.outputs.len());
+    };
+    let progress: Option<ChainProgress<'_>> =
+        if args.stream { Some(&mut report_progress) } else { None };
+
+    match engine.execute_operator_chain(brain, &metaprogram.operators, context, progress) {
         Ok(final_context) => {
             println!("Execution completed successfully for meta-program: {}", metaprogram.name);
             
@@ -235,18 +235,18 @@ mod tests {
         let mut brain = PSI_Brain::new("test");
         
         // Add operators
-        brain.add_operator(common_operators::create_define_entities_operator());
-        brain.add_operator(common_operators::create_create_routes_operator());
-        brain.add_operator(common_operators::create_implement_auth_operator());
-        brain.add_operator(common_operators::create_write_tests_operator());
+        brain.add_operator(common_operators::create_define_entities_operator()).unwrap();
+        brain.add_operator(common_operators::create_create_routes_operator()).unwrap();
+        brain.add_operator(common_operators::create_implement_auth_operator()).unwrap();
+        brain.add_operator(common_operators::create_write_tests_operator()).unwrap();
         
         // Add meta-programs
-        brain.add_meta_program(create_generate_module_metaprogram());
-        brain.add_meta_program(create_refactor_code_metaprogram());
+        brain.add_meta_program(create_generate_module_metaprogram()).unwrap();
+        brain.add_meta_program(create_refactor_code_metaprogram()).unwrap();
         
         // Add heuristics
         for h in create_default_heuristics() {
-            brain.add_heuristic(h);
+            brain.add_heuristic(h).unwrap();
         }
         
         // Test selection
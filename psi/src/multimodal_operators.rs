@@ -78,6 +78,8 @@ pub mod multimodal_operators {
             purity: 0,
             arity_in: 1,
             arity_out: 1,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
             cost_hint: 20,
             kern_template: r#"rule MultiModalCodeGeneration:
     if has_specification()
@@ -116,6 +118,8 @@ pub mod multimodal_operators {
             purity: 0,
             arity_in: 1,
             arity_out: 1,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
             cost_hint: 50,
             kern_template: r#"rule ImageGeneration:
     if has_description()
@@ -1,11 +1,14 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use kern_vm::VirtualMachine;
 use kern_graph_builder::GraphBuilder;
 use kern_bytecode::BytecodeCompiler;
 use kern_parser::{Parser, Program};
 
-use crate::{PSI_Operator, PSI_Brain};
+use crate::{CycleError, PSI_Operator, PSI_Brain};
 
 #[derive(Debug, Clone)]
 pub struct OperatorExecutionContext {
@@ -13,6 +16,7 @@ pub struct OperatorExecutionContext {
     pub outputs: HashMap<String, String>,
     pub context_vars: HashMap<String, String>,
     pub language: String,
+    cancelled: Arc<AtomicBool>, // Set when the operator running with this context has timed out
 }
 
 impl OperatorExecutionContext {
@@ -22,9 +26,16 @@ impl OperatorExecutionContext {
             outputs: HashMap::new(),
             context_vars: HashMap::new(),
             language: "rust".to_string(),
+            cancelled: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Whether the operator running with this context has timed out. Long-running
+    /// native operators should poll this and return once it is set.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
     pub fn set_input(&mut self, key: String, value: String) {
         self.inputs.insert(key, value);
     }
@@ -50,10 +61,48 @@ impl OperatorExecutionContext {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum OperatorExecutionError {
+    NotFound(String),
+    Timeout(String),        // operator name
+    Failed(String, String), // operator name, reason
+    Cycle(Vec<String>),     // operators whose inputs and outputs form a loop
+}
+
+impl std::fmt::Display for OperatorExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperatorExecutionError::NotFound(name) => write!(f, "Operator not found: {}", name),
+            OperatorExecutionError::Timeout(name) => write!(f, "Operator timed out: {}", name),
+            OperatorExecutionError::Failed(name, reason) => write!(f, "Operator {} failed: {}", name, reason),
+            OperatorExecutionError::Cycle(names) => write!(f, "Cyclic operator dependency among: {}", names.join(", ")),
+        }
+    }
+}
+
+impl std::error::Error for OperatorExecutionError {}
+
+impl From<CycleError> for OperatorExecutionError {
+    fn from(error: CycleError) -> Self {
+        OperatorExecutionError::Cycle(error.operators)
+    }
+}
+
+/// Host-implemented operator body, e.g. a call out to an LLM
+pub type NativeOperator = Arc<dyn Fn(&mut OperatorExecutionContext) -> Result<(), String> + Send + Sync>;
+
+/// Observer of a running operator chain, given the operator name and current context
+pub type ChainProgress<'a> = &'a mut dyn FnMut(&str, &OperatorExecutionContext);
+
+pub const DEFAULT_OPERATOR_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct OperatorEngine {
     pub vm: VirtualMachine,
     pub graph_builder: GraphBuilder,
     pub bytecode_compiler: BytecodeCompiler,
+    pub native_operators: HashMap<String, NativeOperator>,
+    pub default_timeout: Duration,
+    pub operator_timeouts: HashMap<String, Duration>, // per-operator overrides of default_timeout
 }
 
 impl OperatorEngine {
@@ -62,14 +111,34 @@ impl OperatorEngine {
             vm: VirtualMachine::new(),
             graph_builder: GraphBuilder::new(),
             bytecode_compiler: BytecodeCompiler::new(),
+            native_operators: HashMap::new(),
+            default_timeout: DEFAULT_OPERATOR_TIMEOUT,
+            operator_timeouts: HashMap::new(),
         })
     }
 
+    /// Runs `handler` instead of the KERN template whenever the named operator executes
+    pub fn register_native_operator(&mut self, name: &str, handler: NativeOperator) {
+        self.native_operators.insert(name.to_string(), handler);
+    }
+
+    pub fn set_operator_timeout(&mut self, name: &str, timeout: Duration) {
+        self.operator_timeouts.insert(name.to_string(), timeout);
+    }
+
+    pub fn operator_timeout(&self, name: &str) -> Duration {
+        self.operator_timeouts.get(name).copied().unwrap_or(self.default_timeout)
+    }
+
     pub fn execute_operator(
         &mut self,
         operator: &PSI_Operator,
         context: &mut OperatorExecutionContext,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(handler) = self.native_operators.get(&operator.name) {
+            return handler(context).map_err(|e| e.into());
+        }
+
         // Select the appropriate emission template based on the context language
         let template = match context.language.as_str() {
             "rust" => operator.emissions.get("rust").unwrap_or(&operator.kern_template),
@@ -142,19 +211,87 @@ impl OperatorEngine {
         Ok(())
     }
 
+    /// Executes an operator on a worker thread, giving up after its configured timeout.
+    ///
+    /// The engine's VM and compiler move to the worker for the duration of the call.
+    /// On timeout the worker's context is marked cancelled (see
+    /// `OperatorExecutionContext::is_cancelled`) and the engine continues with fresh
+    /// components; `context` is left as it was before the call. A thread can't be
+    /// killed, so the worker only stops early if the operator checks the flag:
+    /// KERN templates still run to completion within the VM's step limits, and a
+    /// native operator that never checks keeps its thread until it returns.
+    pub fn execute_operator_with_timeout(
+        &mut self,
+        operator: &PSI_Operator,
+        context: &mut OperatorExecutionContext,
+    ) -> Result<(), OperatorExecutionError> {
+        let timeout = self.operator_timeout(&operator.name);
+        let mut worker = OperatorEngine {
+            vm: std::mem::replace(&mut self.vm, VirtualMachine::new()),
+            graph_builder: std::mem::replace(&mut self.graph_builder, GraphBuilder::new()),
+            bytecode_compiler: std::mem::replace(&mut self.bytecode_compiler, BytecodeCompiler::new()),
+            native_operators: self.native_operators.clone(),
+            default_timeout: self.default_timeout,
+            operator_timeouts: HashMap::new(),
+        };
+        let worker_operator = operator.clone();
+        let mut worker_context = context.clone();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        worker_context.cancelled = Arc::clone(&cancelled);
+
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = worker
+                .execute_operator(&worker_operator, &mut worker_context)
+                .map_err(|e| e.to_string());
+            // The receiver is gone if the operator already timed out
+            let _ = sender.send((worker, worker_context, result));
+        });
+
+        match receiver.recv_timeout(timeout) {
+            Ok((worker, worker_context, result)) => {
+                self.vm = worker.vm;
+                self.graph_builder = worker.graph_builder;
+                self.bytecode_compiler = worker.bytecode_compiler;
+                *context = worker_context;
+                result.map_err(|reason| OperatorExecutionError::Failed(operator.name.clone(), reason))
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                cancelled.store(true, Ordering::Relaxed);
+                Err(OperatorExecutionError::Timeout(operator.name.clone()))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(OperatorExecutionError::Failed(
+                operator.name.clone(),
+                "operator panicked".to_string(),
+            )),
+        }
+    }
+
+    /// Executes operators in data-flow order (see `PSI_Brain::topological_operator_order`),
+    /// aborting the chain at the first failure or timeout.
+    ///
+    /// `progress` is called with each operator's name and the context just before the
+    /// operator runs and again once it has succeeded.
     pub fn execute_operator_chain(
         &mut self,
         brain: &PSI_Brain,
         operator_names: &[String],
         initial_context: OperatorExecutionContext,
-    ) -> Result<OperatorExecutionContext, Box<dyn std::error::Error>> {
+        mut progress: Option<ChainProgress<'_>>,
+    ) -> Result<OperatorExecutionContext, OperatorExecutionError> {
         let mut current_context = initial_context;
 
-        for operator_name in operator_names {
+        for operator_name in &brain.topological_operator_order(operator_names)? {
             if let Some(operator) = brain.operators.iter().find(|op| &op.name == operator_name) {
-                self.execute_operator(operator, &mut current_context)?;
+                if let Some(report) = progress.as_deref_mut() {
+                    report(operator_name, &current_context);
+                }
+                self.execute_operator_with_timeout(operator, &mut current_context)?;
+                if let Some(report) = progress.as_deref_mut() {
+                    report(operator_name, &current_context);
+                }
             } else {
-                return Err(format!("Operator not found: {}", operator_name).into());
+                return Err(OperatorExecutionError::NotFound(operator_name.clone()));
             }
         }
 
@@ -202,6 +339,8 @@ class User {
             purity: 0,
             arity_in: 1,
             arity_out: 1,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
             cost_hint: 5,
             kern_template: r#"rule DefineEntities:
     if has_entities_spec()
@@ -265,6 +404,8 @@ module.exports = router;
             purity: 0,
             arity_in: 1,
             arity_out: 1,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
             cost_hint: 8,
             kern_template: r#"rule CreateRoutes:
     if has_route_spec()
@@ -369,6 +510,8 @@ const authMiddleware = (req, res, next) => {
             purity: 0,
             arity_in: 1,
             arity_out: 1,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
             cost_hint: 15,
             kern_template: r#"rule ImplementAuth:
     if needs_auth()
@@ -452,6 +595,8 @@ describe('User', () => {
             purity: 0,
             arity_in: 1,
             arity_out: 1,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
             cost_hint: 10,
             kern_template: r#"rule WriteTests:
     if has_spec()
@@ -486,4 +631,132 @@ mod tests {
         assert_eq!(ops[2].name, "ImplementAuth");
         assert_eq!(ops[3].name, "WriteTests");
     }
+
+    fn native_operator(name: &str) -> PSI_Operator {
+        PSI_Operator {
+            id: 100,
+            name: name.to_string(),
+            domain: "systems".to_string(),
+            purity: 1,
+            arity_in: 0,
+            arity_out: 0,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            cost_hint: 1,
+            kern_template: String::new(),
+            emissions: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_operator_timeout_aborts_chain() {
+        let mut brain = PSI_Brain::new("timeouts");
+        brain.operators.push(native_operator("SlowCall"));
+        brain.operators.push(native_operator("Record"));
+
+        let mut engine = OperatorEngine::new().unwrap();
+        engine.register_native_operator("SlowCall", Arc::new(|_context| {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(())
+        }));
+        engine.register_native_operator("Record", Arc::new(|context| {
+            context.set_output("recorded".to_string(), "yes".to_string());
+            Ok(())
+        }));
+        engine.set_operator_timeout("SlowCall", Duration::from_millis(20));
+
+        let chain = vec!["SlowCall".to_string(), "Record".to_string()];
+        let result = engine.execute_operator_chain(&brain, &chain, OperatorExecutionContext::new(), None);
+        assert_eq!(result.unwrap_err(), OperatorExecutionError::Timeout("SlowCall".to_string()));
+
+        // The engine is still usable after the aborted chain
+        let result = engine
+            .execute_operator_chain(&brain, &["Record".to_string()], OperatorExecutionContext::new(), None)
+            .unwrap();
+        assert_eq!(result.get_output("recorded"), Some(&"yes".to_string()));
+    }
+
+    #[test]
+    fn test_timed_out_operator_is_cancelled() {
+        let (stopped, worker_stopped) = mpsc::channel();
+        let mut engine = OperatorEngine::new().unwrap();
+        engine.register_native_operator("Spin", Arc::new(move |context| {
+            while !context.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            let _ = stopped.send(());
+            Err("cancelled".to_string())
+        }));
+        engine.set_operator_timeout("Spin", Duration::from_millis(20));
+
+        let mut context = OperatorExecutionContext::new();
+        let result = engine.execute_operator_with_timeout(&native_operator("Spin"), &mut context);
+        assert_eq!(result.unwrap_err(), OperatorExecutionError::Timeout("Spin".to_string()));
+        assert!(!context.is_cancelled());
+
+        // The abandoned worker sees the flag and returns rather than spinning forever
+        worker_stopped.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+
+    fn data_operator(name: &str, inputs: &[&str], outputs: &[&str]) -> PSI_Operator {
+        let mut operator = native_operator(name);
+        operator.inputs = inputs.iter().map(|s| s.to_string()).collect();
+        operator.outputs = outputs.iter().map(|s| s.to_string()).collect();
+        operator
+    }
+
+    #[test]
+    fn test_operator_chain_runs_in_data_flow_order() {
+        let mut brain = PSI_Brain::new("ordering");
+        brain.operators.push(data_operator("WriteTests", &["routes"], &["tests"]));
+        brain.operators.push(data_operator("CreateRoutes", &["entities"], &["routes"]));
+        brain.operators.push(data_operator("DefineEntities", &["spec"], &["entities"]));
+
+        let mut engine = OperatorEngine::new().unwrap();
+        for op in &brain.operators {
+            let name = op.name.clone();
+            engine.register_native_operator(&op.name, Arc::new(move |context| {
+                let trace = context.get_output("trace").cloned().unwrap_or_default();
+                context.set_output("trace".to_string(), format!("{}{};", trace, name));
+                Ok(())
+            }));
+        }
+
+        let chain: Vec<String> = ["WriteTests", "CreateRoutes", "DefineEntities"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            brain.topological_operator_order(&chain).unwrap(),
+            vec!["DefineEntities", "CreateRoutes", "WriteTests"]
+        );
+        let result = engine.execute_operator_chain(&brain, &chain, OperatorExecutionContext::new(), None).unwrap();
+        assert_eq!(
+            result.get_output("trace"),
+            Some(&"DefineEntities;CreateRoutes;WriteTests;".to_string())
+        );
+
+        // Progress is reported before and after each operator, in execution order
+        let mut calls = Vec::new();
+        let mut record = |name: &str, context: &OperatorExecutionContext| {
+            calls.push(format!("{}:{}", name, context.get_output("trace").map_or(0, |t| t.matches(';').count())));
+        };
+        let reported = engine
+            .execute_operator_chain(&brain, &chain, OperatorExecutionContext::new(), Some(&mut record))
+            .unwrap();
+        assert_eq!(
+            calls,
+            vec!["DefineEntities:0", "DefineEntities:1", "CreateRoutes:1", "CreateRoutes:2", "WriteTests:2", "WriteTests:3"]
+        );
+        assert_eq!(reported.outputs, result.outputs);
+
+        // Closing the loop leaves no operator to start with
+        brain.operators[2].inputs.push("tests".to_string());
+        let error = brain.topological_operator_order(&chain).unwrap_err();
+        assert_eq!(error.operators, chain);
+        assert_eq!(
+            engine.execute_operator_chain(&brain, &chain, OperatorExecutionContext::new(), None).unwrap_err(),
+            OperatorExecutionError::Cycle(chain.clone())
+        );
+    }
 }
\ No newline at end of file
@@ -118,37 +118,32 @@ impl Lexer {
             '%' => Some(self.make_token(TokenKind::Percent)),
             ':' => Some(self.make_token(TokenKind::Colon)),
             ' ' | '\r' | '\t' => None, // Skip whitespace
-            '\n' => {
-                self.line += 1;
-                self.column = 1;
-                None
-            }
+            '\n' => None,                // advance() already moved to the next line
             '"' => Some(self.string()),
             '0'..='9' => Some(self.number()),
             'a'..='z' | 'A'..='Z' | '_' => Some(self.identifier()),
             _ => Some(Token::new(
                 TokenKind::Error,
                 format!("Unexpected character: {}", c),
-                self.get_location(),
+                self.token_start_location(),
             )),
         }
     }
 
     fn string(&mut self) -> Token {
         let start_location = self.get_location();
+        let quote_location = self.token_start_location();
+        // advance() keeps line and column in step across newlines
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
-                self.column = 1;
-            }
             self.advance();
         }
 
         if self.is_at_end() {
+            // Cover everything from the opening quote to end of input
             return Token::new(
                 TokenKind::Error,
                 "Unterminated string".to_string(),
-                start_location,
+                quote_location.with_length(self.current - self.start),
             );
         }
 
@@ -244,4 +239,11 @@ impl Lexer {
     fn get_location(&self) -> SourceLocation {
         SourceLocation::new(self.line, self.column, self.current)
     }
+
+    // Location of the first character of the token being scanned, spanning
+    // what has been consumed so far. Only valid before a newline is consumed.
+    fn token_start_location(&self) -> SourceLocation {
+        let consumed = self.current - self.start;
+        SourceLocation::new(self.line, self.column - 1, self.start).with_length(consumed)
+    }
 }
\ No newline at end of file
@@ -24,6 +24,10 @@ pub mod flow_pipeline {
     pub use kern_flow_pipeline::*;
 }
 
+pub mod pipeline;
+
+pub use pipeline::{compile, CompileResult};
+
 use lexer::lexer::Lexer;
 use parser::parser::Parser;
 use shared::diagnostics::Diagnostics;
@@ -69,4 +73,16 @@ impl KernCompiler {
             Some(program)
         }
     }
+
+    /// The accumulated lexer and parser diagnostics as a JSON array, for
+    /// editors and CI annotations
+    pub fn diagnostics_as_json(&self) -> String {
+        let diagnostics: Vec<serde_json::Value> = self
+            .diagnostics
+            .diagnostics
+            .iter()
+            .map(|d| d.to_json())
+            .collect();
+        serde_json::Value::Array(diagnostics).to_string()
+    }
 }
\ No newline at end of file
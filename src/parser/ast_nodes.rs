@@ -215,7 +215,7 @@ pub struct IndexExpression {
 #[derive(Debug, Clone)]
 pub enum Statement {
     Expression(ExpressionStatement),
-    Declaration(Declaration),
+    Declaration(Box<Declaration>),
     Block(BlockStatement),
     If(IfStatement),
     While(Box<WhileStatement>),
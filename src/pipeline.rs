@@ -0,0 +1,56 @@
+use kern_bytecode::{BytecodeCompiler, BytecodeModule};
+use kern_graph_builder::{ExecutionGraph, GraphBuilder};
+use kern_parser::{Parser, Program};
+
+/// CompileResult aggregates the artifacts of every pipeline stage.
+///
+/// Each field is populated only if compilation got that far; a failing stage
+/// records its errors in `diagnostics` and leaves all later fields `None`.
+#[derive(Debug, Clone, Default)]
+pub struct CompileResult {
+    pub ast: Option<Program>,
+    pub graph: Option<ExecutionGraph>,
+    pub bytecode: Option<BytecodeModule>,
+    pub diagnostics: Vec<String>,
+}
+
+impl CompileResult {
+    /// Returns true if every stage completed without diagnostics
+    pub fn is_success(&self) -> bool {
+        self.diagnostics.is_empty() && self.bytecode.is_some()
+    }
+}
+
+/// Compiles KERN source through parsing, graph building and bytecode generation
+pub fn compile(source: &str) -> CompileResult {
+    let mut result = CompileResult::default();
+
+    // Parse
+    let mut parser = Parser::new(source);
+    let program = match parser.parse_program() {
+        Ok(program) => program,
+        Err(errors) => {
+            result.diagnostics = errors.iter().map(|e| e.to_string()).collect();
+            return result;
+        }
+    };
+
+    // Build execution graph
+    let mut graph_builder = GraphBuilder::new();
+    let graph = graph_builder.build_execution_graph(&program);
+    if let Err(errors) = graph.validate() {
+        result.diagnostics = errors.iter().map(|e| e.to_string()).collect();
+        result.ast = Some(program);
+        result.graph = Some(graph);
+        return result;
+    }
+
+    // Compile to bytecode
+    let mut bytecode_compiler = BytecodeCompiler::new();
+    let bytecode = bytecode_compiler.compile_graph(&graph);
+
+    result.ast = Some(program);
+    result.graph = Some(graph);
+    result.bytecode = Some(bytecode);
+    result
+}
@@ -137,6 +137,19 @@ impl Diagnostic {
             self.message
         )
     }
+
+    /// Every diagnostic the compiler produces today is an error
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "code": self.code.to_string(),
+            "message": self.message,
+            "severity": "error",
+            "file": self.file,
+            "line": self.location.line,
+            "column": self.location.column,
+            "length": self.location.length,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -3,11 +3,18 @@ pub struct SourceLocation {
     pub line: usize,
     pub column: usize,
     pub offset: usize,
+    /// Number of source characters covered, newlines included
+    pub length: usize,
 }
 
 impl SourceLocation {
     pub fn new(line: usize, column: usize, offset: usize) -> Self {
-        Self { line, column, offset }
+        Self { line, column, offset, length: 0 }
+    }
+
+    pub fn with_length(mut self, length: usize) -> Self {
+        self.length = length;
+        self
     }
 }
 
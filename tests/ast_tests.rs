@@ -2,7 +2,7 @@ mod common;
 use common::assertions::{assert_equal, assert_true, AssertionResult};
 use kern_parser::ast::{
     Action, Comparator, Condition, ConstraintDef, Definition, EntityDef, Expression, FieldDef,
-    FlowDef, Program, RuleDef, Term,
+    FlowDef, Program, RuleDef, SourceLocation, Term,
 };
 
 #[test]
@@ -40,6 +40,7 @@ fn test_rule_node_creation() {
             right: Box::new(Term::Number(18)),
         }),
         actions: vec![],
+        location: SourceLocation::default(),
     };
 
     assert_eq!(rule.name, "ValidateUser");
@@ -49,6 +50,7 @@ fn test_rule_node_creation() {
 fn test_flow_node_creation() {
     let flow = FlowDef {
         name: "ProcessData".to_string(),
+        outputs: vec![],
         actions: vec![],
     };
 
@@ -86,6 +88,7 @@ fn test_program_node_creation() {
             right: Box::new(Term::Number(1)),
         }),
         actions: vec![],
+        location: SourceLocation::default(),
     };
 
     let program = Program {
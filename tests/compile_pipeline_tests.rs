@@ -0,0 +1,34 @@
+use kern_compiler::compile;
+
+#[test]
+fn test_compile_valid_program_populates_all_stages() {
+    let input = r#"
+        entity Farmer {
+            id
+            location
+        }
+
+        rule CheckLocation:
+            if farmer.location == valid
+            then approve_farmer(farmer)
+    "#;
+
+    let result = compile(input);
+
+    assert!(result.diagnostics.is_empty(), "Unexpected diagnostics: {:?}", result.diagnostics);
+    assert!(result.ast.is_some());
+    assert!(result.graph.is_some());
+    assert!(result.bytecode.is_some());
+    assert!(result.is_success());
+}
+
+#[test]
+fn test_compile_parse_error_yields_only_diagnostics() {
+    let result = compile("entity Farmer { id");
+
+    assert!(!result.diagnostics.is_empty());
+    assert!(result.ast.is_none());
+    assert!(result.graph.is_none());
+    assert!(result.bytecode.is_none());
+    assert!(!result.is_success());
+}
@@ -0,0 +1,101 @@
+use kern_bytecode::{ArithmeticMode, BytecodeCompiler, BytecodeModule, Opcode};
+use kern_graph_builder::{
+    EntryPoint, ExecutionGraph, GraphBuilder, GraphEdge, GraphNode, GraphNodeType, NodeMeta,
+    SpecializedNode, ValueNode, OP_ADD, OP_MUL,
+};
+use kern_parser::Parser;
+use kern_vm::VirtualMachine;
+
+fn node(id: u32, opcode: u8) -> GraphNode {
+    GraphNode {
+        id,
+        node_type: GraphNodeType::Op,
+        opcode,
+        flags: 0,
+        input_regs: [0; 4],
+        output_regs: [0; 2],
+        first_edge: 0,
+        edge_count: 0,
+        meta: NodeMeta {
+            source_ref: 0,
+            cost_hint: 0,
+        },
+    }
+}
+
+// Node 0 computes `left <op> right` from two LOAD_NUM nodes
+fn binary_graph(opcode: u8, left: f64, right: f64) -> ExecutionGraph {
+    let mut graph = ExecutionGraph::empty();
+    graph.nodes = vec![
+        SpecializedNode::Base(node(0, opcode)),
+        SpecializedNode::Value(ValueNode::new_num(node(1, 0x11), left)),
+        SpecializedNode::Value(ValueNode::new_num(node(2, 0x11), right)),
+    ];
+    graph.edges = vec![GraphEdge::new_data(0, 1), GraphEdge::new_data(0, 2)];
+    graph.entry_points = vec![EntryPoint {
+        node_id: 0,
+        entry_type: 0,
+    }];
+    graph
+}
+
+fn compile(graph: &ExecutionGraph, folding: bool) -> BytecodeModule {
+    let mut compiler = BytecodeCompiler::new();
+    compiler.set_constant_folding(folding);
+    compiler.compile_graph(graph)
+}
+
+// The value left in the register the last instruction writes
+fn result(module: &BytecodeModule) -> Option<i64> {
+    let mut vm = VirtualMachine::new();
+    vm.load_program(module.instruction_stream.clone());
+    vm.execute().expect("program should run");
+    vm.get_register(module.instruction_stream.last()?.arg1 as usize)
+}
+
+#[test]
+fn test_folded_addition_matches_unfolded() {
+    let graph = binary_graph(OP_ADD, 2.0, 3.0);
+    let unfolded = compile(&graph, false);
+    let folded = compile(&graph, true);
+
+    assert!(folded.instruction_stream.len() < unfolded.instruction_stream.len());
+    assert_eq!(folded.instruction_stream.len(), 1);
+    assert_eq!(folded.instruction_stream[0].opcode, Opcode::LoadNum as u8);
+    assert_eq!(result(&folded), Some(5));
+    assert_eq!(result(&folded), result(&unfolded));
+}
+
+#[test]
+fn test_checked_overflow_is_not_folded() {
+    let graph = binary_graph(OP_MUL, i64::MAX as f64, 2.0);
+    assert_eq!(compile(&graph, true).instruction_stream.len(), 3);
+
+    let mut compiler = BytecodeCompiler::new();
+    compiler.set_arithmetic_mode(ArithmeticMode::Saturating);
+    let module = compiler.compile_graph(&graph);
+    assert_eq!(module.instruction_stream.len(), 1);
+}
+
+#[test]
+fn test_comparison_of_literals_from_source_is_folded() {
+    let mut parser = Parser::new("rule Check: if 3 > 2 then checked = 1");
+    let program = parser.parse_program().expect("program should parse");
+    let graph = GraphBuilder::new().build_execution_graph(&program);
+
+    let unfolded = compile(&graph, false);
+    let folded = compile(&graph, true);
+
+    let is_compare = |module: &BytecodeModule| {
+        module
+            .instruction_stream
+            .iter()
+            .any(|instruction| instruction.opcode == Opcode::Compare as u8)
+    };
+    assert!(is_compare(&unfolded));
+    assert!(!is_compare(&folded));
+    assert_eq!(
+        folded.instruction_stream.len(),
+        unfolded.instruction_stream.len() - 2
+    );
+}
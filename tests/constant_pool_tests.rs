@@ -0,0 +1,54 @@
+use kern_bytecode::serializer::BytecodeSerializer;
+use kern_bytecode::{BytecodeModule, Constant};
+use kern_compiler::compile;
+
+// The same two rules, declared in opposite orders
+const FORWARD: &str = r#"
+    rule CheckAge:
+        if farmer.age > 5000000000
+        then approve_farmer(farmer)
+
+    rule CheckId:
+        if farmer.id == 7000000000
+        then approve_farmer(farmer)
+"#;
+
+const REVERSED: &str = r#"
+    rule CheckId:
+        if farmer.id == 7000000000
+        then approve_farmer(farmer)
+
+    rule CheckAge:
+        if farmer.age > 5000000000
+        then approve_farmer(farmer)
+"#;
+
+fn serialized_pool(source: &str) -> Vec<u8> {
+    let module = compile(source).bytecode.expect("source should compile");
+    // Serialize a module holding nothing but the constant pool
+    let pool_only = BytecodeModule {
+        instruction_stream: Vec::new(),
+        symbol_table: Vec::new(),
+        rule_table: Vec::new(),
+        graph_table: Vec::new(),
+        metadata: Vec::new(),
+        relocations: Vec::new(),
+        ..module
+    };
+    BytecodeSerializer::new().serialize(&pool_only)
+}
+
+#[test]
+fn test_constant_pool_order_is_independent_of_emission_order() {
+    let forward = compile(FORWARD).bytecode.unwrap();
+    let reversed = compile(REVERSED).bytecode.unwrap();
+    // The two oversized literals, alongside the symbols the rules load
+    let numbers = forward
+        .constant_pool
+        .iter()
+        .filter(|c| matches!(c, Constant::Num(_)))
+        .count();
+    assert_eq!(numbers, 2);
+    assert_eq!(forward.constant_pool, reversed.constant_pool);
+    assert_eq!(serialized_pool(FORWARD), serialized_pool(REVERSED));
+}
@@ -0,0 +1,34 @@
+use kern_compiler::compile;
+
+const SOURCE: &str = r#"
+    entity Farmer {
+        id
+        location
+    }
+
+    rule CheckLocation:
+        if farmer.location == valid
+        then approve_farmer(farmer)
+"#;
+
+fn content_hash(source: &str) -> [u8; 32] {
+    compile(source)
+        .bytecode
+        .expect("source should compile")
+        .content_hash()
+}
+
+#[test]
+fn test_identical_source_yields_identical_hash() {
+    assert_eq!(content_hash(SOURCE), content_hash(SOURCE));
+}
+
+#[test]
+fn test_source_change_alters_hash() {
+    let changed = format!(
+        "{}\n    rule CheckId:\n        if farmer.id == 42\n        then approve_farmer(farmer)\n",
+        SOURCE
+    );
+
+    assert_ne!(content_hash(SOURCE), content_hash(&changed));
+}
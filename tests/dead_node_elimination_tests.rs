@@ -0,0 +1,54 @@
+use kern_bytecode::{BytecodeCompiler, Opcode};
+use kern_graph_builder::{
+    EntryPoint, ExecutionGraph, GraphEdge, GraphNode, GraphNodeType, NodeMeta, SpecializedNode,
+    ValueNode,
+};
+
+fn load_num(id: u32, value: f64) -> SpecializedNode {
+    let base = GraphNode {
+        id,
+        node_type: GraphNodeType::Op,
+        opcode: 0x11, // LOAD_NUM
+        flags: 0,
+        input_regs: [0; 4],
+        output_regs: [0; 2],
+        first_edge: 0,
+        edge_count: 0,
+        meta: NodeMeta {
+            source_ref: 0,
+            cost_hint: 0,
+        },
+    };
+    SpecializedNode::Value(ValueNode::new_num(base, value))
+}
+
+// Literals loaded by the compiled program, in order
+fn loaded_values(compiler: &mut BytecodeCompiler, graph: &ExecutionGraph) -> Vec<u16> {
+    compiler
+        .compile_graph(graph)
+        .instruction_stream
+        .iter()
+        .filter(|i| i.opcode == Opcode::LoadNum as u8)
+        .map(|i| i.arg2)
+        .collect()
+}
+
+#[test]
+fn test_unreachable_node_is_not_compiled() {
+    // 0 -> 1 hangs off the entry point; 2 is connected to nothing
+    let mut graph = ExecutionGraph::empty();
+    graph.nodes = vec![load_num(0, 10.0), load_num(1, 11.0), load_num(2, 99.0)];
+    graph.edges = vec![GraphEdge::new_control(0, 1)];
+    graph.entry_points = vec![EntryPoint {
+        node_id: 0,
+        entry_type: 0,
+    }];
+
+    let mut compiler = BytecodeCompiler::new();
+    assert_eq!(loaded_values(&mut compiler, &graph), vec![10, 11]);
+    assert_eq!(compiler.eliminated_nodes(), 1);
+
+    compiler.set_dead_node_elimination(false);
+    assert_eq!(loaded_values(&mut compiler, &graph), vec![10, 11, 99]);
+    assert_eq!(compiler.eliminated_nodes(), 0);
+}
@@ -0,0 +1,38 @@
+use kern_compiler::KernCompiler;
+use serde_json::Value;
+
+fn diagnostics(source: &str) -> Vec<Value> {
+    let mut compiler = KernCompiler::new();
+    assert!(compiler.compile(source).is_none());
+    match serde_json::from_str(&compiler.diagnostics_as_json()).unwrap() {
+        Value::Array(diagnostics) => diagnostics,
+        other => panic!("expected a JSON array, got {}", other),
+    }
+}
+
+#[test]
+fn test_parse_error_is_reported_as_json() {
+    let diagnostics = diagnostics("entity Farmer { id: int }\nentity {");
+
+    assert_eq!(diagnostics.len(), 1);
+    let error = &diagnostics[0];
+    assert_eq!(error["code"], "EXPECTED_IDENTIFIER");
+    assert_eq!(error["severity"], "error");
+    assert_eq!(error["file"], "unknown");
+    assert_eq!(error["line"], 2);
+    assert_eq!(error["column"], 9);
+}
+
+#[test]
+fn test_lexer_errors_come_before_parser_errors() {
+    let diagnostics = diagnostics("entity Farmer {\n  id: int\n}\nentity Other { x: @ }");
+
+    let codes: Vec<&str> = diagnostics
+        .iter()
+        .map(|d| d["code"].as_str().unwrap())
+        .collect();
+    assert_eq!(codes, vec!["UNEXPECTED_CHARACTER", "EXPECTED_TYPE_NAME"]);
+    assert_eq!(diagnostics[0]["line"], 4);
+    assert_eq!(diagnostics[0]["column"], 19);
+    assert_eq!(diagnostics[0]["length"], 1);
+}
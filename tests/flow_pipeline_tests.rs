@@ -8,6 +8,7 @@ use kern_parser::ast::{Action, Definition, FlowDef, Program};
 fn test_flow_creation() {
     let flow = FlowDef {
         name: "TestDataFlow".to_string(),
+        outputs: vec![],
         actions: vec![],
     };
 
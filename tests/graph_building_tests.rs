@@ -46,7 +46,7 @@ fn test_graph_building_basic_rule() {
         }
         
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
     "#;
 
@@ -156,7 +156,7 @@ fn test_graph_building_with_conditions() {
         }
         
         rule CheckLocation:
-            if farmer.location == "valid" && farmer.id > 0
+            if farmer.location == valid && farmer.id > 0
             then approve_farmer(farmer)
     "#;
 
@@ -201,7 +201,7 @@ fn test_graph_building_with_actions() {
         }
         
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then 
                 approve_farmer(farmer)
                 update_status(farmer, "approved")
@@ -236,7 +236,7 @@ fn test_graph_building_with_control_flow() {
         }
         
         flow ProcessFarmers {
-            if farmer.location == "valid" {
+            if farmer.location == valid {
                 approve_farmer(farmer)
             } else {
                 reject_farmer(farmer)
@@ -282,7 +282,7 @@ fn test_graph_building_with_edges() {
         }
         
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
     "#;
 
@@ -321,7 +321,7 @@ fn test_graph_building_validation() {
         }
         
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
     "#;
 
@@ -355,7 +355,7 @@ fn test_graph_building_cycles_detection() {
         }
         
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
     "#;
 
@@ -389,7 +389,7 @@ fn test_graph_building_optimization() {
         }
         
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
     "#;
 
@@ -439,7 +439,7 @@ fn test_graph_building_multiple_entities_rules_flows() {
         }
         
         rule CheckLocation:
-            if farmer.location == "valid"
+            if farmer.location == valid
             then approve_farmer(farmer)
             
         rule CheckCrop:
@@ -0,0 +1,32 @@
+use kern_compiler::lexer::lexer::Lexer;
+use kern_compiler::lexer::token::TokenKind;
+
+#[test]
+fn test_unterminated_string_error_spans_to_end_of_input() {
+    let source = "rule r {\n  x = \"open\nstill open";
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize();
+
+    let error = tokens
+        .iter()
+        .find(|t| t.kind == TokenKind::Error)
+        .expect("unterminated string should produce an error token");
+    let quote = source.find('"').unwrap();
+
+    assert_eq!(error.lexeme, "Unterminated string");
+    assert_eq!(error.location.line, 2);
+    assert_eq!(error.location.column, 7);
+    assert_eq!(error.location.offset, quote);
+    assert_eq!(error.location.length, source.chars().count() - quote);
+}
+
+#[test]
+fn test_unexpected_character_error_covers_one_character() {
+    let mut lexer = Lexer::new("a @ b");
+    let tokens = lexer.tokenize();
+
+    let error = tokens.iter().find(|t| t.kind == TokenKind::Error).unwrap();
+    assert_eq!(error.location.column, 3);
+    assert_eq!(error.location.offset, 2);
+    assert_eq!(error.location.length, 1);
+}
@@ -0,0 +1,51 @@
+use kern_bytecode::lir_builder::LirBuilder;
+use kern_bytecode::{link, BytecodeCompiler, BytecodeModule, Opcode};
+use kern_vm::VirtualMachine;
+
+fn compile(build: impl FnOnce(&mut LirBuilder)) -> BytecodeModule {
+    let mut builder = LirBuilder::new();
+    build(&mut builder);
+    let mut compiler = BytecodeCompiler::new();
+    compiler.set_position_independent(true);
+    compiler.compile_lir(&builder.build())
+}
+
+#[test]
+fn test_cross_module_rule_call() {
+    // main.kern: calls a rule it doesn't define, then halts
+    let main = compile(|b| {
+        b.load_num(1);
+        b.call_rule("Approve");
+        b.halt();
+    });
+    // rules.kern: defines the rule, with a local jump inside it
+    let rules = compile(|b| {
+        b.rule_entry(1, "Approve");
+        b.jmp(2);
+        b.load_num(99);
+        b.label(2);
+        b.load_num(42);
+        b.return_rule();
+    });
+
+    let linked = link(&[main.clone(), rules]).unwrap();
+    let call = &linked.instruction_stream[1];
+    assert_eq!(call.opcode, Opcode::CallRule as u8);
+    assert_eq!(call.flags, 0);
+    assert_eq!(
+        linked.rule_table[0].entry_pc,
+        main.instruction_stream.len() as u32
+    );
+    assert_eq!(call.arg1 as u32, linked.rule_table[0].entry_pc);
+
+    let mut vm = VirtualMachine::new();
+    vm.load_program(linked.instruction_stream);
+    vm.execute().unwrap();
+
+    // The rule ran (skipping the jumped-over load) and returned to main's HALT
+    assert!(vm.registers.r.contains(&42));
+    assert!(!vm.registers.r.contains(&99));
+    assert!(vm.call_stack.is_empty());
+    assert!(vm.registers.is_halt_requested());
+    assert_eq!(vm.registers.pc, 3); // Just past main's HALT
+}
@@ -0,0 +1,32 @@
+use kern_bytecode::optimizer::BytecodeOptimizer;
+use kern_bytecode::{Instruction, Opcode};
+use kern_vm::VirtualMachine;
+
+fn run(program: Vec<Instruction>) -> VirtualMachine {
+    let mut vm = VirtualMachine::new();
+    vm.load_program(program);
+    vm.execute().expect("program should run");
+    vm
+}
+
+#[test]
+fn test_load_move_fusion_preserves_behavior() {
+    let program = vec![
+        Instruction::new(Opcode::LoadNum as u8, 4, 7, 0, 0), // LOAD_NUM R4, 7
+        Instruction::new(Opcode::Move as u8, 4, 1, 0, 0),    // MOVE R4 -> R1
+        Instruction::new(Opcode::LoadNum as u8, 4, 5, 0, 0), // LOAD_NUM R4, 5
+        Instruction::new(Opcode::Move as u8, 4, 2, 0, 0),    // MOVE R4 -> R2
+        Instruction::new(Opcode::Add as u8, 3, 1, 2, 0),     // R3 = R1 + R2
+        Instruction::new(Opcode::Halt as u8, 0, 0, 0, 0),
+    ];
+
+    let optimized = BytecodeOptimizer::new().optimize(program.clone());
+    assert_eq!(optimized.instructions.len(), 4);
+
+    let before = run(program);
+    let after = run(optimized.instructions);
+    for reg in 1..=3 {
+        assert_eq!(before.get_register(reg), after.get_register(reg));
+    }
+    assert_eq!(after.get_register(3), Some(12));
+}
@@ -298,7 +298,7 @@ fn test_rule_conflict_with_state_conflicts() {
             
         rule UpdateStatusRule:
             if test_entity.id == 1
-            then set_status(test_entity, "processed")
+            then set_status(test_entity, processed)
     "#;
 
     let mut parser = Parser::new(input);
@@ -408,7 +408,7 @@ fn test_rule_conflict_with_complex_conflicts() {
             if test_entity.id == 1 && test_entity.category == "A"
             then 
                 set_value(test_entity, 100)
-                set_status(test_entity, "processed")
+                set_status(test_entity, processed)
             
         rule ComplexRule2:
             if test_entity.id == 1 && test_entity.category == "A"
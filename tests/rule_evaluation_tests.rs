@@ -20,6 +20,8 @@ fn test_rule_evaluation_basic_execution() {
         },
         contexts: kern_graph_builder::ContextPool { contexts: vec![] },
         metadata: kern_graph_builder::GraphMeta { build_hash: 0, version: 1 },
+        flow_outputs: std::collections::HashMap::new(),
+        symbol_table: std::collections::HashMap::new(),
     };
 
     // Add a simple rule node to the graph
@@ -76,6 +78,8 @@ fn test_rule_evaluation_with_conditions() {
         },
         contexts: kern_graph_builder::ContextPool { contexts: vec![] },
         metadata: kern_graph_builder::GraphMeta { build_hash: 0, version: 1 },
+        flow_outputs: std::collections::HashMap::new(),
+        symbol_table: std::collections::HashMap::new(),
     };
 
     // Add a rule node to the graph
@@ -128,6 +132,8 @@ fn test_rule_evaluation_with_multiple_rules() {
         },
         contexts: kern_graph_builder::ContextPool { contexts: vec![] },
         metadata: kern_graph_builder::GraphMeta { build_hash: 0, version: 1 },
+        flow_outputs: std::collections::HashMap::new(),
+        symbol_table: std::collections::HashMap::new(),
     };
 
     // Add multiple rule nodes to the graph
@@ -189,6 +195,8 @@ fn test_rule_evaluation_with_priority() {
         },
         contexts: kern_graph_builder::ContextPool { contexts: vec![] },
         metadata: kern_graph_builder::GraphMeta { build_hash: 0, version: 1 },
+        flow_outputs: std::collections::HashMap::new(),
+        symbol_table: std::collections::HashMap::new(),
     };
 
     // Add two rule nodes to the graph
@@ -309,6 +317,8 @@ fn test_rule_evaluation_activation_count() {
         },
         contexts: kern_graph_builder::ContextPool { contexts: vec![] },
         metadata: kern_graph_builder::GraphMeta { build_hash: 0, version: 1 },
+        flow_outputs: std::collections::HashMap::new(),
+        symbol_table: std::collections::HashMap::new(),
     };
 
     // Add a rule node to the graph
@@ -391,6 +401,8 @@ fn test_rule_evaluation_error_handling() {
         },
         contexts: kern_graph_builder::ContextPool { contexts: vec![] },
         metadata: kern_graph_builder::GraphMeta { build_hash: 0, version: 1 },
+        flow_outputs: std::collections::HashMap::new(),
+        symbol_table: std::collections::HashMap::new(),
     };
 
     // Add a rule node to the graph
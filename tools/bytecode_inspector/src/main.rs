@@ -1,5 +1,6 @@
 use clap::Parser;
-use kern_bytecode::Instruction;
+use kern_bytecode::sha256::to_hex;
+use kern_bytecode::{disassemble, opcode_histogram, BytecodeModule, Opcode};
 use std::fs;
 
 /// KERN Bytecode Inspector - Analyze and verify KERN bytecode
@@ -11,7 +12,7 @@ struct Args {
     input: String,
 
     /// Action to perform
-    #[arg(subcommand)]
+    #[command(subcommand)]
     action: Actions,
 }
 
@@ -22,7 +23,11 @@ enum Actions {
     /// Verify bytecode integrity and validity
     Verify,
     /// Show bytecode metadata
-    Meta,
+    Meta {
+        /// Exit with an error unless the bytecode hash equals this hex digest
+        #[arg(long, value_name = "EXPECTED")]
+        verify_hash: Option<String>,
+    },
     /// Show statistics about the bytecode
     Stats,
 }
@@ -37,8 +42,8 @@ fn main() {
         Actions::Verify => {
             verify_bytecode(&args.input);
         },
-        Actions::Meta => {
-            show_metadata(&args.input);
+        Actions::Meta { verify_hash } => {
+            show_metadata(&args.input, verify_hash.as_deref());
         },
         Actions::Stats => {
             show_stats(&args.input);
@@ -46,68 +51,29 @@ fn main() {
     }
 }
 
-fn disassemble_bytecode(input_file: &str) {
-    // Read the bytecode file
+// Reads a module in the JSON form `kernc build` writes
+fn read_module(input_file: &str) -> BytecodeModule {
     let bytecode_content = fs::read_to_string(input_file)
         .expect("Failed to read bytecode file");
+    serde_json::from_str(&bytecode_content)
+        .expect("Failed to deserialize bytecode")
+}
 
-    // Deserialize the bytecode
-    let bytecode: Vec<Instruction> = serde_json::from_str(&bytecode_content)
-        .expect("Failed to deserialize bytecode");
+fn disassemble_bytecode(input_file: &str) {
+    let module = read_module(input_file);
+    let bytecode = &module.instruction_stream;
 
     println!("Disassembly of {}:", input_file);
     println!("------------------------");
 
-    for (i, instruction) in bytecode.iter().enumerate() {
-        let disasm = disassemble_instruction(instruction);
-        println!("{:04}: {}", i, disasm);
+    for line in disassemble(bytecode) {
+        println!("{}", line);
     }
 }
 
-fn disassemble_instruction(instruction: &Instruction) -> String {
-    // Map opcodes to human-readable mnemonics
-    let mnemonic = match instruction.opcode {
-        0x00 => "NOP",
-        0x01 => "JMP",
-        0x02 => "JMP_IF",
-        0x03 => "HALT",
-        0x10 => "LOAD_SYM",
-        0x11 => "LOAD_NUM",
-        0x12 => "MOVE",
-        0x13 => "COMPARE",
-        0x20 => "GRAPH_NODE_CREATE",
-        0x21 => "GRAPH_EDGE_CREATE",
-        0x22 => "GRAPH_MATCH",
-        0x23 => "GRAPH_TRAVERSE",
-        0x30 => "RULE_LOAD",
-        0x31 => "RULE_EVAL",
-        0x32 => "RULE_FIRE",
-        0x33 => "RULE_PRIORITY_SET",
-        0x40 => "CTX_CREATE",
-        0x41 => "CTX_SWITCH",
-        0x42 => "CTX_CLONE",
-        0x43 => "CTX_DESTROY",
-        0x50 => "ERR_SET",
-        0x51 => "ERR_CLEAR",
-        0x52 => "ERR_CHECK",
-        0x60 => "EXT_CALL",
-        0x61 => "EXT_BIND",
-        0x70 => "RETURN",
-        0x71 => "OUTPUT",
-        _ => "UNKNOWN",
-    };
-
-    format!("{} R{}, R{}, R{}", mnemonic, instruction.arg1, instruction.arg2, instruction.arg3)
-}
-
 fn verify_bytecode(input_file: &str) {
-    // Read the bytecode file
-    let bytecode_content = fs::read_to_string(input_file)
-        .expect("Failed to read bytecode file");
-
-    // Deserialize the bytecode
-    let bytecode: Vec<Instruction> = serde_json::from_str(&bytecode_content)
-        .expect("Failed to deserialize bytecode");
+    let module = read_module(input_file);
+    let bytecode = &module.instruction_stream;
 
     let mut is_valid = true;
     let mut errors = Vec::new();
@@ -145,17 +111,7 @@ fn verify_bytecode(input_file: &str) {
 }
 
 fn is_valid_opcode(opcode: u8) -> bool {
-    // Check if the opcode is one of the valid KERN opcodes
-    matches!(opcode, 
-        0x00 | 0x01 | 0x02 | 0x03 |  // Control flow
-        0x10 | 0x11 | 0x12 | 0x13 |  // Data & Symbol
-        0x20 | 0x21 | 0x22 | 0x23 |  // Graph operations
-        0x30 | 0x31 | 0x32 | 0x33 |  // Rule execution
-        0x40 | 0x41 | 0x42 | 0x43 |  // Context & State
-        0x50 | 0x51 | 0x52 |        // Error handling
-        0x60 | 0x61 |                // External interface
-        0x70 | 0x71                  // Termination
-    )
+    Opcode::from_byte(opcode).is_some()
 }
 
 fn requires_register_arg(opcode: u8) -> bool {
@@ -167,80 +123,65 @@ fn requires_register_arg(opcode: u8) -> bool {
     )
 }
 
-fn show_metadata(input_file: &str) {
-    // Read the bytecode file
-    let bytecode_content = fs::read_to_string(input_file)
-        .expect("Failed to read bytecode file");
-
-    // Deserialize the bytecode
-    let bytecode: Vec<Instruction> = serde_json::from_str(&bytecode_content)
-        .expect("Failed to deserialize bytecode");
+fn show_metadata(input_file: &str, expected_hash: Option<&str>) {
+    let module = read_module(input_file);
+    let bytecode = &module.instruction_stream;
 
     println!("Metadata for {}:", input_file);
     println!("------------------------");
     println!("Total instructions: {}", bytecode.len());
-    println!("File size: {} bytes", bytecode_content.len());
-    println!("SHA256 hash: {}", sha256_hash(&bytecode_content));
+    println!("File size: {} bytes", fs::metadata(input_file).map_or(0, |m| m.len()));
+    let hash = module_hash(&module);
+    println!("SHA256 hash: {}", hash);
+
+    if let Some(expected) = expected_hash {
+        if !expected.trim().eq_ignore_ascii_case(&hash) {
+            eprintln!("✗ Hash mismatch: expected {}, got {}", expected.trim(), hash);
+            std::process::exit(1);
+        }
+        println!("✓ Hash matches");
+    }
 }
 
-fn sha256_hash(content: &str) -> String {
-    // Simple placeholder - in a real implementation, we'd use a proper hashing function
-    format!("{:x}", content.len())  // Just return length as hex for now
+// The module's content hash, the same digest `kernc build --print-hash` prints.
+// It covers the canonical binary form, so JSON formatting doesn't affect it.
+fn module_hash(module: &BytecodeModule) -> String {
+    to_hex(&module.content_hash())
 }
 
 fn show_stats(input_file: &str) {
-    // Read the bytecode file
-    let bytecode_content = fs::read_to_string(input_file)
-        .expect("Failed to read bytecode file");
-
-    // Deserialize the bytecode
-    let bytecode: Vec<Instruction> = serde_json::from_str(&bytecode_content)
-        .expect("Failed to deserialize bytecode");
-
-    let mut opcode_counts = std::collections::HashMap::new();
-    for instruction in &bytecode {
-        *opcode_counts.entry(instruction.opcode).or_insert(0) += 1;
-    }
+    let module = read_module(input_file);
+    let bytecode = &module.instruction_stream;
 
     println!("Statistics for {}:", input_file);
     println!("------------------------");
     println!("Total instructions: {}", bytecode.len());
 
     println!("\nOpcode histogram:");
-    let mut sorted_counts: Vec<_> = opcode_counts.iter().collect();
-    sorted_counts.sort_by(|a, b| b.1.cmp(a.1)); // Sort by count, descending
-    
-    for (opcode, count) in sorted_counts {
-        let mnemonic = match opcode {
-            0x00 => "NOP",
-            0x01 => "JMP",
-            0x02 => "JMP_IF",
-            0x03 => "HALT",
-            0x10 => "LOAD_SYM",
-            0x11 => "LOAD_NUM",
-            0x12 => "MOVE",
-            0x13 => "COMPARE",
-            0x20 => "GRAPH_NODE_CREATE",
-            0x21 => "GRAPH_EDGE_CREATE",
-            0x22 => "GRAPH_MATCH",
-            0x23 => "GRAPH_TRAVERSE",
-            0x30 => "RULE_LOAD",
-            0x31 => "RULE_EVAL",
-            0x32 => "RULE_FIRE",
-            0x33 => "RULE_PRIORITY_SET",
-            0x40 => "CTX_CREATE",
-            0x41 => "CTX_SWITCH",
-            0x42 => "CTX_CLONE",
-            0x43 => "CTX_DESTROY",
-            0x50 => "ERR_SET",
-            0x51 => "ERR_CLEAR",
-            0x52 => "ERR_CHECK",
-            0x60 => "EXT_CALL",
-            0x61 => "EXT_BIND",
-            0x70 => "RETURN",
-            0x71 => "OUTPUT",
-            _ => "UNKNOWN",
-        };
+    for (opcode, count) in opcode_histogram(bytecode) {
+        let mnemonic = Opcode::from_byte(opcode).map_or("UNKNOWN", Opcode::mnemonic);
         println!("  {}: {} (0x{:02X})", mnemonic, count, opcode);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_matches_kernc_print_hash() {
+        let source = "rule CheckLocation: if farmer.location == valid then approve(farmer)";
+        let program = kern_parser::Parser::new(source).parse_program().unwrap();
+        let graph = kern_graph_builder::GraphBuilder::new().build_execution_graph(&program);
+        let module = kern_bytecode::BytecodeCompiler::new().compile_graph(&graph);
+
+        // The file kernc build writes, and the digest --print-hash reports
+        let path = std::env::temp_dir().join(format!("kernbc-hash-{}.kbc", std::process::id()));
+        fs::write(&path, serde_json::to_string_pretty(&module).unwrap()).unwrap();
+        let read = read_module(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(module_hash(&read), to_hex(&module.content_hash()));
+        assert_eq!(module_hash(&read).len(), 64);
+    }
+}
@@ -1,9 +1,10 @@
 use clap::Parser;
 use kern_parser::Parser as KernParser;
-use kern_parser::Definition;
+use kern_parser::{Definition, Program};
 use kern_graph_builder::GraphBuilder;
-use kern_bytecode::{BytecodeCompiler, BytecodeModule};
+use kern_bytecode::{branch_targets, is_jump, opcode_histogram, BytecodeCompiler, BytecodeModule, Opcode};
 use kern_vm::{VirtualMachine, VMConfig};
+use kern_vm::output::JsonFormatter;
 use kern_vm::vm_safety::sandbox::SandboxPolicy;
 use std::fs;
 
@@ -27,7 +28,14 @@ struct Args {
 #[derive(clap::Subcommand, Debug)]
 enum Commands {
     /// Compile source to bytecode
-    Build,
+    Build {
+        /// Print the SHA-256 content hash of the compiled module
+        #[arg(long)]
+        print_hash: bool,
+        /// Compile graph nodes that no entry point reaches instead of dropping them
+        #[arg(long)]
+        keep_dead: bool,
+    },
     /// Parse and validate without output
     Check,
     /// Emit execution graph
@@ -37,18 +45,35 @@ enum Commands {
     /// Verify existing bytecode file
     Verify,
     /// Report symbols, entities, rules
-    Stats,
+    Stats {
+        /// Also compile and report instruction-level metrics
+        #[arg(long)]
+        bytecode: bool,
+    },
     /// Execute bytecode
-    Run,
+    Run {
+        /// Write the final VM state (registers, contexts) as JSON to this file
+        #[arg(long)]
+        dump_state: Option<String>,
+        /// Output format for values written by the program
+        #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+        output_format: OutputFormat,
+    },
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Human,
+    Json,
 }
 
 fn main() {
     let args = Args::parse();
 
     match args.command {
-        Commands::Build => {
+        Commands::Build { print_hash, keep_dead } => {
             println!("Building KERN source: {}", args.input);
-            compile_to_bytecode(&args.input, &args.output);
+            compile_to_bytecode(&args.input, &args.output, print_hash, keep_dead);
         },
         Commands::Check => {
             println!("Checking KERN source: {}", args.input);
@@ -66,34 +91,38 @@ fn main() {
             println!("Verifying bytecode file: {}", args.input);
             verify_bytecode(&args.input);
         },
-        Commands::Stats => {
+        Commands::Stats { bytecode } => {
             println!("Reporting statistics for: {}", args.input);
-            report_stats(&args.input);
+            report_stats(&args.input, bytecode);
         },
-        Commands::Run => {
-            println!("Running KERN bytecode: {}", args.input);
-            run_bytecode(&args.input);
+        Commands::Run { dump_state, output_format } => {
+            print_status(output_format, &format!("Running KERN bytecode: {}", args.input));
+            run_bytecode(&args.input, dump_state.as_deref(), output_format);
         }
     }
 }
 
-fn compile_to_bytecode(input_file: &str, output_file: &str) {
-    // Read the source file
-    let source_code = fs::read_to_string(input_file)
-        .expect("Failed to read input file");
-
-    // Parse
-    let mut parser = KernParser::new(&source_code);
-    let program = match parser.parse_program() {
-        Ok(p) => p,
+// Parses the source, or reports every parse error and exits with a failure status
+fn parse_source(source_code: &str) -> Program {
+    match KernParser::new(source_code).parse_program() {
+        Ok(program) => program,
         Err(errors) => {
             eprintln!("Parsing errors found:");
             for error in errors {
                 eprintln!("  {}", error);
             }
-            return;
+            std::process::exit(1);
         }
-    };
+    }
+}
+
+fn compile_to_bytecode(input_file: &str, output_file: &str, print_hash: bool, keep_dead: bool) {
+    // Read the source file
+    let source_code = fs::read_to_string(input_file)
+        .expect("Failed to read input file");
+
+    // Parse
+    let program = parse_source(&source_code);
 
     // Build execution graph
     let mut graph_builder = GraphBuilder::new();
@@ -101,13 +130,21 @@ fn compile_to_bytecode(input_file: &str, output_file: &str) {
 
     // Compile to bytecode
     let mut bytecode_compiler = BytecodeCompiler::new();
+    bytecode_compiler.set_dead_node_elimination(!keep_dead);
     let bytecode = bytecode_compiler.compile_graph(&execution_graph);
+    if !keep_dead {
+        println!("Eliminated {} unreachable graph nodes", bytecode_compiler.eliminated_nodes());
+    }
 
     // Write bytecode to output file
     fs::write(output_file, serde_json::to_string(&bytecode).unwrap())
         .expect("Failed to write bytecode to output file");
 
     println!("Successfully compiled {} to {}", input_file, output_file);
+
+    if print_hash {
+        println!("Content hash: {}", kern_bytecode::sha256::to_hex(&bytecode.content_hash()));
+    }
 }
 
 fn check_source(input_file: &str) {
@@ -115,17 +152,9 @@ fn check_source(input_file: &str) {
     let source_code = fs::read_to_string(input_file)
         .expect("Failed to read input file");
 
-    // Parse
-    let mut parser = KernParser::new(&source_code);
-    match parser.parse_program() {
-        Ok(_) => println!("Source code is valid - no errors found"),
-        Err(errors) => {
-            eprintln!("Parsing errors found:");
-            for error in errors {
-                eprintln!("  {}", error);
-            }
-        }
-    }
+    // Parse, reporting every error rather than stopping at the first
+    parse_source(&source_code);
+    println!("Source code is valid - no errors found");
 }
 
 fn generate_graph(input_file: &str) {
@@ -134,17 +163,7 @@ fn generate_graph(input_file: &str) {
         .expect("Failed to read input file");
 
     // Parse
-    let mut parser = KernParser::new(&source_code);
-    let program = match parser.parse_program() {
-        Ok(p) => p,
-        Err(errors) => {
-            eprintln!("Parsing errors found:");
-            for error in errors {
-                eprintln!("  {}", error);
-            }
-            return;
-        }
-    };
+    let program = parse_source(&source_code);
 
     // Build execution graph
     let mut graph_builder = GraphBuilder::new();
@@ -164,20 +183,11 @@ fn show_ir(input_file: &str) {
         .expect("Failed to read input file");
 
     // Parse
-    let mut parser = KernParser::new(&source_code);
-    match parser.parse_program() {
-        Ok(program) => {
-            // Print the AST
-            println!("AST representation:");
-            println!("{:#?}", program);
-        },
-        Err(errors) => {
-            eprintln!("Parsing errors found:");
-            for error in errors {
-                eprintln!("  {}", error);
-            }
-        }
-    }
+    let program = parse_source(&source_code);
+
+    // Print the AST
+    println!("AST representation:");
+    println!("{:#?}", program);
 }
 
 fn verify_bytecode(bytecode_file: &str) {
@@ -192,7 +202,15 @@ fn verify_bytecode(bytecode_file: &str) {
     }
 }
 
-fn run_bytecode(input_file: &str) {
+// Status lines go to stderr under JSON output so stdout holds only the program's values
+fn print_status(output_format: OutputFormat, line: &str) {
+    match output_format {
+        OutputFormat::Human => println!("{}", line),
+        OutputFormat::Json => eprintln!("{}", line),
+    }
+}
+
+fn run_bytecode(input_file: &str, dump_state: Option<&str>, output_format: OutputFormat) {
     let bytecode_content = fs::read_to_string(input_file)
         .expect("Failed to read bytecode file");
         
@@ -203,43 +221,48 @@ fn run_bytecode(input_file: &str) {
     let mut config = VMConfig::new();
     let mut policy = SandboxPolicy::new();
     
-    // Allow standard IO and external functions used in examples
+    // Allow standard IO and the external functions the program calls
     policy.allow_io_channel("stdout");
-    // TODO: Use symbol table to map function names to IDs
-    // For now, allow common IDs
-    policy.allow_function("extern_fn_0"); 
-    policy.set_max_calls_for_function("extern_fn_0", 100);
+    for instruction in &module.instruction_stream {
+        if instruction.opcode != Opcode::CallExtern as u8 {
+            continue;
+        }
+        if let Some(symbol) = module.symbol_table.iter().find(|s| s.id == instruction.arg1 as u32) {
+            policy.allow_function(&symbol.name);
+            policy.set_max_calls_for_function(&symbol.name, 100);
+        }
+    }
     
     config.sandbox_policy = policy;
+    config.echo_output = true;
 
     let mut vm = VirtualMachine::with_config(config);
-    // Load constant pool so runtime can resolve and print string constants
-    vm.constant_pool = module.constant_pool.clone();
-    vm.load_program(module.instruction_stream);
+    if output_format == OutputFormat::Json {
+        vm.output_formatter = Box::new(JsonFormatter);
+    }
+    // Load the constant pool with the program so output prints constants, not their indices
+    vm.load_module(module);
     
     match vm.execute() {
-        Ok(_) => println!("Execution finished successfully."),
+        Ok(_) => print_status(output_format, "Execution finished successfully."),
         Err(e) => eprintln!("VM Runtime Error: {:?}", e),
     }
+
+    if let Some(state_file) = dump_state {
+        match fs::write(state_file, vm.export_state()) {
+            Ok(_) => print_status(output_format, &format!("VM state written to {}", state_file)),
+            Err(e) => eprintln!("Failed to write VM state: {}", e),
+        }
+    }
 }
 
-fn report_stats(input_file: &str) {
+fn report_stats(input_file: &str, bytecode: bool) {
     // Read the source file
     let source_code = fs::read_to_string(input_file)
         .expect("Failed to read input file");
 
     // Parse
-    let mut parser = KernParser::new(&source_code);
-    let program = match parser.parse_program() {
-        Ok(p) => p,
-        Err(errors) => {
-            eprintln!("Parsing errors found:");
-            for error in errors {
-                eprintln!("  {}", error);
-            }
-            return;
-        }
-    };
+    let program = parse_source(&source_code);
 
     // Count entities, rules, flows, and constraints
     let mut entity_count = 0;
@@ -262,4 +285,48 @@ fn report_stats(input_file: &str) {
     println!("  Flows: {}", flow_count);
     println!("  Constraints: {}", constraint_count);
     println!("  Total definitions: {}", entity_count + rule_count + flow_count + constraint_count);
+
+    if bytecode {
+        let execution_graph = GraphBuilder::new().build_execution_graph(&program);
+        let module = BytecodeCompiler::new().compile_graph(&execution_graph);
+        for line in bytecode_stats(&module) {
+            println!("  {}", line);
+        }
+    }
+}
+
+fn bytecode_stats(module: &BytecodeModule) -> Vec<String> {
+    let instructions = &module.instruction_stream;
+    let mut lines = vec![format!("Instructions: {}", instructions.len())];
+    lines.push("Opcode histogram:".to_string());
+    for (opcode, count) in opcode_histogram(instructions) {
+        let mnemonic = Opcode::from_byte(opcode).map_or("UNKNOWN", Opcode::mnemonic);
+        lines.push(format!("  {}: {}", mnemonic, count));
+    }
+    lines.push(format!("Jumps: {}", instructions.iter().filter(|i| is_jump(i)).count()));
+    let targets: Vec<String> = branch_targets(instructions).iter().map(|t| t.to_string()).collect();
+    lines.push(format!("Branch targets: [{}]", targets.join(", ")));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytecode_stats_count_compiled_instructions() {
+        let source = "rule CheckLocation: if farmer.location == valid then approve(farmer)";
+        let program = KernParser::new(source).parse_program().unwrap();
+        let graph = GraphBuilder::new().build_execution_graph(&program);
+        let module = BytecodeCompiler::new().compile_graph(&graph);
+        assert!(!module.instruction_stream.is_empty());
+
+        let lines = bytecode_stats(&module);
+        assert_eq!(lines[0], format!("Instructions: {}", module.instruction_stream.len()));
+        let histogram_total: usize = opcode_histogram(&module.instruction_stream)
+            .iter()
+            .map(|(_, count)| count)
+            .sum();
+        assert_eq!(histogram_total, module.instruction_stream.len());
+    }
 }
@@ -1,6 +1,7 @@
 use clap::Parser;
-use kern_vm::{VirtualMachine, VmRegisters};
-use kern_bytecode::Instruction;
+use kern_vm::{ExecutionTraceEntry, VMConfig, VirtualMachine, VmError, VmRegisters};
+use kern_bytecode::{disassemble_one, Instruction, Opcode};
+use std::collections::HashSet;
 use std::fs;
 use std::io::{self, Write};
 
@@ -15,32 +16,43 @@ struct Args {
     /// Start in replay mode from a trace file
     #[arg(short, long)]
     replay: Option<String>,
+
+    /// Write the execution trace to this JSON file when the session ends
+    #[arg(short, long)]
+    trace: Option<String>,
 }
 
 fn main() {
     let args = Args::parse();
 
     if let Some(trace_file) = args.replay {
-        replay_execution(&trace_file);
+        replay_execution(&args.input, &trace_file);
     } else {
-        start_debug_session(&args.input);
+        start_debug_session(&args.input, args.trace.as_deref());
     }
 }
 
-fn start_debug_session(bytecode_file: &str) {
-    println!("Starting KERN debugger for: {}", bytecode_file);
-
+fn load_bytecode(bytecode_file: &str) -> Vec<Instruction> {
     // Read the bytecode file
     let bytecode_content = fs::read_to_string(bytecode_file)
         .expect("Failed to read bytecode file");
 
     // Deserialize the bytecode
-    let bytecode: Vec<Instruction> = serde_json::from_str(&bytecode_content)
-        .expect("Failed to deserialize bytecode");
+    serde_json::from_str(&bytecode_content)
+        .expect("Failed to deserialize bytecode")
+}
 
-    // Create a VM instance
-    let mut vm = VirtualMachine::new();
+fn start_debug_session(bytecode_file: &str, trace_file: Option<&str>) {
+    println!("Starting KERN debugger for: {}", bytecode_file);
+    let bytecode = load_bytecode(bytecode_file);
+
+    // Create a VM instance that keeps every trace entry for replay
+    let mut config = VMConfig::new();
+    config.trace_full = true;
+    config.echo_output = true;
+    let mut vm = VirtualMachine::with_config(config);
     vm.load_program(bytecode);
+    let mut breakpoints = Breakpoints::default();
 
     println!("KERN Debugger started. Type 'help' for commands.");
     
@@ -52,76 +64,191 @@ fn start_debug_session(bytecode_file: &str) {
         let mut input = String::new();
         io::stdin().read_line(&mut input).unwrap();
         let input = input.trim();
+        let words: Vec<&str> = input.split_whitespace().collect();
 
-        match input {
-            "run" | "r" => {
-                println!("Starting execution...");
-                vm.execute().unwrap();
-                println!("Execution completed.");
-                break;
+        match words.as_slice() {
+            ["run" | "r"] | ["continue" | "c"] => {
+                // `continue` resumes past the breakpoint it last stopped at
+                let resume = matches!(words[0], "continue" | "c");
+                if !resume {
+                    println!("Starting execution...");
+                }
+                match run_until_break(&mut vm, &breakpoints, resume) {
+                    Ok(StopReason::Breakpoint(pc)) => {
+                        println!("Breakpoint hit at PC={} | OP={}", pc, get_current_instruction(&vm));
+                    },
+                    Ok(_) => {
+                        println!("Execution completed.");
+                        break;
+                    },
+                    Err(e) => {
+                        println!("Error during execution: {:?}", e);
+                        break;
+                    }
+                }
             },
-            "step" | "s" => {
+            ["step" | "s"] => {
                 match vm.step() {
                     Ok(_) => {
-                        let registers = vm.get_registers();
-                        println!("[STEP] PC={} | OP={:?}", registers.pc, get_current_instruction(&vm));
-                        print_registers(&registers);
+                        println!("[STEP] PC={} | OP={}", vm.registers.pc, get_current_instruction(&vm));
+                        print_registers(&vm.registers);
                     },
                     Err(e) => {
-                        println!("Error during step: {}", e);
+                        println!("Error during step: {:?}", e);
                         break;
                     }
                 }
             },
-            "next" | "n" => {
+            ["next" | "n"] => {
                 println!("Executing next instruction...");
                 match vm.step() {
                     Ok(_) => {
-                        let registers = vm.get_registers();
-                        println!("[NEXT] PC={} | OP={:?}", registers.pc, get_current_instruction(&vm));
-                        print_registers(&registers);
+                        println!("[NEXT] PC={} | OP={}", vm.registers.pc, get_current_instruction(&vm));
+                        print_registers(&vm.registers);
                     },
                     Err(e) => {
-                        println!("Error during next: {}", e);
+                        println!("Error during next: {:?}", e);
                         break;
                     }
                 }
             },
-            "regs" => {
-                let registers = vm.get_registers();
-                print_registers(&registers);
+            ["regs"] => {
+                print_registers(&vm.registers);
             },
-            "ctx" => {
-                println!("Current context: {}", vm.get_context());
+            ["ctx"] => {
+                println!("Current context: {}", vm.registers.ctx);
             },
-            "trace" => {
+            ["trace"] => {
                 let trace = vm.trace_state();
                 println!("{}", trace);
             },
-            "break" | "b" => {
-                println!("Setting breakpoints not yet implemented");
+            ["break" | "b", "op", mnemonic] => match opcode_by_mnemonic(mnemonic) {
+                Some(opcode) => {
+                    breakpoints.opcodes.insert(opcode);
+                    println!("Breakpoint set on opcode {} (0x{:02X})", mnemonic.to_uppercase(), opcode);
+                },
+                None => println!("Unknown opcode mnemonic: '{}'", mnemonic),
+            },
+            ["break" | "b", pc] => match pc.parse::<u32>() {
+                Ok(pc) => {
+                    breakpoints.pcs.insert(pc);
+                    println!("Breakpoint set at PC={}", pc);
+                },
+                Err(_) => println!("Usage: break <pc> | break op <mnemonic>"),
+            },
+            ["delete" | "d", pc] => match pc.parse::<u32>() {
+                Ok(pc) if breakpoints.pcs.remove(&pc) => println!("Deleted breakpoint at PC={}", pc),
+                Ok(pc) => println!("No breakpoint at PC={}", pc),
+                Err(_) => println!("Usage: delete <pc>"),
+            },
+            ["info", "breakpoints" | "b"] => {
+                print_breakpoints(&breakpoints);
             },
-            "mem" => {
+            ["mem"] => {
                 println!("Memory inspection not yet implemented");
             },
-            "help" | "h" => {
+            ["help" | "h"] => {
                 print_help();
             },
-            "quit" | "q" | "exit" => {
+            ["quit" | "q" | "exit"] => {
                 println!("Quitting debugger...");
                 break;
             },
-            "" => continue, // Empty input, just continue
+            [] => continue, // Empty input, just continue
             _ => {
                 println!("Unknown command: '{}'. Type 'help' for available commands.", input);
             }
         }
     }
+
+    if let Some(trace_file) = trace_file {
+        let entries: Vec<&ExecutionTraceEntry> = vm.execution_trace.iter().collect();
+        let json = serde_json::to_string_pretty(&entries).expect("Failed to serialize trace");
+        fs::write(trace_file, json).expect("Failed to write trace file");
+        println!("Wrote {} trace entries to {}", entries.len(), trace_file);
+    }
+}
+
+/// PCs and opcodes at which `run`/`continue` stop before executing
+#[derive(Debug, Default)]
+struct Breakpoints {
+    pcs: HashSet<u32>,
+    opcodes: HashSet<u8>,
+}
+
+impl Breakpoints {
+    /// Whether the instruction the VM is about to execute is a breakpoint
+    fn hit(&self, vm: &VirtualMachine) -> bool {
+        let pc = vm.registers.pc;
+        self.pcs.contains(&pc)
+            || vm
+                .program
+                .get(pc as usize)
+                .is_some_and(|instruction| self.opcodes.contains(&instruction.opcode))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum StopReason {
+    Breakpoint(u32),
+    Halted,
+    End,
+}
+
+/// Steps the VM until the next instruction is a breakpoint, or the program
+/// halts or runs off its end. With `resume`, the instruction at the current
+/// PC runs even if it is a breakpoint, so execution moves past the last stop.
+fn run_until_break(
+    vm: &mut VirtualMachine,
+    breakpoints: &Breakpoints,
+    resume: bool,
+) -> Result<StopReason, VmError> {
+    let mut skip = resume;
+    loop {
+        if vm.registers.is_halt_requested() {
+            return Ok(StopReason::Halted);
+        }
+        if vm.registers.pc as usize >= vm.program.len() {
+            return Ok(StopReason::End);
+        }
+        if !skip && breakpoints.hit(vm) {
+            return Ok(StopReason::Breakpoint(vm.registers.pc));
+        }
+        vm.step()?;
+        skip = false;
+    }
+}
+
+fn opcode_by_mnemonic(mnemonic: &str) -> Option<u8> {
+    Opcode::ALL
+        .iter()
+        .find(|opcode| opcode.mnemonic().eq_ignore_ascii_case(mnemonic))
+        .map(|&opcode| opcode as u8)
+}
+
+fn print_breakpoints(breakpoints: &Breakpoints) {
+    if breakpoints.pcs.is_empty() && breakpoints.opcodes.is_empty() {
+        println!("No breakpoints.");
+        return;
+    }
+    let mut pcs: Vec<_> = breakpoints.pcs.iter().collect();
+    pcs.sort();
+    for pc in pcs {
+        println!("  PC {}", pc);
+    }
+    let mut opcodes: Vec<_> = breakpoints.opcodes.iter().collect();
+    opcodes.sort();
+    for &opcode in opcodes {
+        let mnemonic = Opcode::from_byte(opcode).map_or("UNKNOWN", Opcode::mnemonic);
+        println!("  opcode {} (0x{:02X})", mnemonic, opcode);
+    }
 }
 
 fn get_current_instruction(vm: &VirtualMachine) -> String {
-    // This is a simplified implementation - in a real debugger, we'd need to access the current instruction
-    format!("Instruction at PC")
+    match vm.program.get(vm.registers.pc as usize) {
+        Some(instruction) => disassemble_one(instruction),
+        None => "<end of program>".to_string(),
+    }
 }
 
 fn print_registers(registers: &VmRegisters) {
@@ -137,26 +264,187 @@ fn print_registers(registers: &VmRegisters) {
 
 fn print_help() {
     println!("KERN Debugger Commands:");
-    println!("  run (r)     - Start execution");
+    println!("  run (r)     - Start execution, stopping at breakpoints");
     println!("  step (s)    - Execute next instruction");
     println!("  next (n)    - Execute next instruction");
     println!("  regs        - Show registers");
     println!("  ctx         - Show current context");
     println!("  trace       - Show execution trace");
-    println!("  break (b)   - Set breakpoint");
+    println!("  continue (c) - Resume execution until the next breakpoint");
+    println!("  break (b) <pc>          - Stop before the instruction at <pc>");
+    println!("  break (b) op <mnemonic> - Stop before any instruction with that opcode");
+    println!("  delete (d) <pc>         - Remove the breakpoint at <pc>");
+    println!("  info breakpoints        - List breakpoints");
     println!("  mem         - Inspect memory");
     println!("  help (h)    - Show this help");
     println!("  quit (q)    - Quit debugger");
 }
 
-fn replay_execution(trace_file: &str) {
+fn replay_execution(bytecode_file: &str, trace_file: &str) {
     println!("Replaying execution from trace: {}", trace_file);
-    
+    let program = load_bytecode(bytecode_file);
+
     // Read the trace file
     let trace_content = fs::read_to_string(trace_file)
         .expect("Failed to read trace file");
-    
-    // In a real implementation, we would replay the execution trace
-    println!("Trace content: {}", trace_content);
-    println!("Replay functionality not yet fully implemented");
-}
\ No newline at end of file
+    let trace: Vec<ExecutionTraceEntry> = serde_json::from_str(&trace_content)
+        .expect("Failed to deserialize trace");
+
+    let states = match replay_trace(&program, &trace) {
+        Ok(states) => states,
+        Err(e) => {
+            eprintln!("Replay failed: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    for (step, (entry, registers)) in trace.iter().zip(&states).enumerate() {
+        let changed: Vec<String> = entry
+            .register_diff
+            .iter()
+            .enumerate()
+            .filter(|(_, diff)| **diff != 0)
+            .map(|(i, _)| format!("R{}={}", i, registers[i]))
+            .collect();
+        println!(
+            "[REPLAY {}] PC={} | OP={} | {}",
+            step,
+            entry.pc_before,
+            disassemble_one(&program[entry.pc_before as usize]),
+            if changed.is_empty() { "no register changes".to_string() } else { changed.join(", ") }
+        );
+    }
+    println!("Replayed {} steps.", trace.len());
+}
+
+#[derive(Debug, PartialEq)]
+enum ReplayError {
+    // The trace executed a PC the bytecode doesn't have
+    PcOutOfRange { step: usize, pc: u32 },
+    // The bytecode at a traced PC has a different opcode than the trace recorded
+    OpcodeMismatch { step: usize, pc: u32, traced: u8, actual: u8 },
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::PcOutOfRange { step, pc } => {
+                write!(f, "step {}: PC {} is outside the loaded bytecode", step, pc)
+            }
+            ReplayError::OpcodeMismatch { step, pc, traced, actual } => write!(
+                f,
+                "step {}: trace recorded opcode 0x{:02X} at PC {}, but the bytecode has 0x{:02X}",
+                step, traced, pc, actual
+            ),
+        }
+    }
+}
+
+/// Rebuilds the general purpose registers after each traced step by applying
+/// its register deltas, starting from zeroed registers. Every step is checked
+/// against the bytecode it claims to have executed.
+fn replay_trace(
+    program: &[Instruction],
+    trace: &[ExecutionTraceEntry],
+) -> Result<Vec<[i64; 16]>, ReplayError> {
+    let mut registers = [0i64; 16];
+    let mut states = Vec::with_capacity(trace.len());
+    for (step, entry) in trace.iter().enumerate() {
+        let pc = entry.pc_before;
+        let instruction = program
+            .get(pc as usize)
+            .ok_or(ReplayError::PcOutOfRange { step, pc })?;
+        if instruction.opcode != entry.opcode {
+            return Err(ReplayError::OpcodeMismatch {
+                step,
+                pc,
+                traced: entry.opcode,
+                actual: instruction.opcode,
+            });
+        }
+        for (register, diff) in registers.iter_mut().zip(entry.register_diff) {
+            *register = register.wrapping_add(diff);
+        }
+        states.push(registers);
+    }
+    Ok(states)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vm() -> VirtualMachine {
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::new(0x11, 1, 2, 0, 0), // LOAD_NUM R1, 2
+            Instruction::new(0x11, 2, 3, 0, 0), // LOAD_NUM R2, 3
+            Instruction::new(0x20, 3, 1, 2, 0), // ADD R3, R1, R2
+            Instruction::new(0x11, 4, 9, 0, 0), // LOAD_NUM R4, 9
+        ]);
+        vm
+    }
+
+    #[test]
+    fn test_run_stops_at_pc_breakpoint() {
+        let mut vm = vm();
+        let mut breakpoints = Breakpoints::default();
+        breakpoints.pcs.insert(2);
+
+        assert_eq!(
+            run_until_break(&mut vm, &breakpoints, false).unwrap(),
+            StopReason::Breakpoint(2)
+        );
+        assert_eq!(vm.registers.r[2], 3);
+        assert_eq!(vm.registers.r[3], 0);
+
+        assert_eq!(
+            run_until_break(&mut vm, &breakpoints, true).unwrap(),
+            StopReason::End
+        );
+        assert_eq!(vm.registers.r[3], 5);
+        assert_eq!(vm.registers.r[4], 9);
+    }
+
+    #[test]
+    fn test_run_stops_at_opcode_breakpoint() {
+        let mut vm = vm();
+        let mut breakpoints = Breakpoints::default();
+        breakpoints.opcodes.insert(opcode_by_mnemonic("add").unwrap());
+
+        assert_eq!(
+            run_until_break(&mut vm, &breakpoints, false).unwrap(),
+            StopReason::Breakpoint(2)
+        );
+        assert_eq!(get_current_instruction(&vm), "ADD R3, R1, R2");
+        assert_eq!(opcode_by_mnemonic("nope"), None);
+    }
+
+    #[test]
+    fn test_recorded_trace_replays_to_final_registers() {
+        let program: Vec<Instruction> = vm().program[..3].to_vec();
+        let mut vm = VirtualMachine::new();
+        vm.load_program(program.clone());
+        vm.execute().unwrap();
+
+        let json = serde_json::to_string(&vm.execution_trace).unwrap();
+        let trace: Vec<ExecutionTraceEntry> = serde_json::from_str(&json).unwrap();
+        assert_eq!(trace.len(), 3);
+
+        let states = replay_trace(&program, &trace).unwrap();
+        assert_eq!(states.last(), Some(&vm.registers.r));
+        assert_eq!(states[0][1], 2);
+
+        // The same trace against different bytecode is rejected
+        let mut other = program.clone();
+        other[2] = Instruction::new(0x21, 3, 1, 2, 0); // SUB R3, R1, R2
+        assert_eq!(
+            replay_trace(&other, &trace),
+            Err(ReplayError::OpcodeMismatch { step: 2, pc: 2, traced: 0x20, actual: 0x21 })
+        );
+        assert_eq!(
+            replay_trace(&program[..1], &trace),
+            Err(ReplayError::PcOutOfRange { step: 1, pc: 1 })
+        );
+    }
+}
@@ -1,4 +1,7 @@
 use clap::Parser;
+use kern_graph_builder::{EdgeType, ExecutionGraph, GraphEdge, GraphNode, GraphNodeType};
+use serde_json::json;
+use std::collections::HashMap;
 use std::fs;
 
 /// KERN Graph Visualizer - Render and analyze KERN execution graphs
@@ -40,62 +43,225 @@ fn main() {
     }
 }
 
-fn generate_dot_format(input_file: &str, output_file: &Option<String>) {
-    // For now, we'll create a placeholder DOT format
-    // In a real implementation, we would parse the actual execution graph structure
-    let dot_content = format!(
-        "digraph KERNExecutionGraph {{\n  label=\"{}\";\n  \n  // Nodes would be generated from the execution graph\n  // This is a placeholder implementation\n  node1 [label=\"Start\" shape=ellipse];\n  node2 [label=\"Rule Evaluation\" shape=box];\n  node3 [label=\"Action Execution\" shape=box];\n  node4 [label=\"End\" shape=ellipse];\n  \n  node1 -> node2;\n  node2 -> node3;\n  node3 -> node4;\n}}",
-        input_file
-    );
+fn load_graph(input_file: &str) -> ExecutionGraph {
+    // Read the .kgraph file written by the compiler
+    let graph_content = fs::read_to_string(input_file)
+        .expect("Failed to read graph file");
 
+    serde_json::from_str(&graph_content)
+        .expect("Failed to deserialize execution graph")
+}
+
+fn write_output(content: &str, output_file: &Option<String>, kind: &str) {
     match output_file {
         Some(file) => {
-            fs::write(file, dot_content).expect("Failed to write output file");
-            println!("DOT graph written to {}", file);
+            fs::write(file, content).expect("Failed to write output file");
+            println!("{} graph written to {}", kind, file);
         },
         None => {
-            println!("{}", dot_content);
+            println!("{}", content);
         }
     }
 }
 
+fn generate_dot_format(input_file: &str, output_file: &Option<String>) {
+    let graph = load_graph(input_file);
+    write_output(&render_dot(&graph, input_file), output_file, "DOT");
+}
+
 fn generate_svg_format(input_file: &str, output_file: &Option<String>) {
-    // For now, we'll create a placeholder SVG format
-    let svg_content = format!(
-        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"600\" height=\"400\">\n  <title>KERN Execution Graph: {}</title>\n  \n  <!-- Nodes would be generated from the execution graph -->\n  <!-- This is a placeholder implementation -->\n  <rect x=\"50\" y=\"50\" width=\"100\" height=\"50\" fill=\"#e1f5fe\" stroke=\"#0277bd\" stroke-width=\"2\" />\n  <text x=\"100\" y=\"80\" font-family=\"Arial\" font-size=\"14\" text-anchor=\"middle\">Start</text>\n  \n  <rect x=\"200\" y=\"150\" width=\"100\" height=\"50\" fill=\"#f3e5f5\" stroke=\"#7b1fa2\" stroke-width=\"2\" />\n  <text x=\"250\" y=\"180\" font-family=\"Arial\" font-size=\"14\" text-anchor=\"middle\">Rule Eval</text>\n  \n  <rect x=\"350\" y=\"250\" width=\"100\" height=\"50\" fill=\"#e8f5e8\" stroke=\"#388e3c\" stroke-width=\"2\" />\n  <text x=\"400\" y=\"280\" font-family=\"Arial\" font-size=\"14\" text-anchor=\"middle\">Action</text>\n  \n  <line x1=\"100\" y1=\"100\" x2=\"250\" y2=\"150\" stroke=\"#000\" stroke-width=\"2\" marker-end=\"url(#arrow)\" />\n  <line x1=\"250\" y1=\"200\" x2=\"400\" y2=\"250\" stroke=\"#000\" stroke-width=\"2\" marker-end=\"url(#arrow)\" />\n  \n  <defs>\n    <marker id=\"arrow\" markerWidth=\"10\" markerHeight=\"10\" refX=\"9\" refY=\"3\" orient=\"auto\" markerUnits=\"strokeWidth\">\n      <path d=\"M0,0 L0,6 L9,3 z\" fill=\"#000\" />\n    </marker>\n  </defs>\n</svg>",
-        input_file
-    );
+    let graph = load_graph(input_file);
+    write_output(&render_svg(&graph, input_file), output_file, "SVG");
+}
 
-    match output_file {
-        Some(file) => {
-            fs::write(file, svg_content).expect("Failed to write output file");
-            println!("SVG graph written to {}", file);
-        },
-        None => {
-            println!("{}", svg_content);
-        }
+fn generate_json_format(input_file: &str, output_file: &Option<String>) {
+    let graph = load_graph(input_file);
+    write_output(&render_json(&graph, input_file), output_file, "JSON");
+}
+
+fn node_label(node: &GraphNode) -> String {
+    format!("{:?} #{} (0x{:02X})", node.node_type, node.id, node.opcode)
+}
+
+fn dot_shape(node_type: &GraphNodeType) -> &'static str {
+    match node_type {
+        GraphNodeType::Op => "box",
+        GraphNodeType::Rule => "ellipse",
+        GraphNodeType::Control => "diamond",
+        GraphNodeType::Graph => "hexagon",
+        GraphNodeType::Io => "parallelogram",
     }
 }
 
-fn generate_json_format(input_file: &str, output_file: &Option<String>) {
-    // For now, we'll create a placeholder JSON format
-    // In a real implementation, we would serialize the actual execution graph structure
-    let json_content = format!(
-        "{{\n  \"graph_name\": \"{}\",\n  \"nodes\": [\n    {{\"id\": 1, \"type\": \"start\", \"label\": \"Start\"}},\n    {{\"id\": 2, \"type\": \"rule\", \"label\": \"Rule Evaluation\"}},\n    {{\"id\": 3, \"type\": \"action\", \"label\": \"Action Execution\"}},\n    {{\"id\": 4, \"type\": \"end\", \"label\": \"End\"}}\n  ],\n  \"edges\": [\n    {{\"from\": 1, \"to\": 2, \"type\": \"control\"}},\n    {{\"from\": 2, \"to\": 3, \"type\": \"control\"}},\n    {{\"from\": 3, \"to\": 4, \"type\": \"control\"}}\n  ],\n  \"metadata\": {{\n    \"generated_by\": \"kerngraph\",\n    \"format_version\": \"1.0\"\n  }}\n}}",
-        input_file
-    );
+fn dot_edge_style(edge: &GraphEdge) -> String {
+    match edge.edge_type {
+        EdgeType::Control => "style=solid".to_string(),
+        EdgeType::Data => "style=dashed".to_string(),
+        EdgeType::Condition => format!("style=dotted label=\"{}\"", edge.condition_flag),
+    }
+}
 
-    match output_file {
-        Some(file) => {
-            fs::write(file, json_content).expect("Failed to write output file");
-            println!("JSON graph written to {}", file);
+fn render_dot(graph: &ExecutionGraph, name: &str) -> String {
+    let mut dot = format!("digraph KERNExecutionGraph {{\n  label=\"{}\";\n", name);
+    for node in graph.nodes.iter().map(|n| n.get_base()) {
+        dot.push_str(&format!(
+            "  node{} [label=\"{}\" shape={}];\n",
+            node.id,
+            node_label(node),
+            dot_shape(&node.node_type)
+        ));
+    }
+    for edge in &graph.edges {
+        dot.push_str(&format!(
+            "  node{} -> node{} [{}];\n",
+            edge.from_node,
+            edge.to_node,
+            dot_edge_style(edge)
+        ));
+    }
+    dot.push('}');
+    dot
+}
+
+fn render_json(graph: &ExecutionGraph, name: &str) -> String {
+    let nodes: Vec<_> = graph
+        .nodes
+        .iter()
+        .map(|n| n.get_base())
+        .map(|node| {
+            json!({
+                "id": node.id,
+                "type": format!("{:?}", node.node_type),
+                "opcode": node.opcode,
+                "label": node_label(node),
+            })
+        })
+        .collect();
+    let edges: Vec<_> = graph
+        .edges
+        .iter()
+        .map(|edge| {
+            json!({
+                "from": edge.from_node,
+                "to": edge.to_node,
+                "type": format!("{:?}", edge.edge_type),
+                "condition_flag": edge.condition_flag,
+            })
+        })
+        .collect();
+    let content = json!({
+        "graph_name": name,
+        "nodes": nodes,
+        "edges": edges,
+        "metadata": {
+            "generated_by": "kerngraph",
+            "format_version": "1.0",
         },
-        None => {
-            println!("{}", json_content);
-        }
+    });
+    serde_json::to_string_pretty(&content).expect("Failed to serialize graph")
+}
+
+// SVG layout: nodes fill rows of SVG_COLUMNS cells in graph order
+const SVG_COLUMNS: usize = 4;
+const SVG_CELL_WIDTH: usize = 160;
+const SVG_CELL_HEIGHT: usize = 100;
+
+fn svg_center(index: usize) -> (usize, usize) {
+    (
+        SVG_CELL_WIDTH / 2 + (index % SVG_COLUMNS) * SVG_CELL_WIDTH,
+        SVG_CELL_HEIGHT / 2 + (index / SVG_COLUMNS) * SVG_CELL_HEIGHT,
+    )
+}
+
+fn svg_shape(node_type: &GraphNodeType, x: usize, y: usize) -> String {
+    let style = "fill=\"#e1f5fe\" stroke=\"#0277bd\" stroke-width=\"2\"";
+    let points = |corners: &[(usize, usize)]| {
+        corners
+            .iter()
+            .map(|(px, py)| format!("{},{}", px, py))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    match node_type {
+        GraphNodeType::Op => format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"110\" height=\"44\" {} />",
+            x - 55, y - 22, style
+        ),
+        GraphNodeType::Rule => format!(
+            "<ellipse cx=\"{}\" cy=\"{}\" rx=\"60\" ry=\"26\" {} />",
+            x, y, style
+        ),
+        GraphNodeType::Control => format!(
+            "<polygon points=\"{}\" {} />",
+            points(&[(x, y - 30), (x + 60, y), (x, y + 30), (x - 60, y)]),
+            style
+        ),
+        GraphNodeType::Graph => format!(
+            "<polygon points=\"{}\" {} />",
+            points(&[
+                (x - 60, y),
+                (x - 35, y - 24),
+                (x + 35, y - 24),
+                (x + 60, y),
+                (x + 35, y + 24),
+                (x - 35, y + 24),
+            ]),
+            style
+        ),
+        GraphNodeType::Io => format!(
+            "<polygon points=\"{}\" {} />",
+            points(&[(x - 45, y - 22), (x + 65, y - 22), (x + 45, y + 22), (x - 65, y + 22)]),
+            style
+        ),
     }
 }
 
+fn render_svg(graph: &ExecutionGraph, name: &str) -> String {
+    let positions: HashMap<u32, (usize, usize)> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id(), svg_center(i)))
+        .collect();
+    let rows = graph.nodes.len().div_ceil(SVG_COLUMNS).max(1);
+
+    let mut svg = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n  <title>KERN Execution Graph: {}</title>\n  <defs>\n    <marker id=\"arrow\" markerWidth=\"10\" markerHeight=\"10\" refX=\"9\" refY=\"3\" orient=\"auto\" markerUnits=\"strokeWidth\">\n      <path d=\"M0,0 L0,6 L9,3 z\" fill=\"#000\" />\n    </marker>\n  </defs>\n",
+        SVG_COLUMNS * SVG_CELL_WIDTH,
+        rows * SVG_CELL_HEIGHT,
+        name
+    );
+    for edge in &graph.edges {
+        // Edges to ids missing from the graph have nowhere to be drawn
+        let (Some(&(x1, y1)), Some(&(x2, y2))) =
+            (positions.get(&edge.from_node), positions.get(&edge.to_node))
+        else {
+            continue;
+        };
+        let dash = match edge.edge_type {
+            EdgeType::Control => "",
+            EdgeType::Data => " stroke-dasharray=\"6,4\"",
+            EdgeType::Condition => " stroke-dasharray=\"2,3\"",
+        };
+        svg.push_str(&format!(
+            "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#000\" stroke-width=\"2\"{} marker-end=\"url(#arrow)\" />\n",
+            x1, y1, x2, y2, dash
+        ));
+    }
+    for (i, node) in graph.nodes.iter().map(|n| n.get_base()).enumerate() {
+        let (x, y) = svg_center(i);
+        svg.push_str(&format!("  {}\n", svg_shape(&node.node_type, x, y)));
+        svg.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" font-family=\"Arial\" font-size=\"12\" text-anchor=\"middle\">{}</text>\n",
+            x,
+            y + 4,
+            node_label(node)
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
 fn generate_png_format(input_file: &str, output_file: &Option<String>) {
     // For now, we'll just indicate that PNG generation would happen
     // In a real implementation, we would generate an actual PNG image
@@ -113,4 +279,29 @@ fn generate_png_format(input_file: &str, output_file: &Option<String>) {
             println!("{}", png_info);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kern_graph_builder::GraphBuilder;
+
+    #[test]
+    fn test_dot_reflects_built_graph() {
+        let input = "rule CheckLocation: if farmer.location == valid then approve(farmer)";
+        let program = kern_parser::Parser::new(input).parse_program().unwrap();
+        let built = GraphBuilder::new().build_execution_graph(&program);
+
+        // Round trip through the .kgraph JSON the compiler writes
+        let json = serde_json::to_string(&built).unwrap();
+        let graph: ExecutionGraph = serde_json::from_str(&json).unwrap();
+        assert_eq!(graph.nodes.len(), built.nodes.len());
+
+        let dot = render_dot(&graph, "test.kgraph");
+        let node_lines = dot.lines().filter(|l| l.contains("[label=")).count();
+        assert_eq!(node_lines, built.nodes.len());
+        assert!(dot.contains("[label=\"Rule #"));
+        assert!(dot.contains("shape=ellipse"));
+        assert_eq!(dot.matches(" -> ").count(), built.edges.len());
+    }
+}
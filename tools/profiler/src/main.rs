@@ -1,5 +1,6 @@
 use clap::Parser;
 use kern_vm::VirtualMachine;
+use kern_bytecode::disassembler::disassemble_one;
 use kern_bytecode::Instruction;
 use std::fs;
 use std::collections::HashMap;
@@ -23,17 +24,102 @@ struct Args {
     /// Output file
     #[arg(short, long)]
     output: Option<String>,
+
+    /// Number of hot instructions to report in dynamic mode
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+
+    /// Bytecode file to compare the input against in compare mode
+    #[arg(short, long)]
+    baseline: Option<String>,
+
+    /// Percentage increase over the baseline reported as a regression
+    #[arg(long, default_value_t = 5.0)]
+    threshold: f64,
 }
 
 #[derive(Default)]
 struct ProfileData {
-    instruction_counts: HashMap<u8, u64>,
+    instruction_counts: HashMap<u8, u64>, // occurrences in the program
+    execution_counts: HashMap<u8, u64>,   // times executed; dynamic profiling only
+    hot_instructions: Vec<HotInstruction>, // most executed PCs, hottest first
     rule_executions: HashMap<String, u64>,
     execution_time: u64,
-    memory_usage: u64,
+    memory_usage: u64, // peak heap bytes
+    final_memory_usage: u64,
     context_depth: u64,
 }
 
+struct HotInstruction {
+    pc: u32,
+    instruction: String,
+    count: u64,
+}
+
+/// How one measurement moved between the baseline and the current run
+struct Delta {
+    baseline: u64,
+    current: u64,
+}
+
+impl Delta {
+    fn change(&self) -> i64 {
+        self.current as i64 - self.baseline as i64
+    }
+
+    // None when the baseline is zero, where a percentage is meaningless
+    fn percent(&self) -> Option<f64> {
+        (self.baseline != 0).then(|| self.change() as f64 * 100.0 / self.baseline as f64)
+    }
+
+    // Growth past `threshold` percent; anything appearing from nothing counts
+    fn is_regression(&self, threshold: f64) -> bool {
+        match self.percent() {
+            Some(percent) => percent > threshold,
+            None => self.current > 0,
+        }
+    }
+}
+
+struct Comparison {
+    opcodes: Vec<(u8, Delta)>, // executions per opcode, by opcode
+    total_instructions: Delta,
+    execution_time: Delta,
+    threshold: f64,
+}
+
+impl Comparison {
+    fn new(baseline: &ProfileData, current: &ProfileData, threshold: f64) -> Self {
+        let count = |data: &ProfileData, opcode: &u8| *data.execution_counts.get(opcode).unwrap_or(&0);
+        let mut opcodes: Vec<u8> = baseline
+            .execution_counts
+            .keys()
+            .chain(current.execution_counts.keys())
+            .copied()
+            .collect();
+        opcodes.sort();
+        opcodes.dedup();
+
+        Comparison {
+            opcodes: opcodes
+                .iter()
+                .map(|opcode| {
+                    (*opcode, Delta { baseline: count(baseline, opcode), current: count(current, opcode) })
+                })
+                .collect(),
+            total_instructions: Delta {
+                baseline: baseline.execution_counts.values().sum(),
+                current: current.execution_counts.values().sum(),
+            },
+            execution_time: Delta {
+                baseline: baseline.execution_time,
+                current: current.execution_time,
+            },
+            threshold,
+        }
+    }
+}
+
 fn main() {
     let args = Args::parse();
 
@@ -44,10 +130,14 @@ fn main() {
         },
         "dynamic" => {
             println!("Performing dynamic profiling on: {}", args.input);
-            dynamic_profiling(&args.input, &args.format, &args.output);
+            dynamic_profiling(&args.input, &args.format, &args.output, args.top);
         },
-        "compare" => {
-            println!("Compare mode not yet implemented");
+        "compare" => match &args.baseline {
+            Some(baseline) => {
+                println!("Comparing {} against baseline {}", args.input, baseline);
+                compare_profiles(&args.input, baseline, args.threshold, &args.format, &args.output);
+            },
+            None => eprintln!("Compare mode requires --baseline <file>"),
         },
         "trace" => {
             println!("Trace mode not yet implemented");
@@ -58,34 +148,53 @@ fn main() {
     }
 }
 
-fn static_analysis(input_file: &str, format: &str, output_file: &Option<String>) {
+fn load_bytecode(input_file: &str) -> Vec<Instruction> {
     // Read the bytecode file
     let bytecode_content = fs::read_to_string(input_file)
         .expect("Failed to read bytecode file");
 
     // Deserialize the bytecode
-    let bytecode: Vec<Instruction> = serde_json::from_str(&bytecode_content)
-        .expect("Failed to deserialize bytecode");
+    serde_json::from_str(&bytecode_content)
+        .expect("Failed to deserialize bytecode")
+}
 
-    // Analyze the bytecode statically
-    let mut profile_data = ProfileData::default();
-    for instruction in &bytecode {
-        *profile_data.instruction_counts.entry(instruction.opcode).or_insert(0) += 1;
-    }
+fn static_analysis(input_file: &str, format: &str, output_file: &Option<String>) {
+    let profile_data = profile_static(&load_bytecode(input_file));
+    output_results(&profile_data, format, output_file);
+}
 
-    // Output the results
+fn dynamic_profiling(input_file: &str, format: &str, output_file: &Option<String>, top: usize) {
+    let profile_data = profile_dynamic(load_bytecode(input_file), top);
     output_results(&profile_data, format, output_file);
 }
 
-fn dynamic_profiling(input_file: &str, format: &str, output_file: &Option<String>) {
-    // Read the bytecode file
-    let bytecode_content = fs::read_to_string(input_file)
-        .expect("Failed to read bytecode file");
+fn compare_profiles(
+    input_file: &str,
+    baseline_file: &str,
+    threshold: f64,
+    format: &str,
+    output_file: &Option<String>,
+) {
+    let baseline = profile_dynamic(load_bytecode(baseline_file), 0);
+    let current = profile_dynamic(load_bytecode(input_file), 0);
+    let comparison = Comparison::new(&baseline, &current, threshold);
 
-    // Deserialize the bytecode
-    let bytecode: Vec<Instruction> = serde_json::from_str(&bytecode_content)
-        .expect("Failed to deserialize bytecode");
+    let output = match format {
+        "json" => format_json_comparison(&comparison),
+        "text" => format_text_comparison(&comparison),
+        _ => format!("Unsupported format: {}", format),
+    };
+    write_output(&output, output_file);
+}
+
+fn profile_static(bytecode: &[Instruction]) -> ProfileData {
+    ProfileData {
+        instruction_counts: static_counts(bytecode),
+        ..ProfileData::default()
+    }
+}
 
+fn profile_dynamic(bytecode: Vec<Instruction>, top: usize) -> ProfileData {
     // Create and run the VM with profiling
     let mut vm = VirtualMachine::new();
     vm.load_program(bytecode);
@@ -95,18 +204,53 @@ fn dynamic_profiling(input_file: &str, format: &str, output_file: &Option<String
     vm.execute().unwrap();
     let execution_time = start_time.elapsed().as_micros() as u64;
 
-    // Collect profiling data
-    let mut profile_data = ProfileData::default();
-    profile_data.execution_time = execution_time;
-    
-    // For now, just populate with some basic data
-    // In a real implementation, we'd track actual instruction counts during execution
-    for instruction in &vm.get_program() {
-        *profile_data.instruction_counts.entry(instruction.opcode).or_insert(0) += 1;
+    ProfileData {
+        instruction_counts: static_counts(&vm.program),
+        execution_counts: vm.get_opcode_execution_counts(),
+        hot_instructions: hot_instructions(&vm, top),
+        execution_time,
+        memory_usage: vm.get_peak_memory_usage().heap_used as u64,
+        final_memory_usage: vm.get_memory_usage().heap_used as u64,
+        context_depth: vm.get_max_context_depth() as u64,
+        ..ProfileData::default()
     }
+}
 
-    // Output the results
-    output_results(&profile_data, format, output_file);
+// How many times each opcode appears in the program
+fn static_counts(bytecode: &[Instruction]) -> HashMap<u8, u64> {
+    let mut counts = HashMap::new();
+    for instruction in bytecode {
+        *counts.entry(instruction.opcode).or_insert(0) += 1;
+    }
+    counts
+}
+
+// The `top` most executed PCs, hottest first; ties go to the lower PC
+fn hot_instructions(vm: &VirtualMachine, top: usize) -> Vec<HotInstruction> {
+    let mut hits: Vec<(usize, u64)> = vm
+        .get_pc_hit_counts()
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    hits.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    hits.into_iter()
+        .take(top)
+        .map(|(pc, count)| HotInstruction {
+            pc: pc as u32,
+            instruction: disassemble_one(&vm.program[pc]),
+            count,
+        })
+        .collect()
+}
+
+fn format_counts(result: &mut String, counts: &HashMap<u8, u64>) {
+    let mut opcodes: Vec<_> = counts.iter().collect();
+    opcodes.sort();
+    for (opcode, count) in opcodes {
+        result.push_str(&format!("  0x{:02X}: {}\n", opcode, count));
+    }
 }
 
 fn output_results(profile_data: &ProfileData, format: &str, output_file: &Option<String>) {
@@ -116,7 +260,10 @@ fn output_results(profile_data: &ProfileData, format: &str, output_file: &Option
         "binary" => format_binary_results(profile_data),
         _ => format!("Unsupported format: {}", format),
     };
+    write_output(&output, output_file);
+}
 
+fn write_output(output: &str, output_file: &Option<String>) {
     match output_file {
         Some(file) => {
             fs::write(file, output).expect("Failed to write output file");
@@ -133,13 +280,26 @@ fn format_text_results(profile_data: &ProfileData) -> String {
     result.push_str("KERN Profiling Results\n");
     result.push_str("======================\n\n");
 
-    result.push_str("Instruction Counts:\n");
-    for (opcode, count) in &profile_data.instruction_counts {
-        result.push_str(&format!("  0x{:02X}: {}\n", opcode, count));
+    result.push_str("Static Instruction Frequency:\n");
+    format_counts(&mut result, &profile_data.instruction_counts);
+
+    if !profile_data.execution_counts.is_empty() {
+        result.push_str("\nDynamic Execution Frequency:\n");
+        format_counts(&mut result, &profile_data.execution_counts);
+    }
+
+    if !profile_data.hot_instructions.is_empty() {
+        result.push_str("\nHot Instructions:\n");
+        for hot in &profile_data.hot_instructions {
+            result.push_str(&format!("  PC {:>4}: {:<24} {}\n", hot.pc, hot.instruction, hot.count));
+        }
     }
 
     result.push_str(&format!("\nExecution Time: {} μs\n", profile_data.execution_time));
-    result.push_str(&format!("Memory Usage: {} bytes\n", profile_data.memory_usage));
+    result.push_str(&format!(
+        "Memory Usage: {} bytes peak, {} bytes final\n",
+        profile_data.memory_usage, profile_data.final_memory_usage
+    ));
     result.push_str(&format!("Max Context Depth: {}\n", profile_data.context_depth));
 
     if !profile_data.rule_executions.is_empty() {
@@ -155,19 +315,215 @@ fn format_text_results(profile_data: &ProfileData) -> String {
 fn format_json_results(profile_data: &ProfileData) -> String {
     use serde_json::json;
 
+    let hot_instructions: Vec<_> = profile_data
+        .hot_instructions
+        .iter()
+        .map(|hot| json!({ "pc": hot.pc, "instruction": hot.instruction, "count": hot.count }))
+        .collect();
     let json_data = json!({
         "instruction_counts": profile_data.instruction_counts,
+        "execution_counts": profile_data.execution_counts,
+        "hot_instructions": hot_instructions,
         "rule_executions": profile_data.rule_executions,
         "execution_time_micros": profile_data.execution_time,
         "memory_usage_bytes": profile_data.memory_usage,
+        "final_memory_usage_bytes": profile_data.final_memory_usage,
         "context_depth": profile_data.context_depth
     });
 
     serde_json::to_string_pretty(&json_data).unwrap()
 }
 
+fn format_percent(delta: &Delta) -> String {
+    match delta.percent() {
+        Some(percent) => format!("{:+.1}%", percent),
+        None => "new".to_string(),
+    }
+}
+
+fn format_text_comparison(comparison: &Comparison) -> String {
+    let flag = |delta: &Delta| if delta.is_regression(comparison.threshold) { "  REGRESSION" } else { "" };
+    let mut result = String::new();
+    result.push_str("KERN Profile Comparison\n");
+    result.push_str("=======================\n\n");
+
+    result.push_str("Opcode    Baseline     Current       Delta\n");
+    for (opcode, delta) in &comparison.opcodes {
+        result.push_str(&format!(
+            "  0x{:02X} {:>11} {:>11} {:>+11} ({}){}\n",
+            opcode, delta.baseline, delta.current, delta.change(), format_percent(delta), flag(delta)
+        ));
+    }
+
+    let total = &comparison.total_instructions;
+    result.push_str(&format!(
+        "\nTotal Instructions: {} -> {} ({:+}, {}){}\n",
+        total.baseline, total.current, total.change(), format_percent(total), flag(total)
+    ));
+    let time = &comparison.execution_time;
+    result.push_str(&format!(
+        "Execution Time: {} μs -> {} μs ({:+}, {}){}\n",
+        time.baseline, time.current, time.change(), format_percent(time), flag(time)
+    ));
+    result.push_str(&format!("\nRegression threshold: {}%\n", comparison.threshold));
+
+    result
+}
+
+fn format_json_comparison(comparison: &Comparison) -> String {
+    use serde_json::json;
+
+    let delta_json = |delta: &Delta| {
+        json!({
+            "baseline": delta.baseline,
+            "current": delta.current,
+            "delta": delta.change(),
+            "percent": delta.percent(),
+            "regression": delta.is_regression(comparison.threshold),
+        })
+    };
+    let opcodes: serde_json::Map<String, serde_json::Value> = comparison
+        .opcodes
+        .iter()
+        .map(|(opcode, delta)| (format!("0x{:02X}", opcode), delta_json(delta)))
+        .collect();
+    let json_data = json!({
+        "opcodes": opcodes,
+        "total_instructions": delta_json(&comparison.total_instructions),
+        "execution_time_micros": delta_json(&comparison.execution_time),
+        "threshold_percent": comparison.threshold,
+    });
+
+    serde_json::to_string_pretty(&json_data).unwrap()
+}
+
 fn format_binary_results(profile_data: &ProfileData) -> String {
     // In a real implementation, this would output binary data
     format!("Binary format not fully implemented, but would contain profile data for: {:?}", 
             profile_data.instruction_counts.keys().collect::<Vec<_>>())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loop_body_executes_more_often_than_it_appears() {
+        let program = vec![
+            Instruction::new(0x11, 1, 0, 0, 0), // LOAD_NUM R1, 0
+            Instruction::new(0x11, 2, 5, 0, 0), // LOAD_NUM R2, 5
+            Instruction::new(0x11, 3, 1, 0, 0), // LOAD_NUM R3, 1
+            Instruction::new(0x20, 1, 1, 3, 0), // ADD R1, R1, R3
+            Instruction::new(0x14, 1, 2, 4, 3), // COMPARE R1 < R2 -> R4
+            Instruction::new(0x02, 3, 0, 0, 0), // JMP_IF 3
+        ];
+        let mut vm = VirtualMachine::new();
+        vm.load_program(program.clone());
+        vm.execute().unwrap();
+        assert_eq!(vm.get_register(1), Some(5));
+
+        let static_add = static_counts(&program)[&0x20];
+        let dynamic_add = vm.get_opcode_execution_counts()[&0x20];
+        assert_eq!(static_add, 1);
+        assert_eq!(dynamic_add, 5);
+        assert!(dynamic_add > static_add);
+        assert_eq!(vm.get_opcode_execution_counts()[&0x11], 3);
+    }
+
+    #[test]
+    fn test_loop_body_is_the_hottest_instruction() {
+        let mut vm = VirtualMachine::new();
+        vm.load_program(vec![
+            Instruction::new(0x11, 1, 0, 0, 0), // LOAD_NUM R1, 0
+            Instruction::new(0x11, 2, 4, 0, 0), // LOAD_NUM R2, 4
+            Instruction::new(0x11, 3, 1, 0, 0), // LOAD_NUM R3, 1
+            Instruction::new(0x20, 1, 1, 3, 0), // ADD R1, R1, R3
+            Instruction::new(0x14, 1, 2, 4, 3), // COMPARE R1 < R2 -> R4
+            Instruction::new(0x02, 3, 0, 0, 0), // JMP_IF 3
+        ]);
+        vm.execute().unwrap();
+        assert_eq!(vm.get_pc_hit_counts(), &[1, 1, 1, 4, 4, 4]);
+
+        let hot = hot_instructions(&vm, 2);
+        assert_eq!(hot.len(), 2);
+        assert_eq!((hot[0].pc, hot[0].count), (3, 4));
+        assert!(hot[0].instruction.starts_with("ADD"));
+        assert_eq!(hot[1].pc, 4);
+    }
+
+    #[test]
+    fn test_compare_reports_deltas_and_regressions() {
+        let baseline = vec![
+            Instruction::new(0x11, 1, 2, 0, 0), // LOAD_NUM R1, 2
+            Instruction::new(0x11, 2, 3, 0, 0), // LOAD_NUM R2, 3
+            Instruction::new(0x20, 3, 1, 2, 0), // ADD R3, R1, R2
+        ];
+        let mut larger = baseline.clone();
+        larger.push(Instruction::new(0x20, 4, 3, 3, 0)); // ADD R4, R3, R3
+        larger.push(Instruction::new(0x13, 3, 5, 0, 0)); // MOVE R3 -> R5
+
+        let comparison = Comparison::new(
+            &profile_dynamic(baseline, 0),
+            &profile_dynamic(larger, 0),
+            50.0,
+        );
+        let deltas: Vec<(u8, u64, u64)> = comparison
+            .opcodes
+            .iter()
+            .map(|(opcode, d)| (*opcode, d.baseline, d.current))
+            .collect();
+        assert_eq!(deltas, vec![(0x11, 2, 2), (0x13, 0, 1), (0x20, 1, 2)]);
+        assert_eq!(comparison.total_instructions.change(), 2);
+        assert_eq!(comparison.total_instructions.percent().map(|p| p.round()), Some(67.0));
+
+        // +0% is fine, +100% and a brand new opcode are both past 50%
+        let regressions: Vec<u8> = comparison
+            .opcodes
+            .iter()
+            .filter(|(_, d)| d.is_regression(comparison.threshold))
+            .map(|(opcode, _)| *opcode)
+            .collect();
+        assert_eq!(regressions, vec![0x13, 0x20]);
+        assert!(comparison.total_instructions.is_regression(comparison.threshold));
+
+        let text = format_text_comparison(&comparison);
+        assert!(text.contains("Total Instructions: 3 -> 5 (+2, +66.7%)  REGRESSION"));
+        let json: serde_json::Value =
+            serde_json::from_str(&format_json_comparison(&comparison)).unwrap();
+        assert_eq!(json["opcodes"]["0x20"]["delta"], 1);
+        assert_eq!(json["opcodes"]["0x13"]["percent"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_memory_usage_reports_peak_heap() {
+        let profile = profile_dynamic(
+            vec![
+                Instruction::new(0x40, 1, 0, 0, 0), // GRAPH_NODE_CREATE R1
+                Instruction::new(0x40, 2, 0, 0, 0), // GRAPH_NODE_CREATE R2
+                Instruction::new(0x43, 1, 0, 0, 0), // DELETE_NODE R1
+            ],
+            0,
+        );
+        assert!(profile.final_memory_usage > 0);
+        assert_eq!(profile.memory_usage, 2 * profile.final_memory_usage);
+        assert!(format_text_results(&profile).contains(&format!(
+            "Memory Usage: {} bytes peak, {} bytes final",
+            profile.memory_usage, profile.final_memory_usage
+        )));
+    }
+
+    #[test]
+    fn test_context_depth_is_the_nesting_watermark() {
+        let profile = profile_dynamic(
+            vec![
+                Instruction::new(0x60, 0, 0, 0, 0), // PUSH_CTX
+                Instruction::new(0x60, 0, 0, 0, 0), // PUSH_CTX
+                Instruction::new(0x61, 0, 0, 0, 0), // POP_CTX
+                Instruction::new(0x61, 0, 0, 0, 0), // POP_CTX
+            ],
+            0,
+        );
+        assert_eq!(profile.context_depth, 3);
+        assert!(format_text_results(&profile).contains("Max Context Depth: 3"));
+    }
+}
@@ -367,8 +367,8 @@ fn build_kern_from_metaprogram(mp: &MetaProgram, brain: &PsiBrain) -> String {
 }
 
 fn generate_kern_for_target(_target: &str) -> String {
-    // Produce a simple rule that logs "Hello, World!" to demonstrate end-to-end
-    let src = r##"rule HelloFromPSI: if 1 == 1 then log("Hello, World!")
+    // Produce a simple rule that logs hello_world to demonstrate end-to-end
+    let src = r##"rule HelloFromPSI: if 1 == 1 then log(hello_world)
 "##;
     src.to_string()
 }
@@ -394,7 +394,7 @@ Example format:
     "operator_type": "reasoning",
     "inputs": ["text"],
     "outputs": ["entities", "sentiment"],
-    "kern_template": "rule AnalyzeText: if true then log(\"Analysis complete\")"
+    "kern_template": "rule AnalyzeText: if true then log(analysis_complete)"
   }
 ]
 "#;